@@ -0,0 +1,107 @@
+use crate::commands::utils::*;
+use crate::commands::shared::battery_runtime::format_minutes;
+use crate::config::{Config, ServerDefinition};
+use crate::notifications::{EventKind, FleetEvent, NotificationBatcher};
+
+/// The readings `[alerts]` rules look at. Anything that couldn't be read is
+/// `None` and never alerts.
+#[derive(Debug, Clone, Default)]
+pub struct AlertInput {
+    pub on_battery: Option<bool>,
+    pub battery_percent: Option<u8>,
+    /// Estimated minutes on battery until the halt level
+    pub battery_minutes_left: Option<u32>,
+    pub cpu_celsius: Option<f32>,
+    /// Percent of full speed the CPU is held to while throttled
+    pub cpu_throttled_to: Option<u8>,
+    pub disk_used_percent: Option<u8>,
+    pub caffeinate_running: Option<bool>,
+}
+
+/// Events for every `[alerts]` rule `input` breaks on `host`.
+pub fn evaluate(host: &str, input: &AlertInput, server: Option<&ServerDefinition>, config: &Config) -> Vec<FleetEvent> {
+    let rules = &config.alerts;
+    if !rules.enabled {
+        return Vec::new();
+    }
+
+    let thresholds = config.thresholds(server);
+    let battery_limit = rules.battery_percent.unwrap_or(thresholds.battery_warning_level);
+    let temperature_limit = rules.temperature.unwrap_or(thresholds.temp);
+    let disk_limit = rules.disk_percent.unwrap_or(thresholds.disk_warning_level);
+
+    let event = |kind: EventKind, detail: String| FleetEvent {
+        host: host.to_string(),
+        kind,
+        detail,
+    };
+    let mut events = Vec::new();
+
+    let on_battery = input.on_battery == Some(true);
+    if on_battery && rules.ac_loss {
+        events.push(event(EventKind::OnBattery, "Running on battery power".to_string()));
+    }
+    if let Some(pct) = input.battery_percent.filter(|pct| on_battery && *pct <= battery_limit) {
+        events.push(event(EventKind::LowBattery, format!("Battery at {}%", pct)));
+    }
+    let runtime_limit = rules.runtime_minutes;
+    if let Some(minutes) = input.battery_minutes_left.filter(|minutes| on_battery && runtime_limit > 0 && *minutes <= runtime_limit) {
+        events.push(event(
+            EventKind::BatteryRunningOut,
+            format!("Server will die in ~{} on battery", format_minutes(minutes)),
+        ));
+    }
+    if let Some(celsius) = input.cpu_celsius.filter(|celsius| *celsius > temperature_limit) {
+        events.push(event(EventKind::HighTemperature, format!("CPU at {:.1}°C (limit {:.1}°C)", celsius, temperature_limit)));
+    }
+    if let Some(limit) = input.cpu_throttled_to.filter(|_| rules.throttling) {
+        events.push(event(EventKind::ThermalThrottling, format!("CPU throttled to {}% of full speed", limit)));
+    }
+    if let Some(pct) = input.disk_used_percent.filter(|pct| *pct >= disk_limit) {
+        events.push(event(EventKind::DiskFull, format!("Disk {}% used (limit {}%)", pct, disk_limit)));
+    }
+    if input.caffeinate_running == Some(false) && rules.caffeinate_down {
+        events.push(event(EventKind::CaffeinateStopped, "Caffeinate is not running".to_string()));
+    }
+
+    events
+}
+
+/// Evaluate `input` and send whatever is due through the configured
+/// notifiers, honouring `notifications.cooldown`. Best-effort and quiet, so
+/// monitoring commands can call it on every reading; problems only show up
+/// in verbose output.
+pub fn check(host: &str, input: &AlertInput, server: Option<&ServerDefinition>, config: &Config, verbose: bool) {
+    if !config.notifications.enabled {
+        return;
+    }
+    let events = evaluate(host, input, server, config);
+    if events.is_empty() {
+        return;
+    }
+
+    let mut batcher = NotificationBatcher::load(config);
+    if !batcher.has_notifiers() {
+        print_verbose("Alerts raised but no notifiers are configured", verbose);
+        return;
+    }
+
+    let (notifications, _) = batcher.batch(&events);
+    let mut delivered = true;
+    for notification in &notifications {
+        match batcher.send(notification) {
+            Ok(()) => print_verbose(&format!("Alert sent: {}", notification.summary()), verbose),
+            Err(e) => {
+                print_verbose(&format!("Could not send alert: {}", e), verbose);
+                delivered = false;
+            }
+        }
+    }
+    // Like `client check`, only start the cooldown once delivery worked
+    if !delivered {
+        return;
+    }
+    if let Err(e) = batcher.save() {
+        print_verbose(&format!("Could not save notification state: {}", e), verbose);
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::Result;
+use crate::{Config, OutputFormat};
+use crate::commands::utils::*;
+use crate::history::{History, HistoryEntry};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc};
+use colored::*;
+use serde::Serialize;
+
+/// Best-effort: keep the result of polling `host` for the availability
+/// report; failures only show up in verbose output.
+pub fn record_poll(config: &Config, host: &str, reachable: bool, boot_time: Option<DateTime<Utc>>, verbose: bool) {
+    if let Err(e) = History::open(config).and_then(|history| history.record_reachability(host, reachable, boot_time)) {
+        print_verbose(&format!("Could not record reachability of {}: {}", host, e), verbose);
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Cause {
+    /// Polls failed from `start` until one answered again
+    Unreachable,
+    /// The server booted between two answered polls
+    Reboot,
+}
+
+#[derive(Serialize)]
+struct Downtime {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    cause: Cause,
+    /// Still unreachable at the last poll
+    ongoing: bool,
+}
+
+#[derive(Serialize)]
+struct ServerAvailability {
+    host: String,
+    /// The start of the period, or the first poll if tracking began later
+    tracked_from: Option<DateTime<Utc>>,
+    polls: usize,
+    uptime_percent: Option<f64>,
+    downtime_seconds: i64,
+    downtime: Vec<Downtime>,
+}
+
+#[derive(Serialize)]
+struct AvailabilityReport {
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+    servers: Vec<ServerAvailability>,
+}
+
+pub async fn execute_report(
+    month: Option<String>,
+    host: Option<String>,
+    config: &Config,
+    output: OutputFormat,
+    verbose: bool,
+) -> Result<()> {
+    let (period_start, period_end, label) = period(month.as_deref())?;
+
+    let hosts = match host {
+        Some(host) => vec![config.resolve_server(&host)
+            .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?
+            .name
+            .clone()],
+        None => {
+            note_disabled(&config.disabled_server_names(|_| true));
+            config.enabled_server_names()
+        }
+    };
+
+    let history = History::open(config)?;
+    let mut servers = Vec::new();
+    for host in hosts {
+        let polls: Vec<(DateTime<Utc>, bool, Option<DateTime<Utc>>)> = history.reachability(&host)?
+            .into_iter()
+            .filter_map(|record| match record.entry {
+                HistoryEntry::Reachability { reachable, boot_time } => Some((record.timestamp, reachable, boot_time)),
+                _ => None,
+            })
+            .collect();
+        servers.push(availability(host, &polls, period_start, period_end));
+    }
+    let report = AvailabilityReport { period_start, period_end, servers };
+
+    if output == OutputFormat::Json {
+        return print_json(&report);
+    }
+
+    print_header(&format!("Availability: {}", label));
+    if report.servers.is_empty() {
+        print_info("No enabled servers configured");
+        return Ok(());
+    }
+
+    let width = report.servers.iter().map(|server| server.host.len()).max().unwrap_or(0).max(6);
+    println!(
+        "{:<width$} {:>9} {:>10} {:>8} {:>6}  {}",
+        "SERVER".bold(), "UPTIME".bold(), "DOWNTIME".bold(), "OUTAGES".bold(), "POLLS".bold(), "TRACKED FROM".bold(),
+        width = width
+    );
+    for server in &report.servers {
+        let uptime = match server.uptime_percent {
+            Some(percent) => {
+                let text = format!("{:.3}%", percent);
+                if percent >= 99.9 { text.green() } else if percent >= 99.0 { text.yellow() } else { text.red() }
+            }
+            None => "-".dimmed(),
+        };
+        println!(
+            "{:<width$} {:>9} {:>10} {:>8} {:>6}  {}",
+            server.host,
+            uptime,
+            format_seconds(server.downtime_seconds),
+            server.downtime.len(),
+            server.polls,
+            server.tracked_from.map(local_time).unwrap_or_else(|| "no polls".to_string()),
+            width = width
+        );
+    }
+
+    for server in report.servers.iter().filter(|server| !server.downtime.is_empty()) {
+        println!("\n{}:", server.host.bold());
+        for window in &server.downtime {
+            let cause = match window.cause {
+                Cause::Unreachable => "unreachable",
+                Cause::Reboot => "rebooted",
+            };
+            println!(
+                "  {} → {}  {:>8}  {}{}",
+                local_time(window.start),
+                local_time(window.end),
+                format_seconds((window.end - window.start).num_seconds()),
+                cause,
+                if window.ongoing { " (ongoing)" } else { "" }
+            );
+        }
+    }
+
+    println!();
+    println!("Polls come from 'plan10 client check' and 'client ping'; run 'client check' from cron for an accurate figure");
+    print_verbose("Downtime is measured between polls, so it is accurate to about one poll interval", verbose);
+
+    Ok(())
+}
+
+/// The report's period in UTC and how to label it: a calendar month in
+/// local time, or the last 30 days.
+fn period(month: Option<&str>) -> Result<(DateTime<Utc>, DateTime<Utc>, String)> {
+    let now = Utc::now();
+    let Some(month) = month else {
+        return Ok((now - Duration::days(30), now, "last 30 days".to_string()));
+    };
+
+    let first = if month.is_empty() {
+        Local::now().date_naive().with_day(1)
+    } else {
+        NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()
+    }
+    .ok_or_else(|| anyhow::anyhow!("Invalid month '{}': use YYYY-MM", month))?;
+    let next = first.checked_add_months(chrono::Months::new(1))
+        .ok_or_else(|| anyhow::anyhow!("Invalid month '{}'", month))?;
+
+    let local_midnight = |date: NaiveDate| Local.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map(|time| time.with_timezone(&Utc));
+    let start = local_midnight(first).ok_or_else(|| anyhow::anyhow!("Invalid month '{}'", month))?;
+    let end = local_midnight(next).ok_or_else(|| anyhow::anyhow!("Invalid month '{}'", month))?;
+    if start > now {
+        anyhow::bail!("{} hasn't started yet", first.format("%Y-%m"));
+    }
+    Ok((start, end.min(now), first.format("%Y-%m").to_string()))
+}
+
+/// Downtime windows from the polls, clipped to the period. A run of failed
+/// polls counts from the first failure to the next answer; a boot between
+/// two answered polls counts from the earlier poll to the boot.
+fn availability(
+    host: String,
+    polls: &[(DateTime<Utc>, bool, Option<DateTime<Utc>>)],
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> ServerAvailability {
+    let mut windows = Vec::new();
+    let mut down_since: Option<DateTime<Utc>> = None;
+    let mut previous: Option<&(DateTime<Utc>, bool, Option<DateTime<Utc>>)> = None;
+    for poll in polls {
+        let (timestamp, reachable, boot_time) = *poll;
+        if !reachable {
+            down_since.get_or_insert(timestamp);
+        } else if let Some(start) = down_since.take() {
+            windows.push(Downtime { start, end: timestamp, cause: Cause::Unreachable, ongoing: false });
+        } else if let Some((last, true, last_boot)) = previous {
+            if let Some(boot) = boot_time.filter(|boot| *last_boot != Some(*boot) && boot > last) {
+                windows.push(Downtime { start: *last, end: boot, cause: Cause::Reboot, ongoing: false });
+            }
+        }
+        previous = Some(poll);
+    }
+    if let Some(start) = down_since {
+        windows.push(Downtime { start, end: Utc::now(), cause: Cause::Unreachable, ongoing: true });
+    }
+
+    let windows: Vec<Downtime> = windows.into_iter()
+        .filter(|window| window.end > period_start && window.start < period_end)
+        .map(|window| Downtime {
+            start: window.start.max(period_start),
+            end: window.end.min(period_end),
+            ..window
+        })
+        .collect();
+
+    let polls_in_period = polls.iter().filter(|(timestamp, ..)| *timestamp >= period_start && *timestamp < period_end).count();
+    let tracked_from = polls.first()
+        .map(|(first, ..)| (*first).max(period_start))
+        .filter(|from| *from < period_end);
+    let downtime_seconds: i64 = windows.iter().map(|window| (window.end - window.start).num_seconds()).sum();
+    let uptime_percent = tracked_from.map(|from| (period_end - from).num_seconds()).filter(|tracked| *tracked > 0).map(|tracked| {
+        100.0 * (1.0 - downtime_seconds as f64 / tracked as f64)
+    });
+
+    ServerAvailability {
+        host,
+        tracked_from,
+        polls: polls_in_period,
+        uptime_percent,
+        downtime_seconds,
+        downtime: windows,
+    }
+}
+
+fn local_time(timestamp: DateTime<Utc>) -> String {
+    timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+}
+
+fn format_seconds(seconds: i64) -> String {
+    match seconds {
+        0 => "0".to_string(),
+        1..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86_399 => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+        _ => format!("{}d{:02}h", seconds / 86_400, seconds % 86_400 / 3600),
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::Result;
+use crate::Config;
+use crate::alerts::{self, AlertInput};
+use crate::commands::utils::*;
+use crate::commands::client::availability;
+use crate::config::ServerDefinition;
+use crate::commands::shared::{battery_runtime, metrics_history, readings};
+use crate::commands::shared::power_history::remote_power_settings;
+use crate::history::{History, HistoryEntry, HistoryRecord};
+use crate::nms::CheckResult;
+use crate::notifications::{EventKind, FleetEvent, NotificationBatcher};
+use crate::ssh::{CommandResult, SshClient, SshPool};
+use colored::*;
+use std::collections::HashMap;
+
+pub async fn execute_check(
+    dry_run: bool,
+    site: Option<String>,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    print_header("Fleet Check");
+
+    note_disabled(&config.disabled_server_names(|s| s.in_site(site.as_deref())));
+    let mut servers: Vec<_> = config.servers.values()
+        .filter(|s| s.enabled && s.in_site(site.as_deref()))
+        .collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if servers.is_empty() {
+        print_info("No enabled servers configured");
+        return Ok(());
+    }
+
+    print_verbose(&format!(
+        "Checking {} server(s), up to {} at a time",
+        servers.len(),
+        config.client.concurrent_operations
+    ), verbose);
+
+    let mut pool = SshPool::new(config.clone());
+    let battery = pool.execute_on_all(&servers, "pmset -g batt | head -2").await;
+    let reachable: Vec<_> = servers.iter().copied().filter(|server| pool.connection(server).is_some()).collect();
+    let mut caffeinate: HashMap<_, _> = pool.execute_on_all(&reachable, "pgrep -x caffeinate").await.into_iter().collect();
+
+    let mut events = Vec::new();
+    let mut results = Vec::new();
+    for (server, (_, battery)) in servers.into_iter().zip(battery) {
+        let caffeinate = caffeinate.remove(&server.name);
+        let server_events = match pool.connection(server) {
+            Some(client) => check_server(server, client, battery.ok(), caffeinate.and_then(Result::ok), config),
+            None => {
+                availability::record_poll(config, &server.name, false, None, verbose);
+                let error = battery.err().or_else(|| caffeinate.and_then(Result::err));
+                vec![FleetEvent {
+                    host: server.name.clone(),
+                    kind: EventKind::Unreachable,
+                    detail: error.map_or_else(|| "Connection lost".to_string(), |e| e.to_string()),
+                }]
+            }
+        };
+
+        if server_events.is_empty() {
+            println!("  {} {}", "🟢".green(), server.name);
+        } else {
+            for event in &server_events {
+                println!("  {} {}: {}", "🔴".red(), server.name, event.detail);
+            }
+        }
+        results.push(CheckResult::from_events(&server.name, &server_events));
+        events.extend(server_events);
+    }
+
+    println!();
+    if config.nms.is_configured() {
+        if dry_run {
+            print_info(&format!("Would send {} result(s) to the configured monitoring systems", results.len()));
+        } else {
+            config.nms.push(&results, verbose);
+        }
+    }
+
+    if events.is_empty() {
+        print_success("All servers healthy");
+        return Ok(());
+    }
+
+    if !config.notifications.enabled {
+        print_info("Notifications are disabled");
+        return Ok(());
+    }
+
+    let mut batcher = NotificationBatcher::load(config);
+    let (notifications, suppressed) = batcher.batch(&events);
+
+    if suppressed > 0 {
+        print_info(&format!("{} event(s) suppressed by cooldown", suppressed));
+    }
+
+    if dry_run {
+        for notification in &notifications {
+            print_info(&format!("Would notify: {}", notification.summary()));
+        }
+        return Ok(());
+    }
+
+    batcher.deliver(&notifications, verbose)?;
+    batcher.save()?;
+
+    Ok(())
+}
+
+/// Turn the probe results for a reachable server into events.
+fn check_server(
+    server: &ServerDefinition,
+    client: &SshClient,
+    battery: Option<CommandResult>,
+    caffeinate: Option<CommandResult>,
+    config: &Config,
+) -> Vec<FleetEvent> {
+    let source = readings::Source::Remote(client);
+    availability::record_poll(config, &server.name, true, readings::boot_time(&source), false);
+    let sample = readings::sample(&source);
+    let battery = battery.filter(|result| result.success);
+    let on_battery = battery.as_ref().map(|result| result.stdout.contains("Battery Power"));
+    let battery_percent = battery.as_ref().and_then(|result| parse_battery_percentage(&result.stdout));
+    let input = AlertInput {
+        on_battery,
+        battery_percent,
+        cpu_celsius: sample.cpu_celsius,
+        cpu_throttled_to: readings::thermal_limits(&source).throttled_to(),
+        disk_used_percent: readings::disk_usage(&source),
+        caffeinate_running: Some(caffeinate.is_some_and(|result| result.success && !result.stdout.trim().is_empty())),
+        battery_minutes_left: battery_runtime::estimate(&source, Some(server), on_battery == Some(true), battery_percent, config)
+            .map(|runtime| runtime.minutes),
+    };
+    let mut events = alerts::evaluate(&server.name, &input, Some(server), config);
+
+    if let Some((kind, detail)) = verify_profile_after_update(server, client, config) {
+        events.push(FleetEvent { host: server.name.clone(), kind, detail });
+    }
+
+    metrics_history::record_sample(config, &server.name, sample, false);
+
+    events
+}
+
+/// Compare the server's macOS version with the last recorded snapshot; after
+/// an update, check the power profile and reapply it when policy allows.
+fn verify_profile_after_update(
+    server: &ServerDefinition,
+    client: &SshClient,
+    config: &Config,
+) -> Option<(EventKind, String)> {
+    let history = History::open(config).ok()?;
+    let previous_os = match history.latest_power_settings(&server.name).ok()? {
+        Some(HistoryRecord { entry: HistoryEntry::PowerSettings { os_version, .. }, .. }) => os_version,
+        _ => None,
+    };
+
+    let (os_version, settings) = remote_power_settings(client).ok()?;
+    let _ = history.record_power_settings(&server.name, os_version.clone(), settings.clone());
+
+    let (Some(previous_os), Some(os_version)) = (previous_os, os_version) else {
+        return None;
+    };
+    if previous_os == os_version {
+        return None;
+    }
+
+    let update = format!("macOS {} → {}", previous_os, os_version);
+    let drift = config.power_profile.drift(&settings);
+    if drift.is_empty() {
+        return None;
+    }
+
+    let drift_list: Vec<String> = drift.iter()
+        .map(|(key, expected, actual)| {
+            format!("{} is {} (expected {})", key, actual.as_deref().unwrap_or("unset"), expected)
+        })
+        .collect();
+
+    if !config.power_profile.reapply_after_update {
+        return Some((EventKind::ProfileDrift, format!("{}: {}", update, drift_list.join(", "))));
+    }
+
+    let args: Vec<String> = drift.iter()
+        .map(|(key, expected, _)| format!("{} {}", key, expected))
+        .collect();
+    // -n: never prompt; a password-protected sudo is reported as drift instead
+    let reapplied = client.execute_command(&format!("sudo -n pmset -a {}", args.join(" ")))
+        .ok()
+        .filter(|result| result.success)
+        .and_then(|_| remote_power_settings(client).ok())
+        .map(|(os_version, settings)| {
+            let remaining = config.power_profile.drift(&settings);
+            let _ = history.record_power_settings(&server.name, os_version, settings);
+            remaining.is_empty()
+        })
+        .unwrap_or(false);
+
+    if reapplied {
+        Some((EventKind::ProfileReapplied, format!("{}: reapplied {}", update, args.join(", "))))
+    } else {
+        Some((EventKind::ProfileDrift, format!("{}: reapply failed; {}", update, drift_list.join(", "))))
+    }
+}
+
+pub fn parse_battery_percentage(output: &str) -> Option<u8> {
+    output.lines().find_map(|line| {
+        let end = line.find('%')?;
+        let start = line[..end]
+            .rfind(|c: char| !c.is_ascii_digit())
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        line[start..end].parse().ok()
+    })
+}
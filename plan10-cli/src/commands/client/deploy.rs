@@ -1,16 +1,16 @@
 use anyhow::Result;
 use crate::Config;
 use crate::commands::utils::*;
-use crate::ssh::{SshClient, deploy_files};
-use crate::config::ServerDefinition;
+use crate::ssh::{host_port, SshClient, TransferProgress, deploy_files};
+use crate::config::{AddressFamily, ServerDefinition};
 use colored::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use indicatif::{ProgressBar, ProgressStyle};
 
 pub async fn execute_deploy(
     host: String,
     user: Option<String>,
-    port: u16,
+    port: Option<u16>,
     all: bool,
     scripts_only: bool,
     config_only: bool,
@@ -20,9 +20,9 @@ pub async fn execute_deploy(
     print_header(&format!("Deploying Plan 10 to {}", host));
 
     // Resolve server configuration
-    let server = resolve_or_create_server(&host, user, port, config)?;
+    let server = config.with_group_defaults(&resolve_or_create_server(&host, user, port, config)?);
     
-    print_verbose(&format!("Connecting to {}@{}:{}", server.user, server.host, server.port), verbose);
+    print_verbose(&format!("Connecting to {}@{}", server.user, host_port(&server.host, server.port)), verbose);
 
     // Test connectivity first
     print_info("Testing connection...");
@@ -38,31 +38,54 @@ pub async fn execute_deploy(
         return Ok(());
     }
 
-    // Create progress bar
-    let pb = ProgressBar::new(deployment_items.len() as u64);
+    // Progress is measured in bytes, so one large tree doesn't sit at a
+    // single step while small scripts fly by
+    let total_bytes: u64 = deployment_items.iter()
+        .flat_map(|(_, files)| files)
+        .map(|(local_path, _)| local_size(local_path))
+        .sum();
+    let pb = ProgressBar::new(total_bytes);
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}) {msg}")
             .unwrap()
             .progress_chars("#>-")
     );
+    let mut on_progress = |progress: TransferProgress| {
+        pb.inc(progress.chunk);
+        let percent = match progress.size {
+            0 => 100,
+            size => progress.transferred * 100 / size,
+        };
+        pb.set_message(format!("{} {}%", progress.file.display(), percent));
+    };
+
+    let uploaded: Vec<(PathBuf, String)> = deployment_items.iter()
+        .flat_map(|(_, files)| files.iter().cloned())
+        .filter(|(local_path, _)| local_path.exists())
+        .collect();
 
     // Deploy items
     for (category, files) in deployment_items {
-        pb.set_message(format!("Deploying {}", category));
-        
         match category.as_str() {
-            "server-setup" => deploy_server_setup(&client, verbose).await?,
-            "scripts" => deploy_scripts(&client, &files, verbose).await?,
-            "configs" => deploy_configs(&client, &files, verbose).await?,
-            "services" => deploy_services(&client, &files, verbose).await?,
+            "server-setup" => deploy_server_setup(&client, &mut on_progress, verbose).await?,
+            "scripts" => deploy_scripts(&client, &files, &mut on_progress, verbose).await?,
+            "configs" => deploy_configs(&client, &files, &mut on_progress, verbose).await?,
+            "services" => deploy_services(&client, &files, &mut on_progress, verbose).await?,
             _ => continue,
         }
-        
-        pb.inc(1);
     }
 
-    pb.finish_with_message("Deployment complete");
+    pb.finish_with_message("Upload complete");
+
+    // A dropped or corrupted transfer must not leave half a script behind
+    print_info("Verifying checksums...");
+    client.verify_uploads(&uploaded)?;
+    print_success("Checksums match for all deployed files");
+
+    if let Err(e) = super::inventory::mark_deployed(config, &server.name) {
+        print_warning(&format!("Failed to update inventory tags: {}", e));
+    }
     
     print_success("Plan 10 deployed successfully!");
     print_info("Next steps:");
@@ -76,7 +99,7 @@ pub async fn execute_deploy(
 fn resolve_or_create_server(
     host: &str,
     user: Option<String>,
-    port: u16,
+    port: Option<u16>,
     config: &Config,
 ) -> Result<ServerDefinition> {
     // Try to find existing server
@@ -85,22 +108,48 @@ fn resolve_or_create_server(
     }
 
     // Create temporary server definition
-    let user = user.ok_or_else(|| {
-        anyhow::anyhow!("User not specified and server '{}' not found in config", host)
-    })?;
+    let user = match user {
+        Some(user) => user,
+        None => super::servers::default_user(host, config)?,
+    };
 
     Ok(ServerDefinition {
         name: host.to_string(),
         host: host.to_string(),
         user,
-        port,
-        ssh_key: None,
+        port: port.or(config.client.defaults.port).unwrap_or(22),
+        ssh_key: config.client.defaults.ssh_key.clone(),
         tags: vec!["temporary".to_string()],
         enabled: true,
         last_seen: None,
+        site: None,
+        addresses: Vec::new(),
+        address_family: AddressFamily::Auto,
+        notes: None,
+        runbook: None,
+        jump_host: None,
+        agent_forwarding: false,
+        ssh_options: Default::default(),
+        thresholds: Default::default(),
     })
 }
 
+// Files deployed to servers, as (local path relative to the checkout, remote path)
+pub const SERVER_SETUP: (&str, &str) = ("server_setup.sh", "~/server_setup.sh");
+pub const SCRIPTS: &[(&str, &str)] = &[
+    ("scripts/temp", "~/scripts/temp"),
+    ("scripts/battery", "~/scripts/battery"),
+    ("scripts/power_diagnostics", "~/scripts/power_diagnostics"),
+    ("scripts/setup_aliases.sh", "~/scripts/setup_aliases.sh"),
+];
+pub const CAFFEINATE_PLIST: (&str, &str) = ("caffeinate.plist", "~/Library/LaunchAgents/caffeinate.plist");
+
+fn file_pairs(files: &[(&str, &str)]) -> Vec<(PathBuf, String)> {
+    files.iter()
+        .map(|(local, remote)| (PathBuf::from(local), remote.to_string()))
+        .collect()
+}
+
 fn determine_deployment_items(
     all: bool,
     scripts_only: bool,
@@ -110,42 +159,40 @@ fn determine_deployment_items(
 
     if all || (!scripts_only && !config_only) {
         // Deploy everything
-        items.push(("server-setup".to_string(), vec![
-            (PathBuf::from("server_setup.sh"), "~/server_setup.sh".to_string()),
-        ]));
+        items.push(("server-setup".to_string(), file_pairs(&[SERVER_SETUP])));
         
-        items.push(("scripts".to_string(), vec![
-            (PathBuf::from("scripts/temp"), "~/scripts/temp".to_string()),
-            (PathBuf::from("scripts/battery"), "~/scripts/battery".to_string()),
-            (PathBuf::from("scripts/power_diagnostics"), "~/scripts/power_diagnostics".to_string()),
-            (PathBuf::from("scripts/setup_aliases.sh"), "~/scripts/setup_aliases.sh".to_string()),
-        ]));
+        items.push(("scripts".to_string(), file_pairs(SCRIPTS)));
         
-        items.push(("configs".to_string(), vec![
-            (PathBuf::from("caffeinate.plist"), "~/Library/LaunchAgents/caffeinate.plist".to_string()),
-        ]));
+        items.push(("configs".to_string(), file_pairs(&[CAFFEINATE_PLIST])));
         
         items.push(("services".to_string(), vec![
             (PathBuf::from("docs/"), "~/docs/".to_string()),
         ]));
     } else if scripts_only {
-        items.push(("scripts".to_string(), vec![
-            (PathBuf::from("scripts/temp"), "~/scripts/temp".to_string()),
-            (PathBuf::from("scripts/battery"), "~/scripts/battery".to_string()),
-            (PathBuf::from("scripts/power_diagnostics"), "~/scripts/power_diagnostics".to_string()),
-            (PathBuf::from("scripts/setup_aliases.sh"), "~/scripts/setup_aliases.sh".to_string()),
-        ]));
+        items.push(("scripts".to_string(), file_pairs(SCRIPTS)));
     } else if config_only {
-        items.push(("configs".to_string(), vec![
-            (PathBuf::from("caffeinate.plist"), "~/Library/LaunchAgents/caffeinate.plist".to_string()),
-            (PathBuf::from("server_setup.sh"), "~/server_setup.sh".to_string()),
-        ]));
+        items.push(("configs".to_string(), file_pairs(&[CAFFEINATE_PLIST, SERVER_SETUP])));
     }
 
     Ok(items)
 }
 
-async fn deploy_server_setup(client: &SshClient, verbose: bool) -> Result<()> {
+/// Bytes under `path`: the file's size, or the total of a directory's files.
+fn local_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+async fn deploy_server_setup(
+    client: &SshClient,
+    on_progress: &mut dyn FnMut(TransferProgress),
+    verbose: bool,
+) -> Result<()> {
     print_verbose("Deploying server setup script", verbose);
     
     let local_path = PathBuf::from("server_setup.sh");
@@ -153,14 +200,18 @@ async fn deploy_server_setup(client: &SshClient, verbose: bool) -> Result<()> {
         anyhow::bail!("server_setup.sh not found in current directory");
     }
     
-    client.copy_file(&local_path, "~/server_setup.sh")?;
-    client.execute_command("chmod +x ~/server_setup.sh")?;
+    client.copy_file_with_progress(&local_path, "~/server_setup.sh", on_progress)?;
     
-    print_verbose("Server setup script deployed and made executable", verbose);
+    print_verbose("Server setup script deployed", verbose);
     Ok(())
 }
 
-async fn deploy_scripts(client: &SshClient, files: &[(PathBuf, String)], verbose: bool) -> Result<()> {
+async fn deploy_scripts(
+    client: &SshClient,
+    files: &[(PathBuf, String)],
+    on_progress: &mut dyn FnMut(TransferProgress),
+    verbose: bool,
+) -> Result<()> {
     print_verbose("Deploying monitoring scripts", verbose);
     
     // Ensure scripts directory exists
@@ -172,13 +223,8 @@ async fn deploy_scripts(client: &SshClient, files: &[(PathBuf, String)], verbose
             continue;
         }
         
-        client.copy_file(local_path, remote_path)?;
-        
-        // Make scripts executable
-        if local_path.file_name().unwrap().to_str().unwrap() != "setup_aliases.sh" {
-            let chmod_cmd = format!("chmod +x {}", remote_path);
-            client.execute_command(&chmod_cmd)?;
-        }
+        // Executable bits carry over from the local copy
+        client.copy_file_with_progress(local_path, remote_path, on_progress)?;
         
         print_verbose(&format!("Deployed: {}", local_path.display()), verbose);
     }
@@ -186,7 +232,12 @@ async fn deploy_scripts(client: &SshClient, files: &[(PathBuf, String)], verbose
     Ok(())
 }
 
-async fn deploy_configs(client: &SshClient, files: &[(PathBuf, String)], verbose: bool) -> Result<()> {
+async fn deploy_configs(
+    client: &SshClient,
+    files: &[(PathBuf, String)],
+    on_progress: &mut dyn FnMut(TransferProgress),
+    verbose: bool,
+) -> Result<()> {
     print_verbose("Deploying configuration files", verbose);
     
     for (local_path, remote_path) in files {
@@ -197,18 +248,22 @@ async fn deploy_configs(client: &SshClient, files: &[(PathBuf, String)], verbose
         
         // Ensure parent directory exists
         if let Some(parent) = std::path::Path::new(remote_path).parent() {
-            let mkdir_cmd = format!("mkdir -p {}", parent.display());
-            client.execute_command(&mkdir_cmd)?;
+            client.ensure_directory(&parent.display().to_string())?;
         }
         
-        client.copy_file(local_path, remote_path)?;
+        client.copy_file_with_progress(local_path, remote_path, on_progress)?;
         print_verbose(&format!("Deployed: {}", local_path.display()), verbose);
     }
     
     Ok(())
 }
 
-async fn deploy_services(client: &SshClient, files: &[(PathBuf, String)], verbose: bool) -> Result<()> {
+async fn deploy_services(
+    client: &SshClient,
+    files: &[(PathBuf, String)],
+    on_progress: &mut dyn FnMut(TransferProgress),
+    verbose: bool,
+) -> Result<()> {
     print_verbose("Deploying service files", verbose);
     
     for (local_path, remote_path) in files {
@@ -218,9 +273,9 @@ async fn deploy_services(client: &SshClient, files: &[(PathBuf, String)], verbos
         }
         
         if local_path.is_dir() {
-            client.copy_directory(local_path, remote_path)?;
+            client.copy_directory_with_progress(local_path, remote_path, on_progress)?;
         } else {
-            client.copy_file(local_path, remote_path)?;
+            client.copy_file_with_progress(local_path, remote_path, on_progress)?;
         }
         
         print_verbose(&format!("Deployed: {}", local_path.display()), verbose);
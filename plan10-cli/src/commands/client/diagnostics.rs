@@ -1,7 +1,10 @@
 use anyhow::Result;
 use crate::Config;
 use crate::commands::utils::*;
-use crate::ssh::SshClient;
+use crate::commands::shared::readings::Source;
+use crate::commands::shared::wake_analysis;
+use crate::ssh::{host_port, SshClient};
+use chrono::Duration;
 
 
 pub async fn execute_diagnose(
@@ -14,11 +17,13 @@ pub async fn execute_diagnose(
 ) -> Result<()> {
     let server = config.resolve_server(&host)
         .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+    let server = &config.with_group_defaults(server);
 
     print_header(&format!("Diagnostics for: {}", host));
-    print_verbose(&format!("Connecting to {}@{}:{}", server.user, server.host, server.port), verbose);
+    print_verbose(&format!("Connecting to {}@{}", server.user, host_port(&server.host, server.port)), verbose);
 
     let client = SshClient::connect(server, config).await?;
+    print_verbose(&format!("Connected via {}", client.connection_summary()), verbose);
 
     // Test basic connectivity
     match client.test_connection() {
@@ -39,6 +44,7 @@ pub async fn execute_diagnose(
     // Run appropriate diagnostics based on flags
     if battery {
         run_battery_diagnostics(&client, verbose).await?;
+        return Ok(());
     } else if power {
         run_power_diagnostics(&client, verbose).await?;
     } else if fixes {
@@ -47,6 +53,10 @@ pub async fn execute_diagnose(
         run_basic_diagnostics(&client, verbose).await?;
     }
 
+    print_verbose("Reading the power management and unified logs", verbose);
+    let analysis = wake_analysis::analyse(&Source::Remote(&client), Duration::days(7));
+    wake_analysis::print_analysis(&analysis, "last 7 days");
+
     Ok(())
 }
 
@@ -100,11 +110,9 @@ async fn run_basic_diagnostics(client: &SshClient, _verbose: bool) -> Result<()>
 async fn run_battery_diagnostics(client: &SshClient, _verbose: bool) -> Result<()> {
     print_info("Running battery-focused diagnostics...");
 
-    let result = client.execute_command("~/scripts/battery -d")?;
-    if result.success {
-        println!("{}", result.stdout);
-    } else {
-        print_error(&format!("Battery diagnostics failed: {}", result.stderr));
+    let result = client.execute_command_streaming("~/scripts/battery -d", client.command_timeout(), print_output_line)?;
+    if !result.success {
+        print_remote_failure("Battery diagnostics failed", &result);
     }
 
     Ok(())
@@ -113,11 +121,9 @@ async fn run_battery_diagnostics(client: &SshClient, _verbose: bool) -> Result<(
 async fn run_power_diagnostics(client: &SshClient, _verbose: bool) -> Result<()> {
     print_info("Running power management diagnostics...");
 
-    let result = client.execute_command("~/scripts/power_diagnostics")?;
-    if result.success {
-        println!("{}", result.stdout);
-    } else {
-        print_error(&format!("Power diagnostics failed: {}", result.stderr));
+    let result = client.execute_command_streaming("~/scripts/power_diagnostics", client.command_timeout(), print_output_line)?;
+    if !result.success {
+        print_remote_failure("Power diagnostics failed", &result);
     }
 
     Ok(())
@@ -126,10 +132,8 @@ async fn run_power_diagnostics(client: &SshClient, _verbose: bool) -> Result<()>
 async fn run_comprehensive_diagnostics_with_fixes(client: &SshClient, _verbose: bool) -> Result<()> {
     print_info("Running comprehensive diagnostics with recommended fixes...");
 
-    let result = client.execute_command("~/scripts/power_diagnostics -f")?;
+    let result = client.execute_command_streaming("~/scripts/power_diagnostics -f", client.command_timeout(), print_output_line)?;
     if result.success {
-        println!("{}", result.stdout);
-        
         // Additional checks
         println!("\n🔍 Additional Checks:");
         
@@ -163,7 +167,7 @@ async fn run_comprehensive_diagnostics_with_fixes(client: &SshClient, _verbose:
         }
 
     } else {
-        print_error(&format!("Comprehensive diagnostics failed: {}", result.stderr));
+        print_remote_failure("Comprehensive diagnostics failed", &result);
     }
 
     Ok(())
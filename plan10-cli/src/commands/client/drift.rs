@@ -0,0 +1,129 @@
+use anyhow::Result;
+use crate::Config;
+use crate::commands::shared::power_history::remote_power_settings;
+use crate::commands::utils::*;
+use crate::ssh::{sha256_hex, SshClient};
+use colored::*;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::deploy::{CAFFEINATE_PLIST, SCRIPTS, SERVER_SETUP};
+use super::remote_config::print_settings_drift;
+
+/// Compare what is on each server with what `deploy` and `config push` would
+/// put there: the deployed files, the pmset power profile and the managed
+/// Plan 10 settings. Fails naming the servers that have drifted.
+pub async fn execute_config_diff(
+    host: Option<String>,
+    tag: Option<String>,
+    group: Option<String>,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    let hosts = target_hosts(host, tag, group, config)?;
+    let total = hosts.len();
+    let drifted = Mutex::new(Vec::new());
+
+    for_each_host(hosts, config, |host| {
+        let drifted = &drifted;
+        async move {
+            if diff_server(&host, config, verbose).await? {
+                drifted.lock().unwrap_or_else(|e| e.into_inner()).push(host);
+            }
+            Ok(())
+        }
+    }).await?;
+
+    let mut drifted = drifted.into_inner().unwrap_or_else(|e| e.into_inner());
+    if drifted.is_empty() {
+        print_success(&format!("No drift on {} server(s)", total));
+        return Ok(());
+    }
+    drifted.sort();
+    anyhow::bail!("{} of {} server(s) drifted: {}", drifted.len(), total, drifted.join(", "))
+}
+
+/// Print the differences for one server; `true` if there are any.
+async fn diff_server(host: &str, config: &Config, verbose: bool) -> Result<bool> {
+    let server = config.resolve_server(host)
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+    let client = SshClient::connect(server, config).await?;
+
+    print_header(&format!("Configuration Drift - {}", server.name));
+    let mut drifted = false;
+
+    println!("{}:", "Deployed Files".bold());
+    drifted |= diff_files(&client, verbose)?;
+
+    println!("\n{}:", "Power Profile".bold());
+    if config.power_profile.settings.is_empty() {
+        print_info("No power profile configured");
+    } else {
+        let (_, settings) = remote_power_settings(&client)?;
+        let drift = config.power_profile.drift(&settings);
+        if drift.is_empty() {
+            println!("  {} Matches the power profile", "✅".green());
+        }
+        for (key, expected, actual) in &drift {
+            println!(
+                "  {}: {} (server) → {} (profile)",
+                key.bold(),
+                actual.as_deref().unwrap_or("(unset)").dimmed(),
+                expected
+            );
+        }
+        drifted |= !drift.is_empty();
+    }
+
+    println!("\n{}:", "Managed Settings".bold());
+    drifted |= print_settings_drift(&client, config, server, verbose)?;
+
+    println!();
+    if drifted {
+        print_warning(&format!("{} has drifted since it was last deployed", server.name));
+    } else {
+        print_success(&format!("{} matches the local configuration", server.name));
+    }
+    Ok(drifted)
+}
+
+/// Compare the deploy assets in the current directory with the copies on
+/// the server, showing the lines a redeploy would change.
+fn diff_files(client: &SshClient, verbose: bool) -> Result<bool> {
+    let files: Vec<(&str, &str)> = [SERVER_SETUP, CAFFEINATE_PLIST].into_iter()
+        .chain(SCRIPTS.iter().copied())
+        .filter(|(local, _)| Path::new(local).is_file())
+        .collect();
+    if files.is_empty() {
+        print_warning("Deploy assets not found; run from the Plan 10 checkout to compare files");
+        return Ok(false);
+    }
+
+    let remote_paths: Vec<String> = files.iter().map(|(_, remote)| remote.to_string()).collect();
+    let remote_hashes = client.file_hashes(&remote_paths)?;
+
+    let mut drifted = false;
+    for ((local, remote), remote_hash) in files.iter().zip(remote_hashes) {
+        let contents = std::fs::read(local)?;
+        match remote_hash {
+            None => {
+                println!("  {} {} (not deployed)", "❌".red(), remote);
+                drifted = true;
+            }
+            Some(hash) if hash == sha256_hex(&contents) => {
+                print_verbose(&format!("{} is up to date", remote), verbose);
+            }
+            Some(_) => {
+                println!("  {} {}", "✏️".yellow(), remote);
+                if let Some(deployed) = client.read_file(remote)? {
+                    print_line_diff(&String::from_utf8_lossy(&deployed), &String::from_utf8_lossy(&contents));
+                }
+                drifted = true;
+            }
+        }
+    }
+    if !drifted {
+        println!("  {} {} file(s) match", "✅".green(), files.len());
+    }
+    Ok(drifted)
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use crate::Config;
+use crate::config::ServerDefinition;
+use crate::commands::utils::*;
+use crate::ssh::SshPool;
+use colored::*;
+
+pub async fn execute_exec(
+    command: String,
+    site: Option<String>,
+    tag: Option<String>,
+    group: Option<String>,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    print_header(&format!("Running: {}", command));
+
+    if let Some(group) = &group {
+        if !config.groups.contains_key(group) {
+            anyhow::bail!("Group '{}' not found", group);
+        }
+    }
+    let in_group = |name: &str| group.as_deref().map_or(true, |group| config.server_groups(name).contains(&group));
+
+    let selected = |s: &ServerDefinition| {
+        s.in_site(site.as_deref()) && in_group(&s.name) && config.with_group_defaults(s).has_tag(tag.as_deref())
+    };
+    note_disabled(&config.disabled_server_names(selected));
+    let mut servers: Vec<_> = config.servers.values()
+        .filter(|s| s.enabled && selected(s))
+        .collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if servers.is_empty() {
+        print_info("No enabled servers configured");
+        return Ok(());
+    }
+
+    print_verbose(&format!(
+        "Running on {} server(s), up to {} at a time",
+        servers.len(),
+        config.client.concurrent_operations
+    ), verbose);
+
+    let mut pool = SshPool::new(config.clone());
+    let results = pool.execute_on_all(&servers, &command).await;
+
+    let mut failed = 0;
+    for (name, result) in results {
+        match result {
+            Ok(output) => {
+                if output.success {
+                    println!("{} {}", "🟢".green(), name.bold());
+                } else {
+                    failed += 1;
+                    println!("{} {} (exit code {})", "🔴".red(), name.bold(), output.exit_code);
+                }
+                for line in output.stdout.lines() {
+                    println!("  {}", line);
+                }
+                for line in output.stderr.lines() {
+                    println!("  {}", line.dimmed());
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{} {}: {}", "🔴".red(), name.bold(), e);
+            }
+        }
+    }
+
+    println!();
+    if failed == 0 {
+        print_success(&format!("Succeeded on all {} server(s)", servers.len()));
+        Ok(())
+    } else {
+        anyhow::bail!("Failed on {} of {} server(s)", failed, servers.len())
+    }
+}
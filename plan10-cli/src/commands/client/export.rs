@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::commands::utils::*;
+use crate::config::ServerDefinition;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version of the inventory file layout written by `client export`.
+const INVENTORY_VERSION: u32 = 1;
+
+/// A shareable server inventory: the `servers` table without the settings
+/// that only make sense on one machine.
+#[derive(Debug, Serialize, Deserialize)]
+struct ServerInventory {
+    version: u32,
+    servers: BTreeMap<String, ServerDefinition>,
+}
+
+pub async fn export_servers(output: Option<String>, config: &Config, verbose: bool) -> Result<()> {
+    let inventory = ServerInventory {
+        version: INVENTORY_VERSION,
+        servers: config.servers.iter()
+            .map(|(name, server)| (name.clone(), shareable(server)))
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&inventory)?;
+
+    match output {
+        Some(path) => {
+            let path = shellexpand::tilde(&path).to_string();
+            std::fs::write(&path, json + "\n")
+                .with_context(|| format!("Failed to write {}", path))?;
+            print_success(&format!("Exported {} server(s) to {}", inventory.servers.len(), path));
+        }
+        None => {
+            print_verbose(&format!("Exporting {} server(s)", inventory.servers.len()), verbose);
+            println!("{}", json);
+        }
+    }
+    Ok(())
+}
+
+pub async fn import_servers(file: String, dry_run: bool, config: &Config, verbose: bool) -> Result<()> {
+    print_header("Import Server Inventory");
+
+    let path = shellexpand::tilde(&file).to_string();
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path))?;
+    let inventory: ServerInventory = serde_json::from_str(&content)
+        .with_context(|| format!("{} is not a plan10 server inventory", path))?;
+    if inventory.version > INVENTORY_VERSION {
+        anyhow::bail!("{} was written by a newer plan10 (inventory version {})", path, inventory.version);
+    }
+
+    let mut new_config = config.clone();
+    let (mut added, mut updated) = (0, 0);
+    for (name, mut server) in inventory.servers {
+        server.name = name.clone();
+        match config.servers.get(&name) {
+            None => {
+                println!("  {} {}", "➕".green(), name);
+                new_config.servers.insert(name, server);
+                added += 1;
+            }
+            Some(existing) => {
+                keep_local_settings(&mut server, existing);
+                if serde_json::to_value(&server)? == serde_json::to_value(existing)? {
+                    print_verbose(&format!("{}: unchanged", name), verbose);
+                    continue;
+                }
+                println!("  {} {}", "✏️".yellow(), name);
+                new_config.servers.insert(name, server);
+                updated += 1;
+            }
+        }
+    }
+
+    if added + updated == 0 {
+        print_info("Configuration already matches the inventory");
+        return Ok(());
+    }
+    if dry_run {
+        print_info(&format!("Dry run - would add {} and update {} server(s)", added, updated));
+        return Ok(());
+    }
+
+    new_config.validate()?;
+    new_config.save(None)?;
+    print_success(&format!("Added {} and updated {} server(s)", added, updated));
+    Ok(())
+}
+
+/// `server` without its key path, agent socket and last-seen time, which
+/// belong to the machine the inventory was exported from.
+fn shareable(server: &ServerDefinition) -> ServerDefinition {
+    let mut server = server.clone();
+    server.ssh_key = None;
+    server.ssh_options.identity_agent = None;
+    server.last_seen = None;
+    server
+}
+
+fn keep_local_settings(server: &mut ServerDefinition, existing: &ServerDefinition) {
+    server.ssh_key = existing.ssh_key.clone();
+    server.ssh_options.identity_agent = existing.ssh_options.identity_agent.clone();
+    server.last_seen = existing.last_seen;
+}
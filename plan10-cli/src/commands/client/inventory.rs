@@ -0,0 +1,322 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::commands::utils::*;
+use crate::config::{AddressFamily, ServerDefinition};
+use colored::*;
+use std::fs;
+
+/// Tag carried by every server that appears in an MDM inventory.
+pub const MDM_TAG: &str = "mdm";
+/// Tag carried by imported servers until Plan 10 has been deployed to them.
+pub const UNDEPLOYED_TAG: &str = "undeployed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InventoryFormat {
+    /// Detect the format from the CSV header
+    Auto,
+    /// Jamf Pro computer inventory export
+    Jamf,
+    /// Mosyle Manager device export
+    Mosyle,
+}
+
+impl InventoryFormat {
+    fn source_tag(&self) -> &'static str {
+        match self {
+            InventoryFormat::Auto => "mdm:unknown",
+            InventoryFormat::Jamf => "mdm:jamf",
+            InventoryFormat::Mosyle => "mdm:mosyle",
+        }
+    }
+
+    fn detect(header: &[String]) -> Self {
+        let has = |name: &str| header.iter().any(|h| h == name);
+        if has("computer name") || has("jamf pro computer id") {
+            InventoryFormat::Jamf
+        } else if has("device name") || has("deviceudid") {
+            InventoryFormat::Mosyle
+        } else {
+            InventoryFormat::Auto
+        }
+    }
+}
+
+/// Column names (lowercased) that may hold each field, in order of preference.
+const NAME_COLUMNS: &[&str] = &["computer name", "device name", "name"];
+const HOST_COLUMNS: &[&str] = &[
+    "ip address",
+    "last reported ip address",
+    "reported ip address",
+    "last ip address",
+    "hostname",
+    "host name",
+];
+const SERIAL_COLUMNS: &[&str] = &["serial number", "serial_number", "serial"];
+const SITE_COLUMNS: &[&str] = &["site", "building", "location"];
+
+#[derive(Debug)]
+struct InventoryDevice {
+    name: String,
+    host: Option<String>,
+    serial: Option<String>,
+    site: Option<String>,
+}
+
+pub async fn import_inventory(
+    file: String,
+    format: InventoryFormat,
+    user: String,
+    port: u16,
+    dry_run: bool,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    print_header("MDM Inventory Import");
+
+    let path = shellexpand::tilde(&file).into_owned();
+    let content = fs::read_to_string(&path)
+        .context(format!("Failed to read inventory export: {}", path))?;
+
+    let (format, devices) = parse_inventory(&content, format)?;
+    print_verbose(&format!("Read {} device(s) from {}", devices.len(), path), verbose);
+
+    if devices.is_empty() {
+        print_info("Inventory export contains no devices");
+        return Ok(());
+    }
+
+    let mut new_config = config.clone();
+    let mut added = Vec::new();
+    let mut tagged = Vec::new();
+    let mut skipped = Vec::new();
+    let mut seen = Vec::new();
+
+    for device in &devices {
+        let name = server_name(&device.name);
+        let existing = new_config.servers.values()
+            .find(|s| s.name == name || device.host.as_deref().is_some_and(|h| s.host == h || s.addresses.iter().any(|a| a == h)))
+            .map(|s| s.name.clone());
+
+        if let Some(existing) = existing {
+            let server = new_config.servers.get_mut(&existing).unwrap();
+            let mut changed = add_tag(server, MDM_TAG);
+            changed |= add_tag(server, format.source_tag());
+            if server.site.is_none() && device.site.is_some() {
+                server.site = device.site.clone();
+                changed = true;
+            }
+            if let Some(host) = &device.host {
+                if !server.candidate_hosts().contains(host) {
+                    print_verbose(&format!("{}: MDM reports address {} which is not configured", existing, host), verbose);
+                }
+            }
+            if changed {
+                tagged.push(existing.clone());
+            }
+            seen.push(existing);
+            continue;
+        }
+
+        let Some(host) = device.host.clone() else {
+            skipped.push(format!("{} (no IP address or hostname in export)", device.name));
+            continue;
+        };
+
+        let mut tags = vec![MDM_TAG.to_string(), format.source_tag().to_string(), UNDEPLOYED_TAG.to_string()];
+        if let Some(serial) = &device.serial {
+            tags.push(format!("serial:{}", serial));
+        }
+
+        new_config.servers.insert(name.clone(), ServerDefinition {
+            name: name.clone(),
+            host,
+            user: user.clone(),
+            port,
+            ssh_key: None,
+            tags,
+            enabled: true,
+            last_seen: None,
+            site: device.site.clone(),
+            addresses: Vec::new(),
+            address_family: AddressFamily::Auto,
+            notes: None,
+            runbook: None,
+            jump_host: None,
+            agent_forwarding: false,
+            ssh_options: Default::default(),
+            thresholds: Default::default(),
+        });
+        added.push(name.clone());
+        seen.push(name);
+    }
+
+    for name in &added {
+        println!("  {} {} {}", "➕".green(), name, "(new, not yet deployed)".dimmed());
+    }
+    for name in &tagged {
+        println!("  {} {} {}", "🏷️".blue(), name, "(marked as managed)".dimmed());
+    }
+    for device in &skipped {
+        println!("  {} {}", "⏭️".yellow(), device);
+    }
+
+    // Managed devices that still lack a Plan 10 deployment
+    let mut undeployed: Vec<_> = new_config.servers.values()
+        .filter(|s| has_tag(s, MDM_TAG) && has_tag(s, UNDEPLOYED_TAG))
+        .map(|s| s.name.clone())
+        .collect();
+    undeployed.sort();
+
+    // Servers previously imported that the MDM no longer knows about
+    let mut missing: Vec<_> = new_config.servers.values()
+        .filter(|s| has_tag(s, format.source_tag()) && !seen.contains(&s.name))
+        .map(|s| s.name.clone())
+        .collect();
+    missing.sort();
+
+    println!();
+    println!("Devices in inventory: {}", devices.len());
+    println!("Added: {}  Tagged: {}  Skipped: {}", added.len(), tagged.len(), skipped.len());
+
+    if !undeployed.is_empty() {
+        print_warning(&format!("{} managed device(s) missing Plan 10 deployment: {}", undeployed.len(), undeployed.join(", ")));
+        println!("Deploy with: plan10 client deploy --host <name> --all");
+    }
+
+    if !missing.is_empty() {
+        print_warning(&format!("No longer in the MDM inventory: {}", missing.join(", ")));
+    }
+
+    if dry_run {
+        print_info("Dry run - configuration not saved");
+        return Ok(());
+    }
+
+    if added.is_empty() && tagged.is_empty() {
+        print_success("Configuration already in sync with inventory");
+        return Ok(());
+    }
+
+    new_config.save(None)?;
+    print_success("Configuration updated from MDM inventory");
+    Ok(())
+}
+
+fn parse_inventory(content: &str, format: InventoryFormat) -> Result<(InventoryFormat, Vec<InventoryDevice>)> {
+    let mut rows = parse_csv(content).into_iter();
+    let header: Vec<String> = rows.next()
+        .context("Inventory export is empty")?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+
+    let format = match format {
+        InventoryFormat::Auto => InventoryFormat::detect(&header),
+        format => format,
+    };
+
+    let column = |candidates: &[&str]| candidates.iter().find_map(|c| header.iter().position(|h| h == c));
+    let name_col = column(NAME_COLUMNS)
+        .context("Inventory export has no computer/device name column")?;
+    let host_col = column(HOST_COLUMNS);
+    let serial_col = column(SERIAL_COLUMNS);
+    let site_col = column(SITE_COLUMNS);
+
+    let field = |row: &[String], col: Option<usize>| {
+        col.and_then(|i| row.get(i))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let devices = rows
+        .filter_map(|row| {
+            let name = field(&row, Some(name_col))?;
+            Some(InventoryDevice {
+                name,
+                host: field(&row, host_col),
+                serial: field(&row, serial_col),
+                site: field(&row, site_col),
+            })
+        })
+        .collect();
+
+    Ok((format, devices))
+}
+
+/// Minimal RFC 4180 reader: quoted fields, escaped quotes and embedded newlines.
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            c => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Turn a display name, such as an MDM device name ("Ann's MacBook Pro") or an
+/// SSH host alias, into a config key ("anns-macbook-pro").
+pub fn server_name(display_name: &str) -> String {
+    let mut name = String::new();
+    for c in display_name.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_' || c == '.') && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    name.trim_matches('-').to_string()
+}
+
+fn has_tag(server: &ServerDefinition, tag: &str) -> bool {
+    server.tags.iter().any(|t| t == tag)
+}
+
+fn add_tag(server: &mut ServerDefinition, tag: &str) -> bool {
+    if has_tag(server, tag) {
+        return false;
+    }
+    server.tags.push(tag.to_string());
+    true
+}
+
+/// Clear the undeployed marker once Plan 10 has been deployed to a server.
+pub fn mark_deployed(config: &Config, name: &str) -> Result<()> {
+    let Some(server) = config.get_server(name) else {
+        return Ok(());
+    };
+    if !has_tag(server, UNDEPLOYED_TAG) {
+        return Ok(());
+    }
+
+    let mut new_config = config.clone();
+    if let Some(server) = new_config.servers.get_mut(name) {
+        server.tags.retain(|t| t != UNDEPLOYED_TAG);
+    }
+    new_config.save(None)
+}
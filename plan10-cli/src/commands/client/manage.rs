@@ -1,9 +1,13 @@
 use anyhow::Result;
 use crate::{ManageActions, Config};
 use crate::commands::utils::*;
-use crate::ssh::SshClient;
+use crate::ssh::{host_port, with_reconnect, SshClient};
 use colored::*;
 
+/// `server_setup.sh` may install tools and wait on `pmset`, so it gets far
+/// longer than `ssh.command_timeout`.
+const SETUP_TIMEOUT_SECS: u64 = 30 * 60;
+
 pub async fn execute_manage(
     host: String,
     action: ManageActions,
@@ -12,11 +16,13 @@ pub async fn execute_manage(
 ) -> Result<()> {
     let server = config.resolve_server(&host)
         .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+    let server = &config.with_group_defaults(server);
 
     print_header(&format!("Managing Server: {}", host));
-    print_verbose(&format!("Connecting to {}@{}:{}", server.user, server.host, server.port), verbose);
+    print_verbose(&format!("Connecting to {}@{}", server.user, host_port(&server.host, server.port)), verbose);
 
-    let client = SshClient::connect(server, config).await?;
+    let mut client = SshClient::connect(server, config).await?;
+    print_verbose(&format!("Connected via {}", client.connection_summary()), verbose);
 
     match action {
         ManageActions::Start => {
@@ -25,7 +31,7 @@ pub async fn execute_manage(
             if result.success {
                 print_success("Services started successfully");
             } else {
-                print_error(&format!("Failed to start services: {}", result.stderr));
+                print_remote_failure("Failed to start services", &result);
             }
         }
         ManageActions::Stop => {
@@ -45,22 +51,29 @@ pub async fn execute_manage(
             if result.success {
                 print_success("Services restarted successfully");
             } else {
-                print_error(&format!("Failed to restart services: {}", result.stderr));
+                print_remote_failure("Failed to restart services", &result);
             }
         }
         ManageActions::Update => {
             print_info("Updating Plan 10 installation...");
             // Re-deploy the latest files
             crate::commands::client::deploy::execute_deploy(
-                host.clone(), None, 22, true, false, false, config, verbose
+                host.clone(), None, None, true, false, false, config, verbose
             ).await?;
             print_success("Plan 10 updated successfully");
         }
         ManageActions::Status => {
             print_info("Checking server status...");
             
+            let (caffeinate_result, power_result, uptime_result) = with_reconnect(&mut client, config, |client| {
+                Ok((
+                    client.execute_command("pgrep -x caffeinate")?,
+                    client.execute_command("pmset -g batt | head -1")?,
+                    client.execute_command("uptime")?,
+                ))
+            }).await?;
+
             // Check caffeinate status
-            let caffeinate_result = client.execute_command("pgrep -x caffeinate")?;
             if caffeinate_result.success && !caffeinate_result.stdout.trim().is_empty() {
                 print_success(&format!("Caffeinate running (PID: {})", caffeinate_result.stdout.trim()));
             } else {
@@ -68,7 +81,6 @@ pub async fn execute_manage(
             }
             
             // Check power source
-            let power_result = client.execute_command("pmset -g batt | head -1")?;
             if power_result.success {
                 let power_info = power_result.stdout.trim();
                 if power_info.contains("AC Power") {
@@ -81,19 +93,17 @@ pub async fn execute_manage(
             }
             
             // Check system uptime
-            let uptime_result = client.execute_command("uptime")?;
             if uptime_result.success {
                 print_info(&format!("Uptime: {}", uptime_result.stdout.trim()));
             }
         }
         ManageActions::Configure => {
             print_info("Running server configuration...");
-            let result = client.execute_command("sudo ./server_setup.sh")?;
+            let result = client.execute_sudo_streaming("./server_setup.sh", SETUP_TIMEOUT_SECS, print_output_line)?;
             if result.success {
                 print_success("Server configuration completed");
-                println!("{}", result.stdout);
             } else {
-                print_error(&format!("Configuration failed: {}", result.stderr));
+                print_remote_failure("Configuration failed", &result);
             }
         }
     }
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use crate::{ClientCommands, ManageActions, Config};
+use crate::{ClientCommands, ManageActions, Config, OutputFormat, RemoteConfigCommands, ReportCommands};
 use crate::commands::utils::*;
 use crate::ssh::{SshClient, deploy_files, test_connectivity};
 use colored::*;
@@ -9,44 +9,126 @@ pub mod deploy;
 pub mod manage;
 pub mod diagnostics;
 pub mod servers;
+pub mod check;
+pub mod exec;
+pub mod inventory;
+pub mod remote_config;
+pub mod tunnel;
+pub mod shell;
+pub mod export;
+pub mod drift;
+pub mod ssh_import;
+pub mod ping;
+pub mod availability;
 
-pub async fn execute(cmd: ClientCommands, config: &Config, verbose: bool) -> Result<()> {
+pub async fn execute(cmd: ClientCommands, config: &Config, output: OutputFormat, verbose: bool) -> Result<()> {
     match cmd {
         ClientCommands::Deploy { 
             host, 
+            tag,
+            group,
             user, 
             port, 
             all, 
             scripts_only, 
             config_only 
         } => {
-            deploy::execute_deploy(host, user, port, all, scripts_only, config_only, config, verbose).await
+            for_each_host(target_hosts(host, tag, group, config)?, config, |host| {
+                deploy::execute_deploy(host, user.clone(), port, all, scripts_only, config_only, config, verbose)
+            }).await
         }
-        ClientCommands::Manage { host, action } => {
-            manage::execute_manage(host, action, config, verbose).await
+        ClientCommands::Manage { host, tag, group, action } => {
+            for_each_host(target_hosts(host, tag, group, config)?, config, |host| {
+                manage::execute_manage(host, action, config, verbose)
+            }).await
         }
         ClientCommands::Diagnose { 
             host, 
+            tag,
+            group,
             battery, 
             power, 
             fixes 
         } => {
-            diagnostics::execute_diagnose(host, battery, power, fixes, config, verbose).await
+            for_each_host(target_hosts(host, tag, group, config)?, config, |host| {
+                diagnostics::execute_diagnose(host, battery, power, fixes, config, verbose)
+            }).await
         }
-        ClientCommands::List { detailed } => {
-            servers::list_servers(config, detailed, verbose).await
+        ClientCommands::List { detailed, site } => {
+            servers::list_servers(config, detailed, site, output, verbose).await
         }
         ClientCommands::Add { 
             name, 
             host, 
             user, 
-            port 
+            port,
+            ssh_key,
+            site,
+            addresses,
+            family,
+            notes,
+            runbook,
+            jump_host,
+            forward_agent,
+            tags,
+            group,
         } => {
-            servers::add_server(name, host, user, port, config, verbose).await
+            servers::add_server(name, host, user, port, ssh_key, site, addresses, family, notes, runbook, jump_host, forward_agent, tags, group, config, verbose).await
         }
         ClientCommands::Remove { name } => {
             servers::remove_server(name, config, verbose).await
         }
+        ClientCommands::Rename { old, new } => {
+            servers::rename_server(old, new, config, verbose).await
+        }
+        ClientCommands::Enable { name } => {
+            servers::set_server_enabled(name, true, config).await
+        }
+        ClientCommands::Disable { name } => {
+            servers::set_server_enabled(name, false, config).await
+        }
+        ClientCommands::Notes { name, text, runbook, clear } => {
+            servers::server_notes(name, text, runbook, clear, config).await
+        }
+        ClientCommands::ImportMdm { file, format, user, port, dry_run } => {
+            inventory::import_inventory(file, format, user, port, dry_run, config, verbose).await
+        }
+        ClientCommands::Export { file } => {
+            export::export_servers(file, config, verbose).await
+        }
+        ClientCommands::Import { file: Some(file), dry_run, .. } => {
+            export::import_servers(file, dry_run, config, verbose).await
+        }
+        ClientCommands::Import { file: None, from_ssh_config, from_known_hosts, all, user, tags, dry_run } => {
+            ssh_import::import_ssh_hosts(from_ssh_config, from_known_hosts, all, user, tags, dry_run, config, verbose).await
+        }
+        ClientCommands::Check { dry_run, site } => {
+            check::execute_check(dry_run, site, config, verbose).await
+        }
+        ClientCommands::Exec { command, site, tag, group } => {
+            exec::execute_exec(command, site, tag, group, config, verbose).await
+        }
+        ClientCommands::Ping { host, tag, group, all } => {
+            ping::execute_ping(host, tag, group, all, config, verbose).await
+        }
+        ClientCommands::Report(ReportCommands::Availability { month, host }) => {
+            availability::execute_report(month, host, config, output, verbose).await
+        }
+        ClientCommands::ConfigDiff { host, tag, group } => {
+            drift::execute_config_diff(host, tag, group, config, verbose).await
+        }
+        ClientCommands::Config(RemoteConfigCommands::Push { host, force, dry_run }) => {
+            remote_config::execute_push(host, force, dry_run, config, verbose).await
+        }
+        ClientCommands::Config(RemoteConfigCommands::Pull { host, adopt }) => {
+            remote_config::execute_pull(host, adopt, config, verbose).await
+        }
+        ClientCommands::Tunnel(cmd) => {
+            tunnel::execute(cmd, config, verbose).await
+        }
+        ClientCommands::Shell { host, mosh, no_mosh } => {
+            shell::open_shell(host, mosh, no_mosh, config, verbose).await
+        }
     }
 }
 
@@ -0,0 +1,67 @@
+use anyhow::Result;
+use crate::Config;
+use crate::commands::utils::*;
+use crate::commands::client::availability;
+use crate::commands::shared::readings::{self, Source};
+use crate::ssh::SshClient;
+use colored::*;
+use std::time::Instant;
+
+/// Connect to each server and run a no-op command, printing the round trip.
+/// Servers that answer get their `last_seen` updated when the command ends.
+pub async fn execute_ping(
+    host: Option<String>,
+    tag: Option<String>,
+    group: Option<String>,
+    all: bool,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    print_header("Ping");
+    let hosts = if all {
+        note_disabled(&config.disabled_server_names(|_| true));
+        config.enabled_server_names()
+    } else if host.is_none() && tag.is_none() && group.is_none() {
+        let default = config.client.default_server.clone()
+            .ok_or_else(|| anyhow::anyhow!("No default server; pass --host, --tag, --group or --all"))?;
+        vec![default]
+    } else {
+        target_hosts(host, tag, group, config)?
+    };
+    if hosts.is_empty() {
+        print_info("No enabled servers configured");
+        return Ok(());
+    }
+
+    let total = hosts.len();
+    for_each_host(hosts, config, |host| async move {
+        let server = config.resolve_server(&host)
+            .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+
+        let started = Instant::now();
+        let client = match SshClient::connect(server, config).await {
+            Ok(client) => client,
+            Err(e) => {
+                availability::record_poll(config, &server.name, false, None, verbose);
+                return Err(e);
+            }
+        };
+        let connected = started.elapsed();
+        client.execute_command("true")?;
+        let round_trip = started.elapsed() - connected;
+        availability::record_poll(config, &server.name, true, readings::boot_time(&Source::Remote(&client)), verbose);
+
+        println!(
+            "  {} {} {}",
+            "🟢".green(),
+            server.name.bold(),
+            format!("connect {} ms, command {} ms", connected.as_millis(), round_trip.as_millis()).dimmed()
+        );
+        print_verbose(&client.connection_summary(), verbose);
+        Ok(())
+    }).await?;
+
+    println!();
+    print_success(&format!("{} server(s) answered", total));
+    Ok(())
+}
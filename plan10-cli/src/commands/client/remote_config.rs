@@ -0,0 +1,274 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::commands::utils::*;
+use crate::config::{ManagedStamp, NotificationConfig, PowerProfile, ServerConfig, ServerDefinition};
+use crate::ssh::{shell_quote, SshClient};
+use colored::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Where `plan10` on a macOS server reads its configuration from.
+const REMOTE_CONFIG_PATH: &str = "~/Library/Application Support/plan10/config.toml";
+
+/// The parts of a server's configuration that are owned by the client:
+/// thresholds and services, alert routing and the power profile. Everything
+/// else (SSH settings, the server's own fleet, its name) stays local.
+#[derive(Serialize)]
+struct ManagedSettings {
+    server: ServerConfig,
+    notifications: NotificationConfig,
+    power_profile: PowerProfile,
+}
+
+impl ManagedSettings {
+    fn from_config(config: &Config) -> Self {
+        let mut server = config.server.clone();
+        server.name = String::new();
+        Self {
+            server,
+            notifications: config.notifications.clone(),
+            power_profile: config.power_profile.clone(),
+        }
+    }
+
+    /// The settings meant for `server`, with its own monitoring thresholds.
+    fn for_server(mut self, config: &Config, server: &ServerDefinition) -> Self {
+        let thresholds = config.thresholds(Some(server));
+        self.server.temp_threshold = thresholds.temp;
+        self.server.battery_warning_level = thresholds.battery_warning_level;
+        self.server.disk_warning_level = thresholds.disk_warning_level;
+        self.server.cpu_warning_level = thresholds.cpu_warning_level;
+        self
+    }
+
+    fn apply_to(&self, config: &mut Config) {
+        let name = std::mem::take(&mut config.server.name);
+        config.server = self.server.clone();
+        config.server.name = name;
+        config.notifications = self.notifications.clone();
+        config.power_profile = self.power_profile.clone();
+    }
+
+    /// FNV-1a over the serialized settings; stable across plan10 versions.
+    fn checksum(&self) -> Result<String> {
+        let serialized = toml::to_string(self)?;
+        let hash = serialized.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Ok(format!("{:016x}", hash))
+    }
+
+    /// Flatten to `section.key` → value for diffing.
+    fn flatten(&self) -> Result<BTreeMap<String, String>> {
+        let mut values = BTreeMap::new();
+        if let toml::Value::Table(sections) = toml::Value::try_from(self)? {
+            for (section, table) in sections {
+                let toml::Value::Table(table) = table else {
+                    continue;
+                };
+                for (key, value) in table {
+                    values.insert(format!("{}.{}", section, key), value.to_string());
+                }
+            }
+        }
+        values.remove("server.name");
+        Ok(values)
+    }
+}
+
+pub async fn execute_push(host: String, force: bool, dry_run: bool, config: &Config, verbose: bool) -> Result<()> {
+    let server = config.resolve_server(&host)
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+    print_header(&format!("Pushing Configuration to {}", server.name));
+
+    let client = SshClient::connect(server, config).await?;
+    let remote = fetch_remote_config(&client, verbose)?;
+
+    let canonical = ManagedSettings::from_config(config).for_server(config, server);
+    let canonical_checksum = canonical.checksum()?;
+
+    let mut remote_config = match remote {
+        Some(remote) => remote,
+        None => {
+            print_info("No configuration on the server yet, creating one");
+            let mut fresh = Config::default();
+            fresh.server.name = server.name.clone();
+            fresh
+        }
+    };
+
+    let current = ManagedSettings::from_config(&remote_config);
+    if let Some(stamp) = &remote_config.managed {
+        if current.checksum()? == canonical_checksum {
+            print_success(&format!("Already up to date (version {})", stamp.version));
+            return Ok(());
+        }
+
+        if current.checksum()? != stamp.checksum {
+            print_warning(&format!(
+                "Settings were changed on the server after version {} was pushed",
+                stamp.version
+            ));
+            if !force && !dry_run {
+                print_diff(&current.flatten()?, &canonical.flatten()?, "server", "client");
+                anyhow::bail!("Refusing to overwrite local changes on {}; use --force or adopt them with `client config pull --adopt`", server.name);
+            }
+        }
+    }
+
+    println!("{}:", "Changes".bold());
+    print_diff(&current.flatten()?, &canonical.flatten()?, "server", "client");
+
+    let version = remote_config.managed.as_ref().map_or(1, |stamp| stamp.version + 1);
+    if dry_run {
+        print_info(&format!("Dry run: would push version {}", version));
+        return Ok(());
+    }
+
+    canonical.apply_to(&mut remote_config);
+    remote_config.managed = Some(ManagedStamp {
+        version,
+        checksum: canonical_checksum,
+        pushed_at: chrono::Utc::now(),
+        pushed_from: hostname::get().unwrap_or_default().to_string_lossy().to_string(),
+    });
+    write_remote_config(&client, &remote_config, verbose)?;
+
+    print_success(&format!("Pushed configuration version {} to {}", version, server.name));
+    Ok(())
+}
+
+pub async fn execute_pull(host: String, adopt: bool, config: &Config, verbose: bool) -> Result<()> {
+    let server = config.resolve_server(&host)
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+    print_header(&format!("Configuration on {}", server.name));
+
+    let client = SshClient::connect(server, config).await?;
+    let remote_config = fetch_remote_config(&client, verbose)?
+        .ok_or_else(|| anyhow::anyhow!("No Plan 10 configuration found on {}", server.name))?;
+
+    let remote = ManagedSettings::from_config(&remote_config);
+    let canonical = ManagedSettings::from_config(config).for_server(config, server);
+
+    match &remote_config.managed {
+        Some(stamp) => {
+            println!(
+                "  Version {} pushed from {} at {}",
+                stamp.version,
+                stamp.pushed_from,
+                stamp.pushed_at.format("%Y-%m-%d %H:%M UTC")
+            );
+            if remote.checksum()? == stamp.checksum {
+                print_success("No changes on the server since it was pushed");
+            } else {
+                print_warning("Settings were changed on the server since they were pushed");
+            }
+        }
+        None => print_info("Server settings are not centrally managed yet"),
+    }
+
+    if remote.checksum()? == canonical.checksum()? {
+        print_success("Server matches the client configuration");
+        return Ok(());
+    }
+
+    println!("\n{}:", "Differences".bold());
+    print_diff(&remote.flatten()?, &canonical.flatten()?, "server", "client");
+
+    if adopt {
+        let mut updated = config.clone();
+        remote.apply_to(&mut updated);
+        updated.save(None)?;
+        print_success(&format!("Adopted settings from {} as the client configuration", server.name));
+        print_info("Run `plan10 client config push` to roll them out to other servers");
+    } else {
+        print_info("Run `plan10 client config push` to overwrite, or pull with --adopt to keep the server's settings");
+    }
+
+    Ok(())
+}
+
+/// Print how the server's managed settings differ from the client's, if
+/// they do. Returns whether they differ; a server without a Plan 10 config
+/// has nothing to compare.
+pub fn print_settings_drift(client: &SshClient, config: &Config, server: &ServerDefinition, verbose: bool) -> Result<bool> {
+    let Some(remote_config) = fetch_remote_config(client, verbose)? else {
+        print_info("No Plan 10 configuration on the server");
+        return Ok(false);
+    };
+
+    let remote = ManagedSettings::from_config(&remote_config);
+    let canonical = ManagedSettings::from_config(config).for_server(config, server);
+    if remote.checksum()? == canonical.checksum()? {
+        println!("  {} Matches the client configuration", "✅".green());
+        return Ok(false);
+    }
+
+    print_diff(&remote.flatten()?, &canonical.flatten()?, "server", "client");
+    Ok(true)
+}
+
+fn fetch_remote_config(client: &SshClient, verbose: bool) -> Result<Option<Config>> {
+    let path = client.resolve_remote_path(REMOTE_CONFIG_PATH)?;
+    print_verbose(&format!("Reading {}", path), verbose);
+
+    let result = client.execute_command(&format!("cat {}", shell_quote(&path)))?;
+    if !result.success {
+        if client.file_exists(&path)? {
+            anyhow::bail!("Failed to read {}: {}", path, result.stderr.trim());
+        }
+        return Ok(None);
+    }
+
+    let config = toml::from_str(&result.stdout)
+        .context(format!("Failed to parse {} on the server", path))?;
+    Ok(Some(config))
+}
+
+/// Upload next to the live file and move it into place, keeping the previous
+/// version as `config.toml.bak`.
+fn write_remote_config(client: &SshClient, remote_config: &Config, verbose: bool) -> Result<()> {
+    let path = client.resolve_remote_path(REMOTE_CONFIG_PATH)?;
+    let staged = format!("{}.new", path);
+    let directory = std::path::Path::new(&path)
+        .parent()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    client.ensure_directory(&directory)?;
+
+    let content = toml::to_string_pretty(remote_config)
+        .context("Failed to serialize config")?;
+    let temp = tempfile::NamedTempFile::new()?;
+    std::fs::write(temp.path(), content)?;
+    client.copy_file(temp.path(), &staged)?;
+
+    print_verbose(&format!("Installing {}", path), verbose);
+    client.execute_command(&format!(
+        "{{ [ ! -f {path} ] || cp {path} {path}.bak; }} && mv {staged} {path}",
+        path = shell_quote(&path),
+        staged = shell_quote(&staged),
+    ))?.ensure_success()?;
+
+    Ok(())
+}
+
+fn print_diff(left: &BTreeMap<String, String>, right: &BTreeMap<String, String>, left_name: &str, right_name: &str) {
+    let mut keys: Vec<&String> = left.keys().chain(right.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let (old, new) = (left.get(key), right.get(key));
+        if old != new {
+            println!(
+                "  {}: {} ({}) → {} ({})",
+                key.bold(),
+                old.map_or("(unset)", String::as_str).dimmed(),
+                left_name,
+                new.map_or("(unset)", String::as_str),
+                right_name
+            );
+        }
+    }
+}
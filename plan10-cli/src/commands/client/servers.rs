@@ -1,12 +1,28 @@
 use anyhow::Result;
-use crate::Config;
+use crate::{Config, OutputFormat};
 use crate::commands::utils::*;
-use crate::config::ServerDefinition;
-use crate::ssh::test_connectivity;
+use crate::config::{AddressFamily, ServerDefinition};
+use crate::history::History;
+use crate::ssh::{effective_server, probe_addresses, test_connectivity};
+use crate::ssh_config::SshHostConfig;
 use colored::*;
 use chrono::Utc;
 
-pub async fn list_servers(config: &Config, detailed: bool, verbose: bool) -> Result<()> {
+pub async fn list_servers(
+    config: &Config,
+    detailed: bool,
+    site: Option<String>,
+    output: OutputFormat,
+    verbose: bool,
+) -> Result<()> {
+    if output == OutputFormat::Json {
+        let mut servers: Vec<_> = config.servers.values()
+            .filter(|server| server.in_site(site.as_deref()))
+            .collect();
+        servers.sort_by(|a, b| a.name.cmp(&b.name));
+        return print_json(&servers);
+    }
+
     print_header("Configured Servers");
     
     if config.servers.is_empty() {
@@ -15,12 +31,19 @@ pub async fn list_servers(config: &Config, detailed: bool, verbose: bool) -> Res
         return Ok(());
     }
 
-    let mut servers: Vec<_> = config.servers.iter().collect();
+    let mut servers: Vec<_> = config.servers.iter()
+        .filter(|(_, server)| server.in_site(site.as_deref()))
+        .collect();
     servers.sort_by_key(|(name, _)| *name);
 
+    if servers.is_empty() {
+        print_info(&format!("No servers at site '{}'", site.unwrap_or_default()));
+        return Ok(());
+    }
+
     if detailed {
         for (name, server) in servers {
-            print_server_detailed(name, server, verbose).await;
+            print_server_detailed(name, server, config, verbose).await;
             println!();
         }
     } else {
@@ -33,8 +56,18 @@ pub async fn list_servers(config: &Config, detailed: bool, verbose: bool) -> Res
 pub async fn add_server(
     name: String,
     host: String,
-    user: String,
-    port: u16,
+    user: Option<String>,
+    port: Option<u16>,
+    ssh_key: Option<String>,
+    site: Option<String>,
+    addresses: Vec<String>,
+    family: AddressFamily,
+    notes: Option<String>,
+    runbook: Option<String>,
+    jump_host: Option<String>,
+    forward_agent: bool,
+    tags: Vec<String>,
+    group: Option<String>,
     config: &Config,
     verbose: bool,
 ) -> Result<()> {
@@ -46,21 +79,64 @@ pub async fn add_server(
         return Ok(());
     }
 
+    let server_group = match &group {
+        Some(group) => Some(config.groups.get(group)
+            .ok_or_else(|| anyhow::anyhow!("Group '{}' not found", group))?),
+        None => None,
+    };
+    let defaults = &config.client.defaults;
+
+    // Left empty, the user and key follow the group's defaults
+    let user = match (user, server_group.and_then(|g| g.user.as_ref())) {
+        (Some(user), _) => user,
+        (None, Some(_)) => String::new(),
+        (None, None) => default_user(&host, config)?,
+    };
+    let ssh_key = match (ssh_key, server_group.and_then(|g| g.ssh_key.as_ref())) {
+        (Some(key), _) => Some(key),
+        (None, Some(_)) => None,
+        (None, None) => defaults.ssh_key.clone(),
+    };
+
+    let mut all_tags = vec!["manual".to_string()];
+    for tag in defaults.tags.iter().chain(&tags) {
+        if !all_tags.contains(tag) {
+            all_tags.push(tag.clone());
+        }
+    }
+
     let server = ServerDefinition {
         name: name.clone(),
         host: host.clone(),
         user: user.clone(),
-        port,
-        ssh_key: None,
-        tags: vec!["manual".to_string()],
+        port: port.or(defaults.port).unwrap_or(22),
+        ssh_key,
+        tags: all_tags,
         enabled: true,
         last_seen: None,
+        site: site.clone(),
+        addresses: addresses.clone(),
+        address_family: family,
+        notes: notes.clone(),
+        runbook: runbook.clone(),
+        jump_host: jump_host.clone(),
+        agent_forwarding: forward_agent,
+        ssh_options: Default::default(),
+        thresholds: Default::default(),
     };
 
+    let mut new_config = config.clone();
+    new_config.add_server(server.clone())?;
+    if let Some(group) = &group {
+        if let Some(definition) = new_config.groups.get_mut(group) {
+            definition.members.push(name.clone());
+        }
+    }
+
     // Test connectivity if verbose
     if verbose {
         print_info("Testing connectivity...");
-        match test_connectivity(&server, config).await {
+        match test_connectivity(&server, &new_config).await {
             Ok(true) => print_success("Connection test successful"),
             Ok(false) => print_warning("Connection test failed - server added anyway"),
             Err(e) => print_warning(&format!("Connection test error: {} - server added anyway", e)),
@@ -68,15 +144,42 @@ pub async fn add_server(
     }
 
     // Save the updated configuration
-    let mut new_config = config.clone();
-    new_config.add_server(server)?;
     new_config.save(None)?;
 
+    let server = new_config.with_group_defaults(&server);
     print_success(&format!("Server '{}' added successfully", name));
     println!("Connection details:");
     println!("  Host: {}", host);
-    println!("  User: {}", user);
-    println!("  Port: {}", port);
+    println!("  User: {}", server.user);
+    println!("  Port: {}", server.port);
+    if let Some(ssh_key) = &server.ssh_key {
+        println!("  SSH key: {}", ssh_key);
+    }
+    if let Some(site) = &site {
+        println!("  Site: {}", site);
+    }
+    if !addresses.is_empty() {
+        println!("  Addresses: {}", addresses.join(", "));
+    }
+    if family != AddressFamily::Auto {
+        println!("  Address family: {}", family);
+    }
+    if let Some(notes) = &notes {
+        println!("  Notes: {}", notes);
+    }
+    if let Some(runbook) = &runbook {
+        println!("  Runbook: {}", runbook);
+    }
+    if let Some(jump_host) = &jump_host {
+        println!("  Jump host: {}", jump_host);
+    }
+    if forward_agent {
+        println!("  Agent forwarding: enabled");
+    }
+    if let Some(group) = &group {
+        println!("  Group: {}", group);
+    }
+    println!("  Tags: {}", server.tags.join(", "));
     println!();
     println!("Next steps:");
     println!("  1. Test connection: plan10 client list --detailed");
@@ -118,13 +221,144 @@ pub async fn remove_server(name: String, config: &Config, verbose: bool) -> Resu
     Ok(())
 }
 
-async fn print_server_detailed(name: &str, server: &ServerDefinition, verbose: bool) {
+pub async fn rename_server(old: String, new: String, config: &Config, verbose: bool) -> Result<()> {
+    print_header(&format!("Renaming Server: {} → {}", old, new));
+
+    let mut new_config = config.clone();
+    let updated = new_config.rename_server(&old, &new)?;
+    new_config.validate()?;
+    new_config.save(None)?;
+
+    for reference in &updated {
+        println!("  {} {}", "✏️".yellow(), reference);
+    }
+    // The history is keyed by name too; losing it isn't worth failing over
+    match History::open(config).and_then(|history| history.rename_host(&old, &new)) {
+        Ok(records) => print_verbose(&format!("Moved {} history record(s)", records), verbose),
+        Err(e) => print_warning(&format!("Could not update the history: {:#}", e)),
+    }
+
+    print_success(&format!("Server '{}' is now '{}'", old, new));
+    Ok(())
+}
+
+/// Take a server out of, or back into, the runs of `--tag`, `--group`,
+/// `check`, `exec` and `ping --all`. It can still be targeted by name.
+pub async fn set_server_enabled(name: String, enabled: bool, config: &Config) -> Result<()> {
+    let server = config.get_server(&name)
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", name))?;
+    let state = if enabled { "enabled" } else { "disabled" };
+    if server.enabled == enabled {
+        print_info(&format!("Server '{}' is already {}", name, state));
+        return Ok(());
+    }
+
+    let mut new_config = config.clone();
+    new_config.servers.get_mut(&name).unwrap().enabled = enabled;
+    new_config.save(None)?;
+
+    print_success(&format!("Server '{}' {}", name, state));
+    if !enabled {
+        println!("Fleet-wide commands will skip it; target it with --host to reach it anyway");
+        if config.client.default_server.as_ref() == Some(&name) {
+            print_warning("This is your default server");
+        }
+    }
+    Ok(())
+}
+
+pub async fn server_notes(
+    name: String,
+    text: Option<String>,
+    runbook: Option<String>,
+    clear: bool,
+    config: &Config,
+) -> Result<()> {
+    let server = config.get_server(&name)
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", name))?;
+
+    if text.is_none() && runbook.is_none() && !clear {
+        print_header(&format!("Notes: {}", name));
+        match &server.notes {
+            Some(notes) => println!("{}", notes),
+            None => print_info("No notes recorded"),
+        }
+        if let Some(runbook) = &server.runbook {
+            println!("Runbook: {}", runbook.underline());
+        }
+        return Ok(());
+    }
+
+    let mut new_config = config.clone();
+    let server = new_config.servers.get_mut(&name).unwrap();
+    if clear {
+        server.notes = None;
+        server.runbook = None;
+    }
+    if text.is_some() {
+        server.notes = text;
+    }
+    if runbook.is_some() {
+        server.runbook = runbook;
+    }
+    new_config.save(None)?;
+
+    print_success(&format!("Notes for '{}' updated", name));
+    Ok(())
+}
+
+/// The user to log in as when none was given: the `User` for the host in
+/// `~/.ssh/config`, then `client.defaults.user`, otherwise the local user
+/// name like `ssh` does.
+pub fn default_user(host: &str, config: &Config) -> Result<String> {
+    let from_ssh_config = config.ssh.use_ssh_config
+        .then(|| SshHostConfig::lookup(host).user)
+        .flatten();
+    from_ssh_config
+        .or_else(|| config.client.defaults.user.clone())
+        .or_else(|| std::env::var("USER").ok())
+        .ok_or_else(|| anyhow::anyhow!("No user given for '{}' and none found in ~/.ssh/config", host))
+}
+
+async fn print_server_detailed(name: &str, server: &ServerDefinition, config: &Config, verbose: bool) {
+    let server = &config.with_group_defaults(server);
     let status_icon = if server.enabled { "🟢" } else { "🔴" };
     println!("{} {}", status_icon, name.bold());
     println!("  Host: {}", server.host);
     println!("  User: {}", server.user);
     println!("  Port: {}", server.port);
     println!("  Status: {}", if server.enabled { "Enabled".green() } else { "Disabled".red() });
+
+    if let Some(site) = &server.site {
+        println!("  Site: {}", site);
+    }
+    let groups = config.server_groups(name);
+    if !groups.is_empty() {
+        println!("  Groups: {}", groups.join(", "));
+    }
+
+    if !server.addresses.is_empty() {
+        println!("  Addresses: {}", server.addresses.join(", "));
+    }
+
+    if server.address_family != AddressFamily::Auto {
+        println!("  Address family: {}", server.address_family);
+    }
+
+    if let Some(jump_host) = &server.jump_host {
+        println!("  Jump host: {}", jump_host);
+    }
+
+    if server.agent_forwarding {
+        println!("  Agent forwarding: enabled");
+    }
+
+    if !server.thresholds.is_default() {
+        println!("  Thresholds: {}", server.thresholds.to_string().dimmed());
+    }
+    if !server.ssh_options.is_default() {
+        println!("  SSH options: {}", server.ssh_options.to_string().dimmed());
+    }
     
     if !server.tags.is_empty() {
         println!("  Tags: {}", server.tags.join(", ").dimmed());
@@ -134,6 +368,14 @@ async fn print_server_detailed(name: &str, server: &ServerDefinition, verbose: b
         println!("  SSH Key: {}", ssh_key.dimmed());
     }
 
+    if let Some(notes) = &server.notes {
+        println!("  Notes: {}", notes.yellow());
+    }
+
+    if let Some(runbook) = &server.runbook {
+        println!("  Runbook: {}", runbook.underline());
+    }
+
     match server.last_seen {
         Some(time) => {
             let now = Utc::now();
@@ -152,19 +394,30 @@ async fn print_server_detailed(name: &str, server: &ServerDefinition, verbose: b
     if verbose {
         // Test connectivity
         print!("  Connectivity: ");
-        match test_connectivity(server, &Config::default()).await {
+        match test_connectivity(server, config).await {
             Ok(true) => println!("{}", "✅ Connected".green()),
             Ok(false) => println!("{}", "❌ Failed".red()),
             Err(_) => println!("{}", "❓ Error".yellow()),
         }
+
+        // Addresses behind a bastion are not reachable from here
+        if !server.addresses.is_empty() && server.jump_host.is_none() {
+            println!("  Address latency:");
+            for probe in probe_addresses(&effective_server(server, config), config).await {
+                match probe.latency {
+                    Some(latency) => println!("    {}: {} ms", probe.host, latency.as_millis()),
+                    None => println!("    {}: {}", probe.host, "unreachable".red()),
+                }
+            }
+        }
     }
 }
 
 fn print_servers_table(servers: &[(&String, &ServerDefinition)]) {
-    let widths = [20, 25, 15, 8, 10];
+    let widths = [20, 25, 15, 8, 12, 10];
     
     // Header
-    println!("{}", format_table_row(&["NAME", "HOST", "USER", "PORT", "STATUS"], &widths));
+    println!("{}", format_table_row(&["NAME", "HOST", "USER", "PORT", "SITE", "STATUS"], &widths));
     println!("{}", format_table_separator(&widths));
     
     // Rows
@@ -181,6 +434,7 @@ fn print_servers_table(servers: &[(&String, &ServerDefinition)]) {
             &server.host,
             &server.user,
             &server.port.to_string(),
+            server.site.as_deref().unwrap_or("-"),
             &status_colored,
         ], &widths));
     }
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use crate::Config;
+use crate::commands::utils::*;
+use crate::config::ServerDefinition;
+use crate::ssh::{effective_server, host_port};
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Open an interactive shell on a server by handing the terminal to `ssh`,
+/// or to `mosh` when it is wanted and installed. mosh keeps the session
+/// alive across dropped connections and changing addresses, which suits
+/// laptops that sleep or roam.
+pub async fn open_shell(host: String, mosh: bool, no_mosh: bool, config: &Config, verbose: bool) -> Result<()> {
+    let server = config.resolve_server(&host)
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+    let server = effective_server(server, config);
+
+    if config.ssh.proxy.is_some() {
+        print_warning("ssh.proxy is not passed on to ssh; configure the proxy in ~/.ssh/config");
+    }
+    let ssh_args = ssh_args(&server, config);
+    let destination = format!("{}@{}", server.user, server.host);
+
+    let want_mosh = mosh || (config.ssh.resilient && !no_mosh);
+    let mut command = match (want_mosh, find_on_path("mosh")) {
+        // mosh talks UDP to the server directly, so it cannot follow a
+        // jump host or proxy
+        (true, Some(_)) if server.jump_host.is_some() || config.ssh.proxy.is_some() || config.ssh.proxy_command.is_some() => {
+            print_warning("mosh cannot go through a jump host or proxy; using ssh");
+            ssh_command(&ssh_args, &destination)
+        }
+        (true, Some(mosh_path)) => {
+            let mut command = Command::new(mosh_path);
+            command.arg(format!("--ssh=ssh {}", ssh_args.join(" "))).arg(&destination);
+            command
+        }
+        (true, None) => {
+            if mosh {
+                anyhow::bail!("mosh is not installed (brew install mosh)");
+            }
+            print_verbose("mosh is not installed; using ssh", verbose);
+            ssh_command(&ssh_args, &destination)
+        }
+        (false, _) => ssh_command(&ssh_args, &destination),
+    };
+
+    print_verbose(&format!("Running {:?}", command), verbose);
+    // Only returns if the program could not be started
+    let error = command.exec();
+    anyhow::bail!("Failed to start {}: {}", command.get_program().to_string_lossy(), error)
+}
+
+fn ssh_command(args: &[String], destination: &str) -> Command {
+    let mut command = Command::new("ssh");
+    command.args(args).arg(destination);
+    command
+}
+
+/// OpenSSH options matching how plan10 itself connects to the server.
+fn ssh_args(server: &ServerDefinition, config: &Config) -> Vec<String> {
+    let mut args = vec!["-p".to_string(), server.port.to_string()];
+
+    if let Some(key) = server.ssh_key.as_ref().or(config.ssh.key_path.as_ref()) {
+        args.push("-i".to_string());
+        args.push(shellexpand::tilde(key).to_string());
+    }
+    if let Some(jump) = &server.jump_host {
+        let jump = match config.get_server(jump) {
+            Some(bastion) => {
+                let bastion = config.with_group_defaults(bastion);
+                format!("{}@{}", bastion.user, host_port(&bastion.host, bastion.port))
+            }
+            None => jump.clone(),
+        };
+        args.push("-J".to_string());
+        args.push(jump);
+    }
+    if let Some(command) = &config.ssh.proxy_command {
+        args.push("-o".to_string());
+        args.push(format!("ProxyCommand={}", command));
+    }
+    if server.agent_forwarding {
+        args.push("-A".to_string());
+    }
+    if config.ssh.compression {
+        args.push("-C".to_string());
+    }
+
+    args
+}
+
+fn find_on_path(program: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
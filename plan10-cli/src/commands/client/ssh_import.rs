@@ -0,0 +1,256 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::commands::utils::*;
+use crate::config::{AddressFamily, ServerDefinition};
+use crate::ssh::host_port;
+use crate::ssh_config::{host_aliases, SshHostConfig};
+use colored::*;
+use std::io::{IsTerminal, Write};
+
+use super::inventory::server_name;
+use super::servers::default_user;
+
+/// Tag carried by servers imported from `~/.ssh/config`.
+const SSH_CONFIG_TAG: &str = "ssh-config";
+/// Tag carried by servers imported from `known_hosts`.
+const KNOWN_HOSTS_TAG: &str = "known-hosts";
+
+pub async fn import_ssh_hosts(
+    from_ssh_config: bool,
+    from_known_hosts: bool,
+    all: bool,
+    user: Option<String>,
+    tags: Vec<String>,
+    dry_run: bool,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    print_header("Import Servers from SSH");
+
+    let mut candidates = Vec::new();
+    if from_ssh_config {
+        candidates.extend(ssh_config_candidates(user.as_deref(), config)?);
+    }
+    if from_known_hosts {
+        candidates.extend(known_hosts_candidates(user.as_deref(), config)?);
+    }
+
+    // Drop hosts that are already configured, or listed twice
+    let mut new_servers: Vec<ServerDefinition> = Vec::new();
+    for candidate in candidates {
+        let existing = config.servers.values().find(|s| {
+            s.name == candidate.name
+                || (s.port == candidate.port && s.candidate_hosts().contains(&candidate.host))
+        });
+        if let Some(existing) = existing {
+            print_verbose(&format!("{}: already configured as '{}'", candidate.name, existing.name), verbose);
+            continue;
+        }
+        if new_servers.iter().any(|s| s.name == candidate.name || (s.host == candidate.host && s.port == candidate.port)) {
+            continue;
+        }
+        new_servers.push(candidate);
+    }
+
+    if new_servers.is_empty() {
+        print_info("No hosts found that aren't configured already");
+        return Ok(());
+    }
+
+    for (index, server) in new_servers.iter().enumerate() {
+        println!(
+            "  {:>3}. {} {}",
+            index + 1,
+            server.name.bold(),
+            format!("{}@{}", server.user, host_port(&server.host, server.port)).dimmed()
+        );
+    }
+    println!();
+
+    let selected: Vec<usize> = if all {
+        (0..new_servers.len()).collect()
+    } else if std::io::stdin().is_terminal() {
+        prompt_selection(new_servers.len())?
+    } else {
+        anyhow::bail!("No terminal to pick hosts in; pass --all to import every host listed");
+    };
+    if selected.is_empty() {
+        print_info("Nothing selected");
+        return Ok(());
+    }
+
+    let mut new_config = config.clone();
+    for index in &selected {
+        let mut server = new_servers[*index].clone();
+        for tag in &tags {
+            if !server.tags.contains(tag) {
+                server.tags.push(tag.clone());
+            }
+        }
+        println!("  {} {}", "➕".green(), server.name);
+        new_config.add_server(server)?;
+    }
+
+    if dry_run {
+        print_info("Dry run - configuration not saved");
+        return Ok(());
+    }
+
+    new_config.save(None)?;
+    print_success(&format!("Imported {} server(s)", selected.len()));
+    println!("Deploy with: plan10 client deploy --host <name> --all");
+    Ok(())
+}
+
+fn ssh_config_candidates(user: Option<&str>, config: &Config) -> Result<Vec<ServerDefinition>> {
+    let aliases = host_aliases();
+    if aliases.is_empty() {
+        print_info("No host aliases found in ~/.ssh/config");
+    }
+
+    aliases.iter()
+        .map(|alias| {
+            let ssh = SshHostConfig::lookup(alias);
+            let user = match user.map(str::to_string).or(ssh.user.clone()) {
+                Some(user) => user,
+                None => default_user(alias, config)?,
+            };
+            let mut server = new_server(alias, ssh.host_name.clone().unwrap_or_else(|| alias.clone()), user, SSH_CONFIG_TAG, config);
+            server.port = ssh.port.or(config.client.defaults.port).unwrap_or(22);
+            if ssh.identity_file.is_some() {
+                server.ssh_key = ssh.identity_file.clone();
+            }
+            server.jump_host = ssh.proxy_jump.clone();
+            server.agent_forwarding = ssh.forward_agent.unwrap_or(false);
+            Ok(server)
+        })
+        .collect()
+}
+
+fn known_hosts_candidates(user: Option<&str>, config: &Config) -> Result<Vec<ServerDefinition>> {
+    let path = config.get_known_hosts_path()
+        .context("Could not determine known_hosts path")?;
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => {
+            print_info(&format!("No known_hosts file at {}", path.display()));
+            return Ok(Vec::new());
+        }
+    };
+
+    let hosts = parse_known_hosts(&content);
+    let hashed = content.lines().filter(|line| line.starts_with("|1|")).count();
+    if hashed > 0 {
+        print_info(&format!("Skipping {} hashed known_hosts entries (HashKnownHosts)", hashed));
+    }
+
+    hosts.into_iter()
+        .map(|(host, port)| {
+            let user = match user {
+                Some(user) => user.to_string(),
+                None => default_user(&host, config)?,
+            };
+            let mut server = new_server(&host, host.clone(), user, KNOWN_HOSTS_TAG, config);
+            server.port = port;
+            Ok(server)
+        })
+        .collect()
+}
+
+/// The hosts named in a known_hosts file, with their ports. An entry lists
+/// several names for the same key (e.g. `mini.local,192.168.1.20`); only the
+/// first is used. Hashed names, wildcards and CA/revocation markers are
+/// skipped.
+fn parse_known_hosts(content: &str) -> Vec<(String, u16)> {
+    let mut hosts: Vec<(String, u16)> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') || line.starts_with("|1|") {
+            continue;
+        }
+        let Some(name) = line.split_whitespace().next().and_then(|names| names.split(',').next()) else {
+            continue;
+        };
+        if name.contains(['*', '?', '!']) {
+            continue;
+        }
+
+        // Non-standard ports are written as [host]:port
+        let (host, port) = match name.strip_prefix('[').and_then(|rest| rest.split_once("]:")) {
+            Some((host, port)) => match port.parse() {
+                Ok(port) => (host.to_string(), port),
+                Err(_) => continue,
+            },
+            None => (name.to_string(), 22),
+        };
+        if !hosts.contains(&(host.clone(), port)) {
+            hosts.push((host, port));
+        }
+    }
+    hosts
+}
+
+fn new_server(display_name: &str, host: String, user: String, source_tag: &str, config: &Config) -> ServerDefinition {
+    let defaults = &config.client.defaults;
+    let mut tags = vec![source_tag.to_string()];
+    tags.extend(defaults.tags.iter().filter(|tag| *tag != source_tag).cloned());
+
+    ServerDefinition {
+        name: server_name(display_name),
+        host,
+        user,
+        port: 22,
+        ssh_key: defaults.ssh_key.clone(),
+        tags,
+        enabled: true,
+        last_seen: None,
+        site: None,
+        addresses: Vec::new(),
+        address_family: AddressFamily::Auto,
+        notes: None,
+        runbook: None,
+        jump_host: None,
+        agent_forwarding: false,
+        ssh_options: Default::default(),
+        thresholds: Default::default(),
+    }
+}
+
+/// Ask which of `count` listed hosts to import: numbers and ranges such as
+/// `1 3-5`, `all`, or nothing for none.
+fn prompt_selection(count: usize) -> Result<Vec<usize>> {
+    loop {
+        print!("Import which hosts? (e.g. 1 3-5, all; empty for none): ");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+
+        match parse_selection(answer.trim(), count) {
+            Ok(selected) => return Ok(selected),
+            Err(e) => print_error(&e.to_string()),
+        }
+    }
+}
+
+fn parse_selection(answer: &str, count: usize) -> Result<Vec<usize>> {
+    if answer.eq_ignore_ascii_case("all") {
+        return Ok((0..count).collect());
+    }
+
+    let mut selected = Vec::new();
+    for part in answer.split([' ', ',']).filter(|part| !part.is_empty()) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let parse = |n: &str| -> Result<usize> {
+            match n.trim().parse::<usize>() {
+                Ok(n) if (1..=count).contains(&n) => Ok(n),
+                _ => anyhow::bail!("'{}' is not a number between 1 and {}", n, count),
+            }
+        };
+        for n in parse(first)?..=parse(last)? {
+            if !selected.contains(&(n - 1)) {
+                selected.push(n - 1);
+            }
+        }
+    }
+    Ok(selected)
+}
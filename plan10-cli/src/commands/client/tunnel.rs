@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use crate::{Config, TunnelCommands};
+use crate::commands::utils::*;
+use crate::config::{TunnelDefinition, TunnelDirection};
+use crate::ssh::SshClient;
+use crate::utils::run_command_with_status;
+use colored::*;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long to wait before reconnecting a tunnel whose server went away.
+const RECONNECT_DELAY_SECS: u64 = 5;
+
+pub async fn execute(cmd: TunnelCommands, config: &Config, verbose: bool) -> Result<()> {
+    match cmd {
+        TunnelCommands::Add { name, host, local, remote } => add_tunnel(name, host, local, remote, config).await,
+        TunnelCommands::Remove { name } => remove_tunnel(name, config).await,
+        TunnelCommands::List => list_tunnels(config).await,
+        TunnelCommands::Start { name, foreground: true } => run_tunnel(name, config, verbose).await,
+        TunnelCommands::Start { name, foreground: false } => start_tunnel(name, config, verbose).await,
+        TunnelCommands::Stop { name } => stop_tunnel(name).await,
+    }
+}
+
+async fn add_tunnel(
+    name: String,
+    host: String,
+    local: Option<String>,
+    remote: Option<String>,
+    config: &Config,
+) -> Result<()> {
+    print_header(&format!("Adding Tunnel: {}", name));
+
+    if config.tunnels.contains_key(&name) {
+        anyhow::bail!("Tunnel '{}' already exists", name);
+    }
+    let server = config.resolve_server(&host)
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+
+    let (direction, spec) = match (local, remote) {
+        (Some(spec), _) => (TunnelDirection::Local, spec),
+        (None, Some(spec)) => (TunnelDirection::Remote, spec),
+        (None, None) => anyhow::bail!("Either --local or --remote is required"),
+    };
+    let (listen_port, target_host, target_port) = parse_forward(&spec)?;
+
+    let tunnel = TunnelDefinition {
+        server: server.name.clone(),
+        direction,
+        listen_port,
+        target_host,
+        target_port,
+    };
+
+    let mut new_config = config.clone();
+    new_config.tunnels.insert(name.clone(), tunnel.clone());
+    new_config.save(None)?;
+
+    print_success(&format!("Tunnel '{}' added: {}", name, tunnel));
+    print_info(&format!("Start it with: plan10 client tunnel start {}", name));
+    Ok(())
+}
+
+async fn remove_tunnel(name: String, config: &Config) -> Result<()> {
+    print_header(&format!("Removing Tunnel: {}", name));
+
+    if !config.tunnels.contains_key(&name) {
+        anyhow::bail!("Tunnel '{}' not found", name);
+    }
+    if running_pid(&name).is_some() {
+        stop_tunnel(name.clone()).await?;
+    }
+
+    let mut new_config = config.clone();
+    new_config.tunnels.remove(&name);
+    new_config.save(None)?;
+
+    print_success(&format!("Tunnel '{}' removed", name));
+    Ok(())
+}
+
+async fn list_tunnels(config: &Config) -> Result<()> {
+    print_header("Tunnels");
+
+    if config.tunnels.is_empty() {
+        print_info("No tunnels configured");
+        print_info("Add one with: plan10 client tunnel add <name> -H <server> --local PORT:HOST:HOSTPORT");
+        return Ok(());
+    }
+
+    let mut names: Vec<_> = config.tunnels.keys().collect();
+    names.sort();
+    for name in names {
+        let tunnel = &config.tunnels[name];
+        let status = match running_pid(name) {
+            Some(pid) => format!("running (pid {})", pid).green(),
+            None => "stopped".dimmed(),
+        };
+        println!("{} {}", name.bold(), status);
+        println!("  {}", tunnel);
+    }
+
+    Ok(())
+}
+
+async fn start_tunnel(name: String, config: &Config, verbose: bool) -> Result<()> {
+    let tunnel = config.tunnels.get(&name)
+        .ok_or_else(|| anyhow::anyhow!("Tunnel '{}' not found", name))?;
+    if let Some(pid) = running_pid(&name) {
+        print_info(&format!("Tunnel '{}' is already running (pid {})", name, pid));
+        return Ok(());
+    }
+
+    let (pid_path, log_path) = state_paths(&name)?;
+    let log = fs::File::create(&log_path)
+        .context(format!("Failed to create log file: {}", log_path.display()))?;
+
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    if let Some(path) = config.path() {
+        command.arg("--config").arg(path);
+    }
+    if let Some(workspace) = config.workspace() {
+        command.args(["--workspace", workspace]);
+    }
+    command
+        .args(["client", "tunnel", "start", &name, "--foreground"])
+        .stdin(std::process::Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    // Its own process group, so it outlives the shell that started it
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let child = command.spawn().context("Failed to start tunnel process")?;
+
+    fs::write(&pid_path, child.id().to_string())
+        .context(format!("Failed to write pid file: {}", pid_path.display()))?;
+
+    print_success(&format!("Tunnel '{}' started (pid {}): {}", name, child.id(), tunnel));
+    print_verbose(&format!("Logging to {}", log_path.display()), verbose);
+    Ok(())
+}
+
+async fn stop_tunnel(name: String) -> Result<()> {
+    let Some(pid) = running_pid(&name) else {
+        print_info(&format!("Tunnel '{}' is not running", name));
+        return Ok(());
+    };
+
+    let (_, stderr, success) = run_command_with_status("kill", &[&pid.to_string()])?;
+    if !success {
+        anyhow::bail!("Failed to stop tunnel '{}' (pid {}): {}", name, pid, stderr.trim());
+    }
+    if let Ok((pid_path, _)) = state_paths(&name) {
+        let _ = fs::remove_file(pid_path);
+    }
+
+    print_success(&format!("Tunnel '{}' stopped", name));
+    Ok(())
+}
+
+/// Keep the tunnel up in this process, reconnecting whenever the server
+/// connection drops.
+async fn run_tunnel(name: String, config: &Config, verbose: bool) -> Result<()> {
+    let tunnel = config.tunnels.get(&name)
+        .ok_or_else(|| anyhow::anyhow!("Tunnel '{}' not found", name))?;
+    let server = config.get_server(&tunnel.server)
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", tunnel.server))?;
+
+    // Bind before connecting so a port in use fails straight away
+    let listener = match tunnel.direction {
+        TunnelDirection::Local => Some(
+            std::net::TcpListener::bind(("127.0.0.1", tunnel.listen_port))
+                .context(format!("Could not listen on localhost:{}", tunnel.listen_port))?,
+        ),
+        TunnelDirection::Remote => None,
+    };
+
+    print_info(&format!("Tunnel '{}': {}", name, tunnel));
+    loop {
+        let result = match SshClient::connect(server, config).await {
+            Ok(client) => {
+                print_verbose(&format!("Connected to {}", server.name), verbose);
+                match &listener {
+                    Some(listener) => client.forward_local(
+                        listener.try_clone()?,
+                        &tunnel.target_host,
+                        tunnel.target_port,
+                    ),
+                    None => client.forward_remote(
+                        tunnel.listen_port,
+                        &tunnel.target_host,
+                        tunnel.target_port,
+                    ),
+                }
+            }
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            print_warning(&format!("{:#}", e));
+        }
+        print_info(&format!("Reconnecting in {}s", RECONNECT_DELAY_SECS));
+        tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+    }
+}
+
+/// Parse `PORT:HOST:HOSTPORT` as given to `ssh -L`/`-R`. IPv6 hosts go in
+/// brackets: `5900:[::1]:5900`.
+fn parse_forward(spec: &str) -> Result<(u16, String, u16)> {
+    let invalid = || anyhow::anyhow!("Invalid forward '{}': expected PORT:HOST:HOSTPORT", spec);
+
+    let (listen_port, rest) = spec.split_once(':').ok_or_else(invalid)?;
+    let (target_host, target_port) = rest.rsplit_once(':').ok_or_else(invalid)?;
+    let target_host = target_host.trim_start_matches('[').trim_end_matches(']');
+    if target_host.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok((
+        listen_port.parse().map_err(|_| invalid())?,
+        target_host.to_string(),
+        target_port.parse().map_err(|_| invalid())?,
+    ))
+}
+
+fn state_paths(name: &str) -> Result<(PathBuf, PathBuf)> {
+    let dir = Config::state_dir()
+        .context("Could not determine state directory")?
+        .join("tunnels");
+    fs::create_dir_all(&dir)
+        .context(format!("Failed to create {}", dir.display()))?;
+    Ok((dir.join(format!("{}.pid", name)), dir.join(format!("{}.log", name))))
+}
+
+/// The pid of the tunnel's background process, if it is still alive.
+fn running_pid(name: &str) -> Option<u32> {
+    let (pid_path, _) = state_paths(name).ok()?;
+    let pid: u32 = fs::read_to_string(&pid_path).ok()?.trim().parse().ok()?;
+    match run_command_with_status("kill", &["-0", &pid.to_string()]) {
+        Ok((_, _, true)) => Some(pid),
+        _ => {
+            let _ = fs::remove_file(pid_path);
+            None
+        }
+    }
+}
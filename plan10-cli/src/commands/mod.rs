@@ -49,6 +49,7 @@ pub trait RemoteCommand {
 // Common utilities for all commands
 pub mod utils {
     use colored::*;
+    use crate::ssh::{CommandResult, OutputLine, RemoteError};
     
     pub fn print_header(title: &str) {
         println!("{}", format!("🔧 {}", title).bold().blue());
@@ -67,13 +68,168 @@ pub mod utils {
         println!("{} {}", "❌".red(), message);
     }
     
+    /// Pretty-printed JSON on stdout, for `--output json`.
+    pub fn print_json<T: serde::Serialize + ?Sized>(value: &T) -> anyhow::Result<()> {
+        println!("{}", serde_json::to_string_pretty(value)?);
+        Ok(())
+    }
+    
     pub fn print_info(message: &str) {
         println!("{} {}", "ℹ️".blue(), message);
     }
     
+    pub fn print_remote_failure(context: &str, result: &CommandResult) {
+        let error = RemoteError::classify(result);
+        print_error(&format!("{}: {}", context, error));
+        if let Some(hint) = error.hint() {
+            print_info(hint);
+        }
+    }
+    
+    /// Echo a line of streamed remote output; stderr is dimmed.
+    pub fn print_output_line(line: OutputLine) {
+        match line {
+            OutputLine::Stdout(line) => println!("{}", line),
+            OutputLine::Stderr(line) => eprintln!("{}", line.dimmed()),
+        }
+    }
+    
+    /// Detail that `-v` (passed down as `verbose`) shows on stderr. It is
+    /// logged either way, at debug when not shown, so the log file has it.
     pub fn print_verbose(message: &str, verbose: bool) {
         if verbose {
-            println!("{} {}", "🔍".dimmed(), message.dimmed());
+            tracing::info!("{}", message);
+        } else {
+            tracing::debug!("{}", message);
+        }
+    }
+
+    /// Print the changed lines between two versions, with their line numbers in
+    /// the new version.
+    pub fn print_line_diff(old: &str, new: &str) {
+        let old: Vec<&str> = old.lines().collect();
+        let new: Vec<&str> = new.lines().collect();
+
+        // Longest common subsequence table; only used on small files
+        let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        let mut in_hunk = false;
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                in_hunk = false;
+                i += 1;
+                j += 1;
+                continue;
+            }
+
+            if !in_hunk {
+                println!("{}", format!("@@ line {} @@", j + 1).cyan());
+                in_hunk = true;
+            }
+            if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                println!("{}", format!("- {}", old[i]).red());
+                i += 1;
+            } else {
+                println!("{}", format!("+ {}", new[j]).green());
+                j += 1;
+            }
+        }
+    }
+
+    /// The hosts a command targets: the one given with `--host`, or every
+    /// enabled server tagged with `--tag` or in `--group`.
+    pub fn target_hosts(
+        host: Option<String>,
+        tag: Option<String>,
+        group: Option<String>,
+        config: &crate::Config,
+    ) -> anyhow::Result<Vec<String>> {
+        match (host, tag, group) {
+            (Some(host), _, _) => Ok(vec![host]),
+            (None, Some(tag), _) => {
+                note_disabled(&config.disabled_server_names(|s| config.with_group_defaults(s).has_tag(Some(&tag))));
+                config.tagged_server_names(&tag)
+            }
+            (None, None, Some(group)) => {
+                note_disabled(&config.disabled_server_names(|s| config.server_groups(&s.name).contains(&group.as_str())));
+                config.group_server_names(&group)
+            }
+            (None, None, None) => anyhow::bail!("Either --host, --tag or --group is required"),
+        }
+    }
+
+    /// Mention the disabled servers a fleet-wide command is leaving out.
+    pub fn note_disabled(names: &[String]) {
+        if !names.is_empty() {
+            print_info(&format!("Skipping {} disabled server(s): {}", names.len(), names.join(", ")));
+        }
+    }
+
+    /// Run `run` for every host, carrying on past failures and failing at the
+    /// end if any host failed. Hosts run in parallel, at most
+    /// `client.concurrent_operations` at a time, so their output may
+    /// interleave. A single host is just run.
+    pub async fn for_each_host<F, Fut>(hosts: Vec<String>, config: &crate::Config, run: F) -> anyhow::Result<()>
+    where
+        F: Fn(String) -> Fut + Sync,
+        Fut: std::future::Future<Output = anyhow::Result<()>>,
+    {
+        if hosts.len() == 1 {
+            return run(hosts.into_iter().next().unwrap()).await;
+        }
+
+        let total = hosts.len();
+        let limit = crate::ssh::operation_limit(config);
+        let workers = config.client.concurrent_operations.clamp(1, total);
+        let queue = std::sync::Mutex::new(hosts.into_iter());
+        let failed = std::sync::Mutex::new(Vec::new());
+
+        // The commands make blocking ssh2 calls, so each worker gets its own
+        // thread and runtime; scoped threads let them borrow the config
+        tokio::task::block_in_place(|| std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                        Ok(runtime) => runtime,
+                        Err(e) => {
+                            print_error(&format!("Failed to start worker: {}", e));
+                            return;
+                        }
+                    };
+                    loop {
+                        let Some(host) = queue.lock().unwrap_or_else(|e| e.into_inner()).next() else {
+                            break;
+                        };
+                        let result = runtime.block_on(async {
+                            let _permit = limit.acquire().await?;
+                            run(host.clone()).await
+                        });
+                        if let Err(e) = result {
+                            print_error(&format!("{}: {:#}", host, e));
+                            failed.lock().unwrap_or_else(|e| e.into_inner()).push(host);
+                        }
+                    }
+                });
+            }
+        }));
+
+        let mut failed = failed.into_inner().unwrap_or_else(|e| e.into_inner());
+        failed.sort();
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Failed on {} of {} server(s): {}", failed.len(), total, failed.join(", "))
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::commands::utils::*;
+use crate::snapshot::StatusSnapshot;
+use std::net::TcpListener as StdTcpListener;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const API_LABEL: &str = "com.plan10.api";
+/// Name of the socket entry in the plist's `Sockets` dictionary.
+const LAUNCHD_SOCKET_NAME: &str = "Listeners";
+const PLIST_PATH: &str = "/Library/LaunchDaemons/com.plan10.api.plist";
+
+pub async fn execute_api(listen: String, install: bool, config: &Config, verbose: bool) -> Result<()> {
+    if install {
+        return install_launch_daemon(&listen, config, verbose);
+    }
+
+    // Prefer a socket handed over by launchd; it stays bound across restarts
+    let listener = match launchd_listener()? {
+        Some(listener) => {
+            print_verbose("Using socket from launchd", verbose);
+            listener
+        }
+        None => {
+            let listener = StdTcpListener::bind(&listen)
+                .context(format!("Failed to listen on {}", listen))?;
+            print_info(&format!("Status API listening on http://{}", listen));
+            listener
+        }
+    };
+
+    listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(listener)?;
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        print_verbose(&format!("Request from {}", peer), verbose);
+        tokio::spawn(async move {
+            let _ = handle_connection(stream).await;
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream) -> Result<()> {
+    let mut buffer = [0u8; 4096];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().unwrap_or("");
+
+    let (status, body) = match (method, path) {
+        ("GET", "/health") => ("200 OK", r#"{"status":"ok"}"#.to_string()),
+        ("GET", "/status") => match StatusSnapshot::load() {
+            Ok(Some(snapshot)) => (
+                "200 OK",
+                serde_json::json!({ "age_seconds": snapshot.age_seconds(), "status": snapshot }).to_string(),
+            ),
+            Ok(None) => ("503 Service Unavailable", r#"{"error":"no status collected yet"}"#.to_string()),
+            Err(e) => ("500 Internal Server Error", serde_json::json!({ "error": e.to_string() }).to_string()),
+        },
+        ("GET", _) => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+        _ => ("405 Method Not Allowed", r#"{"error":"read-only API"}"#.to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Collect the listening socket launchd created for us, if we were started
+/// through socket activation.
+#[cfg(target_os = "macos")]
+fn launchd_listener() -> Result<Option<StdTcpListener>> {
+    use nix::libc::{c_char, c_int, size_t};
+    use std::ffi::CString;
+    use std::os::unix::io::FromRawFd;
+
+    extern "C" {
+        fn launch_activate_socket(name: *const c_char, fds: *mut *mut c_int, cnt: *mut size_t) -> c_int;
+    }
+
+    let name = CString::new(LAUNCHD_SOCKET_NAME)?;
+    let mut fds: *mut c_int = std::ptr::null_mut();
+    let mut count: size_t = 0;
+
+    // Fails with ESRCH when not launched by launchd, or ENOENT when the plist
+    // has no such socket; both mean "bind it ourselves"
+    let error = unsafe { launch_activate_socket(name.as_ptr(), &mut fds, &mut count) };
+    if error != 0 || fds.is_null() {
+        return Ok(None);
+    }
+
+    let sockets = unsafe { std::slice::from_raw_parts(fds, count) }.to_vec();
+    unsafe { nix::libc::free(fds as *mut nix::libc::c_void) };
+
+    Ok(sockets.first().map(|fd| unsafe { StdTcpListener::from_raw_fd(*fd) }))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn launchd_listener() -> Result<Option<StdTcpListener>> {
+    Ok(None)
+}
+
+fn install_launch_daemon(listen: &str, config: &Config, verbose: bool) -> Result<()> {
+    print_header("Installing Status API LaunchDaemon");
+    crate::utils::check_sudo()?;
+
+    let (address, port) = listen.rsplit_once(':')
+        .context(format!("Listen address '{}' must be host:port", listen))?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    let program = std::env::current_exe()?;
+    // The file this run loaded, so --config and --profile carry over
+    let config_path = config.path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    // No RunAtLoad/KeepAlive: launchd owns the socket from boot, starts
+    // plan10 on the first connection and again after any crash
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{program}</string>
+        <string>--config</string>
+        <string>{config_path}</string>
+        <string>server</string>
+        <string>api</string>
+    </array>
+    <key>UserName</key>
+    <string>{user}</string>
+    <key>Sockets</key>
+    <dict>
+        <key>{socket}</key>
+        <dict>
+            <key>SockNodeName</key>
+            <string>{address}</string>
+            <key>SockServiceName</key>
+            <string>{port}</string>
+            <key>SockType</key>
+            <string>stream</string>
+        </dict>
+    </dict>
+    <key>StandardErrorPath</key>
+    <string>/tmp/plan10-api.log</string>
+</dict>
+</plist>
+"#,
+        label = API_LABEL,
+        program = program.display(),
+        config_path = config_path,
+        user = std::env::var("SUDO_USER").unwrap_or_else(|_| "root".to_string()),
+        socket = LAUNCHD_SOCKET_NAME,
+        address = address,
+        port = port,
+    );
+
+    std::fs::write(PLIST_PATH, plist)
+        .context(format!("Failed to write {}", PLIST_PATH))?;
+    print_success(&format!("LaunchDaemon written to {}", PLIST_PATH));
+    print_verbose(&format!("Serving status for server '{}'", config.server.name), verbose);
+
+    let (_, stderr, success) = crate::utils::run_command_with_status(
+        "launchctl",
+        &["bootstrap", "system", PLIST_PATH],
+    )?;
+    if success {
+        print_success(&format!("Status API available at http://{}", listen));
+    } else {
+        print_warning(&format!("launchctl bootstrap failed: {}", stderr.trim()));
+        println!("If it is already loaded, run: sudo launchctl bootout system/{} and retry", API_LABEL);
+    }
+
+    Ok(())
+}
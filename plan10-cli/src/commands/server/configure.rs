@@ -42,7 +42,7 @@ pub async fn execute_configure(
     // Show next steps
     println!("\n{}:", "Next Steps".bold());
     println!("1. Check status: plan10 status --detailed");
-    println!("2. Start monitoring: plan10 monitor watch");
+    println!("2. Watch live: plan10 monitor watch");
     println!("3. Test power settings: plan10 monitor power --all");
     
     Ok(())
@@ -141,6 +141,8 @@ LOG_LEVEL={}
     
     // Set up log rotation
     configure_log_rotation(verbose).await?;
+
+    super::monitor_daemon::install_launch_agent(config, verbose)?;
     
     Ok(())
 }
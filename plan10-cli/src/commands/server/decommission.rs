@@ -0,0 +1,513 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::commands::utils::*;
+use crate::utils::{run_command_with_status, system::get_macos_version};
+use chrono::Utc;
+use colored::*;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Namespace used for `ssh-keygen -Y sign`, so a report signature can't be
+/// replayed as a signature for anything else.
+const SIGNATURE_NAMESPACE: &str = "plan10-decommission";
+
+/// Keychain service name under which Plan 10 stores secrets.
+const KEYCHAIN_SERVICE: &str = "plan10";
+
+/// Files installed by `client deploy` and `server configure`.
+const ARTIFACT_FILES: &[&str] = &[
+    "~/server_setup.sh",
+    "~/scripts/temp",
+    "~/scripts/battery",
+    "~/scripts/power_diagnostics",
+    "~/scripts/setup_aliases.sh",
+    "~/scripts/rotate_logs.sh",
+];
+
+/// Directories owned entirely by Plan 10.
+const ARTIFACT_DIRS: &[&str] = &["~/.plan10", "~/Library/Caches/plan10"];
+
+const LAUNCH_AGENTS: &[&str] = &[
+    "~/Library/LaunchAgents/caffeinate.plist",
+    "~/Library/LaunchAgents/com.plan10.caffeinate.plist",
+    "~/Library/LaunchAgents/com.plan10.monitor.plist",
+];
+
+/// Root LaunchDaemons, by label, installed with `sudo plan10 server ... --install`.
+const LAUNCH_DAEMONS: &[(&str, &str)] = &[
+    ("com.plan10.api", "/Library/LaunchDaemons/com.plan10.api.plist"),
+    ("com.plan10.watchdog", "/Library/LaunchDaemons/com.plan10.watchdog.plist"),
+];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum StepStatus {
+    Done,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+struct StepRecord {
+    step: String,
+    status: StepStatus,
+    details: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DecommissionReport {
+    hostname: String,
+    serial_number: Option<String>,
+    macos_version: String,
+    operator: Option<String>,
+    started_at: chrono::DateTime<Utc>,
+    finished_at: chrono::DateTime<Utc>,
+    plan10_version: String,
+    steps: Vec<StepRecord>,
+}
+
+pub async fn execute_decommission(
+    confirm: Option<String>,
+    disable_autologin: bool,
+    reset_power: bool,
+    report_file: Option<String>,
+    sign_key: Option<String>,
+    dry_run: bool,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    print_header("Decommission Plan 10 Server");
+
+    let hostname = hostname::get()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    println!("This removes Plan 10 from {}:", hostname.bold());
+    println!("  • stops caffeinate and unloads Plan 10 LaunchAgents and LaunchDaemons");
+    println!("  • deletes deployed scripts, logs, configuration and state");
+    println!("  • removes authorized SSH keys whose comment mentions plan10");
+    println!("  • deletes '{}' items from the login keychain", KEYCHAIN_SERVICE);
+    if disable_autologin {
+        println!("  • disables automatic login");
+    }
+    if reset_power {
+        println!("  • restores default power management settings");
+    }
+    println!();
+
+    if !dry_run {
+        confirm_hostname(&hostname, confirm)?;
+    }
+
+    let started_at = Utc::now();
+    let mut steps = vec![
+        stop_services(dry_run),
+        remove_artifacts(dry_run),
+        revoke_keys(dry_run),
+        clear_secrets(dry_run),
+    ];
+    if disable_autologin {
+        steps.push(disable_auto_login(dry_run));
+    }
+    if reset_power {
+        steps.push(restore_power_defaults(dry_run));
+    }
+
+    println!();
+    for step in &steps {
+        let icon = match step.status {
+            StepStatus::Done => "✅".green(),
+            StepStatus::Skipped => "⏭️".yellow(),
+            StepStatus::Failed => "❌".red(),
+        };
+        println!("{} {}", icon, step.step);
+        for detail in &step.details {
+            print_verbose(detail, verbose || dry_run);
+        }
+    }
+
+    if dry_run {
+        println!();
+        print_info("Dry run - nothing was changed");
+        return Ok(());
+    }
+
+    let report = DecommissionReport {
+        hostname: hostname.clone(),
+        serial_number: serial_number(),
+        macos_version: get_macos_version().unwrap_or_else(|_| "Unknown".to_string()),
+        operator: std::env::var("USER").ok(),
+        started_at,
+        finished_at: Utc::now(),
+        plan10_version: env!("CARGO_PKG_VERSION").to_string(),
+        steps,
+    };
+
+    let report_path = report_file
+        .map(|path| PathBuf::from(shellexpand::tilde(&path).into_owned()))
+        .unwrap_or_else(|| {
+            PathBuf::from(format!(
+                "plan10-decommission-{}-{}.json",
+                hostname,
+                started_at.format("%Y%m%d_%H%M%S")
+            ))
+        });
+    write_report(&report, &report_path)?;
+
+    println!();
+    print_success(&format!("Decommission report written to {}", report_path.display()));
+
+    let signing_key = sign_key
+        .map(|key| PathBuf::from(shellexpand::tilde(&key).into_owned()))
+        .or_else(|| config.get_ssh_key_path());
+    match signing_key {
+        Some(key) if key.exists() => match sign_report(&report_path, &key) {
+            Ok(signature) => {
+                print_success(&format!("Report signed: {}", signature.display()));
+                println!(
+                    "Verify with: ssh-keygen -Y check-novalidate -n {} -f {}.pub -s {} < {}",
+                    SIGNATURE_NAMESPACE,
+                    key.display(),
+                    signature.display(),
+                    report_path.display()
+                );
+            }
+            Err(e) => print_warning(&format!("Report left unsigned: {}", e)),
+        },
+        _ => print_warning("Report left unsigned: no signing key found (use --sign-key)"),
+    }
+
+    if report.steps.iter().any(|s| matches!(s.status, StepStatus::Failed)) {
+        print_warning("Some steps failed; review the report before handing the machine over");
+    } else {
+        print_success("Plan 10 removed from this machine");
+    }
+
+    Ok(())
+}
+
+fn confirm_hostname(hostname: &str, confirm: Option<String>) -> Result<()> {
+    let typed = match confirm {
+        Some(typed) => typed,
+        None => {
+            print!("{} ", format!("Type the hostname '{}' to continue:", hostname).yellow());
+            io::stdout().flush()?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            input.trim().to_string()
+        }
+    };
+
+    if typed != hostname {
+        anyhow::bail!("Confirmation did not match hostname '{}'; nothing was changed", hostname);
+    }
+    Ok(())
+}
+
+fn stop_services(dry_run: bool) -> StepRecord {
+    let mut details = Vec::new();
+    let mut failed = false;
+
+    for agent in LAUNCH_AGENTS {
+        let path = shellexpand::tilde(agent).into_owned();
+        if !Path::new(&path).exists() {
+            continue;
+        }
+        if dry_run {
+            details.push(format!("would unload and delete {}", path));
+            continue;
+        }
+
+        // Unloading fails when the agent isn't loaded, which is fine here
+        let _ = run_command_with_status("launchctl", &["unload", "-w", &path]);
+        match fs::remove_file(&path) {
+            Ok(()) => details.push(format!("removed {}", path)),
+            Err(e) => {
+                failed = true;
+                details.push(format!("could not remove {}: {}", path, e));
+            }
+        }
+    }
+
+    for (label, path) in LAUNCH_DAEMONS {
+        if !Path::new(path).exists() {
+            continue;
+        }
+        if dry_run {
+            details.push(format!("would boot out system/{} and delete {}", label, path));
+            continue;
+        }
+
+        // As above, a daemon that isn't loaded is fine
+        let _ = run_command_with_status("sudo", &["launchctl", "bootout", &format!("system/{}", label)]);
+        match run_command_with_status("sudo", &["rm", "-f", path]) {
+            Ok((_, _, true)) => details.push(format!("removed {}", path)),
+            Ok((_, stderr, false)) => {
+                failed = true;
+                details.push(format!("could not remove {}: {}", path, stderr.trim()));
+            }
+            Err(e) => {
+                failed = true;
+                details.push(format!("could not remove {}: {}", path, e));
+            }
+        }
+    }
+
+    if dry_run {
+        details.push("would stop caffeinate".to_string());
+    } else if let Ok((_, _, true)) = run_command_with_status("pkill", &["caffeinate"]) {
+        details.push("stopped caffeinate".to_string());
+    }
+
+    step("Stop services", details, failed)
+}
+
+fn remove_artifacts(dry_run: bool) -> StepRecord {
+    let mut details = Vec::new();
+    let mut failed = false;
+
+    let mut paths: Vec<PathBuf> = ARTIFACT_FILES.iter()
+        .chain(ARTIFACT_DIRS)
+        .map(|p| PathBuf::from(shellexpand::tilde(p).into_owned()))
+        .collect();
+    paths.extend(Config::default_config_path().and_then(|p| p.parent().map(Path::to_path_buf)));
+    paths.extend(Config::state_dir());
+    paths.extend(plan10_logs());
+
+    for path in paths {
+        if !path.exists() {
+            continue;
+        }
+        if dry_run {
+            details.push(format!("would delete {}", path.display()));
+            continue;
+        }
+
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => details.push(format!("deleted {}", path.display())),
+            Err(e) => {
+                failed = true;
+                details.push(format!("could not delete {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    // Only remove ~/scripts if Plan 10 was the only thing in it
+    let scripts_dir = PathBuf::from(shellexpand::tilde("~/scripts").into_owned());
+    if !dry_run && fs::read_dir(&scripts_dir).is_ok_and(|mut d| d.next().is_none()) {
+        let _ = fs::remove_dir(&scripts_dir);
+    }
+
+    step("Remove Plan 10 files", details, failed)
+}
+
+fn plan10_logs() -> Vec<PathBuf> {
+    let logs_dir = PathBuf::from(shellexpand::tilde("~/logs").into_owned());
+    let Ok(entries) = fs::read_dir(&logs_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().starts_with("plan10"))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+fn revoke_keys(dry_run: bool) -> StepRecord {
+    let path = PathBuf::from(shellexpand::tilde("~/.ssh/authorized_keys").into_owned());
+    let Ok(content) = fs::read_to_string(&path) else {
+        return step("Revoke deployed SSH keys", vec!["no authorized_keys file".to_string()], false);
+    };
+
+    // Only the comment says who a key belongs to; "plan10" can turn up by
+    // chance inside someone else's key blob
+    let (revoked, kept): (Vec<&str>, Vec<&str>) = content
+        .lines()
+        .partition(|line| {
+            !line.trim_start().starts_with('#')
+                && key_comment(line).is_some_and(|comment| comment.to_lowercase().contains("plan10"))
+        });
+
+    let mut details: Vec<String> = revoked.iter()
+        .map(|line| {
+            let comment = key_comment(line).unwrap_or_default();
+            format!("{} key {}", if dry_run { "would revoke" } else { "revoked" }, comment)
+        })
+        .collect();
+
+    if revoked.is_empty() || dry_run {
+        return step("Revoke deployed SSH keys", details, false);
+    }
+
+    let mut new_content = kept.join("\n");
+    new_content.push('\n');
+    if let Err(e) = fs::write(&path, new_content) {
+        details.push(format!("could not rewrite {}: {}", path.display(), e));
+        return step("Revoke deployed SSH keys", details, true);
+    }
+
+    step("Revoke deployed SSH keys", details, false)
+}
+
+/// The comment of an authorized_keys entry: whatever follows the key type
+/// and blob, after any options (which may hold quoted spaces).
+fn key_comment(line: &str) -> Option<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    for c in line.trim().chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                field.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !field.is_empty() {
+                    fields.push(std::mem::take(&mut field));
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() {
+        fields.push(field);
+    }
+
+    let key_type = fields.iter()
+        .position(|field| ["ssh-", "ecdsa-", "sk-"].iter().any(|prefix| field.starts_with(prefix)))?;
+    let comment = fields.get(key_type + 2..)?.join(" ");
+    (!comment.is_empty()).then_some(comment)
+}
+
+fn clear_secrets(dry_run: bool) -> StepRecord {
+    if dry_run {
+        return step(
+            "Clear stored secrets",
+            vec![format!("would delete keychain items for service '{}'", KEYCHAIN_SERVICE)],
+            false,
+        );
+    }
+
+    // delete-generic-password removes one matching item per call
+    let mut removed = 0;
+    while removed < 100 {
+        match run_command_with_status("security", &["delete-generic-password", "-s", KEYCHAIN_SERVICE]) {
+            Ok((_, _, true)) => removed += 1,
+            _ => break,
+        }
+    }
+
+    step("Clear stored secrets", vec![format!("deleted {} keychain item(s)", removed)], false)
+}
+
+fn disable_auto_login(dry_run: bool) -> StepRecord {
+    if dry_run {
+        return step("Disable automatic login", vec!["would remove autoLoginUser and /etc/kcpassword".to_string()], false);
+    }
+
+    let mut details = Vec::new();
+    let mut failed = false;
+
+    match run_command_with_status("sudo", &["defaults", "delete", "/Library/Preferences/com.apple.loginwindow", "autoLoginUser"]) {
+        Ok((_, _, true)) => details.push("removed autoLoginUser".to_string()),
+        Ok((_, stderr, false)) if stderr.contains("does not exist") => {
+            details.push("automatic login was not enabled".to_string());
+        }
+        Ok((_, stderr, false)) => {
+            failed = true;
+            details.push(format!("could not remove autoLoginUser: {}", stderr.trim()));
+        }
+        Err(e) => {
+            failed = true;
+            details.push(format!("could not remove autoLoginUser: {}", e));
+        }
+    }
+
+    if !matches!(run_command_with_status("sudo", &["rm", "-f", "/etc/kcpassword"]), Ok((_, _, true))) {
+        failed = true;
+        details.push("could not remove /etc/kcpassword".to_string());
+    }
+
+    step("Disable automatic login", details, failed)
+}
+
+fn restore_power_defaults(dry_run: bool) -> StepRecord {
+    if dry_run {
+        return step("Restore power settings", vec!["would run pmset -a restoredefaults".to_string()], false);
+    }
+
+    match run_command_with_status("sudo", &["pmset", "-a", "restoredefaults"]) {
+        Ok((_, _, true)) => step("Restore power settings", vec!["power settings reset to macOS defaults".to_string()], false),
+        Ok((_, stderr, false)) => step("Restore power settings", vec![stderr.trim().to_string()], true),
+        Err(e) => step("Restore power settings", vec![e.to_string()], true),
+    }
+}
+
+fn step(name: &str, details: Vec<String>, failed: bool) -> StepRecord {
+    let status = if failed {
+        StepStatus::Failed
+    } else if details.is_empty() {
+        StepStatus::Skipped
+    } else {
+        StepStatus::Done
+    };
+
+    StepRecord {
+        step: name.to_string(),
+        status,
+        details,
+    }
+}
+
+fn serial_number() -> Option<String> {
+    let output = Command::new("ioreg")
+        .args(["-c", "IOPlatformExpertDevice", "-d", "2"])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    stdout.lines()
+        .find(|line| line.contains("IOPlatformSerialNumber"))
+        .and_then(|line| line.split('"').nth(3))
+        .map(|serial| serial.to_string())
+}
+
+fn write_report(report: &DecommissionReport, path: &Path) -> Result<()> {
+    let content = serde_json::to_string_pretty(report)?;
+    fs::write(path, content)
+        .context(format!("Failed to write decommission report: {}", path.display()))
+}
+
+fn sign_report(report_path: &Path, key: &Path) -> Result<PathBuf> {
+    let (_, stderr, success) = run_command_with_status(
+        "ssh-keygen",
+        &[
+            "-Y", "sign",
+            "-n", SIGNATURE_NAMESPACE,
+            "-f", &key.to_string_lossy(),
+            &report_path.to_string_lossy(),
+        ],
+    )?;
+
+    if !success {
+        anyhow::bail!("ssh-keygen failed: {}", stderr.trim());
+    }
+
+    let mut signature = report_path.as_os_str().to_owned();
+    signature.push(".sig");
+    Ok(PathBuf::from(signature))
+}
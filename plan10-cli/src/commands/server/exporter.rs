@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::commands::utils::*;
+use crate::commands::shared::{battery_runtime, readings::{self, PowerSource, Source}};
+use crate::utils::system::get_system_info;
+use std::fmt::Write;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+pub async fn execute_exporter(bind: String, port: u16, config: &Config, verbose: bool) -> Result<()> {
+    let address = format!("{}:{}", bind, port);
+    let listener = TcpListener::bind(&address).await
+        .context(format!("Failed to listen on {}", address))?;
+    print_info(&format!("Prometheus metrics on http://{}/metrics", address));
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        print_verbose(&format!("Scrape from {}", peer), verbose);
+        let config = config.clone();
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, config).await;
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, config: Config) -> Result<()> {
+    let mut buffer = [0u8; 4096];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().unwrap_or("");
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") => {
+            // Readings shell out to pmset and read the sensors, so keep them off the runtime
+            let body = tokio::task::spawn_blocking(move || collect_metrics(&config)).await?;
+            ("200 OK", "text/plain; version=0.0.4", body)
+        }
+        ("GET", "/") => ("200 OK", "text/plain", "Plan 10 exporter, metrics at /metrics\n".to_string()),
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "read-only\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Prometheus text exposition of one set of local readings. Metrics that
+/// can't be read on this machine are left out rather than reported as zero.
+fn collect_metrics(config: &Config) -> String {
+    let source = Source::Local;
+    let thresholds = config.thresholds(None);
+    let mut metrics = Metrics::default();
+
+    let power = readings::power(&source);
+    metrics.gauge("plan10_on_ac_power", "Whether the machine runs on AC power", &[], bool_value(power.source == PowerSource::Ac));
+    metrics.gauge("plan10_on_battery", "Whether the machine runs on battery", &[], bool_value(power.source == PowerSource::Battery));
+    if let Some(pct) = power.battery_percent {
+        metrics.gauge("plan10_battery_percent", "Battery charge in percent", &[], pct as f64);
+    }
+
+    let temperature = readings::temperature(&source, &thresholds);
+    if let Some(celsius) = temperature.cpu_celsius {
+        metrics.gauge("plan10_cpu_temperature_celsius", "CPU die temperature", &[], celsius as f64);
+    }
+    if let Some(celsius) = temperature.gpu_celsius {
+        metrics.gauge("plan10_gpu_temperature_celsius", "GPU die temperature", &[], celsius as f64);
+    }
+    for fan in &temperature.fans {
+        metrics.gauge("plan10_fan_rpm", "Fan speed", &[("fan", &fan.fan.to_string())], fan.rpm as f64);
+    }
+    // Samples of one metric must be contiguous, hence two passes
+    for (index, gpu) in temperature.gpus.iter().enumerate() {
+        if let Some(percent) = gpu.utilization_percent {
+            let index = index.to_string();
+            let labels = [("gpu", index.as_str()), ("name", gpu.name.as_deref().unwrap_or(""))];
+            metrics.gauge("plan10_gpu_utilization_percent", "GPU utilization", &labels, percent as f64);
+        }
+    }
+    for (index, gpu) in temperature.gpus.iter().enumerate() {
+        if let Some(bytes) = gpu.memory_used_bytes {
+            let index = index.to_string();
+            let labels = [("gpu", index.as_str()), ("name", gpu.name.as_deref().unwrap_or(""))];
+            metrics.gauge("plan10_gpu_memory_used_bytes", "GPU memory in use", &labels, bytes as f64);
+        }
+    }
+    if let Some(limit) = temperature.thermal.cpu_speed_limit {
+        metrics.gauge("plan10_cpu_speed_limit_percent", "CPU speed allowed by thermal management", &[], limit as f64);
+    }
+    if let Some(limit) = temperature.thermal.scheduler_limit {
+        metrics.gauge("plan10_cpu_scheduler_limit_percent", "CPU scheduler time allowed by thermal management", &[], limit as f64);
+    }
+    metrics.gauge("plan10_temperature_threshold_celsius", "Configured temperature alert threshold", &[], thresholds.temp as f64);
+
+    let info = get_system_info().ok();
+    if let Some(info) = &info {
+        metrics.gauge("plan10_cpu_usage_percent", "CPU usage across all cores", &[], info.cpu_usage as f64);
+        for (period, value) in [("1m", info.load_average.0), ("5m", info.load_average.1), ("15m", info.load_average.2)] {
+            metrics.gauge("plan10_load_average", "System load average", &[("period", period)], value);
+        }
+        metrics.gauge("plan10_memory_total_bytes", "Installed memory", &[], info.memory_total as f64);
+        metrics.gauge("plan10_memory_used_bytes", "Memory in use", &[], info.memory_used as f64);
+        metrics.gauge("plan10_memory_available_bytes", "Memory available for new allocations", &[], info.memory_available as f64);
+        // Samples of one metric must be contiguous, hence two passes
+        for disk in &info.disks {
+            metrics.gauge("plan10_disk_total_bytes", "Volume size", &[("mountpoint", &disk.mount_point)], disk.total_space as f64);
+        }
+        for disk in &info.disks {
+            metrics.gauge("plan10_disk_used_bytes", "Space used on the volume", &[("mountpoint", &disk.mount_point)], disk.used_space as f64);
+        }
+        metrics.gauge("plan10_uptime_seconds", "Time since boot", &[], info.uptime as f64);
+    }
+
+    let caffeinate_running = readings::caffeinate_running(&source);
+    metrics.gauge("plan10_caffeinate_running", "Whether caffeinate is keeping the machine awake", &[], bool_value(caffeinate_running));
+
+    // Only numeric pmset values; the rest (e.g. hibernatefile) aren't samples
+    let power_settings = readings::power_settings(&source, config);
+    for (setting, value) in &power_settings.settings {
+        if let Ok(value) = value.parse::<f64>() {
+            metrics.gauge("plan10_pmset_setting", "Current pmset value", &[("setting", setting.as_str())], value);
+        }
+    }
+    metrics.gauge("plan10_power_profile_drift", "Settings that differ from [power_profile]", &[], power_settings.drift.len() as f64);
+
+    // Scrapes only read: anyone who can reach the port may scrape, so
+    // sampling and alerting are left to `server monitor-daemon`
+    let runtime = battery_runtime::estimate(&source, None, power.source == PowerSource::Battery, power.battery_percent, config);
+    if let Some(runtime) = &runtime {
+        metrics.gauge("plan10_battery_runtime_minutes", "Estimated minutes on battery until the halt level", &[], runtime.minutes as f64);
+    }
+    metrics.output
+}
+
+fn bool_value(value: bool) -> f64 {
+    if value { 1.0 } else { 0.0 }
+}
+
+/// Accumulates gauges, writing each metric's HELP/TYPE once before its
+/// first sample as the exposition format requires.
+#[derive(Default)]
+struct Metrics {
+    output: String,
+    described: Vec<&'static str>,
+}
+
+impl Metrics {
+    fn gauge(&mut self, name: &'static str, help: &str, labels: &[(&str, &str)], value: f64) {
+        if !self.described.contains(&name) {
+            self.described.push(name);
+            let _ = writeln!(self.output, "# HELP {} {}", name, help);
+            let _ = writeln!(self.output, "# TYPE {} gauge", name);
+        }
+
+        let labels: Vec<String> = labels.iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect();
+        if labels.is_empty() {
+            let _ = writeln!(self.output, "{} {}", name, value);
+        } else {
+            let _ = writeln!(self.output, "{}{{{}}} {}", name, labels.join(","), value);
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use crate::{MaintenanceActions, Config};
 use crate::commands::utils::*;
+use crate::commands::shared::readings::{self, DiskCondition};
 use colored::*;
 use std::process::Command;
 use std::fs;
@@ -18,8 +19,8 @@ pub async fn execute_maintenance_action(
         MaintenanceActions::Clean => {
             clean_temporary_files(verbose).await
         }
-        MaintenanceActions::Backup { output } => {
-            backup_configuration(output, config, verbose).await
+        MaintenanceActions::Backup { file } => {
+            backup_configuration(file, config, verbose).await
         }
         MaintenanceActions::Restore { input } => {
             restore_configuration(input, verbose).await
@@ -358,6 +359,69 @@ async fn run_health_check(config: &Config, verbose: bool) -> Result<()> {
         }
     }
     
+    // Check CPU load
+    println!("\n{}:", "CPU Load".bold());
+    let thresholds = config.thresholds(None);
+    let mut cpu_busy = false;
+    match readings::cpu_usage(&readings::Source::Local) {
+        Some(usage) if thresholds.cpu_pegged(usage) => {
+            print_warning(&format!("CPU usage: {:.0}% (warning at {}%)", usage, thresholds.cpu_warning_level));
+            warnings += 1;
+            cpu_busy = true;
+        }
+        Some(usage) => print_success(&format!("CPU usage: {:.0}% (OK)", usage)),
+        None => print_info("CPU usage unavailable"),
+    }
+    match readings::cpu_load(&readings::Source::Local) {
+        Some(load) if load.saturated() => {
+            print_warning(&format!(
+                "Load average: {:.2} {:.2} {:.2} on {} cores (Saturated)",
+                load.load_1m, load.load_5m, load.load_15m, load.cores
+            ));
+            warnings += 1;
+            cpu_busy = true;
+        }
+        Some(load) => print_success(&format!(
+            "Load average: {:.2} {:.2} {:.2} on {} cores (OK)",
+            load.load_1m, load.load_5m, load.load_15m, load.cores
+        )),
+        None => print_info("Load average unavailable"),
+    }
+
+    println!("\n{}:", "Temperature".bold());
+    let mut too_hot = false;
+    match crate::sensors::read_sensors().cpu_celsius {
+        Some(celsius) if thresholds.temp_exceeded(celsius) => {
+            print_warning(&format!("CPU temperature: {:.1}°C (threshold {:.1}°C)", celsius, thresholds.temp));
+            warnings += 1;
+            too_hot = true;
+        }
+        Some(celsius) => print_success(&format!("CPU temperature: {:.1}°C (OK)", celsius)),
+        None => print_info("CPU temperature unavailable"),
+    }
+
+    // Check disk SMART health
+    println!("\n{}:", "Disk Health".bold());
+    let disks = readings::disks(&readings::Source::Local, None).disks;
+    if disks.is_empty() {
+        print_warning("No physical disks found");
+        warnings += 1;
+    }
+    for disk in &disks {
+        match disk.condition() {
+            DiskCondition::Healthy => print_success(&format!("{}: Healthy", disk.device)),
+            DiskCondition::Degraded(reason) => {
+                print_warning(&format!("{}: {}", disk.device, reason));
+                warnings += 1;
+            }
+            DiskCondition::Failing(reason) => {
+                print_error(&format!("{}: Failing ({}), back up now", disk.device, reason));
+                issues += 1;
+            }
+            DiskCondition::Unknown => print_info(&format!("{}: SMART status unavailable", disk.device)),
+        }
+    }
+    
     // Summary
     println!("\n{}:", "Health Summary".bold());
     if issues == 0 && warnings == 0 {
@@ -376,6 +440,12 @@ async fn run_health_check(config: &Config, verbose: bool) -> Result<()> {
         if warnings > 0 {
             println!("  • Run 'plan10 monitor power --fixes' for power management recommendations");
         }
+        if cpu_busy {
+            println!("  • Run 'plan10 monitor system' to find the process keeping the CPU busy");
+        }
+        if too_hot {
+            println!("  • Run 'plan10 monitor temp' and check airflow around the machine");
+        }
     }
     
     Ok(())
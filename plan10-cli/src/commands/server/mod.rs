@@ -1,5 +1,5 @@
 use anyhow::Result;
-use crate::{ServerCommands, PowerActions, MaintenanceActions, Config};
+use crate::{ServerCommands, PowerActions, MaintenanceActions, Config, OutputFormat};
 use crate::commands::utils::*;
 use colored::*;
 use std::process::Command;
@@ -8,8 +8,13 @@ pub mod configure;
 pub mod services;
 pub mod power;
 pub mod maintenance;
+pub mod decommission;
+pub mod api;
+pub mod exporter;
+pub mod watchdog;
+pub mod monitor_daemon;
 
-pub async fn execute(cmd: ServerCommands, config: &Config, verbose: bool) -> Result<()> {
+pub async fn execute(cmd: ServerCommands, config: &Config, output: OutputFormat, verbose: bool) -> Result<()> {
     // Ensure we're on macOS for server operations
     if !cfg!(target_os = "macos") {
         print_warning("Server commands are designed for macOS systems");
@@ -32,7 +37,7 @@ pub async fn execute(cmd: ServerCommands, config: &Config, verbose: bool) -> Res
             services::restart_services(service, config, verbose).await
         }
         ServerCommands::Services { detailed } => {
-            services::show_services(detailed, config, verbose).await
+            services::show_services(detailed, config, output, verbose).await
         }
         ServerCommands::Power { action } => {
             power::execute_power_action(action, config, verbose).await
@@ -40,6 +45,37 @@ pub async fn execute(cmd: ServerCommands, config: &Config, verbose: bool) -> Res
         ServerCommands::Maintenance { action } => {
             maintenance::execute_maintenance_action(action, config, verbose).await
         }
+        ServerCommands::Api { listen, install } => {
+            api::execute_api(listen, install, config, verbose).await
+        }
+        ServerCommands::Exporter { port, bind } => {
+            exporter::execute_exporter(bind, port, config, verbose).await
+        }
+        ServerCommands::Watchdog { probe, interval, install, outages, since } => {
+            watchdog::execute_watchdog(probe, interval, install, outages, since, config, verbose).await
+        }
+        ServerCommands::MonitorDaemon { interval, install } => {
+            monitor_daemon::execute_monitor_daemon(interval, install, config, verbose).await
+        }
+        ServerCommands::Decommission {
+            confirm,
+            disable_autologin,
+            reset_power,
+            report,
+            sign_key,
+            dry_run,
+        } => {
+            decommission::execute_decommission(
+                confirm,
+                disable_autologin,
+                reset_power,
+                report,
+                sign_key,
+                dry_run,
+                config,
+                verbose,
+            ).await
+        }
     }
 }
 
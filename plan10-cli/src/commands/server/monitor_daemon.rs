@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::alerts;
+use crate::commands::utils::*;
+use crate::commands::shared::{battery_runtime, battery_trend, metrics_history};
+use crate::commands::shared::readings::{self, BatteryHealth, HostReading, PowerSource, Source, StatusReading};
+use crate::snapshot::StatusSnapshot;
+use chrono::Utc;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::time::{sleep, Duration};
+
+const MONITOR_LABEL: &str = "com.plan10.monitor";
+const PLIST_PATH: &str = "~/Library/LaunchAgents/com.plan10.monitor.plist";
+const LOG_PATH: &str = "~/logs/plan10-monitor.jsonl";
+
+/// Rotate at the same size as `~/scripts/rotate_logs.sh`.
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+/// Rotated logs kept as plan10-monitor.jsonl.1 (newest) to .5
+const KEPT_LOGS: usize = 5;
+
+/// One line of the monitoring log.
+#[derive(Serialize)]
+struct Snapshot {
+    #[serde(flatten)]
+    status: StatusReading,
+    battery_health: BatteryHealth,
+}
+
+pub async fn execute_monitor_daemon(
+    interval: Option<u64>,
+    install: bool,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    if install {
+        return install_launch_agent(config, verbose);
+    }
+
+    let interval = Duration::from_secs(interval.unwrap_or(config.server.monitoring_interval).max(1));
+    let log_path = PathBuf::from(shellexpand::tilde(LOG_PATH).into_owned());
+    print_info(&format!("Sampling every {}s into {}", interval.as_secs(), log_path.display()));
+
+    loop {
+        if let Err(e) = sample(&log_path, config, verbose) {
+            print_warning(&format!("Could not write snapshot: {}", e));
+        }
+
+        tokio::select! {
+            _ = sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Take one reading, log it, and feed it to the alert rules, the monitoring
+/// history, the battery trend and `status --cached`.
+fn sample(log_path: &Path, config: &Config, verbose: bool) -> Result<()> {
+    let source = Source::Local;
+    let mut status = readings::status(&source, config.thresholds(None));
+    status.battery_runtime = battery_runtime::estimate(&source, None, status.power.source == PowerSource::Battery, status.power.battery_percent, config);
+    let battery_health = readings::battery(&source).health;
+
+    alerts::check(&config.server.name, &status.alert_input(), None, config, verbose);
+    metrics_history::record_sample(config, &config.server.name, status.sample(), verbose);
+    battery_trend::record_health(config, &config.server.name, &battery_health, verbose);
+
+    let snapshot = StatusSnapshot {
+        timestamp: Utc::now(),
+        hostname: hostname::get().unwrap_or_default().to_string_lossy().to_string(),
+        on_battery: status.power.source == PowerSource::Battery,
+        on_ac: status.power.source == PowerSource::Ac,
+        battery_percent: status.power.battery_percent,
+        caffeinate_running: status.caffeinate_running,
+        health_issues: status.issues.len(),
+    };
+    if let Err(e) = snapshot.save() {
+        print_verbose(&format!("Could not save status snapshot: {}", e), verbose);
+    }
+    for issue in &status.issues {
+        print_verbose(issue, verbose);
+    }
+
+    let reading = HostReading {
+        host: config.server.name.clone(),
+        timestamp: Utc::now(),
+        reading: Snapshot { status, battery_health },
+    };
+    append(log_path, &serde_json::to_string(&reading)?)
+}
+
+fn append(log_path: &Path, line: &str) -> Result<()> {
+    if let Some(dir) = log_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::metadata(log_path).is_ok_and(|metadata| metadata.len() >= MAX_LOG_BYTES) {
+        rotate(log_path)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .context(format!("Failed to open {}", log_path.display()))?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Shift plan10-monitor.jsonl.N up by one, dropping the oldest.
+fn rotate(log_path: &Path) -> Result<()> {
+    let generation = |n: usize| PathBuf::from(format!("{}.{}", log_path.display(), n));
+    let _ = fs::remove_file(generation(KEPT_LOGS));
+    for n in (1..KEPT_LOGS).rev() {
+        if generation(n).exists() {
+            fs::rename(generation(n), generation(n + 1))?;
+        }
+    }
+    fs::rename(log_path, generation(1))?;
+    Ok(())
+}
+
+/// A per-user LaunchAgent: the readings need no root, and alerts, history
+/// and `status --cached` belong to the user anyway.
+pub fn install_launch_agent(config: &Config, verbose: bool) -> Result<()> {
+    print_info("Setting up LaunchAgent for the monitoring daemon...");
+
+    let program = std::env::current_exe()?;
+    let config_path = config.path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{program}</string>
+        <string>--config</string>
+        <string>{config_path}</string>
+        <string>server</string>
+        <string>monitor-daemon</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>/tmp/plan10-monitor.log</string>
+    <key>StandardErrorPath</key>
+    <string>/tmp/plan10-monitor.log</string>
+</dict>
+</plist>
+"#,
+        label = MONITOR_LABEL,
+        program = program.display(),
+        config_path = config_path,
+    );
+
+    let plist_path = shellexpand::tilde(PLIST_PATH).into_owned();
+    if let Some(dir) = Path::new(&plist_path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&plist_path, plist).context(format!("Failed to write {}", plist_path))?;
+    print_success(&format!("LaunchAgent written to {}", plist_path));
+
+    // Reload so a changed interval or binary takes effect
+    if super::is_launchagent_loaded(MONITOR_LABEL).unwrap_or(false) {
+        let _ = std::process::Command::new("launchctl").args(["unload", &plist_path]).output();
+    }
+    match super::load_launchagent(&plist_path) {
+        Ok(()) => print_success(&format!(
+            "Monitoring daemon running every {}s; snapshots in {}",
+            config.server.monitoring_interval,
+            LOG_PATH
+        )),
+        Err(e) => print_warning(&e.to_string()),
+    }
+    print_verbose("Daemon output goes to /tmp/plan10-monitor.log", verbose);
+
+    Ok(())
+}
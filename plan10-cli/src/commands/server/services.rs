@@ -1,5 +1,5 @@
 use anyhow::Result;
-use crate::Config;
+use crate::{Config, OutputFormat};
 use crate::commands::utils::*;
 use colored::*;
 use std::process::Command;
@@ -39,7 +39,13 @@ pub async fn restart_services(service: Option<String>, config: &Config, verbose:
     }
 }
 
-pub async fn show_services(detailed: bool, config: &Config, verbose: bool) -> Result<()> {
+const LAUNCH_AGENTS: [&str; 3] = ["com.plan10.caffeinate", "caffeinate", "com.plan10.monitor"];
+
+pub async fn show_services(detailed: bool, config: &Config, output: OutputFormat, verbose: bool) -> Result<()> {
+    if output == OutputFormat::Json {
+        return print_services_json(config);
+    }
+
     print_header("Plan 10 Services Status");
     
     let services = &config.server.services;
@@ -60,12 +66,7 @@ pub async fn show_services(detailed: bool, config: &Config, verbose: bool) -> Re
     
     // Check LaunchAgents
     println!("\n{}:", "LaunchAgents".bold());
-    let launch_agents = vec![
-        "com.plan10.caffeinate",
-        "caffeinate",
-    ];
-    
-    for agent in launch_agents {
+    for agent in LAUNCH_AGENTS {
         let loaded = super::is_launchagent_loaded(agent)?;
         let status_icon = if loaded { "🟢" } else { "🔴" };
         println!("{} {}: {}", status_icon, agent,
@@ -75,6 +76,22 @@ pub async fn show_services(detailed: bool, config: &Config, verbose: bool) -> Re
     Ok(())
 }
 
+fn print_services_json(config: &Config) -> Result<()> {
+    let mut services = Vec::new();
+    for name in &config.server.services {
+        let running = super::is_service_running(name)?;
+        let pid = if running { super::get_service_pid(name).ok().flatten() } else { None };
+        services.push(serde_json::json!({ "name": name, "running": running, "pid": pid }));
+    }
+
+    let mut launch_agents = Vec::new();
+    for name in LAUNCH_AGENTS {
+        launch_agents.push(serde_json::json!({ "name": name, "loaded": super::is_launchagent_loaded(name)? }));
+    }
+
+    print_json(&serde_json::json!({ "services": services, "launch_agents": launch_agents }))
+}
+
 async fn start_all_services(config: &Config, verbose: bool) -> Result<()> {
     for service in &config.server.services {
         start_specific_service(service, verbose).await?;
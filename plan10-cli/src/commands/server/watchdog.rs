@@ -0,0 +1,295 @@
+use anyhow::{Context, Result};
+use crate::Config;
+use crate::commands::utils::*;
+use crate::config::WatchdogAction;
+use crate::history::{parse_since, window_start, History, HistoryEntry, HistoryRecord};
+use chrono::{DateTime, Local, Utc};
+use colored::*;
+use std::process::Command;
+use tokio::time::{sleep, Duration};
+
+const WATCHDOG_LABEL: &str = "com.plan10.watchdog";
+const PLIST_PATH: &str = "/Library/LaunchDaemons/com.plan10.watchdog.plist";
+
+pub async fn execute_watchdog(
+    probe: Option<String>,
+    interval: Option<u64>,
+    install: bool,
+    outages: bool,
+    since: String,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    if install {
+        return install_launch_daemon(config, verbose);
+    }
+    if outages {
+        return list_outages(&since, config);
+    }
+
+    let settings = &config.watchdog;
+    let probe = probe.or_else(|| settings.probe.clone());
+    let interval = Duration::from_secs(interval.unwrap_or(settings.interval).max(1));
+    print_info(&format!(
+        "Watching connectivity to {} every {}s",
+        probe.as_deref().unwrap_or("the default gateway"),
+        interval.as_secs()
+    ));
+
+    let mut failures = 0;
+    let mut first_failure: Option<DateTime<Utc>> = None;
+    let mut outage: Option<Outage> = None;
+    loop {
+        // Resolved on every probe, as the gateway changes with the network
+        let target = probe.clone().or_else(|| default_route().map(|route| route.gateway));
+        let reachable = match &target {
+            Some(target) => ping(target).await,
+            None => false,
+        };
+        let target = target.unwrap_or_else(|| "default gateway".to_string());
+        print_verbose(&format!("Probe {}: {}", target, if reachable { "ok" } else { "failed" }), verbose);
+
+        if reachable {
+            if let Some(outage) = outage.take() {
+                outage.finish(config, true);
+            }
+            failures = 0;
+            first_failure = None;
+        } else {
+            failures += 1;
+            let started = *first_failure.get_or_insert_with(Utc::now);
+            if outage.is_none() && failures >= settings.failures {
+                print_warning(&format!("Connectivity lost: {} unreachable since {}", target, local_time(&started)));
+                outage = Some(Outage { started, probe: target, actions: Vec::new() });
+            }
+            // One recovery step per failed probe, so each gets checked
+            if let Some(outage) = &mut outage {
+                if let Some(&action) = settings.actions.get(outage.actions.len()) {
+                    outage.actions.push(action_name(action).to_string());
+                    if let Err(e) = run_action(action, &outage.probe, config, verbose) {
+                        print_warning(&format!("Recovery action {} failed: {}", action_name(action), e));
+                    }
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => {
+                // Still record an outage cut short, up to now
+                if let Some(outage) = outage.take() {
+                    outage.finish(config, false);
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+struct Outage {
+    started: DateTime<Utc>,
+    probe: String,
+    actions: Vec<String>,
+}
+
+impl Outage {
+    fn finish(self, config: &Config, restored: bool) {
+        let seconds = (Utc::now() - self.started).num_seconds().max(0) as u64;
+        match self.actions.last().filter(|_| restored) {
+            Some(action) => print_success(&format!("Connectivity restored after {} (last action: {})", format_seconds(seconds), action)),
+            None if restored => print_success(&format!("Connectivity restored after {}", format_seconds(seconds))),
+            None => print_warning(&format!("Stopped during an outage of {}", format_seconds(seconds))),
+        }
+
+        let record = HistoryRecord {
+            timestamp: Utc::now(),
+            host: config.server.name.clone(),
+            entry: HistoryEntry::Outage { started: self.started, probe: self.probe, actions: self.actions },
+        };
+        if let Err(e) = History::open(config).and_then(|history| history.append(&record)) {
+            print_warning(&format!("Could not record outage: {}", e));
+        }
+    }
+}
+
+/// One ping with a two-second deadline.
+async fn ping(host: &str) -> bool {
+    let timeout_flag = if cfg!(target_os = "macos") { "-t" } else { "-W" };
+    tokio::process::Command::new("ping")
+        .args(["-c", "1", timeout_flag, "2", host])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
+}
+
+struct DefaultRoute {
+    gateway: String,
+    interface: String,
+}
+
+/// From `route -n get default`; `None` without a default route, which
+/// itself means no connectivity.
+fn default_route() -> Option<DefaultRoute> {
+    let output = Command::new("route").args(["-n", "get", "default"]).output().ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let field = |name: &str| output.lines()
+        .find_map(|line| line.trim().strip_prefix(name))
+        .map(|value| value.trim().to_string());
+    Some(DefaultRoute { gateway: field("gateway:")?, interface: field("interface:")? })
+}
+
+fn action_name(action: WatchdogAction) -> &'static str {
+    match action {
+        WatchdogAction::Caffeinate => "caffeinate",
+        WatchdogAction::BounceInterface => "bounce-interface",
+        WatchdogAction::Script => "script",
+    }
+}
+
+fn run_action(action: WatchdogAction, probe: &str, config: &Config, verbose: bool) -> Result<()> {
+    let settings = &config.watchdog;
+    match action {
+        WatchdogAction::Caffeinate => {
+            if super::is_service_running("caffeinate")? {
+                print_verbose("Caffeinate already running", verbose);
+                return Ok(());
+            }
+            Command::new("caffeinate").arg("-imsud").spawn()?;
+            print_info("Started caffeinate");
+        }
+        WatchdogAction::BounceInterface => {
+            // Without a default route the interface can't be looked up
+            let interface = settings.interface.clone()
+                .or_else(|| default_route().map(|route| route.interface))
+                .context("No interface to bounce; set watchdog.interface")?;
+            print_info(&format!("Bouncing {}", interface));
+            let script = format!(
+                "sudo -n ifconfig {0} down && sleep 2 && sudo -n ifconfig {0} up",
+                crate::ssh::shell_quote(&interface)
+            );
+            let status = Command::new("/bin/sh").args(["-c", &script]).status()?;
+            if !status.success() {
+                anyhow::bail!("ifconfig failed; bouncing needs passwordless sudo for ifconfig");
+            }
+        }
+        WatchdogAction::Script => {
+            let Some(script) = &settings.script else {
+                print_verbose("watchdog.script is not set, skipping", verbose);
+                return Ok(());
+            };
+            print_info("Running recovery script");
+            let status = Command::new("/bin/sh")
+                .args(["-c", script])
+                .env("PLAN10_INTERFACE", settings.interface.clone().unwrap_or_default())
+                .env("PLAN10_PROBE", probe)
+                .status()?;
+            if !status.success() {
+                anyhow::bail!("script exited with {}", status);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn list_outages(since: &str, config: &Config) -> Result<()> {
+    let window = parse_since(since)?;
+    let outages = History::open(config)?.outages(&config.server.name, window_start(window)?)?;
+
+    print_header(&format!("Connectivity Outages (last {})", since));
+    if outages.is_empty() {
+        print_info("No outages recorded");
+        return Ok(());
+    }
+
+    let mut total = 0;
+    for record in &outages {
+        let HistoryEntry::Outage { started, probe, actions } = &record.entry else {
+            continue;
+        };
+        let seconds = (record.timestamp - *started).num_seconds().max(0) as u64;
+        total += seconds;
+        let recovery = if actions.is_empty() { "recovered on its own".to_string() } else { actions.join(" → ") };
+        println!("{}  {:>8}  {}  {}", local_time(started), format_seconds(seconds).bold(), probe, recovery.dimmed());
+    }
+    println!("\n{} outage(s), {} offline in total", outages.len(), format_seconds(total));
+    Ok(())
+}
+
+fn local_time(timestamp: &DateTime<Utc>) -> String {
+    timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn format_seconds(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+fn install_launch_daemon(config: &Config, verbose: bool) -> Result<()> {
+    print_header("Installing Watchdog LaunchDaemon");
+    crate::utils::check_sudo()?;
+
+    let program = std::env::current_exe()?;
+    // The file this run loaded, so --config and --profile carry over
+    let config_path = config.path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    // Runs as the invoking user so outages land in their history; bouncing
+    // the interface goes through sudo -n
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{program}</string>
+        <string>--config</string>
+        <string>{config_path}</string>
+        <string>server</string>
+        <string>watchdog</string>
+    </array>
+    <key>UserName</key>
+    <string>{user}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>/tmp/plan10-watchdog.log</string>
+    <key>StandardErrorPath</key>
+    <string>/tmp/plan10-watchdog.log</string>
+</dict>
+</plist>
+"#,
+        label = WATCHDOG_LABEL,
+        program = program.display(),
+        config_path = config_path,
+        user = std::env::var("SUDO_USER").unwrap_or_else(|_| "root".to_string()),
+    );
+
+    std::fs::write(PLIST_PATH, plist)
+        .context(format!("Failed to write {}", PLIST_PATH))?;
+    print_success(&format!("LaunchDaemon written to {}", PLIST_PATH));
+    print_verbose(&format!("Watching connectivity for server '{}'", config.server.name), verbose);
+
+    let (_, stderr, success) = crate::utils::run_command_with_status(
+        "launchctl",
+        &["bootstrap", "system", PLIST_PATH],
+    )?;
+    if success {
+        print_success("Watchdog running; log in /tmp/plan10-watchdog.log");
+    } else {
+        print_warning(&format!("launchctl bootstrap failed: {}", stderr.trim()));
+        println!("If it is already loaded, run: sudo launchctl bootout system/{} and retry", WATCHDOG_LABEL);
+    }
+
+    Ok(())
+}
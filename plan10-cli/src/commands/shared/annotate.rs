@@ -0,0 +1,72 @@
+use anyhow::Result;
+use crate::Config;
+use crate::commands::utils::*;
+use crate::history::{parse_since, History, HistoryEntry, HistoryRecord};
+use chrono::Utc;
+use colored::*;
+
+pub async fn execute(
+    host: Option<String>,
+    text: Option<String>,
+    list: bool,
+    since: Option<String>,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    // Annotations belong to the configured server name, or to this machine
+    let host = match host {
+        Some(host) => config.resolve_server(&host)
+            .map(|server| server.name.clone())
+            .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?,
+        None => config.server.name.clone(),
+    };
+
+    let history = History::open(config)?;
+
+    match text {
+        Some(text) if !list => {
+            history.append(&HistoryRecord {
+                timestamp: Utc::now(),
+                host: host.clone(),
+                entry: HistoryEntry::Annotation {
+                    text: text.clone(),
+                    author: std::env::var("USER").ok(),
+                },
+            })?;
+            print_success(&format!("Annotated {}: {}", host, text));
+            Ok(())
+        }
+        _ => list_annotations(&history, &host, since, verbose),
+    }
+}
+
+fn list_annotations(history: &History, host: &str, since: Option<String>, verbose: bool) -> Result<()> {
+    let cutoff = since.as_deref()
+        .map(parse_since)
+        .transpose()?
+        .map(|window| Utc::now() - window);
+
+    print_header(&format!("Annotations: {}", host));
+
+    let annotations = history.annotations(host, cutoff)?;
+    if annotations.is_empty() {
+        print_info("No annotations recorded");
+        println!("Add one with: plan10 annotate --host {} \"replaced charger\"", host);
+        return Ok(());
+    }
+
+    for record in annotations {
+        let HistoryEntry::Annotation { text, author } = &record.entry else {
+            continue;
+        };
+        print!("{}  {}", record.timestamp.format("%Y-%m-%d %H:%M UTC").to_string().dimmed(), text);
+        if verbose {
+            if let Some(author) = author {
+                print!(" {}", format!("({})", author).dimmed());
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
@@ -1,11 +1,12 @@
 use anyhow::Result;
 use crate::Config;
 use crate::commands::utils::*;
-use crate::ssh::SshClient;
+use crate::commands::shared::readings::{self, BatteryReading, PowerSource, Source, TargetOs};
+use crate::config::Thresholds;
+use crate::ssh::{with_reconnect, SshClient};
 use crate::ExecutionMode;
 use colored::*;
 use std::process::Command;
-use chrono::{DateTime, Utc};
 
 pub struct BatteryMonitor {
     execution_mode: ExecutionMode,
@@ -39,13 +40,12 @@ impl BatteryMonitor {
         }
     }
 
-    async fn execute_local(&self, detailed: bool, raw: bool, verbose: bool) -> Result<()> {
+    async fn execute_local(&self, detailed: bool, raw: bool, _verbose: bool) -> Result<()> {
         if raw {
             self.display_raw_battery().await
-        } else if detailed {
-            self.display_detailed_battery(verbose).await
         } else {
-            self.display_formatted_battery(verbose).await
+            self.display_reading(&readings::battery(&Source::Local), &self.config.thresholds(None), detailed);
+            Ok(())
         }
     }
 
@@ -53,9 +53,29 @@ impl BatteryMonitor {
         let server = self.config.resolve_server(host)
             .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
 
-        let client = SshClient::connect(server, &self.config).await?;
-        
-        let command = if raw {
+        let mut client = SshClient::connect(server, &self.config).await?;
+
+        // The server's own plan10 gives a structured reading, judged by this
+        // server's thresholds here; the deployed script is the fallback
+        // Linux servers have no scripts and are read from sysfs instead
+        let linux = Source::Remote(&client).os() == TargetOs::Linux;
+        if !raw {
+            if let Some(report) = readings::remote_reading::<BatteryReading>(&client, server, "battery") {
+                print_verbose(&format!("Battery reading from plan10 on {}", report.host), verbose);
+                self.display_reading(&report.reading, &self.config.thresholds(Some(server)), detailed);
+                return Ok(());
+            }
+            if linux {
+                print_verbose("plan10 not installed on the Linux server, reading /sys/class/power_supply", verbose);
+                self.display_reading(&readings::battery(&Source::Remote(&client)), &self.config.thresholds(Some(server)), detailed);
+                return Ok(());
+            }
+            print_verbose("plan10 not installed on the server, using ~/scripts/battery", verbose);
+        }
+
+        let command = if linux {
+            "cat /sys/class/power_supply/*/uevent"
+        } else if raw {
             "~/scripts/battery -r"
         } else if detailed {
             "~/scripts/battery -d"
@@ -63,70 +83,85 @@ impl BatteryMonitor {
             "~/scripts/battery"
         };
 
-        let result = client.execute_command(command)?;
+        let result = with_reconnect(&mut client, &self.config, |client| client.execute_command(command)).await?;
         
         if result.success {
             println!("{}", result.stdout);
         } else {
-            print_error(&format!("Remote command failed: {}", result.stderr));
+            print_remote_failure("Remote command failed", &result);
         }
 
         Ok(())
     }
 
-    async fn display_formatted_battery(&self, verbose: bool) -> Result<()> {
+    /// The same report for this machine and for a server's plan10.
+    fn display_reading(&self, reading: &BatteryReading, thresholds: &Thresholds, detailed: bool) {
         println!("{} Battery Status", "🔋".green());
         println!("{}", "=".repeat(18));
 
-        let battery_info = self.get_battery_pmset().await?;
-        
-        if battery_info.is_empty() {
+        let power = &reading.power;
+        let Some(percent) = power.battery_percent else {
             println!("{} Unable to get battery information", "❌".red());
             println!("This device may not have a battery or battery monitoring is unavailable");
-            return Ok(());
-        }
+            return;
+        };
 
-        // Parse battery information
-        let (percentage, status, time_remaining) = self.parse_battery_info(&battery_info)?;
-        
-        println!("Charge Level: {}", percentage);
+        println!("Charge Level: {}%", percent);
+        let status = match (power.source, power.battery_state.as_deref()) {
+            (_, Some("charged")) => "✅ Fully Charged",
+            (PowerSource::Ac, _) => "🔌 Charging (AC Power)",
+            (PowerSource::Battery, _) => "⚡ Discharging",
+            _ => "❓ Unknown",
+        };
         println!("Status: {}", status);
-        
-        if let Some(time) = time_remaining {
-            println!("{}", time);
+        if let Some(time) = &power.time_remaining {
+            if power.source == PowerSource::Battery {
+                println!("Time Remaining: {}", time);
+            } else {
+                println!("Time to Full: {}", time);
+            }
         }
 
-        // Color code percentage
-        if let Some(pct_str) = percentage.strip_suffix('%') {
-            if let Ok(pct_num) = pct_str.parse::<u8>() {
-                match pct_num {
-                    0..=20 => println!("{} Low Battery - Consider charging", "🔴".red()),
-                    21..=50 => println!("{} Medium Battery", "🟡".yellow()),
-                    _ => println!("{} Good Battery Level", "🟢".green()),
-                }
-            }
+        if thresholds.battery_low(percent) {
+            println!("{} Low Battery - Consider charging (warning at {}%)", "🔴".red(), thresholds.battery_warning_level);
+        } else if percent <= 50 {
+            println!("{} Medium Battery", "🟡".yellow());
+        } else {
+            println!("{} Good Battery Level", "🟢".green());
         }
 
-        Ok(())
-    }
+        if !detailed {
+            return;
+        }
 
-    async fn display_detailed_battery(&self, verbose: bool) -> Result<()> {
-        self.display_formatted_battery(verbose).await?;
-        
         println!();
         println!("{} Battery Health", "🏥".blue());
         println!("{}", "=".repeat(16));
-        
-        let health_info = self.get_battery_health().await?;
-        
-        if health_info.is_empty() {
+
+        let health = &reading.health;
+        if health.cycle_count.is_none() && health.condition.is_none() && health.maximum_capacity_percent.is_none() {
             println!("{} Unable to get battery health information", "❌".red());
-            return Ok(());
+            return;
+        }
+        if let Some(cycles) = health.cycle_count {
+            println!("Cycle Count: {}", cycles);
+            match cycles {
+                0..=500 => println!("{} Low cycle count - battery in good shape", "✅".green()),
+                501..=1000 => println!("{} Moderate cycle count", "🔶".yellow()),
+                _ => println!("{} High cycle count - battery may need replacement", "⚠️".red()),
+            }
+        }
+        if let Some(condition) = &health.condition {
+            println!("Condition: {}", condition);
+            if condition.to_lowercase().contains("normal") {
+                println!("{} Battery condition is normal", "✅".green());
+            } else {
+                println!("{} Battery condition: {}", "⚠️".yellow(), condition);
+            }
+        }
+        if let Some(capacity) = health.maximum_capacity_percent {
+            println!("Maximum Capacity: {}%", capacity);
         }
-
-        self.parse_and_display_health(&health_info)?;
-        
-        Ok(())
     }
 
     async fn display_raw_battery(&self) -> Result<()> {
@@ -175,122 +210,6 @@ impl BatteryMonitor {
             Ok(String::new())
         }
     }
-
-    async fn get_battery_health(&self) -> Result<String> {
-        let output = Command::new("system_profiler")
-            .arg("SPPowerDataType")
-            .output()?;
-
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let health_lines: Vec<&str> = stdout
-                .lines()
-                .filter(|line| {
-                    line.contains("Cycle Count") ||
-                    line.contains("Condition") ||
-                    line.contains("Full Charge Capacity") ||
-                    line.contains("Maximum Capacity")
-                })
-                .collect();
-            
-            Ok(health_lines.join("\n"))
-        } else {
-            Ok(String::new())
-        }
-    }
-
-    fn parse_battery_info(&self, battery_info: &str) -> Result<(String, String, Option<String>)> {
-        let mut percentage = String::new();
-        let mut status = String::new();
-        let mut time_remaining = None;
-
-        // Extract percentage
-        for line in battery_info.lines() {
-            if let Some(pct) = self.extract_percentage(line) {
-                percentage = pct;
-                break;
-            }
-        }
-
-        // Extract charging status
-        if battery_info.contains("AC Power") {
-            status = "🔌 Charging (AC Power)".to_string();
-        } else if battery_info.contains("discharging") {
-            status = "⚡ Discharging".to_string();
-        } else if battery_info.contains("charged") {
-            status = "✅ Fully Charged".to_string();
-        } else {
-            status = "❓ Unknown".to_string();
-        }
-
-        // Extract time remaining
-        for line in battery_info.lines() {
-            if let Some(time) = self.extract_time_remaining(line, &battery_info) {
-                time_remaining = Some(time);
-                break;
-            }
-        }
-
-        Ok((percentage, status, time_remaining))
-    }
-
-    fn extract_percentage(&self, line: &str) -> Option<String> {
-        if let Some(start) = line.find(char::is_numeric) {
-            if let Some(end) = line[start..].find('%') {
-                return Some(line[start..start + end + 1].to_string());
-            }
-        }
-        None
-    }
-
-    fn extract_time_remaining(&self, line: &str, full_info: &str) -> Option<String> {
-        // Look for time patterns like "1:23" or "0:45"
-        if let Some(time_match) = line.find(|c: char| c.is_ascii_digit()) {
-            let remainder = &line[time_match..];
-            if let Some(colon_pos) = remainder.find(':') {
-                if colon_pos < 3 && remainder.len() > colon_pos + 2 {
-                    let time_str = &remainder[..colon_pos + 3];
-                    if full_info.contains("discharging") {
-                        return Some(format!("Time Remaining: {}", time_str));
-                    } else if full_info.contains("charging") {
-                        return Some(format!("Time to Full: {}", time_str));
-                    }
-                }
-            }
-        }
-        None
-    }
-
-    fn parse_and_display_health(&self, health_info: &str) -> Result<()> {
-        for line in health_info.lines() {
-            if line.contains("Cycle Count") {
-                if let Some(cycles_str) = line.split(':').nth(1) {
-                    let cycles_str = cycles_str.trim();
-                    if let Ok(cycles) = cycles_str.parse::<u32>() {
-                        println!("Cycle Count: {}", cycles);
-                        match cycles {
-                            0..=500 => println!("{} Low cycle count - battery in good shape", "✅".green()),
-                            501..=1000 => println!("{} Moderate cycle count", "🔶".yellow()),
-                            _ => println!("{} High cycle count - battery may need replacement", "⚠️".red()),
-                        }
-                    }
-                }
-            } else if line.contains("Condition") {
-                if let Some(condition) = line.split(':').nth(1) {
-                    let condition = condition.trim();
-                    println!("Condition: {}", condition);
-                    if condition.to_lowercase().contains("normal") {
-                        println!("{} Battery condition is normal", "✅".green());
-                    } else {
-                        println!("{} Battery condition: {}", "⚠️".yellow(), condition);
-                    }
-                }
-            } else if line.contains("Maximum Capacity") || line.contains("Full Charge Capacity") {
-                println!("{}", line.trim());
-            }
-        }
-        Ok(())
-    }
 }
 
 pub async fn execute_battery_command(
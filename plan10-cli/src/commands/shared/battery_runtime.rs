@@ -0,0 +1,117 @@
+use crate::Config;
+use crate::config::ServerDefinition;
+use crate::commands::utils::*;
+use crate::commands::shared::readings::{self, PowerSource, Source};
+use crate::history::{History, Sample};
+use chrono::{DateTime, Duration, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+/// How far back the history is searched for time spent on battery.
+const HISTORY_DAYS: i64 = 30;
+/// Samples further apart than this weren't one stretch on battery.
+const MAX_SAMPLE_GAP_MINUTES: f64 = 10.0;
+/// Less recorded time on battery than this says little about the drain.
+const MIN_HISTORY_MINUTES: f64 = 15.0;
+
+/// How long a server on battery has until it reaches the halt level.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct RuntimeEstimate {
+    pub minutes: u32,
+    /// pmset's `haltlevel`, or 0 (empty) when it isn't set
+    pub halt_percent: u8,
+    /// From the present current draw and the charge left in mAh
+    pub from_current_draw: Option<u32>,
+    /// From the average drain while on battery over the last 30 days of history
+    pub from_history: Option<u32>,
+}
+
+/// Runtime left on `server`, or this machine, while it runs on battery. The
+/// present draw follows the load right now and the recorded drain smooths
+/// over spikes, so the estimate is the mean of the two, or whichever is
+/// available. `None` on AC power or when neither gives a rate.
+pub fn estimate(
+    source: &Source,
+    server: Option<&ServerDefinition>,
+    on_battery: bool,
+    battery_percent: Option<u8>,
+    config: &Config,
+) -> Option<RuntimeEstimate> {
+    if !on_battery {
+        return None;
+    }
+    let percent = battery_percent?;
+    let halt_percent = readings::halt_level(source).unwrap_or(0);
+    // History lives on this machine, keyed by server name
+    let host = server.map_or(config.server.name.as_str(), |server| server.name.as_str());
+
+    let from_current_draw = readings::battery_charge(source).and_then(|charge| charge.minutes_until(halt_percent));
+    let from_history = History::open(config).ok()
+        .and_then(|history| history.samples(host, Utc::now() - Duration::days(HISTORY_DAYS)).ok())
+        .and_then(|samples| drain_per_hour(&samples))
+        .map(|drain| (percent.saturating_sub(halt_percent) as f64 / drain * 60.0) as u32);
+
+    let minutes = match (from_current_draw, from_history) {
+        (Some(current), Some(history)) => (current + history) / 2,
+        (Some(minutes), None) | (None, Some(minutes)) => minutes,
+        (None, None) => return None,
+    };
+    Some(RuntimeEstimate { minutes, halt_percent, from_current_draw, from_history })
+}
+
+/// Average battery percentage lost per hour between consecutive samples
+/// taken on battery.
+fn drain_per_hour(samples: &[(DateTime<Utc>, Sample)]) -> Option<f64> {
+    let mut dropped = 0.0;
+    let mut minutes = 0.0;
+    for pair in samples.windows(2) {
+        let ((before_time, before), (time, after)) = (&pair[0], &pair[1]);
+        let gap = (*time - *before_time).num_seconds() as f64 / 60.0;
+        if before.power_source != "battery" || after.power_source != "battery" || gap > MAX_SAMPLE_GAP_MINUTES {
+            continue;
+        }
+        if let (Some(before), Some(after)) = (before.battery_percent, after.battery_percent) {
+            dropped += before as f64 - after as f64;
+            minutes += gap;
+        }
+    }
+    (minutes >= MIN_HISTORY_MINUTES && dropped > 0.0).then(|| dropped / minutes * 60.0)
+}
+
+/// "45m", "2h 05m"
+pub fn format_minutes(minutes: u32) -> String {
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// Print the runtime estimate under `monitor battery`, when on battery.
+/// Best-effort like the health check beside it.
+pub async fn print_estimate(host: Option<&str>, config: &Config, verbose: bool) {
+    let result = readings::read_from(host, config, |source, server| {
+        let power = readings::power(source);
+        estimate(source, server, power.source == PowerSource::Battery, power.battery_percent, config)
+    }).await;
+
+    let estimate = match result {
+        Ok(report) => report.reading,
+        Err(e) => {
+            print_verbose(&format!("Could not estimate battery runtime: {}", e), verbose);
+            return;
+        }
+    };
+    let Some(estimate) = estimate else {
+        return;
+    };
+
+    let text = format!("~{} until {}%", format_minutes(estimate.minutes), estimate.halt_percent);
+    let text = if estimate.minutes <= config.alerts.runtime_minutes { text.red() } else { text.normal() };
+    println!("Runtime on battery: {}", text);
+    let sources: Vec<String> = [("current draw", estimate.from_current_draw), ("history", estimate.from_history)]
+        .into_iter()
+        .filter_map(|(name, minutes)| Some(format!("{} {}", name, format_minutes(minutes?))))
+        .collect();
+    println!("  {}", format!("from {}", sources.join(", ")).dimmed());
+}
@@ -0,0 +1,219 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode, OutputFormat};
+use crate::commands::utils::*;
+use crate::commands::shared::readings::{self, BatteryHealth};
+use crate::history::{parse_since, window_start, History, HistoryEntry};
+use chrono::{DateTime, Duration, Local, Utc};
+use colored::*;
+use serde::Serialize;
+
+/// Fewer days of history than this make the degradation rate mostly noise.
+const MIN_TREND_DAYS: f64 = 14.0;
+
+/// Best-effort: keep the battery's wear for `monitor battery --trend`;
+/// failures only show up in verbose output.
+pub fn record_health(config: &Config, host: &str, health: &BatteryHealth, verbose: bool) {
+    let recorded = History::open(config).and_then(|history| {
+        history.record_battery_health(host, health.cycle_count, health.maximum_capacity_percent)
+    });
+    match recorded {
+        Ok(true) => print_verbose(&format!("Recorded battery health for {}", host), verbose),
+        Ok(false) => {}
+        Err(e) => print_verbose(&format!("Could not record battery health: {}", e), verbose),
+    }
+}
+
+/// Read the battery's health and record it, without letting an unreachable
+/// server or a Mac without a battery fail the command that asked.
+pub async fn check_health(host: Option<&str>, config: &Config, verbose: bool) {
+    match readings::read_from(host, config, |source, _| readings::battery(source).health).await {
+        Ok(report) => record_health(config, &report.host, &report.reading, verbose),
+        Err(e) => print_verbose(&format!("Could not read battery health: {}", e), verbose),
+    }
+}
+
+#[derive(Serialize)]
+struct TrendPoint {
+    timestamp: DateTime<Utc>,
+    cycle_count: Option<u32>,
+    maximum_capacity_percent: Option<u8>,
+}
+
+#[derive(Serialize)]
+struct BatteryTrend {
+    host: String,
+    since: String,
+    points: Vec<TrendPoint>,
+    /// Percentage points of capacity per 30 days; negative while wearing
+    capacity_change_per_month: Option<f64>,
+    cycles_per_month: Option<f64>,
+    replacement_capacity_percent: u8,
+    /// `None` without enough history or while capacity isn't dropping
+    projected_replacement: Option<DateTime<Utc>>,
+}
+
+pub async fn execute_trend(
+    since: String,
+    host: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    output: OutputFormat,
+    verbose: bool,
+) -> Result<()> {
+    let window = parse_since(&since)?;
+    let target = readings::target_host(host, &execution_mode);
+
+    // History lives on this machine, keyed by server name
+    let host_key = match &target {
+        Some(target) => config.resolve_server(target)
+            .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", target))?
+            .name
+            .clone(),
+        None => config.server.name.clone(),
+    };
+
+    // Checking the trend is a battery check too, so it adds today's point
+    check_health(target.as_deref(), config, verbose).await;
+
+    let points: Vec<TrendPoint> = History::open(config)?
+        .battery_health(&host_key, window_start(window)?)?
+        .into_iter()
+        .filter_map(|record| match record.entry {
+            HistoryEntry::BatteryHealth { cycle_count, maximum_capacity_percent } => {
+                Some(TrendPoint { timestamp: record.timestamp, cycle_count, maximum_capacity_percent })
+            }
+            _ => None,
+        })
+        .collect();
+    let trend = analyse(host_key, since, points, config.server.battery_replacement_capacity);
+
+    if output == OutputFormat::Json {
+        return print_json(&trend);
+    }
+
+    print_header(&format!("Battery Health Trend: {} (last {})", trend.host, trend.since));
+    if trend.points.is_empty() {
+        print_info("No battery health recorded yet");
+        println!("Every 'plan10 monitor battery' records cycle count and maximum capacity, at most once a day unless they change");
+        return Ok(());
+    }
+
+    // A row per week, or per month for longer spans
+    let span_days = span_days(&trend.points);
+    let period = if span_days > 90.0 { "%Y-%m" } else { "%G-W%V" };
+    println!("{:<10} {:>10} {:>8}", "PERIOD".bold(), "CAPACITY".bold(), "CYCLES".bold());
+    let mut rows: Vec<(String, &TrendPoint)> = Vec::new();
+    for point in &trend.points {
+        let label = point.timestamp.with_timezone(&Local).format(period).to_string();
+        match rows.last_mut() {
+            Some((last, latest)) if *last == label => *latest = point,
+            _ => rows.push((label, point)),
+        }
+    }
+    for (label, point) in &rows {
+        println!(
+            "{:<10} {:>10} {:>8}",
+            label,
+            point.maximum_capacity_percent.map(|pct| format!("{}%", pct)).unwrap_or_else(|| "-".to_string()),
+            point.cycle_count.map(|count| count.to_string()).unwrap_or_else(|| "-".to_string())
+        );
+    }
+    println!();
+
+    if let Some(cycles) = trend.cycles_per_month {
+        println!("{}: {:.0} per month", "Cycles".bold(), cycles);
+    }
+    let current = trend.points.iter().rev().find_map(|point| point.maximum_capacity_percent);
+    let threshold = trend.replacement_capacity_percent;
+    match (current, trend.capacity_change_per_month) {
+        (Some(current), _) if current <= threshold => {
+            print_warning(&format!("Capacity {}% is at or below the replacement threshold of {}%", current, threshold));
+        }
+        (_, None) => {
+            print_info(&format!(
+                "Not enough history for a trend yet ({:.0} of {} days)",
+                span_days,
+                MIN_TREND_DAYS
+            ));
+        }
+        (_, Some(change)) => {
+            println!("{}: {:+.2} percentage points per month", "Capacity".bold(), change);
+            match trend.projected_replacement {
+                Some(date) => println!(
+                    "Projected to reach {}% around {} ({})",
+                    threshold,
+                    date.with_timezone(&Local).format("%Y-%m-%d"),
+                    describe_until(date)
+                ),
+                None => print_success("No measurable capacity loss"),
+            }
+        }
+    }
+    print_verbose(&format!("{} recorded point(s)", trend.points.len()), verbose);
+
+    Ok(())
+}
+
+fn span_days(points: &[TrendPoint]) -> f64 {
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (last.timestamp - first.timestamp).num_seconds() as f64 / 86_400.0,
+        _ => 0.0,
+    }
+}
+
+/// Least-squares rates over the recorded points, and when capacity crosses
+/// `threshold` if it keeps dropping at that rate.
+fn analyse(host: String, since: String, points: Vec<TrendPoint>, threshold: u8) -> BatteryTrend {
+    let days = |point: &TrendPoint| (point.timestamp - points[0].timestamp).num_seconds() as f64 / 86_400.0;
+    let enough = span_days(&points) >= MIN_TREND_DAYS;
+
+    let capacity: Vec<(f64, f64)> = points.iter()
+        .filter_map(|point| point.maximum_capacity_percent.map(|pct| (days(point), pct as f64)))
+        .collect();
+    let cycles: Vec<(f64, f64)> = points.iter()
+        .filter_map(|point| point.cycle_count.map(|count| (days(point), count as f64)))
+        .collect();
+    let capacity_slope = slope(&capacity).filter(|_| enough);
+
+    let projected_replacement = capacity_slope.filter(|slope| *slope < 0.0).and_then(|slope| {
+        let (_, current) = *capacity.last()?;
+        let remaining_days = (current - threshold as f64) / -slope;
+        // Past ~50 years the projection means nothing and overflows chrono
+        (remaining_days > 0.0 && remaining_days < 18_250.0)
+            .then(|| points.last().map(|last| last.timestamp + Duration::seconds((remaining_days * 86_400.0) as i64)))
+            .flatten()
+    });
+
+    BatteryTrend {
+        host,
+        since,
+        capacity_change_per_month: capacity_slope.map(|slope| slope * 30.0),
+        cycles_per_month: slope(&cycles).filter(|_| enough).map(|slope| slope * 30.0),
+        replacement_capacity_percent: threshold,
+        projected_replacement,
+        points,
+    }
+}
+
+/// Slope of the least-squares line through `(x, y)` points.
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+fn describe_until(date: DateTime<Utc>) -> String {
+    let days = (date - Utc::now()).num_days();
+    match days {
+        ..=0 => "now".to_string(),
+        1..=60 => format!("in {} days", days),
+        61..=730 => format!("in about {} months", days / 30),
+        _ => format!("in about {:.1} years", days as f64 / 365.0),
+    }
+}
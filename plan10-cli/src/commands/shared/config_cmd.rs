@@ -1,15 +1,30 @@
-use anyhow::Result;
-use crate::Config;
+use anyhow::{Context, Result};
+use crate::{Config, ConfigCommands, SchemaFormat, SecretCommands};
+use crate::config::{CLI_ENV_VARS, ConfigIssue, IssueSeverity, Secret, SecretsStore, ServerDefinition};
 use crate::commands::utils::*;
+use crate::ssh::{effective_server, host_port, resolve_host};
 use colored::*;
+use std::io::{self, IsTerminal, Write};
 use std::process::Command;
 
 pub async fn execute(
     server: Option<String>,
     edit: bool,
+    command: Option<ConfigCommands>,
     config: &Config,
     verbose: bool,
 ) -> Result<()> {
+    match command {
+        Some(ConfigCommands::Schema { format }) => return print_schema(format),
+        Some(ConfigCommands::Secret(cmd)) => return manage_secret(cmd),
+        Some(ConfigCommands::Validate { offline }) => return validate_config(config, offline, verbose).await,
+        Some(ConfigCommands::Get { key }) => return get_setting(&key, config),
+        Some(ConfigCommands::Set { key, value }) => return set_setting(&key, Some(&value), config, verbose),
+        Some(ConfigCommands::Unset { key }) => return set_setting(&key, None, config, verbose),
+        Some(ConfigCommands::Env) => return show_env_overrides(config),
+        None => {}
+    }
+
     if edit {
         edit_config(config, verbose).await
     } else if let Some(server_name) = server {
@@ -19,16 +34,112 @@ pub async fn execute(
     }
 }
 
+/// Print a setting bare so scripts can use it: strings without quotes,
+/// lists and tables as JSON, unset values as nothing.
+fn get_setting(key: &str, config: &Config) -> Result<()> {
+    match config.get_value(key)? {
+        serde_json::Value::String(value) => println!("{}", value),
+        serde_json::Value::Null => {}
+        value @ (serde_json::Value::Array(_) | serde_json::Value::Object(_)) => {
+            println!("{}", serde_json::to_string_pretty(&value)?)
+        }
+        value => println!("{}", value),
+    }
+    Ok(())
+}
+
+/// Change a setting, or clear it when `value` is `None`.
+fn set_setting(key: &str, value: Option<&str>, config: &Config, verbose: bool) -> Result<()> {
+    let mut new_config = config.clone();
+    match value {
+        Some(value) => new_config.set_value(key, value)?,
+        None => new_config.unset_value(key)?,
+    }
+    new_config.save(None)?;
+    print_verbose(&format!("{} = {}", key, new_config.get_value(key)?), verbose);
+    Ok(())
+}
+
+/// List the `PLAN10_*` variables in effect and what they changed.
+fn show_env_overrides(config: &Config) -> Result<()> {
+    print_header("Environment Overrides");
+
+    let overrides = config.env_overrides();
+    if overrides.is_empty() {
+        print_info("No settings are overridden from the environment");
+    } else {
+        println!("{}:", "Overridden Settings".bold());
+        for env in overrides {
+            let original = match &env.original {
+                Some(serde_json::Value::Object(_)) => "(table)".to_string(),
+                Some(value) => value.to_string(),
+                None => "(not set)".to_string(),
+            };
+            let value = match &env.value {
+                serde_json::Value::Object(_) => "(table)".to_string(),
+                value => value.to_string(),
+            };
+            println!("  {} = {}", env.key.bold(), value);
+            println!("    from {} (file: {})", env.var.cyan(), original.dimmed());
+        }
+    }
+
+    let set: Vec<(&str, String)> = CLI_ENV_VARS.iter()
+        .filter_map(|var| std::env::var(var).ok().map(|value| (*var, value)))
+        .collect();
+    if !set.is_empty() {
+        println!("\n{}:", "Command Line".bold());
+        for (var, value) in set {
+            println!("  {}={}", var.cyan(), value);
+        }
+    }
+
+    let env_server = overrides.iter().any(|env| env.var == "PLAN10_HOST");
+    let unused: Vec<&str> = ["PLAN10_HOST", "PLAN10_USER", "PLAN10_PORT"].into_iter()
+        .filter(|var| !env_server && std::env::var(var).is_ok())
+        .collect();
+    if !unused.is_empty() {
+        println!();
+        print_warning(&format!("Ignored without both PLAN10_HOST and PLAN10_USER: {}", unused.join(", ")));
+    }
+
+    let ignored = config.ignored_env_vars();
+    if !ignored.is_empty() {
+        println!();
+        print_warning(&format!("Ignored as they name no setting: {}", ignored.join(", ")));
+    }
+
+    println!();
+    println!("Override any setting as PLAN10_<PATH>, the dotted path in capitals with");
+    println!("`__` between its parts, e.g. PLAN10_SERVER__TEMP_THRESHOLD=85.");
+    println!("Overrides apply to this run only and are never saved.");
+    Ok(())
+}
+
 async fn show_full_config(config: &Config, verbose: bool) -> Result<()> {
     print_header("Plan 10 Configuration");
     
     // Configuration file location
-    let config_path = Config::default_config_path()
+    let config_path = config.path()
         .map(|p| p.display().to_string())
         .unwrap_or_else(|| "Not found".to_string());
     
     println!("{}:", "Configuration File".bold());
     println!("  Location: {}", config_path);
+    if let Some(profile) = config.profile() {
+        println!("  Profile: {}", profile);
+    }
+    let profiles = Config::profiles();
+    if !profiles.is_empty() {
+        println!("  Available profiles: {}", profiles.join(", "));
+    }
+    if let Some(workspace) = config.workspace() {
+        println!("  Workspace: {}", workspace);
+    }
+    let included = config.included_files();
+    if !included.is_empty() {
+        println!("  Included: {} file(s) from {}", included.len(), config.include.as_deref().unwrap_or_default());
+    }
     
     // Client configuration
     println!("\n{}:", "Client Settings".bold());
@@ -37,6 +148,7 @@ async fn show_full_config(config: &Config, verbose: bool) -> Result<()> {
     println!("  Deployment timeout: {}s", config.client.deployment_timeout);
     println!("  Concurrent operations: {}", config.client.concurrent_operations);
     println!("  Auto backup: {}", config.client.auto_backup);
+    println!("  Latency routing: {}", config.client.latency_routing);
     
     // Server configuration
     println!("\n{}:", "Server Settings".bold());
@@ -44,6 +156,9 @@ async fn show_full_config(config: &Config, verbose: bool) -> Result<()> {
     println!("  Monitoring interval: {}s", config.server.monitoring_interval);
     println!("  Temperature threshold: {:.1}°C", config.server.temp_threshold);
     println!("  Battery warning level: {}%", config.server.battery_warning_level);
+    println!("  Disk warning level: {}%", config.server.disk_warning_level);
+    println!("  CPU warning level: {}%", config.server.cpu_warning_level);
+    println!("  Battery replacement capacity: {}%", config.server.battery_replacement_capacity);
     println!("  Auto restart services: {}", config.server.auto_restart_services);
     println!("  Log level: {}", config.server.log_level);
     
@@ -55,6 +170,16 @@ async fn show_full_config(config: &Config, verbose: bool) -> Result<()> {
     println!("  Known hosts: {}", config.ssh.known_hosts_file.as_deref().unwrap_or("Default"));
     println!("  Compression: {}", config.ssh.compression);
     println!("  Keep alive: {}", config.ssh.keep_alive);
+    println!("  Remote PATH additions: {}", config.ssh.remote_path.join(":"));
+    println!("  Remote env file: {}", config.ssh.remote_env_file.as_deref().unwrap_or("None"));
+    
+    // Power profile
+    println!("\n{}:", "Power Profile".bold());
+    let profile: Vec<_> = config.power_profile.settings.iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    println!("  Settings: {}", profile.join(" "));
+    println!("  Reapply after macOS update: {}", config.power_profile.reapply_after_update);
     
     // Servers
     println!("\n{}:", "Configured Servers".bold());
@@ -65,12 +190,13 @@ async fn show_full_config(config: &Config, verbose: bool) -> Result<()> {
         servers.sort_by_key(|(name, _)| *name);
         
         for (name, server) in servers {
+            let server = &config.with_group_defaults(server);
             let status_icon = if server.enabled { "🟢" } else { "🔴" };
             let last_seen = server.last_seen
                 .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
                 .unwrap_or_else(|| "Never".to_string());
             
-            println!("  {} {} ({}@{}:{})", status_icon, name, server.user, server.host, server.port);
+            println!("  {} {} ({}@{})", status_icon, name, server.user, host_port(&server.host, server.port));
             if verbose {
                 println!("    Tags: {}", server.tags.join(", "));
                 println!("    Last seen: {}", last_seen);
@@ -95,6 +221,7 @@ async fn show_full_config(config: &Config, verbose: bool) -> Result<()> {
 async fn show_server_config(server_name: &str, config: &Config, verbose: bool) -> Result<()> {
     let server = config.get_server(server_name)
         .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", server_name))?;
+    let server = &config.with_group_defaults(server);
     
     print_header(&format!("Server Configuration - {}", server_name));
     
@@ -112,6 +239,33 @@ async fn show_server_config(server_name: &str, config: &Config, verbose: bool) -
     if !server.tags.is_empty() {
         println!("  Tags: {}", server.tags.join(", "));
     }
+
+    if let Some(site) = &server.site {
+        println!("  Site: {}", site);
+    }
+
+    if !server.addresses.is_empty() {
+        println!("  Addresses: {}", server.addresses.join(", "));
+    }
+    println!("  Address family: {}", server.address_family);
+
+    if let Some(jump_host) = &server.jump_host {
+        println!("  Jump host: {}", jump_host);
+    }
+    if server.agent_forwarding {
+        println!("  Agent forwarding: enabled");
+    }
+    if !server.ssh_options.is_default() {
+        println!("  SSH options: {}", server.ssh_options);
+    }
+
+    if let Some(notes) = &server.notes {
+        println!("  Notes: {}", notes);
+    }
+
+    if let Some(runbook) = &server.runbook {
+        println!("  Runbook: {}", runbook);
+    }
     
     if let Some(last_seen) = server.last_seen {
         println!("  Last seen: {}", last_seen.format("%Y-%m-%d %H:%M:%S UTC"));
@@ -133,7 +287,7 @@ async fn show_server_config(server_name: &str, config: &Config, verbose: bool) -
 }
 
 async fn edit_config(config: &Config, verbose: bool) -> Result<()> {
-    let config_path = Config::default_config_path()
+    let config_path = config.path()
         .ok_or_else(|| anyhow::anyhow!("Could not determine config file path"))?;
     
     if !config_path.exists() {
@@ -153,35 +307,290 @@ async fn edit_config(config: &Config, verbose: bool) -> Result<()> {
             }
         });
     
-    print_info(&format!("Opening config file with {}", editor));
-    print_verbose(&format!("Config file: {}", config_path.display()), verbose);
-    
-    let status = Command::new(&editor)
-        .arg(&config_path)
-        .status()?;
-    
-    if status.success() {
-        print_success("Configuration file updated");
-        
-        // Validate the updated configuration
-        match Config::load(Some(&config_path.to_string_lossy())) {
-            Ok(new_config) => {
-                if let Err(e) = new_config.validate() {
-                    print_warning(&format!("Configuration validation failed: {}", e));
-                    print_info("Please fix the configuration file and try again");
-                } else {
-                    print_success("Configuration is valid");
-                }
+    let original = std::fs::read_to_string(&config_path)
+        .context(format!("Failed to read {}", config_path.display()))?;
+
+    loop {
+        print_info(&format!("Opening config file with {}", editor));
+        print_verbose(&format!("Config file: {}", config_path.display()), verbose);
+
+        let status = Command::new(&editor)
+            .arg(&config_path)
+            .status()?;
+        if !status.success() {
+            print_error("Editor exited with error");
+        }
+
+        let edited = std::fs::read_to_string(&config_path)
+            .context(format!("Failed to read {}", config_path.display()))?;
+        if edited == original {
+            print_info("No changes made");
+            return Ok(());
+        }
+
+        println!("\n{}:", "Changes".bold());
+        print_line_diff(&original, &edited);
+        println!();
+
+        let problem = match Config::parse_file(&config_path, &edited) {
+            Ok(new_config) => new_config.validate().err()
+                .map(|e| format!("Configuration validation failed: {}", e)),
+            Err(e) => Some(format!("Failed to parse configuration: {}", e)),
+        };
+
+        let Some(problem) = problem else {
+            // Keep the previous version around for a manual rollback
+            let mut backup = config_path.clone().into_os_string();
+            backup.push(".bak");
+            let backup = std::path::PathBuf::from(backup);
+            std::fs::write(&backup, &original)
+                .context(format!("Failed to write {}", backup.display()))?;
+            print_success("Configuration updated and valid");
+            print_verbose(&format!("Previous version saved to {}", backup.display()), verbose);
+            return Ok(());
+        };
+
+        print_error(&problem);
+        match prompt_after_invalid_edit()? {
+            InvalidEditChoice::EditAgain => continue,
+            InvalidEditChoice::Keep => {
+                print_warning("Keeping the invalid configuration; plan10 will fail to load it until it is fixed");
+                return Ok(());
             }
-            Err(e) => {
-                print_error(&format!("Failed to parse configuration: {}", e));
-                print_info("Please check the configuration file syntax");
+            InvalidEditChoice::Rollback => {
+                std::fs::write(&config_path, &original)
+                    .context(format!("Failed to restore {}", config_path.display()))?;
+                print_success("Rolled back to the previous configuration");
+                return Ok(());
             }
         }
+    }
+}
+
+enum InvalidEditChoice {
+    EditAgain,
+    Rollback,
+    Keep,
+}
+
+fn prompt_after_invalid_edit() -> Result<InvalidEditChoice> {
+    loop {
+        print!("{} [{}]: ", "(e)dit again, (r)oll back or (k)eep anyway?".cyan(), "r".dimmed());
+        io::stdout().flush()?;
+
+        // EOF (no terminal) falls through to the safe default
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        match input.trim().to_lowercase().as_str() {
+            "e" | "edit" => return Ok(InvalidEditChoice::EditAgain),
+            "" | "r" | "rollback" => return Ok(InvalidEditChoice::Rollback),
+            "k" | "keep" => return Ok(InvalidEditChoice::Keep),
+            other => print_warning(&format!("Invalid choice: {}", other)),
+        }
+    }
+}
+
+fn print_schema(format: SchemaFormat) -> Result<()> {
+    match format {
+        SchemaFormat::JsonSchema => {
+            println!("{}", serde_json::to_string_pretty(&crate::schema::json_schema()?)?);
+        }
+        SchemaFormat::Markdown => print!("{}", crate::schema::markdown()?),
+    }
+    Ok(())
+}
+
+/// Check the config file as written: the static checks of
+/// `Config::diagnose`, referenced key files, and unless `offline` whether
+/// each enabled server resolves and its SSH port answers. Every problem is
+/// reported with its line, and the command fails if any is an error.
+async fn validate_config(config: &Config, offline: bool, verbose: bool) -> Result<()> {
+    let path = config.path()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine config file path"))?;
+    print_header(&format!("Validating {}", path.display()));
+
+    let content = std::fs::read_to_string(&path)
+        .context(format!("Failed to read {}", path.display()))?;
+    let parsed = match Config::parse_file(&path, &content) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            print_error(&format!("{}: does not parse", path.display()));
+            println!("{}", format!("{:#}", e).dimmed());
+            anyhow::bail!("Configuration is invalid");
+        }
+    };
+
+    let mut issues = parsed.diagnose();
+    issues.extend(missing_key_files(&parsed));
+    if offline {
+        print_verbose("Skipping DNS and port checks", verbose);
     } else {
-        print_error("Editor exited with error");
+        issues.extend(unreachable_servers(&parsed, verbose).await);
+    }
+
+    let file = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    for issue in &issues {
+        let location = match issue.line_in(&content) {
+            Some(line) => format!("{}:{}", file, line),
+            None => file.clone(),
+        };
+        let message = format!("{} {}", location.dimmed(), issue);
+        match issue.severity {
+            IssueSeverity::Error => print_error(&message),
+            IssueSeverity::Warning => print_warning(&message),
+        }
+        if let Some(hint) = &issue.hint {
+            println!("   {} {}", "hint:".cyan(), hint);
+        }
+    }
+
+    let errors = issues.iter().filter(|issue| issue.severity == IssueSeverity::Error).count();
+    let warnings = issues.len() - errors;
+    if errors > 0 {
+        anyhow::bail!("Configuration has {} error(s) and {} warning(s)", errors, warnings);
+    }
+    if warnings > 0 {
+        print_info(&format!("Configuration is valid, with {} warning(s)", warnings));
+    } else {
+        print_success("Configuration is valid");
+    }
+    Ok(())
+}
+
+/// Key files named in the config that don't exist.
+fn missing_key_files(config: &Config) -> Vec<ConfigIssue> {
+    let mut keys: Vec<(Vec<String>, &String)> = Vec::new();
+    if let Some(key) = &config.ssh.key_path {
+        keys.push((path_of(&["ssh", "key_path"]), key));
+    }
+    if let Some(file) = &config.ssh.known_hosts_file {
+        keys.push((path_of(&["ssh", "known_hosts_file"]), file));
+    }
+    for (prefix, servers) in fleets(config) {
+        for (name, server) in servers {
+            if let Some(key) = &server.ssh_key {
+                keys.push(([prefix.clone(), path_of(&["servers", name, "ssh_key"])].concat(), key));
+            }
+        }
+    }
+    for (name, group) in &config.groups {
+        if let Some(key) = &group.ssh_key {
+            keys.push((path_of(&["groups", name, "ssh_key"]), key));
+        }
+    }
+
+    keys.sort();
+    keys.into_iter()
+        .filter(|(_, file)| !std::path::Path::new(shellexpand::tilde(file).as_ref()).exists())
+        .map(|(path, file)| ConfigIssue::warning(path, format!("file not found: {}", file)))
+        .collect()
+}
+
+/// Enabled servers whose host does not resolve (an error) or whose SSH port
+/// does not answer (a warning: the Mac may just be asleep or away). Servers
+/// behind a jump host or proxy are skipped, since they can't be reached
+/// directly.
+async fn unreachable_servers(config: &Config, verbose: bool) -> Vec<ConfigIssue> {
+    let timeout = std::time::Duration::from_secs(config.ssh.failover_timeout.max(1));
+    let proxied = config.ssh.proxy.is_some() || config.ssh.proxy_command.is_some();
+
+    let mut checks = tokio::task::JoinSet::new();
+    for (prefix, servers) in fleets(config) {
+        for (name, server) in servers {
+            let server = effective_server(server, config);
+            if !server.enabled || server.host.is_empty() {
+                continue;
+            }
+            if proxied || server.jump_host.is_some() {
+                print_verbose(&format!("Skipping {}: not reachable directly", name), verbose);
+                continue;
+            }
+            let path = [prefix.clone(), path_of(&["servers", name, "host"])].concat();
+            checks.spawn(async move {
+                let addresses = match tokio::time::timeout(timeout, resolve_host(&server.host, server.port, server.address_family)).await {
+                    Ok(Ok(addresses)) if !addresses.is_empty() => addresses,
+                    _ => {
+                        return Some(ConfigIssue::error(path, format!("'{}' does not resolve", server.host))
+                            .with_hint("check the name, or use an IP address"));
+                    }
+                };
+                for address in &addresses {
+                    if let Ok(Ok(_)) = tokio::time::timeout(timeout, tokio::net::TcpStream::connect(address)).await {
+                        return None;
+                    }
+                }
+                Some(ConfigIssue::warning(
+                    path,
+                    format!("nothing answers on {}", host_port(&server.host, server.port)),
+                ).with_hint("check the port and that Remote Login is on, or ignore if the Mac is offline"))
+            });
+        }
+    }
+
+    let mut issues = Vec::new();
+    while let Some(result) = checks.join_next().await {
+        if let Ok(Some(issue)) = result {
+            issues.push(issue);
+        }
+    }
+    issues.sort_by_key(|issue| issue.path.clone());
+    issues
+}
+
+/// The top-level servers and each workspace's, with the key path prefix for
+/// each fleet.
+fn fleets(config: &Config) -> Vec<(Vec<String>, Vec<(&String, &ServerDefinition)>)> {
+    fn sorted(servers: &std::collections::HashMap<String, ServerDefinition>) -> Vec<(&String, &ServerDefinition)> {
+        let mut servers: Vec<_> = servers.iter().collect();
+        servers.sort_by_key(|(name, _)| *name);
+        servers
+    }
+
+    let mut fleets = vec![(Vec::new(), sorted(&config.servers))];
+    let mut workspaces: Vec<_> = config.workspaces.iter().collect();
+    workspaces.sort_by_key(|(name, _)| *name);
+    for (name, workspace) in workspaces {
+        fleets.push((path_of(&["workspaces", name]), sorted(&workspace.servers)));
+    }
+    fleets
+}
+
+fn path_of(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| key.to_string()).collect()
+}
+
+fn manage_secret(cmd: SecretCommands) -> Result<()> {
+    crate::utils::require_macos()?;
+
+    match cmd {
+        SecretCommands::Set { name } => {
+            let secret: Secret = name.parse()?;
+            let value = if io::stdin().is_terminal() {
+                crate::utils::prompt_password(&format!("Value for {}: ", secret))?
+            } else {
+                // Piped in, e.g. from a password manager's CLI
+                let mut value = String::new();
+                io::stdin().read_line(&mut value)?;
+                value.trim_end_matches(['\r', '\n']).to_string()
+            };
+            if value.is_empty() {
+                anyhow::bail!("No value given for {}", secret);
+            }
+            SecretsStore::set(&secret, &value)?;
+            print_success(&format!("Stored {} in the keychain", secret));
+        }
+        SecretCommands::Get { name } => {
+            let secret: Secret = name.parse()?;
+            let value = SecretsStore::get(&secret)
+                .ok_or_else(|| anyhow::anyhow!("No secret {} in the keychain", secret))?;
+            println!("{}", value);
+        }
+        SecretCommands::Rm { name } => {
+            let secret: Secret = name.parse()?;
+            SecretsStore::remove(&secret)?;
+            print_success(&format!("Removed {} from the keychain", secret));
+        }
     }
-    
     Ok(())
 }
 
@@ -191,6 +600,9 @@ pub fn show_help() {
     println!("Options:");
     println!("  -s, --server <NAME>  Show configuration for specific server");
     println!("  -e, --edit           Edit configuration file");
+    println!("  schema [--format F]  Print the config schema (json-schema, markdown)");
+    println!("  validate [--offline] Check the config, key files and server reachability");
+    println!("  secret set|get|rm N  Manage secrets in the macOS keychain");
     println!("  -v, --verbose        Show detailed information");
     println!("  -h, --help           Show this help message");
     println!();
@@ -199,4 +611,5 @@ pub fn show_help() {
     println!("  plan10 config --server myserver  # Show specific server config");
     println!("  plan10 config --edit             # Edit configuration file");
     println!("  plan10 config --verbose          # Show detailed configuration");
+    println!("  plan10 config schema > plan10.schema.json");
 }
\ No newline at end of file
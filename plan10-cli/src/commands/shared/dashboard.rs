@@ -0,0 +1,568 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode};
+use crate::alerts::{self, AlertInput};
+use crate::commands::shared::multi_watch::{changed_lines, fit, render_pane, terminal_size, Pane};
+use crate::commands::shared::{battery_runtime, metrics_history, readings};
+use crate::commands::shared::watch_summary::{Metric, WatchSummary};
+use crate::commands::shared::battery_runtime::RuntimeEstimate;
+use crate::commands::shared::readings::{PowerReading, PowerSource, TemperatureReading};
+use crate::config::{Thresholds, WatchLayoutDefinition, WatchPanel};
+use crate::history::{History, Sample, SAMPLE_INTERVAL_SECONDS};
+use chrono::{DateTime, Utc};
+use colored::*;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Duration, Instant};
+
+/// Points kept per sparkline; wider terminals just show the newest ones.
+const SPARKLINE_POINTS: usize = 240;
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const HELP: &str = "←/→ host  1-4 panel  0 all  r refresh  +/- interval  q quit";
+
+enum Key {
+    NextHost,
+    PreviousHost,
+    /// Position in the layout's panels; `None` shows all of them
+    Focus(Option<usize>),
+    Refresh,
+    Faster,
+    Slower,
+    Quit,
+}
+
+/// Everything one refresh collects from a host.
+struct Reading {
+    temperature: TemperatureReading,
+    power: PowerReading,
+    caffeinate_running: bool,
+    profile_drift: usize,
+    cpu_usage: Option<f32>,
+    load_average: Option<(f32, f32, f32)>,
+    swap_used_bytes: Option<u64>,
+    paging: Option<readings::PagingCounters>,
+    battery_runtime: Option<RuntimeEstimate>,
+    disk_used_percent: Option<u8>,
+    thresholds: Thresholds,
+}
+
+impl Reading {
+    fn sample(&self) -> Sample {
+        Sample {
+            power_source: self.power.source.name().to_string(),
+            battery_percent: self.power.battery_percent,
+            cpu_celsius: self.temperature.cpu_celsius,
+            cpu_usage: self.cpu_usage,
+            swap_used_bytes: self.swap_used_bytes,
+            swapins: self.paging.map(|paging| paging.swapins),
+            swapouts: self.paging.map(|paging| paging.swapouts),
+        }
+    }
+
+    fn alert_input(&self) -> AlertInput {
+        AlertInput {
+            on_battery: (self.power.source != PowerSource::Unknown).then(|| self.power.source == PowerSource::Battery),
+            battery_percent: self.power.battery_percent,
+            battery_minutes_left: self.battery_runtime.map(|runtime| runtime.minutes),
+            cpu_celsius: self.temperature.cpu_celsius,
+            cpu_throttled_to: self.temperature.thermal.throttled_to(),
+            disk_used_percent: self.disk_used_percent,
+            caffeinate_running: Some(self.caffeinate_running),
+        }
+    }
+}
+
+/// Recent values behind the sparklines, seeded from recorded history.
+#[derive(Default)]
+struct Trends {
+    temp: VecDeque<f32>,
+    battery: VecDeque<f32>,
+    cpu: VecDeque<f32>,
+}
+
+impl Trends {
+    fn push(&mut self, sample: &Sample) {
+        for (series, value) in [
+            (&mut self.temp, sample.cpu_celsius),
+            (&mut self.battery, sample.battery_percent.map(f32::from)),
+            (&mut self.cpu, sample.cpu_usage),
+        ] {
+            if let Some(value) = value {
+                series.push_back(value);
+                if series.len() > SPARKLINE_POINTS {
+                    series.pop_front();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct HostState {
+    reading: Option<Reading>,
+    /// The reading before, for `--diff`
+    previous: Option<Reading>,
+    error: Option<String>,
+    updated: Option<DateTime<Utc>>,
+    trends: Trends,
+    last_sample: Option<DateTime<Utc>>,
+}
+
+/// Interactive dashboard for `monitor watch`: the layout's temperature,
+/// battery, power and system panels with sparklines for one host at a time,
+/// switching hosts and panels from the keyboard. `focus` is the panel to
+/// start on. Ctrl+C or `q` restores the terminal, closes the
+/// SSH sessions and prints what the session observed. With `diff`, values
+/// that changed since the previous refresh are highlighted.
+pub async fn execute_dashboard(
+    interval: u64,
+    focus: Option<WatchPanel>,
+    host: Option<String>,
+    layout: WatchLayoutDefinition,
+    diff: bool,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    verbose: bool,
+) -> Result<()> {
+    // Every refresh runs several remote commands; keep one session per host
+    crate::ssh::reuse_sessions();
+
+    let (hosts, mut current) = dashboard_hosts(host, &execution_mode, config)?;
+    let mut states: HashMap<String, HostState> = HashMap::new();
+    let mut focus = focus;
+    let mut interval = interval.max(1);
+    let mut summary = WatchSummary::start();
+
+    // Registered before anything is drawn so Ctrl+C never kills the process
+    // with the terminal still in key-at-a-time mode. Unlike `ctrl_c()`, the
+    // stream also keeps a Ctrl+C pressed during a (blocking) refresh.
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let terminal = TerminalGuard::enter();
+    let mut keys = read_keys();
+    let mut next_refresh = Instant::now();
+
+    loop {
+        let (label, target) = &hosts[current];
+        let state = states.entry(label.clone()).or_insert_with(|| seed_state(label, config));
+
+        if Instant::now() >= next_refresh {
+            tokio::select! {
+                _ = refresh(state, label, target.as_deref(), config, verbose) => {}
+                _ = interrupt.recv() => break,
+            }
+            if let Some(reading) = state.reading.as_ref().filter(|_| state.error.is_none()) {
+                summary.record(label, &[
+                    (Metric::CpuTemperature, reading.temperature.cpu_celsius),
+                    (Metric::Battery, reading.power.battery_percent.map(f32::from)),
+                    (Metric::CpuUsage, reading.cpu_usage),
+                    (Metric::Load, reading.load_average.map(|(one, _, _)| one)),
+                ]);
+            }
+            next_refresh = Instant::now() + Duration::from_secs(interval);
+        }
+        render(&hosts, current, state, &layout, focus, interval, diff, verbose);
+
+        let key = tokio::select! {
+            key = next_key(&mut keys) => key,
+            _ = sleep_until(next_refresh) => continue,
+            _ = interrupt.recv() => break,
+        };
+
+        match key {
+            Key::NextHost => current = (current + 1) % hosts.len(),
+            Key::PreviousHost => current = (current + hosts.len() - 1) % hosts.len(),
+            Key::Focus(None) => focus = None,
+            Key::Focus(Some(index)) => focus = layout.panels.get(index).copied().or(focus),
+            Key::Refresh => {}
+            Key::Faster => interval = interval.saturating_sub(1).max(1),
+            Key::Slower => interval += 1,
+            Key::Quit => break,
+        }
+        // Show the new host (or the requested refresh) right away
+        if matches!(key, Key::NextHost | Key::PreviousHost | Key::Refresh) {
+            next_refresh = Instant::now();
+        }
+    }
+
+    // Leave the alternate screen first so the summary stays visible
+    drop(terminal);
+    crate::ssh::close_sessions();
+    summary.print();
+    Ok(())
+}
+
+/// The hosts the dashboard can switch between, labelled for display, and the
+/// index of the one to start on: this machine (unless targeting a default
+/// server) followed by every enabled server.
+fn dashboard_hosts(
+    host: Option<String>,
+    execution_mode: &ExecutionMode,
+    config: &Config,
+) -> Result<(Vec<(String, Option<String>)>, usize)> {
+    let start = match readings::target_host(host, execution_mode) {
+        Some(target) => Some(config.resolve_server(&target)
+            .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", target))?
+            .name
+            .clone()),
+        None => None,
+    };
+
+    let mut hosts = Vec::new();
+    if !matches!(execution_mode, ExecutionMode::Remote { .. }) {
+        hosts.push((config.server.name.clone(), None));
+    }
+    let mut names: Vec<&String> = config.servers.iter()
+        .filter(|(name, server)| server.enabled || start.as_ref() == Some(*name))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    hosts.extend(names.into_iter().map(|name| (name.clone(), Some(name.clone()))));
+
+    let current = hosts.iter().position(|(_, target)| *target == start).unwrap_or(0);
+    Ok((hosts, current))
+}
+
+/// Start a host's sparklines from the last hour of recorded samples.
+fn seed_state(host: &str, config: &Config) -> HostState {
+    let mut state = HostState::default();
+    let since = Utc::now() - chrono::Duration::hours(1);
+    if let Ok(samples) = History::open(config).and_then(|history| history.samples(host, since)) {
+        for (_, sample) in &samples {
+            state.trends.push(sample);
+        }
+    }
+    state
+}
+
+async fn refresh(state: &mut HostState, host: &str, target: Option<&str>, config: &Config, verbose: bool) {
+    let result = readings::read_from(target, config, |source, server| {
+        let power = readings::power(source);
+        Reading {
+            temperature: readings::temperature(source, &config.thresholds(server)),
+            battery_runtime: battery_runtime::estimate(source, server, power.source == PowerSource::Battery, power.battery_percent, config),
+            power,
+            caffeinate_running: readings::caffeinate_running(source),
+            profile_drift: readings::power_settings(source, config).drift.len(),
+            cpu_usage: readings::cpu_usage(source),
+            load_average: readings::load_average(source),
+            swap_used_bytes: readings::swap_usage(source).map(|(used, _)| used),
+            paging: readings::paging_counters(source),
+            disk_used_percent: readings::disk_usage(source),
+            thresholds: config.thresholds(server),
+        }
+    }).await;
+
+    let now = Utc::now();
+    state.updated = Some(now);
+    match result {
+        Ok(reading) => {
+            let sample = reading.reading.sample();
+            state.trends.push(&sample);
+            // Keep a sample for `monitor history` at the rate history stores them
+            if state.last_sample.map_or(true, |last| (now - last).num_seconds() >= SAMPLE_INTERVAL_SECONDS) {
+                metrics_history::record_sample(config, host, sample, verbose);
+                state.last_sample = Some(now);
+            }
+            // The notification cooldown keeps this from firing every refresh
+            alerts::check(host, &reading.reading.alert_input(), config.resolve_server(host), config, verbose);
+            state.previous = state.reading.replace(reading.reading);
+            state.error = None;
+        }
+        Err(e) => state.error = Some(e.to_string()),
+    }
+}
+
+fn render(
+    hosts: &[(String, Option<String>)],
+    current: usize,
+    state: &HostState,
+    layout: &WatchLayoutDefinition,
+    focus: Option<WatchPanel>,
+    interval: u64,
+    diff: bool,
+    verbose: bool,
+) {
+    let (width, height) = terminal_size();
+    let (host, _) = &hosts[current];
+
+    let mut out = String::from("\x1B[2J\x1B[1;1H");
+    out.push_str(&format!(
+        "{} {}  ({}/{})  {}  every {}s\n",
+        "🕐".cyan(),
+        host.bold(),
+        current + 1,
+        hosts.len(),
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        interval
+    ));
+
+    // Header and footer take a line each
+    let body = height.saturating_sub(2).max(8);
+    let panels: Vec<WatchPanel> = match focus {
+        Some(panel) => vec![panel],
+        None => layout.panels.clone(),
+    };
+    let columns = if panels.len() > 1 && width >= 80 { 2 } else { 1 };
+    let rows = panels.len().div_ceil(columns);
+    let pane_width = (width.saturating_sub(columns - 1) / columns).max(20);
+    let pane_height = (body / rows).max(4);
+
+    for row in panels.chunks(columns) {
+        let rendered: Vec<Vec<String>> = row.iter()
+            .map(|panel| {
+                let pane = panel_pane(*panel, layout, state, state.reading.as_ref(), pane_width.saturating_sub(4));
+                // The same panel drawn from the reading before; sparklines
+                // come from the shared trends, so only the values differ
+                let changed = match state.previous.as_ref().filter(|_| diff) {
+                    Some(previous) => {
+                        let before = panel_pane(*panel, layout, state, Some(previous), pane_width.saturating_sub(4));
+                        changed_lines(&before.lines, &pane.lines)
+                    }
+                    None => Vec::new(),
+                };
+                render_pane(&pane, pane_width, pane_height, &changed, verbose)
+            })
+            .collect();
+        for line in 0..pane_height {
+            let parts: Vec<&str> = rendered.iter().map(|pane| pane[line].as_str()).collect();
+            out.push_str(&parts.join(" "));
+            out.push('\n');
+        }
+    }
+
+    out.push_str(&fit(HELP, width, ' ').dimmed().to_string());
+    print!("{}", out);
+    io::stdout().flush().unwrap();
+}
+
+/// Title, health and lines for one panel drawn from `reading`; `width` is
+/// the usable text width. Compact layouts keep only the key lines.
+fn panel_pane(
+    panel: WatchPanel,
+    layout: &WatchLayoutDefinition,
+    state: &HostState,
+    reading: Option<&Reading>,
+    width: usize,
+) -> Pane {
+    let name = match panel {
+        WatchPanel::Temp => "Temperature",
+        WatchPanel::Battery => "Battery",
+        WatchPanel::Power => "Power",
+        WatchPanel::System => "System",
+    };
+    // Numbered by position, which is the key that shows it
+    let name = match layout.panels.iter().position(|shown| *shown == panel) {
+        Some(index) => format!("{} {}", index + 1, name),
+        None => name.to_string(),
+    };
+    let detailed = !layout.compact;
+    let mut pane = Pane {
+        name,
+        healthy: None,
+        lines: Vec::new(),
+        updated: state.updated,
+        summary: None,
+        previous_lines: Vec::new(),
+        previous_summary: None,
+    };
+
+    let Some(reading) = reading else {
+        match &state.error {
+            Some(error) => {
+                pane.healthy = Some(false);
+                pane.lines.push(error.clone());
+            }
+            None => pane.lines.push("connecting...".to_string()),
+        }
+        return pane;
+    };
+    if let Some(error) = &state.error {
+        pane.healthy = Some(false);
+        pane.lines.push(format!("stale: {}", error));
+    }
+
+    let trends = &state.trends;
+    match panel {
+        WatchPanel::Temp => {
+            let temperature = &reading.temperature;
+            match temperature.cpu_celsius {
+                Some(celsius) => pane.lines.push(format!("CPU  {:.1}°C (threshold {:.1}°C)", celsius, temperature.threshold_celsius)),
+                None => pane.lines.push("CPU  n/a (no readable sensor)".to_string()),
+            }
+            if detailed {
+                let utilization = temperature.gpus.iter().find_map(|gpu| gpu.utilization_percent);
+                match (temperature.gpu_celsius, utilization) {
+                    (Some(celsius), Some(busy)) => pane.lines.push(format!("GPU  {:.1}°C, {:.0}% busy", celsius, busy)),
+                    (Some(celsius), None) => pane.lines.push(format!("GPU  {:.1}°C", celsius)),
+                    (None, Some(busy)) => pane.lines.push(format!("GPU  {:.0}% busy", busy)),
+                    (None, None) => {}
+                }
+                if !temperature.fans.is_empty() {
+                    let fans: Vec<String> = temperature.fans.iter().map(|fan| format!("{:.0}", fan.rpm)).collect();
+                    pane.lines.push(format!("Fans {} rpm", fans.join(" / ")));
+                }
+                pane.lines.push(sparkline(&trends.temp, 30.0, 100.0, width));
+            }
+            pane.healthy = pane.healthy.or(temperature.cpu_celsius.map(|_| !temperature.over_threshold));
+        }
+        WatchPanel::Battery => {
+            let power = &reading.power;
+            match power.battery_percent {
+                Some(pct) => {
+                    let state = power.battery_state.as_deref().unwrap_or("unknown");
+                    pane.lines.push(format!("Charge     {}% ({})", pct, state));
+                }
+                None => pane.lines.push("Charge     no battery reported".to_string()),
+            }
+            if detailed {
+                if let Some(remaining) = &power.time_remaining {
+                    pane.lines.push(format!("Remaining  {}", remaining));
+                }
+                if let Some(runtime) = &reading.battery_runtime {
+                    pane.lines.push(format!("Estimate   ~{} to {}%", battery_runtime::format_minutes(runtime.minutes), runtime.halt_percent));
+                }
+                pane.lines.push(sparkline(&trends.battery, 0.0, 100.0, width));
+            }
+            let low = power.source == PowerSource::Battery
+                && power.battery_percent.is_some_and(|pct| reading.thresholds.battery_low(pct));
+            pane.healthy = pane.healthy.or(Some(!low));
+        }
+        WatchPanel::Power => {
+            let source = match reading.power.source {
+                PowerSource::Ac => "AC power",
+                PowerSource::Battery => "battery",
+                PowerSource::Unknown => "unknown",
+            };
+            pane.lines.push(format!("Source      {}", source));
+            pane.lines.push(format!("Caffeinate  {}", if reading.caffeinate_running { "running" } else { "stopped" }));
+            match reading.profile_drift {
+                0 if !detailed => {}
+                0 => pane.lines.push("Profile     matches [power_profile]".to_string()),
+                drift => pane.lines.push(format!("Profile     {} setting(s) drifted", drift)),
+            }
+            pane.healthy = pane.healthy.or(Some(reading.caffeinate_running && reading.profile_drift == 0));
+        }
+        WatchPanel::System => {
+            match reading.cpu_usage {
+                Some(usage) => pane.lines.push(format!("CPU   {:.1}%", usage)),
+                None => pane.lines.push("CPU   unavailable".to_string()),
+            }
+            if detailed {
+                pane.lines.push(sparkline(&trends.cpu, 0.0, 100.0, width));
+            }
+            if let Some((one, five, fifteen)) = reading.load_average {
+                pane.lines.push(format!("Load  {:.2} {:.2} {:.2}", one, five, fifteen));
+            }
+            if let Some(pct) = reading.disk_used_percent.filter(|_| detailed) {
+                pane.lines.push(format!("Disk  {}% of / used", pct));
+            }
+            let disk_full = reading.disk_used_percent.is_some_and(|pct| reading.thresholds.disk_full(pct));
+            pane.healthy = pane.healthy.or(Some(!disk_full));
+        }
+    }
+
+    pane
+}
+
+/// The newest values that fit in `width`, scaled between `min` and `max`.
+fn sparkline(values: &VecDeque<f32>, min: f32, max: f32, width: usize) -> String {
+    if values.is_empty() {
+        return "collecting...".to_string();
+    }
+    values.iter()
+        .skip(values.len().saturating_sub(width))
+        .map(|value| {
+            let scaled = ((value - min) / (max - min)).clamp(0.0, 1.0);
+            SPARK_BARS[(scaled * (SPARK_BARS.len() - 1) as f32).round() as usize]
+        })
+        .collect()
+}
+
+/// Reads keys on a plain thread: a blocking stdin read would otherwise hold
+/// up runtime shutdown. The channel closes when stdin does.
+fn read_keys() -> mpsc::UnboundedReceiver<Key> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut byte = [0u8; 1];
+        let mut escape = Vec::new();
+        while let Ok(1) = stdin.read(&mut byte) {
+            // Arrow keys arrive as ESC [ C / ESC [ D, shift-tab as ESC [ Z
+            if byte[0] == 0x1b || !escape.is_empty() {
+                escape.push(byte[0]);
+                if escape.len() < 3 {
+                    continue;
+                }
+            }
+            let key = match (escape.as_slice(), byte[0]) {
+                ([0x1b, b'[', b'C'], _) | (_, b'\t') | ([], b'n') | ([], b'l') => Some(Key::NextHost),
+                ([0x1b, b'[', b'D'], _) | ([0x1b, b'[', b'Z'], _) | ([], b'p') | ([], b'h') => Some(Key::PreviousHost),
+                ([], b'0') | ([], b'a') => Some(Key::Focus(None)),
+                ([], digit @ b'1'..=b'4') => Some(Key::Focus(Some((digit - b'1') as usize))),
+                ([], b'r') => Some(Key::Refresh),
+                ([], b'+') | ([], b'=') => Some(Key::Faster),
+                ([], b'-') => Some(Key::Slower),
+                ([], b'q') | ([], b'Q') => Some(Key::Quit),
+                _ => None,
+            };
+            escape.clear();
+            if let Some(key) = key {
+                if sender.send(key).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    receiver
+}
+
+/// The next key press; waits forever once stdin is closed so the dashboard
+/// keeps refreshing when it isn't attached to a terminal.
+async fn next_key(keys: &mut mpsc::UnboundedReceiver<Key>) -> Key {
+    match keys.recv().await {
+        Some(key) => key,
+        None => std::future::pending().await,
+    }
+}
+
+/// Switches to the alternate screen with key-at-a-time input and restores
+/// the terminal on drop. Ctrl+C still raises SIGINT, which the dashboard
+/// turns into a clean exit.
+struct TerminalGuard {
+    original: Option<nix::libc::termios>,
+}
+
+impl TerminalGuard {
+    fn enter() -> Self {
+        use nix::libc::{tcgetattr, tcsetattr, ECHO, ICANON, STDIN_FILENO, TCSANOW, VMIN, VTIME};
+
+        let original = unsafe {
+            let mut original: nix::libc::termios = std::mem::zeroed();
+            if tcgetattr(STDIN_FILENO, &mut original) == 0 {
+                let mut keys = original;
+                keys.c_lflag &= !(ICANON | ECHO);
+                keys.c_cc[VMIN] = 1;
+                keys.c_cc[VTIME] = 0;
+                (tcsetattr(STDIN_FILENO, TCSANOW, &keys) == 0).then_some(original)
+            } else {
+                None
+            }
+        };
+
+        // Alternate screen, hidden cursor
+        print!("\x1B[?1049h\x1B[?25l");
+        let _ = io::stdout().flush();
+        Self { original }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if let Some(original) = &self.original {
+            unsafe { nix::libc::tcsetattr(nix::libc::STDIN_FILENO, nix::libc::TCSANOW, original) };
+        }
+        print!("\x1B[?25h\x1B[?1049l");
+        let _ = io::stdout().flush();
+    }
+}
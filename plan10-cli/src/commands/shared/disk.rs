@@ -0,0 +1,71 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode};
+use crate::commands::utils::*;
+use crate::commands::shared::readings::{self, DiskCondition, DiskHealth, HealthSource};
+use colored::*;
+
+pub async fn execute_disk(
+    device: Option<String>,
+    host: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    verbose: bool,
+) -> Result<()> {
+    let target = readings::target_host(host, &execution_mode);
+    print_verbose("Sampling disk I/O for one second", verbose);
+    let report = readings::read_from(target.as_deref(), config, |source, _| {
+        readings::disks(source, device.as_deref())
+    }).await?;
+
+    print_header(&format!("Disks: {}", report.host));
+    if report.reading.disks.is_empty() {
+        print_warning("No physical disks found");
+        return Ok(());
+    }
+    for disk in &report.reading.disks {
+        print_disk(disk);
+    }
+    if report.reading.disks.iter().all(|disk| disk.source == HealthSource::Diskutil) {
+        println!("\nInstall smartmontools (brew install smartmontools) for wear and error counters");
+    }
+
+    Ok(())
+}
+
+fn print_disk(disk: &DiskHealth) {
+    let condition = match disk.condition() {
+        DiskCondition::Healthy => "healthy".green(),
+        DiskCondition::Degraded(reason) => format!("degraded: {}", reason).yellow(),
+        DiskCondition::Failing(reason) => format!("FAILING: {}", reason).red().bold(),
+        DiskCondition::Unknown => "unknown".dimmed(),
+    };
+    println!("\n{} ({})", disk.device.bold(), condition);
+
+    if let Some(model) = &disk.model {
+        println!("  Model: {}", model);
+    }
+    if let Some(status) = &disk.smart_status {
+        println!("  SMART status: {} (via {})", status, disk.source.name());
+    }
+    if let Some(used) = disk.percentage_used {
+        println!("  Wear: {}% of rated endurance used", used);
+    }
+    if let Some(spare) = disk.available_spare {
+        match disk.available_spare_threshold {
+            Some(threshold) => println!("  Available spare: {}% (threshold {}%)", spare, threshold),
+            None => println!("  Available spare: {}%", spare),
+        }
+    }
+    if let Some(errors) = disk.media_errors {
+        println!("  Media errors: {}", errors);
+    }
+    if let Some(hours) = disk.power_on_hours {
+        println!("  Power-on time: {} hours", hours);
+    }
+    if let Some(celsius) = disk.temperature_celsius {
+        println!("  Temperature: {:.0}°C", celsius);
+    }
+    if let Some(rate) = disk.megabytes_per_second {
+        println!("  I/O: {:.2} MB/s", rate);
+    }
+}
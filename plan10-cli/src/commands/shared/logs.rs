@@ -0,0 +1,121 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode};
+use crate::commands::utils::*;
+use crate::commands::shared::readings;
+use crate::ssh::SshClient;
+use clap::ValueEnum;
+use std::process::Command;
+
+/// Exit status of the tail script when none of the log files exist.
+const NO_LOGS: i32 = 66;
+
+/// Plan 10 components whose log `monitor logs` can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogService {
+    /// The caffeinate LaunchAgent keeping the Mac awake
+    Caffeinate,
+    /// The connectivity watchdog (`server watchdog --install`)
+    Watchdog,
+    /// The monitoring daemon (`server monitor-daemon --install`)
+    Monitor,
+    /// The status API (`server api --install`)
+    Api,
+    /// plan10's own debug log
+    Cli,
+    /// Everything else in ~/logs, such as the deployed scripts' logs
+    Scripts,
+}
+
+impl LogService {
+    /// The service's log files as a shell word; `$HOME` rather than `~` so
+    /// it expands the same way locally and over SSH.
+    fn files(&self) -> &'static str {
+        match self {
+            LogService::Caffeinate => "/tmp/plan10-caffeinate.log",
+            LogService::Watchdog => "/tmp/plan10-watchdog.log",
+            LogService::Monitor => "/tmp/plan10-monitor.log",
+            LogService::Api => "/tmp/plan10-api.log",
+            LogService::Cli => "\"$HOME\"/logs/plan10-cli.log.*",
+            LogService::Scripts => "\"$HOME\"/logs/*.log",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            LogService::Caffeinate => "caffeinate",
+            LogService::Watchdog => "watchdog",
+            LogService::Monitor => "monitor",
+            LogService::Api => "api",
+            LogService::Cli => "cli",
+            LogService::Scripts => "scripts",
+        }
+    }
+}
+
+/// A script that tails whichever of the services' log files exist, so a
+/// service that was never installed doesn't make `tail` fail. The CLI log
+/// gets a file per day, of which only the newest is of interest.
+fn tail_script(services: &[LogService], lines: usize, follow: bool) -> String {
+    let mut script = String::from("set --; ");
+    for service in services {
+        if *service == LogService::Cli {
+            script.push_str(&format!(
+                "newest=$(ls -t {} 2>/dev/null | head -n 1); [ -n \"$newest\" ] && set -- \"$@\" \"$newest\"; ",
+                service.files()
+            ));
+        } else {
+            script.push_str(&format!("for f in {}; do [ -f \"$f\" ] && set -- \"$@\" \"$f\"; done; ", service.files()));
+        }
+    }
+    script.push_str(&format!(
+        "[ $# -gt 0 ] || exit {}; exec tail -n {} {}\"$@\"",
+        NO_LOGS,
+        lines,
+        if follow { "-F " } else { "" }
+    ));
+    script
+}
+
+pub async fn execute_logs(
+    services: Vec<LogService>,
+    follow: bool,
+    lines: usize,
+    host: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    verbose: bool,
+) -> Result<()> {
+    let services = if services.is_empty() {
+        LogService::value_variants().to_vec()
+    } else {
+        services
+    };
+    let script = tail_script(&services, lines, follow);
+    print_verbose(&format!("Tail script: {}", script), verbose);
+
+    let target = readings::target_host(host, &execution_mode);
+    let (host, exit_code) = match target.as_deref() {
+        Some(host) => {
+            let server = config.resolve_server(host)
+                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+            let client = SshClient::connect(server, config).await?;
+            // Following runs until Ctrl+C, so it can't time out
+            let timeout = if follow { 0 } else { client.command_timeout() };
+            let result = client.execute_command_streaming(&script, timeout, print_output_line)?;
+            (server.name.clone(), result.exit_code)
+        }
+        None => {
+            let status = Command::new("/bin/sh").args(["-c", script.as_str()]).status()?;
+            (config.server.name.clone(), status.code().unwrap_or(1))
+        }
+    };
+
+    match exit_code {
+        0 => Ok(()),
+        NO_LOGS => {
+            let names: Vec<&str> = services.iter().map(LogService::name).collect();
+            anyhow::bail!("No logs for {} on {}; the services may not be installed", names.join(", "), host)
+        }
+        code => anyhow::bail!("tail exited with status {} on {}", code, host),
+    }
+}
@@ -0,0 +1,303 @@
+use anyhow::{Context, Result};
+use crate::{Config, ExecutionMode, ExportFormat, HistoryMetric, OutputFormat};
+use crate::commands::utils::*;
+use crate::commands::shared::readings;
+use crate::history::{parse_since, window_start, History, Sample};
+use chrono::{DateTime, Local, Utc};
+use crate::utils::formatting::format_bytes;
+use colored::*;
+use serde::Serialize;
+use std::io::Write;
+
+/// Best-effort: keep a monitoring sample for `monitor history`; failures only
+/// show up in verbose output.
+pub fn record_sample(config: &Config, host: &str, sample: Sample, verbose: bool) {
+    match History::open(config).and_then(|history| history.record_sample(host, sample)) {
+        Ok(true) => print_verbose(&format!("Recorded monitoring sample for {}", host), verbose),
+        Ok(false) => {}
+        Err(e) => print_verbose(&format!("Could not record monitoring sample: {}", e), verbose),
+    }
+}
+
+pub async fn execute_history(
+    since: String,
+    metric: Option<HistoryMetric>,
+    csv: bool,
+    host: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    output: OutputFormat,
+    verbose: bool,
+) -> Result<()> {
+    let window = parse_since(&since)?;
+
+    // History lives on this machine, keyed by server name
+    let host_key = match readings::target_host(host, &execution_mode) {
+        Some(target) => config.resolve_server(&target)
+            .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", target))?
+            .name
+            .clone(),
+        None => config.server.name.clone(),
+    };
+
+    let samples = History::open(config)?.samples(&host_key, window_start(window)?)?;
+    let columns: Vec<HistoryMetric> = match metric {
+        Some(metric) => vec![metric],
+        None => vec![HistoryMetric::Battery, HistoryMetric::Temp, HistoryMetric::Cpu, HistoryMetric::Swap, HistoryMetric::Power],
+    };
+    let points = points(&samples);
+
+    if output == OutputFormat::Json {
+        let series: Vec<_> = points.iter()
+            .map(|point| {
+                let mut value = serde_json::json!({ "timestamp": point.timestamp });
+                for column in &columns {
+                    value[column.name()] = column.json_value(point);
+                }
+                value
+            })
+            .collect();
+        return print_json(&serde_json::json!({ "host": host_key, "since": since, "samples": series }));
+    }
+
+    if csv {
+        let header: Vec<&str> = columns.iter().map(HistoryMetric::name).collect();
+        println!("timestamp,{}", header.join(","));
+        for point in &points {
+            let values: Vec<String> = columns.iter().map(|column| column.text_value(point)).collect();
+            println!("{},{}", point.timestamp.to_rfc3339(), values.join(","));
+        }
+        return Ok(());
+    }
+
+    print_header(&format!("Monitoring History: {} (last {})", host_key, since));
+
+    if samples.is_empty() {
+        print_info(&format!("No samples recorded in the last {}", since));
+        println!("Samples are kept by 'plan10 status', 'monitor watch', 'client check', 'server exporter' and 'server monitor-daemon'");
+        return Ok(());
+    }
+
+    let header: Vec<String> = columns.iter().map(|column| format!("{:>10}", column.name())).collect();
+    println!("{:<17} {}", "TIME".bold(), header.join(" ").bold());
+    for point in &points {
+        let values: Vec<String> = columns.iter()
+            .map(|column| format!("{:>10}", column.display_value(point)))
+            .collect();
+        println!("{:<17} {}", local_time(point.timestamp), values.join(" "));
+    }
+
+    println!();
+    for column in &columns {
+        if let Some(summary) = column.summary(&points) {
+            println!("{}: {}", column.name().bold(), summary);
+        }
+    }
+    print_verbose(&format!("{} sample(s)", samples.len()), verbose);
+
+    Ok(())
+}
+
+/// A recorded sample with the swapping since the one before it.
+struct Point<'a> {
+    timestamp: &'a DateTime<Utc>,
+    sample: &'a Sample,
+    /// Pages swapped in and out per minute
+    swap_rate: Option<(f32, f32)>,
+}
+
+fn points(samples: &[(DateTime<Utc>, Sample)]) -> Vec<Point<'_>> {
+    let mut previous: Option<&(DateTime<Utc>, Sample)> = None;
+    samples.iter()
+        .map(|current| {
+            let swap_rate = previous.and_then(|before| swap_rate(before, current));
+            previous = Some(current);
+            Point { timestamp: &current.0, sample: &current.1, swap_rate }
+        })
+        .collect()
+}
+
+/// Pages swapped in and out per minute between two samples; `None` across
+/// a reboot, which resets the counters.
+fn swap_rate((before_time, before): &(DateTime<Utc>, Sample), (time, sample): &(DateTime<Utc>, Sample)) -> Option<(f32, f32)> {
+    let minutes = (*time - *before_time).num_seconds() as f32 / 60.0;
+    let swapins = sample.swapins?.checked_sub(before.swapins?)?;
+    let swapouts = sample.swapouts?.checked_sub(before.swapouts?)?;
+    (minutes > 0.0).then(|| (swapins as f32 / minutes, swapouts as f32 / minutes))
+}
+
+/// One exported sample; the same fields as the history, plus the host.
+#[derive(Serialize)]
+struct ExportRow<'a> {
+    timestamp: &'a DateTime<Utc>,
+    host: &'a str,
+    #[serde(flatten)]
+    sample: &'a Sample,
+}
+
+pub async fn execute_export(
+    format: ExportFormat,
+    since: String,
+    file: Option<String>,
+    host: Option<String>,
+    all: bool,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    verbose: bool,
+) -> Result<()> {
+    let window = parse_since(&since)?;
+    let history = History::open(config)?;
+
+    let hosts = if all {
+        history.hosts()?
+    } else {
+        match readings::target_host(host, &execution_mode) {
+            Some(target) => vec![config.resolve_server(&target)
+                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", target))?
+                .name
+                .clone()],
+            None => vec![config.server.name.clone()],
+        }
+    };
+
+    let mut rows = Vec::new();
+    for host in &hosts {
+        for (timestamp, sample) in history.samples(host, window_start(window)?)? {
+            rows.push((host.as_str(), timestamp, sample));
+        }
+    }
+    rows.sort_by_key(|(_, timestamp, _)| *timestamp);
+
+    let mut out = String::new();
+    match format {
+        ExportFormat::Csv => {
+            out.push_str("timestamp,host,power_source,battery_percent,cpu_celsius,cpu_usage,swap_used_bytes,swapins,swapouts\n");
+            for (host, timestamp, sample) in &rows {
+                out.push_str(&format!(
+                    "{},{},{},{},{},{},{},{},{}\n",
+                    timestamp.to_rfc3339(),
+                    csv_field(host),
+                    csv_field(&sample.power_source),
+                    sample.battery_percent.map(|pct| pct.to_string()).unwrap_or_default(),
+                    sample.cpu_celsius.map(|celsius| format!("{:.1}", celsius)).unwrap_or_default(),
+                    sample.cpu_usage.map(|usage| format!("{:.1}", usage)).unwrap_or_default(),
+                    sample.swap_used_bytes.map(|bytes| bytes.to_string()).unwrap_or_default(),
+                    sample.swapins.map(|pages| pages.to_string()).unwrap_or_default(),
+                    sample.swapouts.map(|pages| pages.to_string()).unwrap_or_default()
+                ));
+            }
+        }
+        ExportFormat::Jsonl => {
+            for (host, timestamp, sample) in &rows {
+                out.push_str(&serde_json::to_string(&ExportRow { timestamp, host, sample })?);
+                out.push('\n');
+            }
+        }
+    }
+
+    match file {
+        Some(path) => {
+            let path = shellexpand::tilde(&path).into_owned();
+            std::fs::write(&path, out).context(format!("Failed to write {}", path))?;
+            print_success(&format!("Exported {} sample(s) from {} host(s) to {}", rows.len(), hosts.len(), path));
+        }
+        None => {
+            std::io::stdout().write_all(out.as_bytes())?;
+            print_verbose(&format!("Exported {} sample(s)", rows.len()), verbose);
+        }
+    }
+
+    Ok(())
+}
+
+/// Quote a CSV field that needs it.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn local_time(timestamp: &DateTime<Utc>) -> String {
+    timestamp.with_timezone(&Local).format("%m-%d %H:%M").to_string()
+}
+
+impl HistoryMetric {
+    fn name(&self) -> &'static str {
+        match self {
+            HistoryMetric::Battery => "battery",
+            HistoryMetric::Temp => "temp",
+            HistoryMetric::Cpu => "cpu",
+            HistoryMetric::Power => "power",
+            HistoryMetric::Swap => "swap",
+        }
+    }
+
+    fn number(&self, point: &Point) -> Option<f32> {
+        let sample = point.sample;
+        match self {
+            HistoryMetric::Battery => sample.battery_percent.map(f32::from),
+            HistoryMetric::Temp => sample.cpu_celsius,
+            HistoryMetric::Cpu => sample.cpu_usage,
+            HistoryMetric::Swap => point.swap_rate.map(|(_, swapouts)| swapouts),
+            HistoryMetric::Power => None,
+        }
+    }
+
+    fn json_value(&self, point: &Point) -> serde_json::Value {
+        match self {
+            HistoryMetric::Power => serde_json::json!(point.sample.power_source),
+            HistoryMetric::Swap => serde_json::json!({
+                "used_bytes": point.sample.swap_used_bytes,
+                "swapins_per_minute": point.swap_rate.map(|(swapins, _)| swapins),
+                "swapouts_per_minute": point.swap_rate.map(|(_, swapouts)| swapouts),
+            }),
+            _ => serde_json::json!(self.number(point)),
+        }
+    }
+
+    /// Raw value for CSV; empty when the sample has none.
+    fn text_value(&self, point: &Point) -> String {
+        match self {
+            HistoryMetric::Power => point.sample.power_source.clone(),
+            _ => self.number(point).map(|value| format!("{:.1}", value)).unwrap_or_default(),
+        }
+    }
+
+    fn display_value(&self, point: &Point) -> String {
+        match (self, self.number(point)) {
+            (HistoryMetric::Power, _) => point.sample.power_source.clone(),
+            (_, None) => "-".to_string(),
+            (HistoryMetric::Battery, Some(value)) => format!("{:.0}%", value),
+            (HistoryMetric::Temp, Some(value)) => format!("{:.1}°C", value),
+            (HistoryMetric::Swap, Some(value)) => format!("{:.0}/min", value),
+            (_, Some(value)) => format!("{:.1}%", value),
+        }
+    }
+
+    /// min/avg/max for numeric metrics, time on battery for power.
+    fn summary(&self, points: &[Point]) -> Option<String> {
+        if let HistoryMetric::Power = self {
+            let on_battery = points.iter().filter(|point| point.sample.power_source == "battery").count();
+            return Some(format!("on battery in {} of {} samples", on_battery, points.len()));
+        }
+
+        let values: Vec<f32> = points.iter().filter_map(|point| self.number(point)).collect();
+        if values.is_empty() {
+            return None;
+        }
+        let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let avg = values.iter().sum::<f32>() / values.len() as f32;
+        let mut summary = format!("min {:.1}, avg {:.1}, max {:.1}", min, avg, max);
+        if let HistoryMetric::Swap = self {
+            let peak = points.iter().filter_map(|point| point.sample.swap_used_bytes).max();
+            summary.push_str(" pages swapped out per minute");
+            if let Some(peak) = peak {
+                summary.push_str(&format!(", up to {} of swap in use", format_bytes(peak)));
+            }
+        }
+        Some(summary)
+    }
+}
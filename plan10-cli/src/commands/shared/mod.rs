@@ -5,6 +5,25 @@ pub mod monitor;
 pub mod status;
 pub mod setup;
 pub mod config_cmd;
+pub mod workspace;
+pub mod multi_watch;
+pub mod power_history;
+pub mod annotate;
+pub mod tips;
+pub mod readings;
+pub mod powermetrics;
+pub mod metrics_history;
+pub mod dashboard;
+pub mod watch_summary;
+pub mod sensors;
+pub mod network;
+pub mod disk;
+pub mod battery_trend;
+pub mod battery_runtime;
+pub mod power_events;
+pub mod power_assertions;
+pub mod logs;
+pub mod wake_analysis;
 
 use anyhow::Result;
 use crate::{Config, ExecutionMode, MonitorCommands, WatchType};
@@ -21,25 +40,79 @@ pub async fn execute_monitor_command(
         MonitorCommands::Temp { raw, host } => {
             temp::execute_temp_command(raw, host, config, execution_mode, verbose).await
         }
-        MonitorCommands::Battery { detailed, raw, host } => {
+        MonitorCommands::Battery { trend: true, since, host, .. } => {
+            battery_trend::execute_trend(since, host, config, execution_mode, crate::OutputFormat::Text, verbose).await
+        }
+        MonitorCommands::Battery { detailed, raw, host, .. } => {
             battery::execute_battery_command(detailed, raw, host, config, execution_mode, verbose).await
         }
-        MonitorCommands::Power { verbose: power_verbose, battery, sleep, all, fixes, host } => {
+        MonitorCommands::Power { battery, sleep, all, fixes, changes, since, host } => {
+            if changes {
+                return power_history::execute_power_changes(host, since, config, execution_mode, verbose).await;
+            }
             power_diagnostics::execute_power_diagnostics_command(
-                power_verbose, battery, sleep, all, fixes, host, config, execution_mode, verbose
+                verbose, battery, sleep, all, fixes, host, config, execution_mode, verbose
             ).await
         }
-        MonitorCommands::System { host } => {
-            execute_system_monitor(host, config, execution_mode, verbose).await
+        MonitorCommands::Network { interface, all, host } => {
+            network::execute_network(interface, all, host, config, execution_mode, verbose).await
+        }
+        MonitorCommands::Events { since, kind, process, host } => {
+            power_events::execute_events(since, kind, process, host, config, execution_mode, crate::OutputFormat::Text, verbose).await
+        }
+        MonitorCommands::Assertions { watch, interval, host } => {
+            power_assertions::execute_assertions(watch, interval, host, config, execution_mode, crate::OutputFormat::Text, verbose).await
+        }
+        MonitorCommands::Logs { service, follow, lines, host } => {
+            logs::execute_logs(service, follow, lines, host, config, execution_mode, verbose).await
+        }
+        MonitorCommands::Disk { device, host } => {
+            disk::execute_disk(device, host, config, execution_mode, verbose).await
+        }
+        MonitorCommands::Sensors { kind, host } => {
+            sensors::execute_sensors(kind, host, config, execution_mode, verbose).await
+        }
+        MonitorCommands::History { since, metric, csv, host } => {
+            metrics_history::execute_history(since, metric, csv, host, config, execution_mode, crate::OutputFormat::Text, verbose).await
+        }
+        MonitorCommands::Export { format, since, file, host, all } => {
+            metrics_history::execute_export(format, since, file, host, all, config, execution_mode, verbose).await
+        }
+        MonitorCommands::System { host, top } => {
+            execute_system_monitor(host, top, config, execution_mode, verbose).await
         }
-        MonitorCommands::Watch { interval, monitor, host } => {
-            execute_watch_monitor(interval, monitor, host, config, execution_mode, verbose).await
+        MonitorCommands::Watch { interval, monitor, host, all, layout, diff } => {
+            let mut layout = config.watch_layout(&layout)?;
+            let host = if all {
+                note_disabled(&config.disabled_server_names(|_| true));
+                config.enabled_server_names()
+            } else {
+                host
+            };
+            // The table compares hosts, so it stays a table even for one
+            let table = layout.arrangement == crate::config::WatchArrangement::Table && !host.is_empty();
+            if all || table || host.len() > 1 {
+                let panel = match monitor {
+                    WatchType::All => None,
+                    WatchType::Temp => Some(crate::config::WatchPanel::Temp),
+                    WatchType::Battery => Some(crate::config::WatchPanel::Battery),
+                    WatchType::Power => Some(crate::config::WatchPanel::Power),
+                    WatchType::System => Some(crate::config::WatchPanel::System),
+                };
+                if let Some(panel) = panel {
+                    layout.panels = vec![panel];
+                }
+                multi_watch::execute_multi_watch(interval, host, layout, diff, config, verbose).await
+            } else {
+                execute_watch_monitor(interval, monitor, host.into_iter().next(), config, execution_mode, verbose).await
+            }
         }
     }
 }
 
 async fn execute_system_monitor(
     host: Option<String>,
+    top: usize,
     config: &Config,
     execution_mode: ExecutionMode,
     verbose: bool,
@@ -48,23 +121,23 @@ async fn execute_system_monitor(
     
     match execution_mode {
         ExecutionMode::Local => {
-            execute_local_system_monitor(verbose).await
+            execute_local_system_monitor(top, verbose).await
         }
         ExecutionMode::Remote { host: default_host } => {
             let target_host = host.unwrap_or(default_host);
-            execute_remote_system_monitor(&target_host, config, verbose).await
+            execute_remote_system_monitor(&target_host, top, config, verbose).await
         }
         ExecutionMode::Auto => {
             if let Some(target_host) = host {
-                execute_remote_system_monitor(&target_host, config, verbose).await
+                execute_remote_system_monitor(&target_host, top, config, verbose).await
             } else {
-                execute_local_system_monitor(verbose).await
+                execute_local_system_monitor(top, verbose).await
             }
         }
     }
 }
 
-async fn execute_local_system_monitor(verbose: bool) -> Result<()> {
+async fn execute_local_system_monitor(top: usize, verbose: bool) -> Result<()> {
     use sysinfo::{System, SystemExt, CpuExt, DiskExt};
     
     let mut system = System::new_all();
@@ -80,11 +153,7 @@ async fn execute_local_system_monitor(verbose: bool) -> Result<()> {
     println!("  Usage: {:.1}%", system.global_cpu_info().cpu_usage());
     println!("  Load Average: {:?}", system.load_average());
     
-    // Memory info
-    println!("\n{}:", "Memory".bold());
-    println!("  Total: {} GB", system.total_memory() / 1_000_000);
-    println!("  Used: {} GB", system.used_memory() / 1_000_000);
-    println!("  Available: {} GB", system.available_memory() / 1_000_000);
+    monitor::print_memory(&readings::memory(&readings::Source::Local), readings::paging_rates(&readings::Source::Local));
     
     // Disk info
     println!("\n{}:", "Storage".bold());
@@ -98,22 +167,29 @@ async fn execute_local_system_monitor(verbose: bool) -> Result<()> {
                  disk.mount_point().display(),
                  used_gb, total_gb, usage_pct);
     }
+
+    monitor::print_gpus(&readings::gpus(&readings::Source::Local), crate::sensors::read_sensors().gpu_celsius);
+
+    if top > 0 {
+        monitor::print_processes(&readings::processes(&readings::Source::Local, top));
+    }
     
     Ok(())
 }
 
 async fn execute_remote_system_monitor(
     host: &str,
+    top: usize,
     config: &Config,
     verbose: bool,
 ) -> Result<()> {
     let server = config.resolve_server(host)
         .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
 
-    let client = crate::ssh::SshClient::connect(server, config).await?;
+    let mut client = crate::ssh::SshClient::connect(server, config).await?;
     
     // Get system information
-    let system_info = client.get_system_info()?;
+    let system_info = crate::ssh::with_reconnect(&mut client, config, |client| client.get_system_info()).await?;
     
     println!("{}:", "System Information".bold());
     println!("  Hostname: {}", system_info.hostname);
@@ -121,8 +197,18 @@ async fn execute_remote_system_monitor(
     println!("  Uptime: {}", system_info.uptime);
     println!("  User: {}", system_info.current_user);
     
+    let source = readings::Source::Remote(&client);
+    monitor::print_memory(&readings::memory(&source), readings::paging_rates(&source));
+
     println!("\n{}:", "Storage".bold());
     println!("{}", system_info.disk_usage);
+
+    let temperature = readings::temperature(&source, &config.thresholds(Some(server)));
+    monitor::print_gpus(&temperature.gpus, temperature.gpu_celsius);
+
+    if top > 0 {
+        monitor::print_processes(&readings::processes(&source, top));
+    }
     
     Ok(())
 }
@@ -158,7 +244,7 @@ async fn execute_watch_monitor(
                 println!();
                 battery::execute_battery_command(false, false, host.clone(), config, execution_mode.clone(), false).await?;
                 println!();
-                execute_system_monitor(host.clone(), config, execution_mode.clone(), false).await?;
+                execute_system_monitor(host.clone(), 0, config, execution_mode.clone(), false).await?;
             }
             WatchType::Temp => {
                 temp::execute_temp_command(false, host.clone(), config, execution_mode.clone(), false).await?;
@@ -172,7 +258,7 @@ async fn execute_watch_monitor(
                 ).await?;
             }
             WatchType::System => {
-                execute_system_monitor(host.clone(), config, execution_mode.clone(), false).await?;
+                execute_system_monitor(host.clone(), 0, config, execution_mode.clone(), false).await?;
             }
         }
         
@@ -1,47 +1,178 @@
 use anyhow::Result;
-use crate::{Config, ExecutionMode, MonitorCommands, WatchType};
+use crate::{Config, ExecutionMode, MonitorCommands, OutputFormat};
 use crate::commands::utils::*;
-use crate::commands::shared::{temp, battery, power_diagnostics};
+use crate::config::{WatchArrangement, WatchPanel};
+use crate::commands::shared::{temp, battery, power_diagnostics, power_history, metrics_history, multi_watch, dashboard, readings, battery_runtime, sensors, network, disk, battery_trend, power_events, power_assertions, logs};
+use crate::commands::shared::readings::PowerSource;
+use crate::utils::formatting::format_bytes;
 use colored::*;
-use tokio::time::{sleep, Duration};
-use std::io::{self, Write};
 
 pub async fn execute(
     cmd: MonitorCommands,
     config: &Config,
     execution_mode: ExecutionMode,
+    output: OutputFormat,
     verbose: bool,
 ) -> Result<()> {
+    // History takes --output json itself; export has its own --format
+    if output == OutputFormat::Json && !matches!(cmd, MonitorCommands::History { .. } | MonitorCommands::Export { .. }) {
+        return print_monitor_json(cmd, config, execution_mode).await;
+    }
+
     match cmd {
         MonitorCommands::Temp { raw, host } => {
             temp::execute_temp_command(raw, host, config, execution_mode, verbose).await
         }
-        MonitorCommands::Battery { detailed, raw, host } => {
-            battery::execute_battery_command(detailed, raw, host, config, execution_mode, verbose).await
+        MonitorCommands::Battery { trend: true, since, host, .. } => {
+            battery_trend::execute_trend(since, host, config, execution_mode, output, verbose).await
+        }
+        MonitorCommands::Battery { detailed, raw, host, .. } => {
+            let target = readings::target_host(host.clone(), &execution_mode);
+            battery::execute_battery_command(detailed, raw, host, config, execution_mode, verbose).await?;
+            battery_runtime::print_estimate(target.as_deref(), config, verbose).await;
+            battery_trend::check_health(target.as_deref(), config, verbose).await;
+            Ok(())
         }
         MonitorCommands::Power { 
-            verbose: power_verbose, 
             battery, 
             sleep, 
             all, 
             fixes, 
+            changes,
+            since,
             host 
         } => {
+            if changes {
+                return power_history::execute_power_changes(host, since, config, execution_mode, verbose).await;
+            }
             power_diagnostics::execute_power_diagnostics_command(
-                power_verbose, battery, sleep, all, fixes, host, config, execution_mode, verbose
+                verbose, battery, sleep, all, fixes, host, config, execution_mode, verbose
             ).await
         }
-        MonitorCommands::System { host } => {
-            execute_system_monitor(host, config, execution_mode, verbose).await
+        MonitorCommands::Network { interface, all, host } => {
+            network::execute_network(interface, all, host, config, execution_mode, verbose).await
+        }
+        MonitorCommands::Events { since, kind, process, host } => {
+            power_events::execute_events(since, kind, process, host, config, execution_mode, output, verbose).await
+        }
+        MonitorCommands::Assertions { watch, interval, host } => {
+            power_assertions::execute_assertions(watch, interval, host, config, execution_mode, output, verbose).await
+        }
+        MonitorCommands::Logs { service, follow, lines, host } => {
+            logs::execute_logs(service, follow, lines, host, config, execution_mode, verbose).await
+        }
+        MonitorCommands::Disk { device, host } => {
+            disk::execute_disk(device, host, config, execution_mode, verbose).await
+        }
+        MonitorCommands::Sensors { kind, host } => {
+            sensors::execute_sensors(kind, host, config, execution_mode, verbose).await
+        }
+        MonitorCommands::History { since, metric, csv, host } => {
+            metrics_history::execute_history(since, metric, csv, host, config, execution_mode, output, verbose).await
         }
-        MonitorCommands::Watch { interval, monitor, host } => {
-            execute_watch_monitor(interval, monitor, host, config, execution_mode, verbose).await
+        MonitorCommands::Export { format, since, file, host, all } => {
+            metrics_history::execute_export(format, since, file, host, all, config, execution_mode, verbose).await
+        }
+        MonitorCommands::System { host, top } => {
+            execute_system_monitor(host, top, config, execution_mode, verbose).await
+        }
+        MonitorCommands::Watch { interval, monitor, host, all, layout, diff } => {
+            let mut layout = config.watch_layout(&layout)?;
+            let focus = match monitor {
+                crate::WatchType::All => None,
+                crate::WatchType::Temp => Some(WatchPanel::Temp),
+                crate::WatchType::Battery => Some(WatchPanel::Battery),
+                crate::WatchType::Power => Some(WatchPanel::Power),
+                crate::WatchType::System => Some(WatchPanel::System),
+            };
+            let host = if all {
+                note_disabled(&config.disabled_server_names(|_| true));
+                config.enabled_server_names()
+            } else {
+                host
+            };
+            // The table compares hosts, so it stays a table even for one
+            let table = layout.arrangement == WatchArrangement::Table && !host.is_empty();
+            if all || table || host.len() > 1 {
+                // Panes have no keys to switch panels, so a monitor narrows them
+                if let Some(panel) = focus {
+                    layout.panels = vec![panel];
+                }
+                multi_watch::execute_multi_watch(interval, host, layout, diff, config, verbose).await
+            } else {
+                dashboard::execute_dashboard(interval, focus, host.into_iter().next(), layout, diff, config, execution_mode, verbose).await
+            }
+        }
+    }
+}
+
+/// Structured readings for temp, battery and power; the other monitors are
+/// interactive or already covered by `status`.
+async fn print_monitor_json(cmd: MonitorCommands, config: &Config, execution_mode: ExecutionMode) -> Result<()> {
+    match cmd {
+        MonitorCommands::Temp { host, .. } => {
+            let target = readings::target_host(host, &execution_mode);
+            print_json(&readings::read_from(target.as_deref(), config, |source, server| {
+                readings::temperature(source, &config.thresholds(server))
+            }).await?)
+        }
+        MonitorCommands::Network { interface, all, host } => {
+            let target = readings::target_host(host, &execution_mode);
+            let mut report = readings::read_from(target.as_deref(), config, |source, _| readings::network(source)).await?;
+            network::select_interfaces(&mut report.reading, interface.as_deref(), all);
+            print_json(&report)
+        }
+        MonitorCommands::Sensors { kind, host } => {
+            let target = readings::target_host(host, &execution_mode);
+            let report = readings::read_from(target.as_deref(), config, |source, _| readings::sensors(source)).await?;
+            let mut reading = report.reading?;
+            reading.sensors.retain(|sensor| kind.map_or(true, |kind| sensor.kind == kind));
+            print_json(&readings::HostReading { host: report.host, timestamp: report.timestamp, reading })
+        }
+        MonitorCommands::Events { since, kind, process, host } => {
+            power_events::execute_events(since, kind, process, host, config, execution_mode, OutputFormat::Json, false).await
+        }
+        MonitorCommands::Assertions { watch, interval, host } => {
+            power_assertions::execute_assertions(watch, interval, host, config, execution_mode, OutputFormat::Json, false).await
+        }
+        MonitorCommands::Disk { device, host } => {
+            let target = readings::target_host(host, &execution_mode);
+            print_json(&readings::read_from(target.as_deref(), config, |source, _| {
+                readings::disks(source, device.as_deref())
+            }).await?)
+        }
+        MonitorCommands::Battery { trend: true, since, host, .. } => {
+            battery_trend::execute_trend(since, host, config, execution_mode, OutputFormat::Json, false).await
+        }
+        MonitorCommands::Battery { host, .. } => {
+            let target = readings::target_host(host, &execution_mode);
+            let report = readings::read_from(target.as_deref(), config, |source, server| {
+                let mut reading = readings::battery(source);
+                let power = readings::power(source);
+                reading.runtime = battery_runtime::estimate(source, server, power.source == PowerSource::Battery, power.battery_percent, config);
+                reading
+            }).await?;
+            battery_trend::record_health(config, &report.host, &report.reading.health, false);
+            print_json(&report)
+        }
+        MonitorCommands::Power { changes: false, host, .. } => {
+            let target = readings::target_host(host, &execution_mode);
+            print_json(&readings::read_from(target.as_deref(), config, |source, _| {
+                readings::power_settings(source, config)
+            }).await?)
+        }
+        MonitorCommands::Power { changes: true, .. } => {
+            anyhow::bail!("--output json is not supported for 'monitor power --changes'")
+        }
+        MonitorCommands::System { .. } | MonitorCommands::Watch { .. } | MonitorCommands::History { .. } | MonitorCommands::Export { .. } | MonitorCommands::Logs { .. } => {
+            anyhow::bail!("--output json is supported for 'monitor temp', 'monitor sensors', 'monitor network', 'monitor disk', 'monitor events', 'monitor assertions', 'monitor battery' and 'monitor power'")
         }
     }
 }
 
 async fn execute_system_monitor(
     host: Option<String>,
+    top: usize,
     config: &Config,
     execution_mode: ExecutionMode,
     verbose: bool,
@@ -50,23 +181,23 @@ async fn execute_system_monitor(
     
     match execution_mode {
         ExecutionMode::Local => {
-            execute_local_system_monitor(verbose).await
+            execute_local_system_monitor(top, verbose).await
         }
         ExecutionMode::Remote { host: default_host } => {
             let target_host = host.unwrap_or(default_host);
-            execute_remote_system_monitor(&target_host, config, verbose).await
+            execute_remote_system_monitor(&target_host, top, config, verbose).await
         }
         ExecutionMode::Auto => {
             if let Some(target_host) = host {
-                execute_remote_system_monitor(&target_host, config, verbose).await
+                execute_remote_system_monitor(&target_host, top, config, verbose).await
             } else {
-                execute_local_system_monitor(verbose).await
+                execute_local_system_monitor(top, verbose).await
             }
         }
     }
 }
 
-async fn execute_local_system_monitor(verbose: bool) -> Result<()> {
+async fn execute_local_system_monitor(top: usize, verbose: bool) -> Result<()> {
     use sysinfo::{System, SystemExt, CpuExt, DiskExt};
     
     let mut system = System::new_all();
@@ -82,11 +213,7 @@ async fn execute_local_system_monitor(verbose: bool) -> Result<()> {
     println!("  Usage: {:.1}%", system.global_cpu_info().cpu_usage());
     println!("  Load Average: {:?}", system.load_average());
     
-    // Memory info
-    println!("\n{}:", "Memory".bold());
-    println!("  Total: {} GB", system.total_memory() / 1_000_000);
-    println!("  Used: {} GB", system.used_memory() / 1_000_000);
-    println!("  Available: {} GB", system.available_memory() / 1_000_000);
+    print_memory(&readings::memory(&readings::Source::Local), readings::paging_rates(&readings::Source::Local));
     
     // Disk info
     println!("\n{}:", "Storage".bold());
@@ -100,12 +227,19 @@ async fn execute_local_system_monitor(verbose: bool) -> Result<()> {
                  disk.mount_point().display(),
                  used_gb, total_gb, usage_pct);
     }
+
+    print_gpus(&readings::gpus(&readings::Source::Local), crate::sensors::read_sensors().gpu_celsius);
+
+    if top > 0 {
+        print_processes(&readings::processes(&readings::Source::Local, top));
+    }
     
     Ok(())
 }
 
 async fn execute_remote_system_monitor(
     host: &str,
+    top: usize,
     config: &Config,
     verbose: bool,
 ) -> Result<()> {
@@ -123,59 +257,116 @@ async fn execute_remote_system_monitor(
     println!("  Uptime: {}", system_info.uptime);
     println!("  User: {}", system_info.current_user);
     
+    let source = readings::Source::Remote(&client);
+    print_verbose(&format!("Reading {} as a {} server", host, source.os().name()), verbose);
+    print_memory(&readings::memory(&source), readings::paging_rates(&source));
+
     println!("\n{}:", "Storage".bold());
     println!("{}", system_info.disk_usage);
+
+    let temperature = readings::temperature(&source, &config.thresholds(Some(server)));
+    print_gpus(&temperature.gpus, temperature.gpu_celsius);
+
+    if top > 0 {
+        print_processes(&readings::processes(&source, top));
+    }
     
     Ok(())
 }
 
-async fn execute_watch_monitor(
-    interval: u64,
-    monitor_type: WatchType,
-    host: Option<String>,
-    config: &Config,
-    execution_mode: ExecutionMode,
-    verbose: bool,
-) -> Result<()> {
-    print_info(&format!("Starting continuous monitoring ({}s interval)", interval));
-    print_info("Press Ctrl+C to stop");
-    
-    loop {
-        // Clear screen
-        print!("\x1B[2J\x1B[1;1H");
-        io::stdout().flush().unwrap();
-        
-        // Show timestamp
-        let now = chrono::Utc::now();
-        println!("{} Monitor Update - {}", "🕐".cyan(), now.format("%Y-%m-%d %H:%M:%S UTC"));
-        println!("{}", "=".repeat(50));
-        
-        match monitor_type {
-            WatchType::All => {
-                // Show all monitoring data
-                temp::execute_temp_command(false, host.clone(), config, execution_mode.clone(), false).await?;
-                println!();
-                battery::execute_battery_command(false, false, host.clone(), config, execution_mode.clone(), false).await?;
-                println!();
-                execute_system_monitor(host.clone(), config, execution_mode.clone(), false).await?;
-            }
-            WatchType::Temp => {
-                temp::execute_temp_command(false, host.clone(), config, execution_mode.clone(), false).await?;
-            }
-            WatchType::Battery => {
-                battery::execute_battery_command(false, false, host.clone(), config, execution_mode.clone(), false).await?;
-            }
-            WatchType::Power => {
-                power_diagnostics::execute_power_diagnostics_command(
-                    false, false, false, false, false, host.clone(), config, execution_mode.clone(), false
-                ).await?;
-            }
-            WatchType::System => {
-                execute_system_monitor(host.clone(), config, execution_mode.clone(), false).await?;
-            }
+/// The busiest processes, as two short tables.
+pub fn print_processes(processes: &readings::ProcessesReading) {
+    for (title, list) in [("Top Processes by CPU", &processes.by_cpu), ("Top Processes by Memory", &processes.by_memory)] {
+        println!("\n{}:", title.bold());
+        if list.is_empty() {
+            println!("  (process list unavailable)");
+        }
+        for process in list {
+            println!(
+                "  {:>7}  {:<28} {:>6.1}%  {:>10}",
+                process.pid,
+                process.name,
+                process.cpu_percent,
+                format_bytes(process.memory_bytes)
+            );
         }
-        
-        println!("\n{} Next update in {}s...", "⏰".dimmed(), interval);
-        sleep(Duration::from_secs(interval)).await;
     }
-}
\ No newline at end of file
+}
+
+/// Memory use as macOS counts it, with pressure, compression, swap and
+/// paging activity coloured by how worried to be.
+pub fn print_memory(memory: &readings::MemoryReading, paging: Option<readings::PagingRates>) {
+    println!("\n{}:", "Memory".bold());
+    let Some(used) = memory.used_bytes else {
+        println!("  (memory statistics unavailable)");
+        return;
+    };
+
+    match memory.total_bytes {
+        Some(total) => println!("  Used: {} / {}", format_bytes(used), format_bytes(total)),
+        None => println!("  Used: {}", format_bytes(used)),
+    }
+    let parts: Vec<String> = [("app", memory.app_bytes), ("wired", memory.wired_bytes), ("cached", memory.cached_bytes)]
+        .into_iter()
+        .filter_map(|(name, bytes)| Some(format!("{} {}", name, format_bytes(bytes?))))
+        .collect();
+    if !parts.is_empty() {
+        println!("  {}", parts.join(", ").dimmed());
+    }
+
+    if let Some(pressure) = memory.pressure {
+        let free = memory.free_percent.map(|percent| format!(" ({}% free)", percent)).unwrap_or_default();
+        let text = match pressure {
+            readings::MemoryPressure::Normal => format!("🟢 Normal{}", free).green(),
+            readings::MemoryPressure::Warning => format!("🟡 Warning{}", free).yellow(),
+            readings::MemoryPressure::Critical => format!("🔴 Critical{}", free).red(),
+        };
+        println!("  Pressure: {}", text);
+    }
+    if let (Some(compressed), Some(percent)) = (memory.compressed_bytes, memory.compressed_percent()) {
+        let text = format!("{} ({:.0}% of memory)", format_bytes(compressed), percent);
+        println!("  Compressed: {}", if percent >= 25.0 { text.yellow() } else { text.normal() });
+    }
+    match (memory.swap_used_bytes, memory.swap_total_bytes) {
+        (Some(0), _) => println!("  Swap: {}", "none in use".green()),
+        (Some(used), Some(total)) => println!("  Swap: {}", format!("{} / {}", format_bytes(used), format_bytes(total)).yellow()),
+        (Some(used), None) => println!("  Swap: {}", format_bytes(used).yellow()),
+        _ => {}
+    }
+    if let (Some(count), Some(bytes)) = (memory.swapfiles, memory.swapfile_bytes) {
+        println!("  Swap files: {} ({})", count, format_bytes(bytes));
+    }
+    if let Some(paging) = paging {
+        let text = format!(
+            "{:.0} in, {:.0} out per second ({:.0} pageouts)",
+            paging.swapins_per_second, paging.swapouts_per_second, paging.pageouts_per_second
+        );
+        // Steady swapping means the working set no longer fits in memory
+        let text = if paging.swapins_per_second + paging.swapouts_per_second > 0.0 { text.yellow() } else { text.normal() };
+        println!("  Swapping: {}", text);
+    }
+}
+
+/// Utilization and memory of each GPU, with the die temperature when known.
+pub fn print_gpus(gpus: &[readings::GpuReading], celsius: Option<f32>) {
+    if gpus.is_empty() && celsius.is_none() {
+        return;
+    }
+    println!("\n{}:", "GPU".bold());
+    for gpu in gpus {
+        if let Some(name) = &gpu.name {
+            println!("  {}", name);
+        }
+        if let Some(percent) = gpu.utilization_percent {
+            println!("  Utilization: {:.0}%", percent);
+        }
+        match (gpu.memory_used_bytes, gpu.memory_total_bytes) {
+            (Some(used), Some(total)) => println!("  VRAM: {} / {}", format_bytes(used), format_bytes(total)),
+            (Some(used), None) => println!("  Memory in use: {}", format_bytes(used)),
+            _ => {}
+        }
+    }
+    if let Some(celsius) = celsius {
+        println!("  Temperature: {:.1}°C", celsius);
+    }
+}
@@ -0,0 +1,492 @@
+use anyhow::Result;
+use crate::Config;
+use crate::commands::client::check::parse_battery_percentage;
+use crate::commands::shared::readings;
+use crate::commands::shared::watch_summary::{Metric, WatchSummary};
+use crate::commands::utils::*;
+use crate::config::{ServerDefinition, Thresholds, WatchArrangement, WatchLayoutDefinition, WatchPanel};
+use crate::ssh::SshClient;
+use colored::*;
+use std::io::{self, Write};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::{sleep, Duration};
+
+/// One remote round trip per refresh; sections are separated by markers so a
+/// missing tool on the server only blanks its own section.
+const PANE_COMMAND: &str = "echo '@batt'; pmset -g batt 2>/dev/null | head -2; \
+    echo '@caffeinate'; pgrep -x caffeinate >/dev/null && echo yes || echo no; \
+    echo '@therm'; pmset -g therm 2>/dev/null; \
+    echo '@temp'; plan10 --server-mode --json monitor temp 2>/dev/null; \
+    echo '@load'; sysctl -n vm.loadavg 2>/dev/null; \
+    echo '@uptime'; uptime | sed 's/.*up \\([^,]*\\),.*/\\1/'; \
+    echo '@disk'; df -h / | tail -1 | awk '{print $5}'";
+
+#[derive(Debug, Clone)]
+pub struct Pane {
+    pub name: String,
+    pub healthy: Option<bool>,
+    pub lines: Vec<String>,
+    pub updated: Option<chrono::DateTime<chrono::Utc>>,
+    /// The host's latest readings, for the table layout
+    pub summary: Option<Summary>,
+    /// Lines and readings from the refresh before, for `--diff`
+    pub previous_lines: Vec<String>,
+    pub previous_summary: Option<Summary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    pub on_battery: bool,
+    pub battery_percent: Option<u8>,
+    pub cpu_celsius: Option<f32>,
+    /// CPU speed limit in percent; below 100 means throttled
+    pub speed_limit: Option<u8>,
+    pub caffeinate: bool,
+    pub load: String,
+}
+
+/// Watch several hosts at once, one pane per host showing the layout's
+/// panels. Each host keeps its own
+/// connection and refreshes independently, so a slow or unreachable machine
+/// never holds up the others. Ctrl+C closes the connections and prints what
+/// the session observed. With `diff`, values that changed since the previous
+/// refresh are highlighted.
+pub async fn execute_multi_watch(
+    interval: u64,
+    hosts: Vec<String>,
+    layout: WatchLayoutDefinition,
+    diff: bool,
+    config: &Config,
+    verbose: bool,
+) -> Result<()> {
+    if hosts.is_empty() {
+        anyhow::bail!("No enabled servers configured");
+    }
+
+    let mut servers = Vec::new();
+    for host in &hosts {
+        let server = config.resolve_server(host)
+            .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+        servers.push(server.clone());
+    }
+
+    print_info(&format!("Watching {} hosts ({}s interval)", servers.len(), interval));
+    print_info("Press Ctrl+C to stop");
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let summary = Arc::new(Mutex::new(WatchSummary::start()));
+
+    let panes: Arc<Mutex<Vec<Pane>>> = Arc::new(Mutex::new(
+        servers.iter()
+            .map(|server| Pane {
+                name: server.name.clone(),
+                healthy: None,
+                lines: vec!["connecting...".to_string()],
+                updated: None,
+                summary: None,
+                previous_lines: Vec::new(),
+                previous_summary: None,
+            })
+            .collect(),
+    ));
+
+    let mut tasks = Vec::new();
+    for (index, server) in servers.into_iter().enumerate() {
+        let panes = Arc::clone(&panes);
+        let summary = Arc::clone(&summary);
+        let config = config.clone();
+        let layout = layout.clone();
+        tasks.push(tokio::spawn(async move {
+            watch_host(index, server, layout, interval, config, panes, summary).await;
+        }));
+    }
+
+    loop {
+        let snapshot = panes.lock().unwrap().clone();
+        render(&snapshot, &layout, interval, diff, verbose);
+        tokio::select! {
+            _ = sleep(Duration::from_secs(interval.clamp(1, 2))) => {}
+            _ = interrupt.recv() => break,
+        }
+    }
+
+    // Hand the dropped clients to the session cache so they are disconnected
+    // properly rather than just losing their sockets
+    crate::ssh::reuse_sessions();
+    for task in &tasks {
+        task.abort();
+    }
+    for task in tasks {
+        let _ = task.await;
+    }
+    crate::ssh::close_sessions();
+
+    summary.lock().unwrap().print();
+    Ok(())
+}
+
+async fn watch_host(
+    index: usize,
+    server: ServerDefinition,
+    layout: WatchLayoutDefinition,
+    interval: u64,
+    config: Config,
+    panes: Arc<Mutex<Vec<Pane>>>,
+    summary: Arc<Mutex<WatchSummary>>,
+) {
+    let mut client: Option<SshClient> = None;
+    let limit = crate::ssh::operation_limit(&config);
+
+    loop {
+        // Only the refresh itself counts against client.concurrent_operations
+        let permit = limit.clone().acquire_owned().await;
+        if client.is_none() {
+            match SshClient::connect(&server, &config).await {
+                Ok(connected) => client = Some(connected),
+                Err(e) => update_pane(&panes, index, Some(false), vec![format!("unreachable: {}", e)]),
+            }
+        }
+
+        if let Some(connected) = client.take() {
+            // ssh2 calls block, so run them off the async workers
+            let result = tokio::task::spawn_blocking(move || {
+                let result = connected.execute_command(PANE_COMMAND);
+                (connected, result)
+            }).await;
+
+            match result {
+                Ok((connected, Ok(output))) if output.success => {
+                    let (healthy, lines, readings) = pane_lines(&output.stdout, &layout, config.thresholds(Some(&server)));
+                    summary.lock().unwrap().record(&server.name, &[
+                        (Metric::CpuTemperature, readings.cpu_celsius),
+                        (Metric::Battery, readings.battery_percent.map(f32::from)),
+                        (Metric::Load, readings.load.split_whitespace().next().and_then(|load| load.parse().ok())),
+                    ]);
+                    update_pane(&panes, index, Some(healthy), lines);
+                    set_summary(&panes, index, readings);
+                    client = Some(connected);
+                }
+                Ok((connected, Ok(output))) => {
+                    let error = crate::ssh::RemoteError::classify(&output);
+                    update_pane(&panes, index, Some(false), vec![error.to_string()]);
+                    client = Some(connected);
+                }
+                // Drop the connection and reconnect on the next round
+                Ok((_, Err(e))) => update_pane(&panes, index, Some(false), vec![format!("connection lost: {}", e)]),
+                Err(e) => update_pane(&panes, index, Some(false), vec![e.to_string()]),
+            }
+        }
+        drop(permit);
+
+        idle(&mut client, interval).await;
+    }
+}
+
+/// Wait for the next refresh, sending SSH keepalives meanwhile so a quiet
+/// connection isn't dropped by NAT or a Wi-Fi hiccup. A failed keepalive drops
+/// the client so the next round reconnects.
+async fn idle(client: &mut Option<SshClient>, interval: u64) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(interval);
+
+    loop {
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return;
+        }
+
+        let mut wait = deadline - now;
+        if let Some(connected) = client.as_ref() {
+            match connected.send_keepalive() {
+                Ok(Some(next)) => wait = wait.min(next),
+                Ok(None) => {}
+                Err(_) => *client = None,
+            }
+        }
+        sleep(wait).await;
+    }
+}
+
+fn update_pane(panes: &Mutex<Vec<Pane>>, index: usize, healthy: Option<bool>, lines: Vec<String>) {
+    let mut panes = panes.lock().unwrap();
+    if let Some(pane) = panes.get_mut(index) {
+        pane.healthy = healthy;
+        // "connecting..." is nothing to compare with
+        let previous = std::mem::replace(&mut pane.lines, lines);
+        pane.previous_lines = if pane.updated.is_some() { previous } else { Vec::new() };
+        pane.updated = Some(chrono::Utc::now());
+        // An error in between leaves nothing to compare the next summary with
+        pane.previous_summary = pane.summary.take();
+    }
+}
+
+fn set_summary(panes: &Mutex<Vec<Pane>>, index: usize, summary: Summary) {
+    if let Some(pane) = panes.lock().unwrap().get_mut(index) {
+        pane.summary = Some(summary);
+    }
+}
+
+fn pane_lines(output: &str, layout: &WatchLayoutDefinition, thresholds: Thresholds) -> (bool, Vec<String>, Summary) {
+    let section = |name: &str| -> String {
+        let marker = format!("@{}", name);
+        output.lines()
+            .skip_while(|line| line.trim() != marker)
+            .skip(1)
+            .take_while(|line| !line.starts_with('@'))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let batt = section("batt");
+    let on_battery = batt.contains("Battery Power");
+    let percent = parse_battery_percentage(&batt);
+    let caffeinate = section("caffeinate").trim() == "yes";
+    let thermal = readings::parse_thermal_limits(&section("therm"));
+    let speed_limit = thermal.throttled_to().or(thermal.cpu_speed_limit);
+    // The server's own plan10 reads the sensors; older installs print nothing
+    let cpu_celsius = serde_json::from_str::<serde_json::Value>(&section("temp")).ok()
+        .and_then(|reading| reading.get("cpu_celsius")?.as_f64())
+        .map(|celsius| celsius as f32);
+    let load = section("load").trim().trim_matches(|c| c == '{' || c == '}').trim().to_string();
+    let uptime = section("uptime").trim().to_string();
+    let disk = section("disk").trim().to_string();
+    let disk_percent = disk.trim_end_matches('%').parse::<u8>().ok();
+
+    let detailed = !layout.compact;
+    let mut lines = Vec::new();
+    let mut power_shown = false;
+    for panel in &layout.panels {
+        match panel {
+            // Battery and power share the power source line
+            WatchPanel::Battery | WatchPanel::Power if !power_shown => {
+                power_shown = true;
+                let source = if on_battery { "battery" } else { "AC" };
+                match percent {
+                    Some(pct) => lines.push(format!("Power:   {} ({}%)", source, pct)),
+                    None => lines.push(format!("Power:   {}", source)),
+                }
+                if layout.panels.contains(&WatchPanel::Power) {
+                    lines.push(format!("Caffeinate: {}", if caffeinate { "running" } else { "stopped" }));
+                }
+            }
+            WatchPanel::Battery | WatchPanel::Power => {}
+            WatchPanel::Temp => {
+                if let Some(celsius) = cpu_celsius {
+                    lines.push(format!("CPU:     {:.0}°C", celsius));
+                }
+                match speed_limit {
+                    Some(limit) if limit < 100 => lines.push(format!("Thermal: throttled ({}%)", limit)),
+                    Some(_) if detailed => lines.push("Thermal: normal".to_string()),
+                    None if detailed => lines.push("Thermal: unknown".to_string()),
+                    _ => {}
+                }
+            }
+            WatchPanel::System => {
+                lines.push(format!("Load:    {}", load));
+                if detailed {
+                    lines.push(format!("Uptime:  {}", uptime));
+                    lines.push(format!("Disk /:  {}", disk));
+                }
+            }
+        }
+    }
+
+    let low_battery = on_battery && percent.is_some_and(|pct| thresholds.battery_low(pct));
+    let throttled = speed_limit.is_some_and(|limit| limit < 100);
+    let disk_full = disk_percent.is_some_and(|pct| thresholds.disk_full(pct));
+    let hot = cpu_celsius.is_some_and(|celsius| thresholds.temp_exceeded(celsius));
+    let summary = Summary { on_battery, battery_percent: percent, cpu_celsius, speed_limit, caffeinate, load };
+    (caffeinate && !low_battery && !throttled && !disk_full && !hot, lines, summary)
+}
+
+fn render(panes: &[Pane], layout: &WatchLayoutDefinition, interval: u64, diff: bool, verbose: bool) {
+    let (width, _) = terminal_size();
+    let columns = match layout.arrangement {
+        WatchArrangement::Rows => 1,
+        WatchArrangement::Columns => panes.len().max(1),
+        WatchArrangement::Grid => (panes.len() as f64).sqrt().ceil().max(1.0) as usize,
+        WatchArrangement::Table => return render_table(panes, width, interval, diff, verbose),
+    };
+    // One character of separator between neighbouring panes
+    let pane_width = (width.saturating_sub(columns - 1) / columns).max(20);
+
+    let mut out = String::new();
+    out.push_str("\x1B[2J\x1B[1;1H");
+    out.push_str(&header(panes.len(), interval));
+
+    for row in panes.chunks(columns) {
+        let height = row.iter().map(|pane| pane.lines.len()).max().unwrap_or(0) + 2;
+        let rendered: Vec<Vec<String>> = row.iter()
+            .map(|pane| {
+                let changed = if diff { changed_lines(&pane.previous_lines, &pane.lines) } else { Vec::new() };
+                render_pane(pane, pane_width, height, &changed, verbose)
+            })
+            .collect();
+
+        for line in 0..height {
+            let parts: Vec<&str> = rendered.iter().map(|pane| pane[line].as_str()).collect();
+            out.push_str(&parts.join(" "));
+            out.push('\n');
+        }
+    }
+
+    print!("{}", out);
+    io::stdout().flush().unwrap();
+}
+
+fn header(hosts: usize, interval: u64) -> String {
+    format!(
+        "{} Monitor Update - {}  ({} hosts, {}s interval)\n",
+        "🕐".cyan(),
+        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        hosts,
+        interval
+    )
+}
+
+/// One row per host with its latest readings, whatever `monitor` is, so
+/// hosts compare at a glance.
+fn render_table(panes: &[Pane], width: usize, interval: u64, diff: bool, verbose: bool) {
+    let name_width = panes.iter().map(|pane| pane.name.chars().count()).max().unwrap_or(0).max(4);
+
+    let mut out = String::new();
+    out.push_str("\x1B[2J\x1B[1;1H");
+    out.push_str(&header(panes.len(), interval));
+    out.push('\n');
+    let heading = format!(
+        "   {}  {:<7} {:>7} {:>6} {:>9} {:<10} {}",
+        fit("HOST", name_width, ' '), "POWER", "BATTERY", "TEMP", "THROTTLE", "CAFFEINATE", "LOAD"
+    );
+    out.push_str(&format!("{}\n", fit(&heading, width, ' ').trim_end().bold()));
+
+    for pane in panes {
+        let status = match pane.healthy {
+            Some(true) => "ok".green(),
+            Some(false) => "!!".red(),
+            None => "..".dimmed(),
+        };
+        let mut changed = false;
+        let mut row = match &pane.summary {
+            Some(summary) => {
+                let mut cells = table_cells(summary);
+                if let Some(previous) = pane.previous_summary.as_ref().filter(|_| diff) {
+                    // Changed cells get a trailing `*`, which survives without colour
+                    for (cell, before) in cells.iter_mut().zip(table_cells(previous)) {
+                        if *cell != before {
+                            cell.push('*');
+                            changed = true;
+                        }
+                    }
+                }
+                format!(
+                    "{:<7} {:>7} {:>6} {:>9} {:<10} {}",
+                    cells[0], cells[1], cells[2], cells[3], cells[4], cells[5]
+                )
+            }
+            // The error, or "connecting..."
+            None => pane.lines.first().cloned().unwrap_or_default(),
+        };
+        if verbose {
+            if let Some(updated) = pane.updated {
+                row.push_str(&format!("  {}", updated.format("%H:%M:%S")));
+            }
+        }
+        let line = fit(&format!("{}  {}", fit(&pane.name, name_width, ' '), row), width.saturating_sub(3), ' ');
+        let line = if changed { line.trim_end().yellow().bold() } else { line.trim_end().normal() };
+        out.push_str(&format!("{} {}\n", status, line));
+    }
+
+    print!("{}", out);
+    io::stdout().flush().unwrap();
+}
+
+/// Power, battery, temperature, throttling, caffeinate and load columns of
+/// the table layout.
+fn table_cells(summary: &Summary) -> [String; 6] {
+    [
+        if summary.on_battery { "battery" } else { "AC" }.to_string(),
+        summary.battery_percent.map(|pct| format!("{}%", pct)).unwrap_or_else(|| "-".to_string()),
+        summary.cpu_celsius.map(|celsius| format!("{:.0}°C", celsius)).unwrap_or_else(|| "-".to_string()),
+        match summary.speed_limit {
+            Some(limit) if limit < 100 => format!("{}%", limit),
+            Some(_) => "no".to_string(),
+            None => "-".to_string(),
+        },
+        if summary.caffeinate { "running" } else { "stopped" }.to_string(),
+        summary.load.clone(),
+    ]
+}
+
+/// Which of `after` differ from the line at the same position in `before`.
+/// Nothing is marked on the first refresh, when there is no `before` yet.
+pub fn changed_lines(before: &[String], after: &[String]) -> Vec<bool> {
+    if before.is_empty() {
+        return Vec::new();
+    }
+    after.iter().enumerate().map(|(i, line)| before.get(i) != Some(line)).collect()
+}
+
+/// Draw a pane in a box; lines flagged in `changed` are marked with `*` and
+/// highlighted.
+pub fn render_pane(pane: &Pane, width: usize, height: usize, changed: &[bool], verbose: bool) -> Vec<String> {
+    let inner = width.saturating_sub(2);
+    let status = match pane.healthy {
+        Some(true) => "ok",
+        Some(false) => "!!",
+        None => "..",
+    };
+
+    let mut title = format!("─ {} {} ", status, pane.name);
+    if verbose {
+        if let Some(updated) = pane.updated {
+            title.push_str(&format!("{} ", updated.format("%H:%M:%S")));
+        }
+    }
+    let title = fit(&title, inner, '─');
+    let title = match pane.healthy {
+        Some(true) => title.green().to_string(),
+        Some(false) => title.red().to_string(),
+        None => title.dimmed().to_string(),
+    };
+
+    let mut lines = vec![format!("┌{}┐", title)];
+    for i in 0..height - 2 {
+        let text = pane.lines.get(i).map(String::as_str).unwrap_or("");
+        if changed.get(i) == Some(&true) {
+            lines.push(format!("│{}│", fit(&format!("*{}", text), inner, ' ').yellow().bold()));
+        } else {
+            lines.push(format!("│{}│", fit(&format!(" {}", text), inner, ' ')));
+        }
+    }
+    lines.push(format!("└{}┘", "─".repeat(inner)));
+    lines
+}
+
+/// Truncate or pad `text` to exactly `width` characters.
+pub fn fit(text: &str, width: usize, pad: char) -> String {
+    let mut fitted: String = text.chars().take(width).collect();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat(pad).take(width - len));
+    fitted
+}
+
+pub fn terminal_size() -> (usize, usize) {
+    let from_env = |name: &str| std::env::var(name).ok().and_then(|v| v.parse().ok());
+    if let (Some(cols), Some(lines)) = (from_env("COLUMNS"), from_env("LINES")) {
+        return (cols, lines);
+    }
+
+    Command::new("stty")
+        .arg("size")
+        .stdin(std::fs::File::open("/dev/tty").map(Into::into).unwrap_or_else(|_| std::process::Stdio::null()))
+        .output()
+        .ok()
+        .and_then(|output| {
+            let size = String::from_utf8_lossy(&output.stdout).to_string();
+            let mut parts = size.split_whitespace().map(|v| v.parse::<usize>().ok());
+            let lines = parts.next()??;
+            let cols = parts.next()??;
+            Some((cols, lines))
+        })
+        .unwrap_or((120, 40))
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode};
+use crate::commands::utils::*;
+use crate::commands::shared::readings::{self, InterfaceReading, NetworkReading};
+use crate::utils::formatting::format_bytes;
+use colored::*;
+
+/// Interfaces worth showing by default: up, and either linked or addressed.
+/// `--all` adds the idle and virtual ones (awdl, utun, bridges, ...).
+pub fn select_interfaces(reading: &mut NetworkReading, interface: Option<&str>, all: bool) {
+    reading.interfaces.retain(|candidate| match interface {
+        Some(name) => candidate.name == name,
+        None => all || (candidate.up && (candidate.status.as_deref() == Some("active") || !candidate.ipv4.is_empty())),
+    });
+}
+
+pub async fn execute_network(
+    interface: Option<String>,
+    all: bool,
+    host: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    verbose: bool,
+) -> Result<()> {
+    let target = readings::target_host(host, &execution_mode);
+    print_verbose("Sampling interface counters for one second", verbose);
+    let mut report = readings::read_from(target.as_deref(), config, |source, _| readings::network(source)).await?;
+    select_interfaces(&mut report.reading, interface.as_deref(), all);
+
+    print_header(&format!("Network: {}", report.host));
+    if report.reading.interfaces.is_empty() {
+        match &interface {
+            Some(name) => print_warning(&format!("No interface named '{}'", name)),
+            None => print_warning("No active interfaces (use --all to list every interface)"),
+        }
+    }
+    for interface in &report.reading.interfaces {
+        print_interface(interface);
+    }
+
+    if let Some(wifi) = &report.reading.wifi {
+        println!("\n{}:", "Wi-Fi".bold());
+        println!("  Network: {}", wifi.ssid.as_deref().unwrap_or("(hidden)"));
+        if let Some(rssi) = wifi.rssi_dbm {
+            let quality = match rssi {
+                rssi if rssi >= -60 => "good".green(),
+                rssi if rssi >= -70 => "fair".yellow(),
+                _ => "weak".red(),
+            };
+            match wifi.noise_dbm {
+                Some(noise) => println!("  Signal: {} dBm ({}), noise {} dBm, SNR {} dB", rssi, quality, noise, rssi - noise),
+                None => println!("  Signal: {} dBm ({})", rssi, quality),
+            }
+        }
+        if let Some(channel) = &wifi.channel {
+            println!("  Channel: {}", channel);
+        }
+        if let Some(rate) = wifi.tx_rate_mbps {
+            println!("  Transmit rate: {} Mbps", rate);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_interface(interface: &InterfaceReading) {
+    let link = match (interface.up, interface.status.as_deref()) {
+        (false, _) => "down".red(),
+        (true, Some("inactive")) => "no link".yellow(),
+        (true, _) => "up".green(),
+    };
+    println!("\n{} ({})", interface.name.bold(), link);
+
+    if let Some(media) = &interface.media {
+        println!("  Media: {}", media);
+    }
+    for address in interface.ipv4.iter().chain(&interface.ipv6) {
+        println!("  Address: {}", address);
+    }
+    if let Some(mac) = &interface.mac {
+        println!("  MAC: {}", mac);
+    }
+    if let (Some(rx), Some(tx)) = (interface.rx_bytes_per_second, interface.tx_bytes_per_second) {
+        println!("  Throughput: ↓ {}/s  ↑ {}/s", format_bytes(rx), format_bytes(tx));
+    }
+    let errors = interface.rx_errors.unwrap_or(0) + interface.tx_errors.unwrap_or(0);
+    if errors > 0 {
+        println!("  {} {} input / {} output errors since boot", "⚠️".yellow(),
+            interface.rx_errors.unwrap_or(0), interface.tx_errors.unwrap_or(0));
+    }
+}
@@ -0,0 +1,221 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode, OutputFormat};
+use crate::commands::utils::*;
+use crate::commands::shared::readings;
+use crate::utils::formatting::format_duration;
+use colored::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// One assertion from the "Listed by owning process" part of
+/// `pmset -g assertions`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerAssertion {
+    pub pid: u32,
+    pub process: String,
+    /// e.g. PreventUserIdleSystemSleep
+    pub assertion_type: String,
+    /// The description the process gave it
+    pub name: Option<String>,
+    /// How long it has been held
+    pub age_seconds: Option<u64>,
+    /// Seconds until it is released on its own, if it has a timeout
+    pub timeout_seconds: Option<u64>,
+    pub details: Option<String>,
+}
+
+pub type AssertionKey = (u32, String, Option<String>);
+
+impl PowerAssertion {
+    /// Identifies the assertion across refreshes of `--watch`.
+    fn key(&self) -> AssertionKey {
+        (self.pid, self.assertion_type.clone(), self.name.clone())
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AssertionsReading {
+    /// "Assertion status system-wide": how many of each type are held
+    pub system_wide: BTreeMap<String, u32>,
+    pub assertions: Vec<PowerAssertion>,
+}
+
+impl AssertionsReading {
+    /// Whether any assertion of `assertion_type` is held system-wide.
+    pub fn held(&self, assertion_type: &str) -> Option<bool> {
+        self.system_wide.get(assertion_type).map(|count| *count > 0)
+    }
+}
+
+/// Typed `pmset -g assertions` output:
+///
+/// ```text
+/// Assertion status system-wide:
+///    PreventUserIdleDisplaySleep    1
+/// Listed by owning process:
+///    pid 312(caffeinate): [0x000a1b2c00019999] 01:23:45 PreventUserIdleDisplaySleep named: "caffeinate command-line tool"
+///         Details: caffeinate asserting forever
+///         Timeout will fire in 3600 secs Action=TimeoutActionRelease
+/// ```
+///
+/// Kernel assertions and idle sleep preventers are left out.
+pub fn parse_assertions(output: &str) -> AssertionsReading {
+    let mut reading = AssertionsReading::default();
+    let mut section = "";
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if !line.starts_with(char::is_whitespace) {
+            section = trimmed;
+            continue;
+        }
+        match section {
+            "Assertion status system-wide:" => {
+                let mut fields = trimmed.split_whitespace();
+                if let (Some(name), Some(count)) = (fields.next(), fields.next().and_then(|count| count.parse().ok())) {
+                    reading.system_wide.insert(name.to_string(), count);
+                }
+            }
+            "Listed by owning process:" => {
+                if let Some(assertion) = parse_owner_line(trimmed) {
+                    reading.assertions.push(assertion);
+                } else if let Some(assertion) = reading.assertions.last_mut() {
+                    if let Some(details) = trimmed.strip_prefix("Details:") {
+                        assertion.details = Some(details.trim().to_string());
+                    } else if let Some(rest) = trimmed.strip_prefix("Timeout will fire in ") {
+                        assertion.timeout_seconds = rest.split_whitespace().next().and_then(|secs| secs.parse().ok());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    reading
+}
+
+/// `pid 312(caffeinate): [0x...] 01:23:45 PreventUserIdleDisplaySleep named: "..."`;
+/// older releases leave out the age.
+fn parse_owner_line(line: &str) -> Option<PowerAssertion> {
+    let rest = line.strip_prefix("pid ")?;
+    let (pid, rest) = rest.split_once('(')?;
+    let (process, rest) = rest.split_once("): ")?;
+    let rest = rest.split_once("] ").map_or(rest, |(_, rest)| rest).trim();
+
+    let (first, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    let age_seconds = parse_age(first);
+    let (assertion_type, rest) = match age_seconds {
+        Some(_) => rest.split_once(' ').unwrap_or((rest, "")),
+        None => (first, rest),
+    };
+
+    Some(PowerAssertion {
+        pid: pid.trim().parse().ok()?,
+        process: process.to_string(),
+        assertion_type: assertion_type.to_string(),
+        name: rest.trim().strip_prefix("named:").map(|name| name.trim().trim_matches('"').to_string()),
+        age_seconds,
+        timeout_seconds: None,
+        details: None,
+    })
+}
+
+/// "01:23:45" as seconds.
+fn parse_age(value: &str) -> Option<u64> {
+    let parts: Vec<u64> = value.split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    match parts[..] {
+        [hours, minutes, seconds] => Some(hours * 3600 + minutes * 60 + seconds),
+        [minutes, seconds] => Some(minutes * 60 + seconds),
+        _ => None,
+    }
+}
+
+/// The held types, then each assertion by process; `new` ones get a +.
+pub fn print_assertions(reading: &AssertionsReading, new: &HashSet<AssertionKey>) {
+    let held: Vec<String> = reading.system_wide.iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect();
+    println!("{}:", "Held system-wide".bold());
+    if held.is_empty() {
+        println!("  none");
+    } else {
+        println!("  {}", held.join(", "));
+    }
+
+    println!("\n{}:", "By process".bold());
+    if reading.assertions.is_empty() {
+        println!("  no process holds an assertion");
+    }
+    for assertion in &reading.assertions {
+        let age = assertion.age_seconds.map(format_duration).unwrap_or_else(|| "?".to_string());
+        let marker = if new.contains(&assertion.key()) { "+".green().bold() } else { " ".normal() };
+        println!(
+            "{} {:<24} {:<32} {:>10}  {}",
+            marker,
+            format!("{} ({})", assertion.process, assertion.pid),
+            assertion.assertion_type.cyan(),
+            age,
+            assertion.name.as_deref().unwrap_or("").dimmed()
+        );
+        if let Some(timeout) = assertion.timeout_seconds {
+            println!("  {:<24} {}", "", format!("released in {}", format_duration(timeout)).dimmed());
+        }
+    }
+}
+
+pub async fn execute_assertions(
+    watch: bool,
+    interval: u64,
+    host: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    output: OutputFormat,
+    verbose: bool,
+) -> Result<()> {
+    let target = readings::target_host(host, &execution_mode);
+    if output == OutputFormat::Json {
+        if watch {
+            anyhow::bail!("--output json is not supported for 'monitor assertions --watch'");
+        }
+        let report = readings::read_from(target.as_deref(), config, |source, _| readings::pmset_assertions(source)).await?;
+        let reading = parse_assertions(&report.reading.unwrap_or_default());
+        return print_json(&readings::HostReading { host: report.host, timestamp: report.timestamp, reading });
+    }
+
+    if !watch {
+        let report = readings::read_from(target.as_deref(), config, |source, _| readings::pmset_assertions(source)).await?;
+        print_header(&format!("Power Assertions: {}", report.host));
+        print_assertions(&parse_assertions(&report.reading.unwrap_or_default()), &HashSet::new());
+        return Ok(());
+    }
+
+    crate::ssh::reuse_sessions();
+    let mut previous: Option<AssertionsReading> = None;
+    loop {
+        let report = readings::read_from(target.as_deref(), config, |source, _| readings::pmset_assertions(source)).await?;
+        let reading = parse_assertions(&report.reading.unwrap_or_default());
+        // Assertions taken since the last refresh are marked with a +
+        let new = match &previous {
+            Some(previous) => {
+                let seen: HashSet<_> = previous.assertions.iter().map(PowerAssertion::key).collect();
+                reading.assertions.iter().map(PowerAssertion::key).filter(|key| !seen.contains(key)).collect()
+            }
+            None => HashSet::new(),
+        };
+
+        print!("\x1B[2J\x1B[1;1H");
+        print_header(&format!("Power Assertions: {}", report.host));
+        print_assertions(&reading, &new);
+        println!("\n{}", format!("Refreshing every {}s, Ctrl+C to stop", interval).dimmed());
+        print_verbose(&format!("{} assertion(s), {} new", reading.assertions.len(), new.len()), verbose);
+        previous = Some(reading);
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(std::time::Duration::from_secs(interval)) => {}
+        }
+    }
+    crate::ssh::close_sessions();
+    Ok(())
+}
@@ -1,11 +1,16 @@
 use anyhow::Result;
 use crate::Config;
 use crate::commands::utils::*;
+use crate::commands::shared::power_assertions;
+use crate::commands::shared::power_history;
+use crate::commands::shared::readings::{self, Source};
+use crate::commands::shared::power_events::{self, PowerEvent, PowerEventKind};
+use crate::commands::shared::wake_analysis;
 use crate::ssh::SshClient;
 use crate::ExecutionMode;
 use colored::*;
 use std::process::Command;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub struct PowerDiagnostics {
     execution_mode: ExecutionMode,
@@ -49,6 +54,8 @@ impl PowerDiagnostics {
     }
 
     async fn execute_local(&self, verbose: bool, battery: bool, sleep: bool, all: bool, fixes: bool) -> Result<()> {
+        power_history::record_power_settings(&self.config, &self.config.server.name, power_history::local_power_settings(), verbose);
+
         if all {
             self.show_all_diagnostics().await
         } else if fixes {
@@ -75,6 +82,15 @@ impl PowerDiagnostics {
             .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
 
         let client = SshClient::connect(server, &self.config).await?;
+        power_history::record_power_settings(&self.config, &server.name, power_history::remote_power_settings(&client), verbose);
+
+        // The deployed script predates lid and assertion detection
+        let source = Source::Remote(&client);
+        if let Some(lid) = readings::lid(&source).describe() {
+            println!("{} Lid: {}", "💻".cyan(), lid);
+        }
+        print_display_assertion(readings::display_sleep_prevented(&source));
+        println!();
         
         let mut args = Vec::new();
         if verbose { args.push("-v"); }
@@ -94,7 +110,7 @@ impl PowerDiagnostics {
         if result.success {
             println!("{}", result.stdout);
         } else {
-            print_error(&format!("Remote command failed: {}", result.stderr));
+            print_remote_failure("Remote command failed", &result);
         }
 
         Ok(())
@@ -126,6 +142,11 @@ impl PowerDiagnostics {
             println!("{} Battery Level: {}% ({})", icon, pct_num, status);
         }
 
+        if let Some(lid) = readings::lid(&Source::Local).describe() {
+            println!("{} Lid: {}", "💻".cyan(), lid);
+        }
+        print_display_assertion(readings::display_sleep_prevented(&Source::Local));
+
         // Check caffeinate status
         if self.is_caffeinate_running().await? {
             let pid = self.get_caffeinate_pid().await?;
@@ -208,6 +229,23 @@ impl PowerDiagnostics {
             }
         }
 
+        // Closed on battery, the network only stays up while a display sleep
+        // assertion is held
+        let on_battery = self.get_pmset_battery().await?.contains("Battery Power");
+        let lid_closed_on_battery = readings::lid(&Source::Local).closed_on_battery(on_battery);
+        match readings::display_sleep_prevented(&Source::Local) {
+            Some(true) => println!("{} display sleep assertion: held (good)", "✅".green()),
+            Some(false) if lid_closed_on_battery => {
+                println!(
+                    "{} CRITICAL: lid closed on battery without a display sleep assertion; the network will drop (run caffeinate -d)",
+                    "🔴".red()
+                );
+                issues_found += 1;
+            }
+            Some(false) => println!("{} display sleep assertion: not held (needed with the lid closed on battery)", "ℹ️".blue()),
+            None => {}
+        }
+
         println!();
         if issues_found == 0 {
             println!("{} No power management issues found!", "🎉".green());
@@ -281,23 +319,25 @@ impl PowerDiagnostics {
         println!("{} Power Assertions (what's keeping system awake):", "🔒".cyan());
         println!("{}", "=".repeat(52));
         let assertions_output = self.get_power_assertions().await?;
-        let lines: Vec<&str> = assertions_output.lines().take(20).collect();
-        println!("{}", lines.join("\n"));
+        power_assertions::print_assertions(&power_assertions::parse_assertions(&assertions_output), &HashSet::new());
+        println!("Follow them live: plan10 monitor assertions --watch");
 
         // Recent wake/sleep log
         println!();
         println!("{} Recent Sleep/Wake Events:", "📝".yellow());
         println!("{}", "=".repeat(29));
         let log_output = self.get_pmset_log().await?;
-        let wake_events: Vec<&str> = log_output
-            .lines()
-            .filter(|line| line.contains("Sleep") || line.contains("Wake") || line.contains("DarkWake"))
-            .rev()
-            .take(10)
+        let events: Vec<PowerEvent> = power_events::parse_pmset_log(&log_output)
+            .into_iter()
+            .filter(|event| matches!(event.kind, PowerEventKind::Sleep | PowerEventKind::Wake | PowerEventKind::DarkWake))
             .collect();
-        for event in wake_events.iter().rev() {
-            println!("{}", event);
+        for event in &events[events.len().saturating_sub(10)..] {
+            power_events::print_event(event);
         }
+        println!("Full timeline: plan10 monitor events --since 24h");
+
+        let analysis = wake_analysis::analyse(&crate::commands::shared::readings::Source::Local, chrono::Duration::days(7));
+        wake_analysis::print_analysis(&analysis, "last 7 days");
 
         println!();
         Ok(())
@@ -542,6 +582,15 @@ impl PowerDiagnostics {
     }
 }
 
+/// The display sleep assertion line of the basic status.
+fn print_display_assertion(prevented: Option<bool>) {
+    match prevented {
+        Some(true) => println!("{} Display sleep assertion: ✅ Held (PreventUserIdleDisplaySleep)", "🖥️".cyan()),
+        Some(false) => println!("{} Display sleep assertion: ❌ Not held", "🖥️".cyan()),
+        None => {}
+    }
+}
+
 pub async fn execute_power_diagnostics_command(
     verbose: bool,
     battery: bool,
@@ -0,0 +1,244 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode, OutputFormat};
+use crate::commands::utils::*;
+use crate::commands::shared::readings;
+use crate::history::{parse_since, window_start};
+use chrono::{DateTime, FixedOffset, Local};
+use clap::ValueEnum;
+use colored::*;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerEventKind {
+    Sleep,
+    Wake,
+    /// Woken in the background (network, maintenance) with the display off
+    DarkWake,
+    AssertionCreated,
+    /// Released by the process, or timed out
+    AssertionReleased,
+    Shutdown,
+}
+
+impl PowerEventKind {
+    fn label(&self) -> ColoredString {
+        match self {
+            PowerEventKind::Sleep => "Sleep".blue(),
+            PowerEventKind::Wake => "Wake".green(),
+            PowerEventKind::DarkWake => "DarkWake".cyan(),
+            PowerEventKind::AssertionCreated => "Assert+".yellow(),
+            PowerEventKind::AssertionReleased => "Assert-".dimmed(),
+            PowerEventKind::Shutdown => "Shutdown".red(),
+        }
+    }
+}
+
+/// One line of `pmset -g log`, typed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerEvent {
+    pub timestamp: DateTime<FixedOffset>,
+    pub kind: PowerEventKind,
+    /// Why: "Clamshell Sleep", "EC.LidOpen/Lid Open", or the shutdown cause
+    pub reason: Option<String>,
+    /// Owner of an assertion, as "name (pid)"
+    pub process: Option<String>,
+    /// Assertion type, e.g. PreventUserIdleSystemSleep
+    pub assertion: Option<String>,
+    /// The assertion's description as given by the process
+    pub assertion_name: Option<String>,
+    pub shutdown_cause: Option<i32>,
+    /// "ac" or "battery" at the time of the event
+    pub power_source: Option<&'static str>,
+    pub charge_percent: Option<u8>,
+    /// The log line's message, unparsed
+    pub message: String,
+}
+
+/// Typed events from `pmset -g log` output. Categories other than sleep,
+/// wake, assertions and shutdown causes are skipped.
+pub fn parse_pmset_log(log: &str) -> Vec<PowerEvent> {
+    log.lines().filter_map(parse_line).collect()
+}
+
+/// "2024-01-15 08:12:34 -0800 Sleep               \tEntering Sleep state due to 'Clamshell Sleep':..."
+fn parse_line(line: &str) -> Option<PowerEvent> {
+    let (head, message) = line.split_once('\t')?;
+    let timestamp = DateTime::parse_from_str(head.get(..25)?, "%Y-%m-%d %H:%M:%S %z").ok()?;
+    let category = head.get(25..)?.trim();
+    let message = message.trim();
+
+    let between = |start: &str, end: &str| -> Option<String> {
+        let rest = &message[message.find(start)? + start.len()..];
+        Some(rest[..rest.find(end).unwrap_or(rest.len())].trim().to_string())
+    };
+    let mut event = PowerEvent {
+        timestamp,
+        kind: PowerEventKind::Sleep,
+        reason: None,
+        process: None,
+        assertion: None,
+        assertion_name: None,
+        shutdown_cause: None,
+        power_source: if message.contains("Using AC") {
+            Some("ac")
+        } else if message.contains("Using Batt") {
+            Some("battery")
+        } else {
+            None
+        },
+        charge_percent: between("(Charge:", "%").and_then(|pct| pct.parse().ok()),
+        message: message.to_string(),
+    };
+
+    match category {
+        "Sleep" => {
+            event.reason = between("due to '", "'");
+        }
+        "Wake" | "DarkWake" => {
+            event.kind = if category == "Wake" { PowerEventKind::Wake } else { PowerEventKind::DarkWake };
+            event.reason = between("due to ", " Using").filter(|reason| !reason.is_empty());
+        }
+        "Assertions" => {
+            // "PID 123(caffeinate) Created PreventUserIdleSystemSleep "caffeinate command-line tool" 00:00:00 ..."
+            let (kind, action) = [
+                (PowerEventKind::AssertionCreated, " Created "),
+                (PowerEventKind::AssertionReleased, " Released "),
+                (PowerEventKind::AssertionReleased, " TimedOut "),
+            ].into_iter().find(|(_, action)| message.contains(action))?;
+            event.kind = kind;
+            event.reason = (action == " TimedOut ").then(|| "timed out".to_string());
+            event.process = message.strip_prefix("PID ")
+                .and_then(|rest| rest.split_once('('))
+                .and_then(|(pid, rest)| Some(format!("{} ({})", rest.split_once(')')?.0, pid)));
+            event.assertion = between(action, " ");
+            event.assertion_name = between("\"", "\"");
+        }
+        category if category.starts_with("Shutdown") => {
+            // "Shutdown Cause=5" / "Previous shutdown cause: -128"
+            let cause: i32 = message.rsplit(['=', ':']).next()?.trim().parse().ok()?;
+            event.kind = PowerEventKind::Shutdown;
+            event.shutdown_cause = Some(cause);
+            event.reason = Some(shutdown_cause(cause).to_string());
+        }
+        _ => return None,
+    }
+    Some(event)
+}
+
+/// A shutdown found elsewhere than `pmset -g log`, such as the unified
+/// log's "Previous shutdown cause".
+pub fn shutdown_event(timestamp: DateTime<FixedOffset>, cause: i32) -> PowerEvent {
+    PowerEvent {
+        timestamp,
+        kind: PowerEventKind::Shutdown,
+        reason: Some(shutdown_cause(cause).to_string()),
+        process: None,
+        assertion: None,
+        assertion_name: None,
+        shutdown_cause: Some(cause),
+        power_source: None,
+        charge_percent: None,
+        message: format!("Previous shutdown cause: {}", cause),
+    }
+}
+
+/// Apple's shutdown cause codes, as far as they are known.
+fn shutdown_cause(code: i32) -> &'static str {
+    match code {
+        5 => "normal shutdown",
+        3 => "hard shutdown (power button held)",
+        0 => "power lost",
+        -3 => "overheating (multiple temperature sensors)",
+        -60 => "bad disk directory block",
+        -61 => "watchdog timer",
+        -62 => "watchdog timer (unresponsive)",
+        -64 => "kernel panic",
+        -74 => "battery temperature",
+        -86 => "proximity temperature",
+        -95 => "CPU temperature",
+        -100 => "power supply temperature",
+        -103 => "battery undervoltage",
+        -104 => "battery cell failure",
+        -128 => "unknown (often a power or memory fault)",
+        _ => "unrecognised cause",
+    }
+}
+
+/// Compact one-line rendering shared with `monitor power --sleep`.
+pub fn print_event(event: &PowerEvent) {
+    let detail = match event.kind {
+        PowerEventKind::AssertionCreated | PowerEventKind::AssertionReleased => format!(
+            "{} {}{}{}",
+            event.process.as_deref().unwrap_or("?"),
+            event.assertion.as_deref().unwrap_or(""),
+            event.assertion_name.as_ref().map(|name| format!(" \"{}\"", name)).unwrap_or_default(),
+            event.reason.as_ref().map(|reason| format!(" ({})", reason)).unwrap_or_default()
+        ),
+        _ => event.reason.clone().unwrap_or_default(),
+    };
+    let power = match (event.power_source, event.charge_percent) {
+        (Some("ac"), Some(pct)) => format!("AC {}%", pct),
+        (Some(_), Some(pct)) => format!("battery {}%", pct),
+        _ => String::new(),
+    };
+    println!(
+        "{}  {:<9} {:<60} {}",
+        event.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+        event.kind.label(),
+        detail,
+        power.dimmed()
+    );
+}
+
+pub async fn execute_events(
+    since: String,
+    kind: Vec<PowerEventKind>,
+    process: Option<String>,
+    host: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    output: OutputFormat,
+    verbose: bool,
+) -> Result<()> {
+    let window = parse_since(&since)?;
+    let cutoff = window_start(window)?;
+    let target = readings::target_host(host, &execution_mode);
+    let report = readings::read_from(target.as_deref(), config, |source, _| readings::pmset_log(source)).await?;
+
+    let process = process.map(|process| process.to_lowercase());
+    let events: Vec<PowerEvent> = parse_pmset_log(&report.reading.unwrap_or_default())
+        .into_iter()
+        .filter(|event| event.timestamp >= cutoff)
+        .filter(|event| kind.is_empty() || kind.contains(&event.kind))
+        .filter(|event| process.as_ref().map_or(true, |process| {
+            event.process.as_ref().is_some_and(|owner| owner.to_lowercase().contains(process))
+        }))
+        .collect();
+
+    if output == OutputFormat::Json {
+        return print_json(&serde_json::json!({ "host": report.host, "since": since, "events": events }));
+    }
+
+    print_header(&format!("Power Events: {} (last {})", report.host, since));
+    if events.is_empty() {
+        print_info("No matching events in the power management log");
+        return Ok(());
+    }
+    for event in &events {
+        print_event(event);
+    }
+
+    let count = |kind: PowerEventKind| events.iter().filter(|event| event.kind == kind).count();
+    println!(
+        "\n{} sleep(s), {} wake(s), {} dark wake(s), {} assertion(s) created, {} shutdown(s)",
+        count(PowerEventKind::Sleep),
+        count(PowerEventKind::Wake),
+        count(PowerEventKind::DarkWake),
+        count(PowerEventKind::AssertionCreated),
+        count(PowerEventKind::Shutdown)
+    );
+    print_verbose(&format!("{} event(s)", events.len()), verbose);
+
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode};
+use crate::commands::utils::*;
+use crate::history::{parse_since, window_start, History, HistoryEntry, HistoryRecord};
+use crate::ssh::SshClient;
+use chrono::{DateTime, FixedOffset, Utc};
+use colored::*;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+const PMSET_COMMAND: &str = "pmset -g";
+const OS_VERSION_COMMAND: &str = "sw_vers -productVersion";
+
+/// One settings change between two consecutive snapshots.
+struct PowerChange {
+    /// Last snapshot with the old settings
+    after: DateTime<Utc>,
+    /// First snapshot with the new settings
+    before: DateTime<Utc>,
+    os_update: Option<(String, String)>,
+    settings: Vec<(String, Option<String>, Option<String>)>,
+}
+
+/// A pmset invocation found in the sudo audit log.
+struct AuditEntry {
+    timestamp: DateTime<Utc>,
+    user: String,
+    command: String,
+}
+
+/// Turn `pmset -g` output into setting → value, dropping section headers and
+/// the volatile "(sleep prevented by ...)" annotations.
+pub fn parse_pmset_values(output: &str) -> BTreeMap<String, String> {
+    let mut settings = BTreeMap::new();
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.ends_with(':') {
+            continue;
+        }
+
+        let mut parts = trimmed.splitn(2, char::is_whitespace);
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let value = value.split(" (").next().unwrap_or("").trim();
+        if !value.is_empty() {
+            settings.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    settings
+}
+
+pub fn local_power_settings() -> Result<(Option<String>, BTreeMap<String, String>)> {
+    let output = Command::new("pmset").arg("-g").output()?;
+    if !output.status.success() {
+        anyhow::bail!("pmset -g failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let settings = parse_pmset_values(&String::from_utf8_lossy(&output.stdout));
+    let os_version = crate::utils::system::get_macos_version().ok();
+    Ok((os_version, settings))
+}
+
+pub fn remote_power_settings(client: &SshClient) -> Result<(Option<String>, BTreeMap<String, String>)> {
+    let result = client.execute_command(PMSET_COMMAND)?.ensure_success()?;
+    let settings = parse_pmset_values(&result.stdout);
+    let os_version = client.execute_command(OS_VERSION_COMMAND)
+        .ok()
+        .filter(|r| r.success)
+        .map(|r| r.stdout.trim().to_string());
+    Ok((os_version, settings))
+}
+
+/// Best-effort: store the current settings in history; failures only show
+/// up in verbose output so diagnostics keep working on read-only homes.
+pub fn record_power_settings(
+    config: &Config,
+    host: &str,
+    collected: Result<(Option<String>, BTreeMap<String, String>)>,
+    verbose: bool,
+) {
+    let result = collected.and_then(|(os_version, settings)| {
+        History::open(config)?.record_power_settings(host, os_version, settings)
+    });
+    match result {
+        Ok(true) => print_verbose(&format!("Recorded power settings snapshot for {}", host), verbose),
+        Ok(false) => {}
+        Err(e) => print_verbose(&format!("Could not record power settings: {}", e), verbose),
+    }
+}
+
+pub async fn execute_power_changes(
+    host: Option<String>,
+    since: String,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    verbose: bool,
+) -> Result<()> {
+    let window = parse_since(&since)?;
+
+    let target = match execution_mode {
+        ExecutionMode::Local => None,
+        ExecutionMode::Remote { host: default_host } => Some(host.unwrap_or(default_host)),
+        ExecutionMode::Auto => host,
+    };
+
+    let (host_key, client) = match &target {
+        Some(target) => {
+            let server = config.resolve_server(target)
+                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", target))?;
+            let client = SshClient::connect(server, config).await?;
+            record_power_settings(config, &server.name, remote_power_settings(&client), verbose);
+            (server.name.clone(), Some(client))
+        }
+        None => {
+            record_power_settings(config, &config.server.name, local_power_settings(), verbose);
+            (config.server.name.clone(), None)
+        }
+    };
+
+    print_header(&format!("Power Settings Changes: {} (last {})", host_key, since));
+
+    let history = History::open(config)?;
+    let snapshots: Vec<_> = history
+        .records(&host_key)?
+        .into_iter()
+        .filter(|record| matches!(record.entry, HistoryEntry::PowerSettings { .. }))
+        .collect();
+
+    let cutoff = window_start(window)?;
+    let changes = find_changes(&snapshots, cutoff);
+    let annotations = history.annotations(&host_key, None)?;
+
+    if changes.is_empty() {
+        print_success(&format!("No power setting changes recorded in the last {}", since));
+        if let Some(first) = snapshots.first() {
+            print_verbose(
+                &format!("{} snapshot(s) since {}", snapshots.len(), first.timestamp.format("%Y-%m-%d %H:%M UTC")),
+                verbose,
+            );
+        }
+        return Ok(());
+    }
+
+    let audit = match changes.iter().map(|c| c.after).min() {
+        Some(earliest) => {
+            let minutes = Utc::now().signed_duration_since(earliest).num_minutes() + 1;
+            read_audit_log(client.as_ref(), minutes)
+        }
+        None => Vec::new(),
+    };
+
+    for change in &changes {
+        println!(
+            "{} between {} and {}",
+            "🔄".yellow(),
+            change.after.format("%Y-%m-%d %H:%M UTC"),
+            change.before.format("%Y-%m-%d %H:%M UTC")
+        );
+
+        if let Some((old, new)) = &change.os_update {
+            println!("  {} macOS updated {} → {}", "🍎", old, new.bold());
+        }
+
+        for (key, old, new) in &change.settings {
+            let old = old.as_deref().unwrap_or("(unset)");
+            let new = new.as_deref().unwrap_or("(unset)");
+            println!("  {}: {} → {}", key.bold(), old.dimmed(), new);
+        }
+
+        let causes: Vec<_> = audit.iter()
+            .filter(|entry| entry.timestamp > change.after && entry.timestamp <= change.before)
+            .collect();
+        if causes.is_empty() {
+            if change.os_update.is_some() {
+                println!("  {}", "Likely reset by the macOS update".yellow());
+            } else {
+                println!("  {}", "No matching pmset command in the audit log".dimmed());
+            }
+        } else {
+            for cause in causes {
+                println!(
+                    "  {} {} ran '{}' at {}",
+                    "👤".cyan(),
+                    cause.user,
+                    cause.command,
+                    cause.timestamp.format("%H:%M:%S UTC")
+                );
+            }
+        }
+
+        for record in annotations.iter().filter(|r| r.timestamp > change.after && r.timestamp <= change.before) {
+            if let HistoryEntry::Annotation { text, .. } = &record.entry {
+                println!("  {} {} ({})", "📝".cyan(), text, record.timestamp.format("%Y-%m-%d %H:%M UTC"));
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn find_changes(snapshots: &[HistoryRecord], cutoff: DateTime<Utc>) -> Vec<PowerChange> {
+    let mut changes = Vec::new();
+
+    for pair in snapshots.windows(2) {
+        let (previous, current) = (&pair[0], &pair[1]);
+        if current.timestamp < cutoff {
+            continue;
+        }
+
+        let (
+            HistoryEntry::PowerSettings { os_version: old_os, settings: old },
+            HistoryEntry::PowerSettings { os_version: new_os, settings: new },
+        ) = (&previous.entry, &current.entry) else {
+            continue;
+        };
+
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        let settings: Vec<_> = keys.into_iter()
+            .filter(|key| old.get(*key) != new.get(*key))
+            .map(|key| (key.clone(), old.get(key).cloned(), new.get(key).cloned()))
+            .collect();
+
+        let os_update = match (old_os, new_os) {
+            (Some(old), Some(new)) if old != new => Some((old.clone(), new.clone())),
+            _ => None,
+        };
+
+        if !settings.is_empty() || os_update.is_some() {
+            changes.push(PowerChange {
+                after: previous.timestamp,
+                before: current.timestamp,
+                os_update,
+                settings,
+            });
+        }
+    }
+
+    changes
+}
+
+/// pmset runs through sudo, which logs the user and full command line.
+fn read_audit_log(client: Option<&SshClient>, minutes: i64) -> Vec<AuditEntry> {
+    let command = format!(
+        "log show --style syslog --last {}m --predicate 'process == \"sudo\" AND eventMessage CONTAINS \"pmset\"'",
+        minutes
+    );
+
+    let output = match client {
+        Some(client) => client.execute_command(&command)
+            .ok()
+            .filter(|r| r.success)
+            .map(|r| r.stdout),
+        None => Command::new("sh")
+            .args(["-c", &command])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string()),
+    };
+
+    output
+        .map(|output| output.lines().filter_map(parse_audit_line).collect())
+        .unwrap_or_default()
+}
+
+/// Parse e.g. `2026-10-12 03:14:22.123456-0700  host sudo[123]:  admin : TTY=ttys001 ; ... ; COMMAND=/usr/bin/pmset -a sleep 0`
+fn parse_audit_line(line: &str) -> Option<AuditEntry> {
+    let command = line.split("COMMAND=").nth(1)?.trim().to_string();
+    let stamp: String = line.split_whitespace().take(2).collect::<Vec<_>>().join(" ");
+    let timestamp = DateTime::<FixedOffset>::parse_from_str(&stamp, "%Y-%m-%d %H:%M:%S%.f%z").ok()?;
+    let user = line.split("]:").nth(1)?.split(':').next()?.trim().to_string();
+
+    Some(AuditEntry {
+        timestamp: timestamp.with_timezone(&Utc),
+        user,
+        command,
+    })
+}
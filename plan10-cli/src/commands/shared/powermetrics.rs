@@ -0,0 +1,66 @@
+use crate::sensors::FanSpeed;
+use plist::{Dictionary, Value};
+use serde::Serialize;
+use std::io::Cursor;
+
+/// Samplers for die temperatures, fans, package power and GPU residency.
+/// `smc` only exists on Intel Macs; Apple Silicon rejects the whole list
+/// over it and is asked for `POWER_SAMPLERS` instead.
+pub const TEMPERATURE_SAMPLERS: &[&str] = &["smc", "cpu_power", "gpu_power"];
+pub const POWER_SAMPLERS: &[&str] = &["cpu_power", "gpu_power"];
+
+/// One sample of `powermetrics --format plist`, which keeps its keys
+/// across macOS releases where the text report's wording drifts.
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct PowermetricsReading {
+    pub cpu_celsius: Option<f32>,
+    pub gpu_celsius: Option<f32>,
+    pub fans: Vec<FanSpeed>,
+    /// CPU package power, or CPU, GPU and ANE combined on Apple Silicon
+    pub package_watts: Option<f32>,
+    /// Share of the sample the GPU was busy
+    pub gpu_active_percent: Option<f32>,
+}
+
+/// The shell command for one 200 ms sample of `samplers`. `-n` keeps sudo
+/// from prompting; without passwordless sudo the command fails.
+pub fn command(samplers: &[&str]) -> String {
+    format!(
+        "sudo -n powermetrics --samplers {} --format plist -n 1 -i 200 2>/dev/null",
+        samplers.join(",")
+    )
+}
+
+/// Parse the first plist in `output`. powermetrics ends each sample with a
+/// NUL byte, so several samples arrive as one stream.
+pub fn parse(output: &str) -> Option<PowermetricsReading> {
+    let xml = output.split('\0').map(str::trim).find(|sample| !sample.is_empty())?;
+    let root = Value::from_reader(Cursor::new(xml.as_bytes())).ok()?.into_dictionary()?;
+    let section = |name: &str| root.get(name).and_then(Value::as_dictionary);
+
+    let smc = section("smc");
+    let processor = section("processor");
+    // Apple Silicon reports milliwatts, Intel watts
+    let package_watts = processor.and_then(|processor| number(processor, "package_watts")
+        .or_else(|| number(processor, "combined_power").map(|milliwatts| milliwatts / 1000.0)));
+
+    Some(PowermetricsReading {
+        cpu_celsius: smc.and_then(|smc| number(smc, "cpu_die")),
+        gpu_celsius: smc.and_then(|smc| number(smc, "gpu_die")),
+        fans: smc.and_then(|smc| number(smc, "fan"))
+            .map(|rpm| vec![FanSpeed { fan: 0, rpm }])
+            .unwrap_or_default(),
+        package_watts,
+        gpu_active_percent: section("gpu")
+            .and_then(|gpu| number(gpu, "idle_ratio"))
+            .map(|idle| ((1.0 - idle) * 100.0).clamp(0.0, 100.0)),
+    })
+}
+
+/// A real or integer entry of `dictionary` as f32.
+fn number(dictionary: &Dictionary, key: &str) -> Option<f32> {
+    let value = dictionary.get(key)?;
+    value.as_real()
+        .or_else(|| value.as_signed_integer().map(|integer| integer as f64))
+        .map(|value| value as f32)
+}
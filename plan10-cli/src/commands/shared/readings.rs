@@ -0,0 +1,1642 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode};
+use crate::alerts::AlertInput;
+use crate::commands::shared::battery_runtime::RuntimeEstimate;
+use crate::commands::shared::power_assertions::parse_assertions;
+use crate::commands::shared::power_history::parse_pmset_values;
+use crate::commands::shared::powermetrics::{self, PowermetricsReading};
+use crate::config::{ServerDefinition, Thresholds};
+use crate::history::Sample;
+use crate::ssh::SshClient;
+use crate::sensors::{all_sensors, read_sensors, FanSpeed, Sensor};
+use crate::utils::system::get_system_info;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::process::Command;
+use std::sync::Mutex;
+
+/// Where readings are taken: this machine, or a server over an open SSH
+/// connection. Both run the same commands.
+pub enum Source<'a> {
+    Local,
+    Remote(&'a SshClient),
+}
+
+impl Source<'_> {
+    /// Standard output of a shell command; `None` when it fails.
+    fn run(&self, command: &str) -> Option<String> {
+        match self {
+            Source::Local => {
+                let output = Command::new("/bin/sh").args(["-c", command]).output().ok()?;
+                output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+            Source::Remote(client) => client.execute_command(command).ok()
+                .filter(|result| result.success)
+                .map(|result| result.stdout),
+        }
+    }
+
+    /// The operating system the readings come from. A server is asked once
+    /// per run; anything other than Linux is read as macOS.
+    pub fn os(&self) -> TargetOs {
+        match self {
+            Source::Local if cfg!(target_os = "linux") => TargetOs::Linux,
+            Source::Local => TargetOs::MacOs,
+            Source::Remote(client) => {
+                let name = client.get_server_info().name.clone();
+                let known = REMOTE_OS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(&name).copied();
+                if let Some(os) = known {
+                    return os;
+                }
+                // A failed `uname` isn't remembered, so the next reading asks again
+                let Some(kernel) = self.run("uname -s") else { return TargetOs::MacOs };
+                let os = if kernel.trim() == "Linux" { TargetOs::Linux } else { TargetOs::MacOs };
+                REMOTE_OS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(name, os);
+                os
+            }
+        }
+    }
+
+    fn is_linux(&self) -> bool {
+        self.os() == TargetOs::Linux
+    }
+}
+
+/// Operating systems readings can be taken on. macOS is read with pmset,
+/// ioreg and friends; Linux with sysfs and `/proc`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetOs {
+    MacOs,
+    Linux,
+}
+
+impl TargetOs {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetOs::MacOs => "macOS",
+            TargetOs::Linux => "Linux",
+        }
+    }
+}
+
+/// `uname -s` of every server read so far, by server name.
+static REMOTE_OS: Mutex<BTreeMap<String, TargetOs>> = Mutex::new(BTreeMap::new());
+
+/// A reading labelled with the host it was taken on.
+#[derive(Serialize, Deserialize)]
+pub struct HostReading<T> {
+    pub host: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub reading: T,
+}
+
+/// Where the machine draws its power from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    /// Also what a source this version doesn't know reads as
+    #[serde(other)]
+    Unknown,
+}
+
+impl PowerSource {
+    /// As in the JSON output and the history, e.g. "ac"
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerSource::Ac => "ac",
+            PowerSource::Battery => "battery",
+            PowerSource::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PowerReading {
+    pub source: PowerSource,
+    pub battery_percent: Option<u8>,
+    /// pmset's charge state, e.g. "charging", "discharging", "charged"
+    pub battery_state: Option<String>,
+    /// Estimate as h:mm; absent while pmset has no estimate
+    pub time_remaining: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BatteryHealth {
+    pub cycle_count: Option<u32>,
+    pub condition: Option<String>,
+    pub maximum_capacity_percent: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BatteryReading {
+    #[serde(flatten)]
+    pub power: PowerReading,
+    pub health: BatteryHealth,
+    /// Only estimated on battery power
+    pub runtime: Option<RuntimeEstimate>,
+}
+
+/// Charge and current from the battery's gas gauge.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct BatteryCharge {
+    pub current_mah: u32,
+    pub max_mah: u32,
+    /// Negative while discharging
+    pub amperage_ma: i32,
+}
+
+impl BatteryCharge {
+    /// Minutes at the present draw until the charge is down to
+    /// `halt_percent` of capacity; `None` unless discharging.
+    pub fn minutes_until(&self, halt_percent: u8) -> Option<u32> {
+        if self.amperage_ma >= 0 {
+            return None;
+        }
+        let halt_mah = self.max_mah as f64 * halt_percent as f64 / 100.0;
+        let left_mah = (self.current_mah as f64 - halt_mah).max(0.0);
+        Some((left_mah / -(self.amperage_ma as f64) * 60.0) as u32)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TemperatureReading {
+    /// `None` when neither the sensors nor powermetrics could be read
+    pub cpu_celsius: Option<f32>,
+    pub gpu_celsius: Option<f32>,
+    /// Empty on fanless Macs
+    pub fans: Vec<FanSpeed>,
+    pub gpus: Vec<GpuReading>,
+    pub thermal: ThermalLimits,
+    pub threshold_celsius: f32,
+    pub over_threshold: bool,
+}
+
+/// The CPU limits macOS applies when the machine runs hot, from
+/// `pmset -g therm`. Apple Silicon Macs and Macs that never throttled since
+/// boot report no limits.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug)]
+pub struct ThermalLimits {
+    /// Percent of full CPU speed allowed
+    pub cpu_speed_limit: Option<u8>,
+    /// Percent of scheduler time allowed
+    pub scheduler_limit: Option<u8>,
+    pub available_cpus: Option<u32>,
+}
+
+impl ThermalLimits {
+    /// The tighter of the two limits, when either is below 100%.
+    pub fn throttled_to(&self) -> Option<u8> {
+        self.cpu_speed_limit.into_iter()
+            .chain(self.scheduler_limit)
+            .filter(|limit| *limit < 100)
+            .min()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct GpuReading {
+    pub name: Option<String>,
+    pub utilization_percent: Option<f32>,
+    /// Dedicated VRAM on discrete GPUs; on Apple Silicon, the unified memory
+    /// the GPU has in use
+    pub memory_used_bytes: Option<u64>,
+    /// Only known for discrete GPUs
+    pub memory_total_bytes: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct SensorsReading {
+    pub sensors: Vec<Sensor>,
+}
+
+#[derive(Serialize)]
+pub struct NetworkReading {
+    pub interfaces: Vec<InterfaceReading>,
+    /// `None` without Wi-Fi hardware or when it couldn't be read
+    pub wifi: Option<WifiReading>,
+}
+
+#[derive(Serialize)]
+pub struct InterfaceReading {
+    pub name: String,
+    pub up: bool,
+    /// ifconfig's link status, "active" or "inactive"; absent for loopback
+    /// and tunnels
+    pub status: Option<String>,
+    pub mac: Option<String>,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    /// e.g. "autoselect (1000baseT <full-duplex>)"
+    pub media: Option<String>,
+    pub mtu: Option<u32>,
+    /// Measured over one second
+    pub rx_bytes_per_second: Option<u64>,
+    pub tx_bytes_per_second: Option<u64>,
+    /// Totals since boot
+    pub rx_errors: Option<u64>,
+    pub tx_errors: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct WifiReading {
+    /// Hidden by macOS 14+ unless the caller has location access
+    pub ssid: Option<String>,
+    pub rssi_dbm: Option<i32>,
+    pub noise_dbm: Option<i32>,
+    /// e.g. "149,80" or "149 (5GHz, 80MHz)"
+    pub channel: Option<String>,
+    pub tx_rate_mbps: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct DiskReading {
+    pub disks: Vec<DiskHealth>,
+}
+
+/// Which tool a disk's health was read with.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthSource {
+    Smartctl,
+    /// Only the SMART verdict, for when smartctl isn't installed
+    Diskutil,
+}
+
+impl HealthSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HealthSource::Smartctl => "smartctl",
+            HealthSource::Diskutil => "diskutil",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct DiskHealth {
+    /// e.g. "disk0"
+    pub device: String,
+    pub model: Option<String>,
+    pub source: HealthSource,
+    /// smartctl's overall assessment, or diskutil's "Verified", "Failing"
+    /// or "Not Supported"
+    pub smart_status: Option<String>,
+    pub passed: Option<bool>,
+    /// NVMe wear: share of rated endurance used, can exceed 100
+    pub percentage_used: Option<u8>,
+    pub available_spare: Option<u8>,
+    pub available_spare_threshold: Option<u8>,
+    pub media_errors: Option<u64>,
+    pub power_on_hours: Option<u64>,
+    pub temperature_celsius: Option<f32>,
+    /// Reads and writes together, from a one-second iostat sample
+    pub megabytes_per_second: Option<f32>,
+}
+
+/// How worried to be about a disk.
+pub enum DiskCondition {
+    Healthy,
+    /// Still working, but wearing out or logging errors
+    Degraded(String),
+    Failing(String),
+    Unknown,
+}
+
+impl DiskHealth {
+    pub fn condition(&self) -> DiskCondition {
+        if self.passed == Some(false) {
+            return DiskCondition::Failing(format!("SMART status {}", self.smart_status.as_deref().unwrap_or("failed")));
+        }
+        if let (Some(spare), Some(threshold)) = (self.available_spare, self.available_spare_threshold) {
+            if spare <= threshold {
+                return DiskCondition::Failing(format!("available spare {}% at or below {}%", spare, threshold));
+            }
+        }
+        if let Some(used) = self.percentage_used.filter(|used| *used >= 90) {
+            return DiskCondition::Degraded(format!("{}% of rated endurance used", used));
+        }
+        if let Some(errors) = self.media_errors.filter(|errors| *errors > 0) {
+            return DiskCondition::Degraded(format!("{} media error(s)", errors));
+        }
+        match self.passed {
+            Some(true) => DiskCondition::Healthy,
+            _ => DiskCondition::Unknown,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProcessesReading {
+    pub by_cpu: Vec<ProcessReading>,
+    pub by_memory: Vec<ProcessReading>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ProcessReading {
+    pub pid: u32,
+    pub name: String,
+    /// Percent of one core, so busy multithreaded processes exceed 100
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+}
+
+/// Memory the way Activity Monitor counts it. `used_bytes` is app, wired and
+/// compressed memory; the file cache sysinfo also counts as used is handed
+/// back whenever an app asks for it.
+#[derive(Serialize, Default)]
+pub struct MemoryReading {
+    pub total_bytes: Option<u64>,
+    pub used_bytes: Option<u64>,
+    pub app_bytes: Option<u64>,
+    pub wired_bytes: Option<u64>,
+    /// Physical memory taken up by the compressor
+    pub compressed_bytes: Option<u64>,
+    /// File-backed and purgeable pages, reclaimable at any time
+    pub cached_bytes: Option<u64>,
+    pub swap_used_bytes: Option<u64>,
+    pub swap_total_bytes: Option<u64>,
+    /// Swap files in `/private/var/vm`, which macOS adds as swap grows
+    pub swapfiles: Option<u32>,
+    pub swapfile_bytes: Option<u64>,
+    pub pressure: Option<MemoryPressure>,
+    /// `memory_pressure`'s system-wide free percentage
+    pub free_percent: Option<u8>,
+}
+
+/// The kernel's memory pressure level, as in Activity Monitor's graph.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryPressure {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// Pages written out and swapped in and out since boot, from `vm_stat`.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct PagingCounters {
+    pub pageouts: u64,
+    pub swapins: u64,
+    pub swapouts: u64,
+}
+
+/// Paging activity per second over a short window.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct PagingRates {
+    pub pageouts_per_second: f32,
+    pub swapins_per_second: f32,
+    pub swapouts_per_second: f32,
+}
+
+/// Seconds `paging_rates` watches the counters for.
+const PAGING_WINDOW_SECONDS: u64 = 2;
+
+impl MemoryReading {
+    /// Share of physical memory taken up by the compressor.
+    pub fn compressed_percent(&self) -> Option<f32> {
+        let (compressed, total) = (self.compressed_bytes?, self.total_bytes?);
+        (total > 0).then(|| compressed as f32 * 100.0 / total as f32)
+    }
+}
+
+#[derive(Serialize)]
+pub struct PowerSettingsReading {
+    pub os_version: Option<String>,
+    pub lid: LidReading,
+    /// A PreventUserIdleDisplaySleep assertion, e.g. `caffeinate -d`, is held
+    pub display_sleep_prevented: Option<bool>,
+    pub settings: BTreeMap<String, String>,
+    /// Settings that differ from `[power_profile]`, as expected/actual
+    pub drift: Vec<PowerDrift>,
+}
+
+#[derive(Serialize)]
+pub struct PowerDrift {
+    pub setting: String,
+    pub expected: String,
+    pub actual: Option<String>,
+}
+
+/// Whether the lid is closed and whether closing it puts the machine to
+/// sleep, from IOKit's `AppleClamshellState` and `AppleClamshellCausesSleep`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct LidReading {
+    /// `None` on machines without a lid, such as a Mac mini
+    pub closed: Option<bool>,
+    /// The machine stays awake with the lid closed
+    pub sleep_inhibited: Option<bool>,
+    /// Why, e.g. "clamshell mode" or "sleep disabled"
+    pub inhibited_by: Vec<String>,
+}
+
+impl LidReading {
+    /// "Closed, staying awake (clamshell mode)"; `None` without a lid.
+    pub fn describe(&self) -> Option<String> {
+        let state = if self.closed? { "Closed" } else { "Open" };
+        let sleep = match (self.closed?, self.sleep_inhibited) {
+            (true, Some(true)) => "staying awake",
+            (true, _) => "sleep not inhibited",
+            (false, Some(true)) => "stays awake when closed",
+            (false, _) => "sleeps when closed",
+        };
+        Some(if self.inhibited_by.is_empty() {
+            format!("{}, {}", state, sleep)
+        } else {
+            format!("{}, {} ({})", state, sleep, self.inhibited_by.join(", "))
+        })
+    }
+
+    /// Closed on battery power, where Plan 10 loses the network and heat
+    /// builds up inside the closed case.
+    pub fn closed_on_battery(&self, on_battery: bool) -> bool {
+        on_battery && self.closed == Some(true)
+    }
+}
+
+#[derive(Serialize)]
+pub struct StatusReading {
+    pub power: PowerReading,
+    pub lid: LidReading,
+    pub caffeinate_running: bool,
+    pub disk_used_percent: Option<u8>,
+    pub cpu_celsius: Option<f32>,
+    pub cpu_usage_percent: Option<f32>,
+    pub load: Option<CpuLoad>,
+    pub swap_used_bytes: Option<u64>,
+    pub paging: Option<PagingCounters>,
+    /// Filled in by callers that know the host, as it needs its history
+    pub battery_runtime: Option<RuntimeEstimate>,
+    pub thermal: ThermalLimits,
+    pub thresholds: Thresholds,
+    /// Same checks as the text health summary; empty when all is well
+    pub issues: Vec<String>,
+}
+
+/// The host a command without `--tag`/`--group` targets; `None` for this machine.
+pub fn target_host(host: Option<String>, execution_mode: &ExecutionMode) -> Option<String> {
+    match execution_mode {
+        ExecutionMode::Local => None,
+        ExecutionMode::Remote { host: default_host } => Some(host.unwrap_or_else(|| default_host.clone())),
+        ExecutionMode::Auto => host,
+    }
+}
+
+/// Take a reading on `host`, or locally when it is `None`. The closure gets
+/// the server definition for per-server thresholds.
+pub async fn read_from<T>(
+    host: Option<&str>,
+    config: &Config,
+    read: impl FnOnce(&Source, Option<&ServerDefinition>) -> T,
+) -> Result<HostReading<T>> {
+    let timestamp = Utc::now();
+    match host {
+        Some(host) => {
+            let server = config.resolve_server(host)
+                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+            let client = SshClient::connect(server, config).await?;
+            let reading = read(&Source::Remote(&client), Some(server));
+            Ok(HostReading { host: server.name.clone(), timestamp, reading })
+        }
+        None => Ok(HostReading {
+            host: config.server.name.clone(),
+            timestamp,
+            reading: read(&Source::Local, None),
+        }),
+    }
+}
+
+/// Ask the server's own plan10 for `monitor <command>` as JSON, so a remote
+/// reading gets the same formatting, thresholds and history as a local one.
+/// `None` when plan10 isn't installed there or is too old to print this
+/// reading, for callers to fall back to the deployed scripts.
+pub fn remote_reading<T: DeserializeOwned>(client: &SshClient, server: &ServerDefinition, command: &str) -> Option<HostReading<T>> {
+    let script = format!("command -v plan10 >/dev/null 2>&1 && plan10 --server-mode --json monitor {}", command);
+    let result = client.execute_command(&script).ok().filter(|result| result.success)?;
+    // Skip anything a login script printed before the JSON
+    let json = &result.stdout[result.stdout.find('{')?..];
+    let mut reading: HostReading<T> = serde_json::from_str(json).ok()?;
+    // The server knows itself by its own name; label it the way this client does
+    reading.host = server.name.clone();
+    Some(reading)
+}
+
+pub fn power(source: &Source) -> PowerReading {
+    if source.is_linux() {
+        return linux_power(&power_supplies(source));
+    }
+    let output = source.run("pmset -g batt").unwrap_or_default();
+    let source = if output.contains("Battery Power") {
+        PowerSource::Battery
+    } else if output.contains("AC Power") {
+        PowerSource::Ac
+    } else {
+        PowerSource::Unknown
+    };
+
+    // " -InternalBattery-0 (id=1234)\t87%; discharging; 4:12 remaining present: true"
+    let battery_line = output.lines().find(|line| line.contains('%'));
+    let fields: Vec<&str> = battery_line
+        .and_then(|line| line.split('\t').nth(1))
+        .map(|rest| rest.split(';').map(str::trim).collect())
+        .unwrap_or_default();
+
+    PowerReading {
+        source,
+        battery_percent: fields.first().and_then(|pct| pct.trim_end_matches('%').parse().ok()),
+        battery_state: fields.get(1).map(|state| state.to_string()),
+        time_remaining: fields.get(2)
+            .and_then(|rest| rest.split_whitespace().next())
+            .filter(|estimate| estimate.contains(':'))
+            .map(str::to_string),
+    }
+}
+
+/// From `ioreg -rn AppleSmartBattery`. Apple Silicon reports `CurrentCapacity`
+/// in percent, so the raw mAh keys come first; a negative amperage may be
+/// printed as its unsigned 64-bit two's complement.
+pub fn battery_charge(source: &Source) -> Option<BatteryCharge> {
+    if source.is_linux() {
+        return linux_battery_charge(&power_supplies(source));
+    }
+    let output = source.run("ioreg -rn AppleSmartBattery")?;
+    let value = |key: &str| -> Option<i64> {
+        let value = output.lines().find_map(|line| line.trim().strip_prefix(&format!("\"{}\" = ", key)))?.trim();
+        value.parse::<i64>().ok().or_else(|| value.parse::<u64>().ok().map(|value| value as i64))
+    };
+
+    let current_mah = value("AppleRawCurrentCapacity").or_else(|| value("CurrentCapacity"))?;
+    let max_mah = value("AppleRawMaxCapacity").or_else(|| value("MaxCapacity"))?;
+    let amperage_ma = value("InstantAmperage").or_else(|| value("Amperage"))?;
+    // A capacity of 100 is a percentage, not mAh
+    if max_mah <= 100 {
+        return None;
+    }
+    Some(BatteryCharge {
+        current_mah: u32::try_from(current_mah).ok()?,
+        max_mah: u32::try_from(max_mah).ok()?,
+        amperage_ma: i32::try_from(amperage_ma).ok()?,
+    })
+}
+
+/// pmset's `haltlevel`, the battery percentage at which macOS shuts down;
+/// `None` when it isn't set.
+pub fn halt_level(source: &Source) -> Option<u8> {
+    if source.is_linux() {
+        return None;
+    }
+    source.run("pmset -g")
+        .map(|output| parse_pmset_values(&output))?
+        .get("haltlevel")?
+        .parse()
+        .ok()
+}
+
+pub fn battery(source: &Source) -> BatteryReading {
+    if source.is_linux() {
+        let supplies = power_supplies(source);
+        return BatteryReading { power: linux_power(&supplies), health: linux_battery_health(&supplies), runtime: None };
+    }
+    let profile = source.run("system_profiler SPPowerDataType").unwrap_or_default();
+    let field = |name: &str| profile.lines()
+        .filter_map(|line| line.trim().strip_prefix(name))
+        .find_map(|rest| rest.strip_prefix(':'))
+        .map(|value| value.trim().to_string());
+
+    BatteryReading {
+        power: power(source),
+        health: BatteryHealth {
+            cycle_count: field("Cycle Count").and_then(|count| count.parse().ok()),
+            condition: field("Condition"),
+            maximum_capacity_percent: field("Maximum Capacity")
+                .and_then(|pct| pct.trim_end_matches('%').parse().ok()),
+        },
+        runtime: None,
+    }
+}
+
+pub fn temperature(source: &Source, thresholds: &Thresholds) -> TemperatureReading {
+    let (cpu_celsius, gpu_celsius, fans) = die_temperatures(source);
+    TemperatureReading {
+        cpu_celsius,
+        gpu_celsius,
+        fans,
+        gpus: gpus(source),
+        thermal: thermal_limits(source),
+        threshold_celsius: thresholds.temp,
+        over_threshold: cpu_celsius.is_some_and(|celsius| thresholds.temp_exceeded(celsius)),
+    }
+}
+
+pub fn thermal_limits(source: &Source) -> ThermalLimits {
+    if source.is_linux() {
+        return ThermalLimits::default();
+    }
+    parse_thermal_limits(&source.run("pmset -g therm").unwrap_or_default())
+}
+
+/// "\tCPU_Scheduler_Limit \t= 100" lines, the latest of each if the
+/// notifications repeat.
+pub fn parse_thermal_limits(output: &str) -> ThermalLimits {
+    let mut limits = ThermalLimits::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let value = value.trim();
+        match key.trim() {
+            "CPU_Speed_Limit" => limits.cpu_speed_limit = value.parse().ok(),
+            "CPU_Scheduler_Limit" => limits.scheduler_limit = value.parse().ok(),
+            "CPU_Available_CPUs" => limits.available_cpus = value.parse().ok(),
+            _ => {}
+        }
+    }
+    limits
+}
+
+/// CPU and GPU temperatures and fan speeds. Read natively where possible:
+/// locally from the sensors, remotely through the server's own plan10.
+/// powermetrics, which needs passwordless sudo, is the fallback.
+fn die_temperatures(source: &Source) -> (Option<f32>, Option<f32>, Vec<FanSpeed>) {
+    if source.is_linux() {
+        return linux_temperatures(source);
+    }
+    let native = match source {
+        Source::Local => {
+            let sensors = read_sensors();
+            (sensors.cpu_celsius, sensors.gpu_celsius, sensors.fans)
+        }
+        Source::Remote(_) => remote_sensors(source).unwrap_or_default(),
+    };
+    if native.0.is_some() {
+        return native;
+    }
+
+    let Some(sample) = powermetrics_sample(source) else { return native };
+    let fans = if native.2.is_empty() { sample.fans } else { native.2 };
+    (sample.cpu_celsius, sample.gpu_celsius, fans)
+}
+
+/// Sensor readings from `plan10 monitor temp --json` on the server, if it
+/// has plan10 installed.
+fn remote_sensors(source: &Source) -> Option<(Option<f32>, Option<f32>, Vec<FanSpeed>)> {
+    let output = source.run("plan10 --server-mode --json monitor temp 2>/dev/null")?;
+    let reading: serde_json::Value = serde_json::from_str(&output).ok()?;
+    let celsius = |key: &str| reading.get(key).and_then(serde_json::Value::as_f64).map(|value| value as f32);
+    let fans = reading.get("fans")
+        .and_then(|fans| serde_json::from_value(fans.clone()).ok())
+        .unwrap_or_default();
+    Some((celsius("cpu_celsius"), celsius("gpu_celsius"), fans))
+}
+
+/// Each GPU's utilization and memory from the IOAccelerator performance
+/// statistics, which need no privileges. Where they lack utilization,
+/// `powermetrics --samplers gpu_power` (passwordless sudo) fills it in.
+pub fn gpus(source: &Source) -> Vec<GpuReading> {
+    if source.is_linux() {
+        return Vec::new();
+    }
+    let output = source.run("ioreg -r -d 1 -w 0 -c IOAccelerator").unwrap_or_default();
+    let mut gpus: Vec<GpuReading> = output.split("+-o ")
+        .filter_map(|entry| {
+            let statistics = parse_ioreg_dictionary(entry, "\"PerformanceStatistics\" = {")?;
+            // "model" = "Apple M1 Pro", or <"AMD Radeon Pro 5500M"> on Intel Macs
+            let name = entry.lines()
+                .find_map(|line| line.trim().strip_prefix("\"model\" = "))
+                .map(|name| name.trim_matches(|c| c == '"' || c == '<' || c == '>').to_string());
+            let used = statistics.get("vramUsedBytes").or(statistics.get("In use system memory")).copied();
+            Some(GpuReading {
+                name,
+                utilization_percent: statistics.get("Device Utilization %").map(|value| *value as f32),
+                memory_used_bytes: used,
+                memory_total_bytes: statistics.get("vramFreeBytes").zip(used).map(|(free, used)| free + used),
+            })
+        })
+        .collect();
+
+    if gpus.iter().all(|gpu| gpu.utilization_percent.is_none()) {
+        let residency = source.run(&powermetrics::command(&["gpu_power"]))
+            .and_then(|output| powermetrics::parse(&output)?.gpu_active_percent);
+        if let Some(residency) = residency {
+            match gpus.first_mut() {
+                Some(gpu) => gpu.utilization_percent = Some(residency),
+                None => gpus.push(GpuReading { utilization_percent: Some(residency), ..Default::default() }),
+            }
+        }
+    }
+
+    gpus
+}
+
+/// The numeric entries of a one-line ioreg dictionary such as
+/// `"PerformanceStatistics" = {"Device Utilization %"=12,"vramUsedBytes"=1024}`.
+fn parse_ioreg_dictionary(entry: &str, prefix: &str) -> Option<HashMap<String, u64>> {
+    let start = entry.find(prefix)? + prefix.len();
+    let body = &entry[start..start + entry[start..].find('}')?];
+    Some(body.split(',')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            Some((key.trim().trim_matches('"').to_string(), value.trim().parse().ok()?))
+        })
+        .collect())
+}
+
+/// Every SMC and HID sensor. Remotely this needs plan10 on the server, as
+/// the SMC can't be read from a shell command.
+pub fn sensors(source: &Source) -> Result<SensorsReading> {
+    match source {
+        Source::Local => Ok(SensorsReading { sensors: all_sensors() }),
+        Source::Remote(_) => {
+            let output = source.run("plan10 --server-mode --json monitor sensors 2>/dev/null")
+                .ok_or_else(|| anyhow::anyhow!("Reading sensors needs plan10 installed on the server"))?;
+            let reading: serde_json::Value = serde_json::from_str(&output)?;
+            let sensors = serde_json::from_value(reading.get("sensors").cloned().unwrap_or_default())?;
+            Ok(SensorsReading { sensors })
+        }
+    }
+}
+
+/// One powermetrics sample, asking again without `smc` on Apple Silicon.
+pub fn powermetrics_sample(source: &Source) -> Option<PowermetricsReading> {
+    let output = source.run(&powermetrics::command(powermetrics::TEMPERATURE_SAMPLERS))
+        .or_else(|| source.run(&powermetrics::command(powermetrics::POWER_SAMPLERS)))?;
+    powermetrics::parse(&output)
+}
+
+/// CPU usage across all cores in percent. Remotely this sums `ps`, which is
+/// instant but smooths over short bursts.
+pub fn cpu_usage(source: &Source) -> Option<f32> {
+    match source {
+        Source::Local => get_system_info().ok().map(|info| info.cpu_usage),
+        Source::Remote(_) => source
+            .run("ps -A -o %cpu= | awk -v cores=$(sysctl -n hw.ncpu 2>/dev/null || nproc) '{sum += $1} END {printf \"%.1f\", sum / cores}'")?
+            .trim()
+            .parse()
+            .ok(),
+    }
+}
+
+/// 1, 5 and 15 minute load averages.
+pub fn load_average(source: &Source) -> Option<(f32, f32, f32)> {
+    match source {
+        Source::Local => get_system_info().ok()
+            .map(|info| (info.load_average.0 as f32, info.load_average.1 as f32, info.load_average.2 as f32)),
+        Source::Remote(_) => {
+            // "{ 1.52 1.31 1.20 }", or "1.52 1.31 1.20 2/345 6789" from /proc/loadavg
+            let output = source.run("sysctl -n vm.loadavg 2>/dev/null || cat /proc/loadavg")?;
+            let mut loads = output.trim_matches(|c: char| c == '{' || c == '}' || c.is_whitespace())
+                .split_whitespace()
+                .map(|value| value.parse().ok());
+            Some((loads.next()??, loads.next()??, loads.next()??))
+        }
+    }
+}
+
+/// Load averages next to the number of cores the work competes for.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct CpuLoad {
+    pub load_1m: f32,
+    pub load_5m: f32,
+    pub load_15m: f32,
+    pub cores: u32,
+}
+
+impl CpuLoad {
+    /// Both the 5 and 15 minute averages exceed the core count, so work has
+    /// been queueing for the CPU for a while rather than during one burst.
+    pub fn saturated(&self) -> bool {
+        let cores = self.cores as f32;
+        self.load_5m > cores && self.load_15m > cores
+    }
+}
+
+/// Logical CPU cores.
+pub fn cpu_cores(source: &Source) -> Option<u32> {
+    match source {
+        Source::Local => std::thread::available_parallelism().ok()
+            .and_then(|cores| u32::try_from(cores.get()).ok()),
+        Source::Remote(_) => source.run("sysctl -n hw.ncpu 2>/dev/null || nproc")?.trim().parse().ok(),
+    }
+}
+
+pub fn cpu_load(source: &Source) -> Option<CpuLoad> {
+    let (load_1m, load_5m, load_15m) = load_average(source)?;
+    Some(CpuLoad { load_1m, load_5m, load_15m, cores: cpu_cores(source)? })
+}
+
+/// Memory use, swap and pressure from `vm_stat`, `sysctl` and
+/// `memory_pressure`, which need no privileges.
+pub fn memory(source: &Source) -> MemoryReading {
+    if source.is_linux() {
+        return linux_memory(source);
+    }
+    let mut reading = MemoryReading {
+        total_bytes: source.run("sysctl -n hw.memsize").and_then(|total| total.trim().parse().ok()),
+        ..Default::default()
+    };
+
+    if let Some((page_size, counters)) = vm_stat(source) {
+        let bytes = |name: &str| counters.get(name).map(|pages| pages * page_size);
+        let purgeable = bytes("Pages purgeable").unwrap_or(0);
+        reading.app_bytes = bytes("Anonymous pages").map(|anonymous| anonymous.saturating_sub(purgeable));
+        reading.wired_bytes = bytes("Pages wired down");
+        reading.compressed_bytes = bytes("Pages occupied by compressor");
+        reading.cached_bytes = bytes("File-backed pages").map(|file_backed| file_backed + purgeable);
+        reading.used_bytes = match (reading.app_bytes, reading.wired_bytes, reading.compressed_bytes) {
+            (Some(app), Some(wired), Some(compressed)) => Some(app + wired + compressed),
+            _ => None,
+        };
+    }
+
+    if let Some((used, total)) = swap_usage(source) {
+        reading.swap_used_bytes = Some(used);
+        reading.swap_total_bytes = Some(total);
+    }
+    if let Some((count, bytes)) = swapfiles(source) {
+        reading.swapfiles = Some(count);
+        reading.swapfile_bytes = Some(bytes);
+    }
+
+    reading.pressure = match source.run("sysctl -n kern.memorystatus_vm_pressure_level").as_deref().map(str::trim) {
+        Some("1") => Some(MemoryPressure::Normal),
+        Some("2") => Some(MemoryPressure::Warning),
+        Some("4") => Some(MemoryPressure::Critical),
+        _ => None,
+    };
+    // "System-wide memory free percentage: 62%"
+    reading.free_percent = source.run("memory_pressure")
+        .and_then(|output| output.lines()
+            .find_map(|line| line.strip_prefix("System-wide memory free percentage:"))
+            .and_then(|value| value.trim().trim_end_matches('%').parse().ok()));
+
+    reading
+}
+
+/// Swap used and allocated in bytes, from `sysctl -n vm.swapusage`:
+/// "total = 2048.00M  used = 1024.50M  free = 1023.50M  (encrypted)".
+pub fn swap_usage(source: &Source) -> Option<(u64, u64)> {
+    if source.is_linux() {
+        let meminfo = meminfo(source)?;
+        let total = *meminfo.get("SwapTotal")?;
+        return Some((total.saturating_sub(*meminfo.get("SwapFree")?), total));
+    }
+    let output = source.run("sysctl -n vm.swapusage")?;
+    let megabytes = |name: &str| output.split(&format!("{} = ", name))
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|value| value.trim_end_matches('M').parse::<f64>().ok())
+        .map(|megabytes| (megabytes * 1024.0 * 1024.0) as u64);
+    Some((megabytes("used")?, megabytes("total")?))
+}
+
+/// How many swap files there are and their combined size.
+fn swapfiles(source: &Source) -> Option<(u32, u64)> {
+    let output = source.run("for f in /private/var/vm/swapfile*; do [ -f \"$f\" ] && stat -f %z \"$f\"; done; true")?;
+    let sizes: Vec<u64> = output.lines().filter_map(|line| line.trim().parse().ok()).collect();
+    Some((sizes.len() as u32, sizes.iter().sum()))
+}
+
+pub fn paging_counters(source: &Source) -> Option<PagingCounters> {
+    if source.is_linux() {
+        return linux_paging_from(&source.run("cat /proc/vmstat")?);
+    }
+    let (_, counters) = parse_vm_stat(&source.run("vm_stat")?)?;
+    paging_from(&counters)
+}
+
+/// Watch the paging counters for a couple of seconds, in a single command so
+/// a remote server is only asked once.
+pub fn paging_rates(source: &Source) -> Option<PagingRates> {
+    let (before, after) = if source.is_linux() {
+        let output = source.run(&format!("cat /proc/vmstat; sleep {}; echo ---; cat /proc/vmstat", PAGING_WINDOW_SECONDS))?;
+        let (before, after) = output.split_once("---")?;
+        (linux_paging_from(before)?, linux_paging_from(after)?)
+    } else {
+        let output = source.run(&format!("vm_stat; sleep {}; vm_stat", PAGING_WINDOW_SECONDS))?;
+        let mut snapshots = output.split("Mach Virtual Memory Statistics")
+            .skip(1)
+            .filter_map(|snapshot| paging_from(&parse_vm_stat(snapshot)?.1));
+        (snapshots.next()?, snapshots.next()?)
+    };
+    let rate = |before: u64, after: u64| after.saturating_sub(before) as f32 / PAGING_WINDOW_SECONDS as f32;
+    Some(PagingRates {
+        pageouts_per_second: rate(before.pageouts, after.pageouts),
+        swapins_per_second: rate(before.swapins, after.swapins),
+        swapouts_per_second: rate(before.swapouts, after.swapouts),
+    })
+}
+
+fn paging_from(counters: &HashMap<String, u64>) -> Option<PagingCounters> {
+    Some(PagingCounters {
+        pageouts: *counters.get("Pageouts")?,
+        swapins: *counters.get("Swapins")?,
+        swapouts: *counters.get("Swapouts")?,
+    })
+}
+
+fn vm_stat(source: &Source) -> Option<(u64, HashMap<String, u64>)> {
+    parse_vm_stat(&source.run("vm_stat")?)
+}
+
+/// The page size and counters of `vm_stat`, whose output starts with
+/// "Mach Virtual Memory Statistics: (page size of 16384 bytes)" followed by
+/// lines like "Pages wired down:     123456.".
+fn parse_vm_stat(output: &str) -> Option<(u64, HashMap<String, u64>)> {
+    let page_size = output.lines().next()?
+        .split("page size of ").nth(1)?
+        .split_whitespace().next()?
+        .parse().ok()?;
+    let counters = output.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().trim_matches('"').to_string(), value.trim().trim_end_matches('.').parse().ok()?))
+        })
+        .collect();
+    Some((page_size, counters))
+}
+
+/// Homebrew's bin directories aren't on the PATH of an SSH session.
+const SMARTCTL: &str = "PATH=$PATH:/opt/homebrew/bin:/opt/homebrew/sbin:/usr/local/bin:/usr/local/sbin smartctl";
+
+/// SMART health of each physical disk (or just `device`), from smartctl when
+/// it is installed and `diskutil info` otherwise, with I/O from iostat.
+pub fn disks(source: &Source, device: Option<&str>) -> DiskReading {
+    let devices: Vec<String> = match device {
+        Some(device) => vec![device.trim_start_matches("/dev/").to_string()],
+        // "/dev/disk0 (internal, physical):"
+        None => source.run("diskutil list physical")
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| line.starts_with("/dev/disk"))
+            .filter_map(|line| line.split_whitespace().next())
+            .map(|device| device.trim_start_matches("/dev/").to_string())
+            .collect(),
+    };
+
+    // smartctl's exit status is a bit mask that is non-zero for failing
+    // disks, which are exactly the ones to report
+    let mut disks: Vec<DiskHealth> = devices.iter()
+        .map(|device| {
+            source.run(&format!("{} -a -j /dev/{} 2>/dev/null; true", SMARTCTL, device))
+                .and_then(|output| parse_smartctl(device, &output))
+                .unwrap_or_else(|| diskutil_health(source, device))
+        })
+        .collect();
+
+    // "    KB/t  tps  MB/s     KB/t  tps  MB/s" then two samples; the last
+    // is the one-second interval
+    if !disks.is_empty() {
+        let command = format!("iostat -d -c 2 -w 1 {}", devices.join(" "));
+        if let Some(output) = source.run(&command) {
+            let fields: Vec<f32> = output.lines().last().unwrap_or_default()
+                .split_whitespace()
+                .filter_map(|value| value.parse().ok())
+                .collect();
+            for (disk, sample) in disks.iter_mut().zip(fields.chunks(3)) {
+                disk.megabytes_per_second = sample.get(2).copied();
+            }
+        }
+    }
+
+    DiskReading { disks }
+}
+
+fn parse_smartctl(device: &str, output: &str) -> Option<DiskHealth> {
+    let report: serde_json::Value = serde_json::from_str(output).ok()?;
+    // Without a device section smartctl couldn't open the disk
+    report.get("device")?;
+    let number = |path: &str| report.pointer(path).and_then(serde_json::Value::as_u64);
+    let nvme = |key: &str| number(&format!("/nvme_smart_health_information_log/{}", key));
+    let passed = report.pointer("/smart_status/passed").and_then(serde_json::Value::as_bool);
+
+    Some(DiskHealth {
+        device: device.to_string(),
+        model: report.get("model_name").and_then(serde_json::Value::as_str).map(str::to_string),
+        source: HealthSource::Smartctl,
+        smart_status: passed.map(|passed| if passed { "PASSED" } else { "FAILED" }.to_string()),
+        passed,
+        percentage_used: nvme("percentage_used").map(|value| value.min(255) as u8),
+        available_spare: nvme("available_spare").map(|value| value.min(255) as u8),
+        available_spare_threshold: nvme("available_spare_threshold").map(|value| value.min(255) as u8),
+        media_errors: nvme("media_errors"),
+        power_on_hours: number("/power_on_time/hours"),
+        temperature_celsius: number("/temperature/current").map(|value| value as f32),
+        megabytes_per_second: None,
+    })
+}
+
+fn diskutil_health(source: &Source, device: &str) -> DiskHealth {
+    let output = source.run(&format!("diskutil info {}", device)).unwrap_or_default();
+    let field = |name: &str| output.lines()
+        .find_map(|line| line.trim().strip_prefix(name))
+        .map(|value| value.trim().to_string());
+    let smart_status = field("SMART Status:");
+
+    DiskHealth {
+        device: device.to_string(),
+        model: field("Device / Media Name:"),
+        source: HealthSource::Diskutil,
+        passed: match smart_status.as_deref() {
+            Some("Verified") => Some(true),
+            Some("Failing") => Some(false),
+            _ => None,
+        },
+        smart_status,
+        percentage_used: None,
+        available_spare: None,
+        available_spare_threshold: None,
+        media_errors: None,
+        power_on_hours: None,
+        temperature_celsius: None,
+        megabytes_per_second: None,
+    }
+}
+
+/// The `top` processes by CPU and by memory. Locally CPU is measured over
+/// sysinfo's minimum update interval; remotely it is `ps`'s decaying average.
+pub fn processes(source: &Source, top: usize) -> ProcessesReading {
+    let mut processes = match source {
+        Source::Local => {
+            use sysinfo::{PidExt, ProcessExt, System, SystemExt};
+
+            let mut system = System::new();
+            system.refresh_processes();
+            std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+            system.refresh_processes();
+            system.processes().values()
+                .map(|process| ProcessReading {
+                    pid: process.pid().as_u32(),
+                    name: process.name().to_string(),
+                    cpu_percent: process.cpu_usage(),
+                    memory_bytes: process.memory(),
+                })
+                .collect()
+        }
+        // "  312   4.1  52344 WindowServer"; rss is in KiB. procps has no
+        // -c, and its comm is the bare name already
+        Source::Remote(_) => source.run(if source.is_linux() { "ps -Ao pid=,%cpu=,rss=,comm=" } else { "ps -Aco pid=,%cpu=,rss=,comm=" })
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                Some(ProcessReading {
+                    pid: fields.next()?.parse().ok()?,
+                    cpu_percent: fields.next()?.parse().ok()?,
+                    memory_bytes: fields.next()?.parse::<u64>().ok()? * 1024,
+                    name: fields.collect::<Vec<_>>().join(" "),
+                })
+            })
+            .collect::<Vec<_>>(),
+    };
+
+    processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+    let by_cpu = processes.iter().take(top).cloned().collect();
+    processes.sort_by(|a, b| b.memory_bytes.cmp(&a.memory_bytes));
+    processes.truncate(top);
+    ProcessesReading { by_cpu, by_memory: processes }
+}
+
+/// The readings kept in history for `monitor history`.
+pub fn sample(source: &Source) -> Sample {
+    let power = power(source);
+    let paging = paging_counters(source);
+    Sample {
+        power_source: power.source.name().to_string(),
+        battery_percent: power.battery_percent,
+        cpu_celsius: die_temperatures(source).0,
+        cpu_usage: cpu_usage(source),
+        swap_used_bytes: swap_usage(source).map(|(used, _)| used),
+        swapins: paging.map(|paging| paging.swapins),
+        swapouts: paging.map(|paging| paging.swapouts),
+    }
+}
+
+/// From `sysctl -n kern.boottime`: "{ sec = 1697000000, usec = 123456 } Wed Oct 11 ...",
+/// or the "btime 1697000000" line of `/proc/stat` on Linux.
+pub fn boot_time(source: &Source) -> Option<DateTime<Utc>> {
+    if source.is_linux() {
+        let output = source.run("grep '^btime' /proc/stat")?;
+        return DateTime::from_timestamp(output.split_whitespace().nth(1)?.parse().ok()?, 0);
+    }
+    let output = source.run("sysctl -n kern.boottime")?;
+    let seconds = output.split("sec = ").nth(1)?.split(',').next()?.trim().parse().ok()?;
+    DateTime::from_timestamp(seconds, 0)
+}
+
+/// Usage percentage of the root volume.
+pub fn disk_usage(source: &Source) -> Option<u8> {
+    source.run("df -P / | tail -1 | awk '{print $5}'")?
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+}
+
+/// Lid state. macOS stays awake with the lid closed in clamshell mode (an
+/// external display attached on AC power) or with sleep disabled; Linux
+/// when logind ignores the lid switch.
+pub fn lid(source: &Source) -> LidReading {
+    if source.is_linux() {
+        return linux_lid(source);
+    }
+    // "AppleClamshellState" = Yes, "AppleClamshellCausesSleep" = No, and
+    // " SleepDisabled		1" from pmset
+    let output = source
+        .run("ioreg -r -k AppleClamshellState -d 1 | grep Clamshell; pmset -g | grep SleepDisabled; true")
+        .unwrap_or_default();
+    let flag = |key: &str| output.lines()
+        .find_map(|line| line.split(&format!("\"{}\" = ", key)).nth(1))
+        .map(|value| value.trim() == "Yes");
+    let closed = flag("AppleClamshellState");
+
+    let mut inhibited_by = Vec::new();
+    if flag("AppleClamshellCausesSleep") == Some(false) {
+        inhibited_by.push("clamshell mode".to_string());
+    }
+    if parse_pmset_values(&output).get("SleepDisabled").is_some_and(|value| value == "1") {
+        inhibited_by.push("sleep disabled".to_string());
+    }
+    LidReading {
+        closed,
+        sleep_inhibited: closed.map(|_| !inhibited_by.is_empty()),
+        inhibited_by,
+    }
+}
+
+/// Whether anything holds a PreventUserIdleDisplaySleep assertion, as
+/// `caffeinate -d` does. A closed MacBook on battery keeps its network only
+/// while one is held. `None` on Linux and when pmset can't be read.
+pub fn display_sleep_prevented(source: &Source) -> Option<bool> {
+    if source.is_linux() {
+        return None;
+    }
+    parse_assertions(&pmset_assertions(source)?).held("PreventUserIdleDisplaySleep")
+}
+
+pub fn pmset_assertions(source: &Source) -> Option<String> {
+    source.run("pmset -g assertions")
+}
+
+/// Whether something keeps the machine awake: caffeinate on macOS; on
+/// Linux, a masked `sleep.target` or a systemd sleep inhibitor.
+pub fn caffeinate_running(source: &Source) -> bool {
+    if source.is_linux() {
+        return source
+            .run("systemctl is-enabled sleep.target 2>/dev/null | grep -qx masked || systemd-inhibit --list --no-legend 2>/dev/null | grep -q sleep")
+            .is_some();
+    }
+    source.run("pgrep -x caffeinate").is_some_and(|pids| !pids.trim().is_empty())
+}
+
+/// The `airport` utility; gone in recent macOS, where system_profiler is
+/// the (slower) fallback.
+const AIRPORT: &str = "/System/Library/PrivateFrameworks/Apple80211.framework/Versions/Current/Resources/airport";
+
+/// Interfaces from ifconfig, throughput from two `netstat -ib` samples a
+/// second apart, and Wi-Fi signal from airport or system_profiler.
+pub fn network(source: &Source) -> NetworkReading {
+    let mut interfaces = parse_ifconfig(&source.run("ifconfig -a").unwrap_or_default());
+
+    let counters = source.run("netstat -ibn; sleep 1; echo ---; netstat -ibn").unwrap_or_default();
+    let (before, after) = counters.split_once("---").unwrap_or_default();
+    let (before, after) = (parse_netstat(before), parse_netstat(after));
+    for interface in &mut interfaces {
+        let Some(&(rx_after, tx_after, rx_errors, tx_errors)) = after.get(&interface.name) else {
+            continue;
+        };
+        interface.rx_errors = Some(rx_errors);
+        interface.tx_errors = Some(tx_errors);
+        if let Some(&(rx_before, tx_before, _, _)) = before.get(&interface.name) {
+            interface.rx_bytes_per_second = Some(rx_after.saturating_sub(rx_before));
+            interface.tx_bytes_per_second = Some(tx_after.saturating_sub(tx_before));
+        }
+    }
+
+    NetworkReading { interfaces, wifi: wifi(source) }
+}
+
+/// "en0: flags=8863<UP,BROADCAST,...> mtu 1500" starts an interface; the
+/// indented lines that follow describe it.
+fn parse_ifconfig(output: &str) -> Vec<InterfaceReading> {
+    let mut interfaces: Vec<InterfaceReading> = Vec::new();
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            let Some((name, rest)) = line.split_once(": flags=") else {
+                continue;
+            };
+            let flags = rest.split_once('<').and_then(|(_, flags)| flags.split_once('>')).map_or("", |(flags, _)| flags);
+            interfaces.push(InterfaceReading {
+                name: name.to_string(),
+                up: flags.split(',').any(|flag| flag == "UP"),
+                status: None,
+                mac: None,
+                ipv4: Vec::new(),
+                ipv6: Vec::new(),
+                media: None,
+                mtu: rest.split_once("mtu ").and_then(|(_, mtu)| mtu.split_whitespace().next()?.parse().ok()),
+                rx_bytes_per_second: None,
+                tx_bytes_per_second: None,
+                rx_errors: None,
+                tx_errors: None,
+            });
+            continue;
+        }
+
+        let Some(interface) = interfaces.last_mut() else {
+            continue;
+        };
+        let line = line.trim();
+        let (key, value) = line.split_once(|c: char| c == ' ' || c == '\t').unwrap_or((line, ""));
+        let first = value.split_whitespace().next().map(str::to_string);
+        match key {
+            "ether" => interface.mac = first,
+            "inet" => interface.ipv4.extend(first),
+            // Drop the "%en0" scope suffix of link-local addresses
+            "inet6" => interface.ipv6.extend(first.map(|address| address.split('%').next().unwrap_or_default().to_string())),
+            "status:" => interface.status = Some(value.trim().to_string()),
+            "media:" => interface.media = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+    interfaces
+}
+
+/// Received bytes, sent bytes, input errors and output errors per
+/// interface, from the `<Link#n>` rows of `netstat -ibn`. Counted from the
+/// right, as the address column is empty for some interfaces.
+fn parse_netstat(output: &str) -> HashMap<String, (u64, u64, u64, u64)> {
+    output.lines()
+        .filter(|line| line.contains("<Link#"))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // ... Ipkts Ierrs Ibytes Opkts Oerrs Obytes Coll
+            let column = |from_end: usize| fields.len().checked_sub(from_end).and_then(|i| fields[i].parse().ok());
+            let name = fields.first()?.trim_end_matches('*').to_string();
+            Some((name, (column(5)?, column(2)?, column(6)?, column(3)?)))
+        })
+        .collect()
+}
+
+fn wifi(source: &Source) -> Option<WifiReading> {
+    let airport = source.run(&format!("{} -I 2>/dev/null", AIRPORT)).unwrap_or_default();
+    let field = |name: &str| airport.lines()
+        .filter_map(|line| line.trim().strip_prefix(name))
+        .find_map(|rest| rest.strip_prefix(": "))
+        .map(|value| value.trim().to_string());
+    if let Some(rssi) = field("agrCtlRSSI") {
+        return Some(WifiReading {
+            ssid: field("SSID"),
+            rssi_dbm: rssi.parse().ok(),
+            noise_dbm: field("agrCtlNoise").and_then(|noise| noise.parse().ok()),
+            channel: field("channel"),
+            tx_rate_mbps: field("lastTxRate").and_then(|rate| rate.parse().ok()),
+        });
+    }
+
+    // "Current Network Information:" is followed by "<SSID>:" and its details,
+    // e.g. "Signal / Noise: -52 dBm / -92 dBm"
+    let profile = source.run("system_profiler SPAirPortDataType")?;
+    let mut lines = profile.lines()
+        .skip_while(|line| !line.contains("Current Network Information:"))
+        .skip(1)
+        .map(str::trim);
+    let ssid = lines.next()?.trim_end_matches(':').to_string();
+    let details: Vec<&str> = lines.take_while(|line| !line.ends_with(':')).collect();
+    let detail = |name: &str| details.iter().find_map(|line| line.strip_prefix(name)).map(str::trim);
+    let (signal, noise) = detail("Signal / Noise:")?.split_once('/')?;
+    let dbm = |value: &str| value.trim().trim_end_matches("dBm").trim().parse().ok();
+
+    Some(WifiReading {
+        ssid: Some(ssid),
+        rssi_dbm: dbm(signal),
+        noise_dbm: dbm(noise),
+        channel: detail("Channel:").map(str::to_string),
+        tx_rate_mbps: detail("Transmit Rate:").and_then(|rate| rate.parse().ok()),
+    })
+}
+
+/// The sleep, wake, assertion and shutdown lines of `pmset -g log`, which
+/// otherwise runs to tens of thousands of lines; `None` if there are none.
+pub fn pmset_log(source: &Source) -> Option<String> {
+    source.run("pmset -g log | grep -E '^[0-9]{4}-[0-9]{2}-[0-9]{2} [0-9:]{8} [-+][0-9]{4} (Sleep|Wake|DarkWake|Assertions|Shutdown)'")
+}
+
+/// "Previous shutdown cause" entries of the unified log over `window`,
+/// which outlive pmset's log. `log show` takes a while on a busy machine.
+pub fn shutdown_causes(source: &Source, window: chrono::Duration) -> Vec<(DateTime<chrono::FixedOffset>, i32)> {
+    let command = format!(
+        "log show --style compact --last {}h --predicate 'eventMessage contains \"Previous shutdown cause\"' 2>/dev/null",
+        window.num_hours().max(1)
+    );
+    // "2024-01-15 08:00:00.123456-0800 Df kernel[0:...] ... Previous shutdown cause: -128"
+    source.run(&command)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let timestamp = DateTime::parse_from_str(line.get(..31)?, "%Y-%m-%d %H:%M:%S%.6f%z").ok()?;
+            let cause = line.rsplit(':').next()?.trim().parse().ok()?;
+            Some((timestamp, cause))
+        })
+        .collect()
+}
+
+pub fn power_settings(source: &Source, config: &Config) -> PowerSettingsReading {
+    let settings = source.run("pmset -g").map(|output| parse_pmset_values(&output)).unwrap_or_default();
+    let drift = config.power_profile.drift(&settings)
+        .into_iter()
+        .map(|(setting, expected, actual)| PowerDrift { setting, expected, actual })
+        .collect();
+
+    PowerSettingsReading {
+        os_version: source.run("sw_vers -productVersion").map(|version| version.trim().to_string()),
+        lid: lid(source),
+        display_sleep_prevented: display_sleep_prevented(source),
+        settings,
+        drift,
+    }
+}
+
+pub fn status(source: &Source, thresholds: Thresholds) -> StatusReading {
+    let power = power(source);
+    let lid = lid(source);
+    let caffeinate_running = caffeinate_running(source);
+    let disk_used_percent = disk_usage(source);
+    let cpu_celsius = die_temperatures(source).0;
+    let cpu_usage_percent = cpu_usage(source);
+    let load = cpu_load(source);
+    let swap_used_bytes = swap_usage(source).map(|(used, _)| used);
+    let paging = paging_counters(source);
+    let thermal = thermal_limits(source);
+
+    let mut issues = Vec::new();
+    if !caffeinate_running {
+        issues.push(match source.os() {
+            TargetOs::Linux => "Sleep is neither masked nor inhibited".to_string(),
+            TargetOs::MacOs => "Caffeinate is not running".to_string(),
+        });
+    }
+    if let Some(pct) = power.battery_percent.filter(|pct| power.source == PowerSource::Battery && thresholds.battery_low(*pct)) {
+        issues.push(format!("Battery low ({}%, warning at {}%)", pct, thresholds.battery_warning_level));
+    }
+    if lid.closed_on_battery(power.source == PowerSource::Battery) {
+        issues.push("Lid closed on battery power".to_string());
+    }
+    if let Some(pct) = disk_used_percent.filter(|pct| thresholds.disk_full(*pct)) {
+        issues.push(format!("Disk nearly full ({}% used, warning at {}%)", pct, thresholds.disk_warning_level));
+    }
+    if let Some(celsius) = cpu_celsius.filter(|celsius| thresholds.temp_exceeded(*celsius)) {
+        issues.push(format!("CPU at {:.1}°C (threshold {:.1}°C)", celsius, thresholds.temp));
+    }
+    if let Some(limit) = thermal.throttled_to() {
+        issues.push(format!("CPU thermally throttled to {}%", limit));
+    }
+    if let Some(pct) = cpu_usage_percent.filter(|pct| thresholds.cpu_pegged(*pct)) {
+        issues.push(format!("CPU busy at {:.0}% (warning at {}%)", pct, thresholds.cpu_warning_level));
+    }
+    if let Some(load) = load.filter(CpuLoad::saturated) {
+        issues.push(format!("Load {:.2} over 15 minutes on {} cores", load.load_15m, load.cores));
+    }
+
+    StatusReading {
+        power,
+        lid,
+        caffeinate_running,
+        disk_used_percent,
+        cpu_celsius,
+        cpu_usage_percent,
+        load,
+        swap_used_bytes,
+        paging,
+        battery_runtime: None,
+        thermal,
+        thresholds,
+        issues,
+    }
+}
+
+impl StatusReading {
+    pub fn sample(&self) -> Sample {
+        Sample {
+            power_source: self.power.source.name().to_string(),
+            battery_percent: self.power.battery_percent,
+            cpu_celsius: self.cpu_celsius,
+            cpu_usage: self.cpu_usage_percent,
+            swap_used_bytes: self.swap_used_bytes,
+            swapins: self.paging.map(|paging| paging.swapins),
+            swapouts: self.paging.map(|paging| paging.swapouts),
+        }
+    }
+
+    pub fn alert_input(&self) -> AlertInput {
+        AlertInput {
+            on_battery: (self.power.source != PowerSource::Unknown).then(|| self.power.source == PowerSource::Battery),
+            battery_percent: self.power.battery_percent,
+            battery_minutes_left: self.battery_runtime.map(|runtime| runtime.minutes),
+            cpu_celsius: self.cpu_celsius,
+            cpu_throttled_to: self.thermal.throttled_to(),
+            disk_used_percent: self.disk_used_percent,
+            caffeinate_running: Some(self.caffeinate_running),
+        }
+    }
+}
+
+/// Attributes of every device of a sysfs class, by device then attribute,
+/// read with one `grep` so a server is only asked once. `patterns` are the
+/// attribute files to read, e.g. "/sys/class/power_supply/*/*".
+fn sysfs(source: &Source, patterns: &str) -> BTreeMap<String, BTreeMap<String, String>> {
+    let mut devices: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    // "/sys/class/power_supply/BAT0/capacity:87"
+    for line in source.run(&format!("grep -sH . {}; true", patterns)).unwrap_or_default().lines() {
+        let Some((path, value)) = line.split_once(':') else { continue };
+        let mut parts = path.rsplit('/');
+        let (Some(attribute), Some(device)) = (parts.next(), parts.next()) else { continue };
+        devices.entry(device.to_string()).or_default().insert(attribute.to_string(), value.trim().to_string());
+    }
+    devices
+}
+
+fn power_supplies(source: &Source) -> BTreeMap<String, BTreeMap<String, String>> {
+    sysfs(source, "/sys/class/power_supply/*/*")
+}
+
+/// The first supply whose `type` is "Battery".
+fn linux_battery(supplies: &BTreeMap<String, BTreeMap<String, String>>) -> Option<&BTreeMap<String, String>> {
+    supplies.values().find(|attributes| attributes.get("type").is_some_and(|kind| kind == "Battery"))
+}
+
+/// Power from `/sys/class/power_supply`. A machine without a battery runs
+/// on mains, whether or not it lists a supply for it.
+fn linux_power(supplies: &BTreeMap<String, BTreeMap<String, String>>) -> PowerReading {
+    let battery = linux_battery(supplies);
+    let mut external = supplies.values()
+        .filter(|attributes| attributes.get("type").is_some_and(|kind| kind != "Battery"))
+        .filter_map(|attributes| attributes.get("online"))
+        .peekable();
+    let has_external = external.peek().is_some();
+    let online = external.any(|online| online == "1");
+    let status = battery.and_then(|battery| battery.get("status")).map(String::as_str);
+
+    let source = match (battery, online, status) {
+        (None, _, _) | (_, true, _) => PowerSource::Ac,
+        (Some(_), false, Some("Discharging")) => PowerSource::Battery,
+        (Some(_), false, _) if has_external => PowerSource::Battery,
+        _ => PowerSource::Unknown,
+    };
+
+    // energy in µWh over power in µW, or charge in µAh over current in µA
+    let attribute = |name: &str| battery.and_then(|battery| battery.get(name)).and_then(|value| value.parse::<f64>().ok());
+    let hours_left = (status == Some("Discharging"))
+        .then(|| match (attribute("energy_now"), attribute("power_now"), attribute("charge_now"), attribute("current_now")) {
+            (Some(energy), Some(power), _, _) if power > 0.0 => Some(energy / power),
+            (_, _, Some(charge), Some(current)) if current > 0.0 => Some(charge / current),
+            _ => None,
+        })
+        .flatten();
+
+    PowerReading {
+        source,
+        battery_percent: attribute("capacity").map(|pct| pct as u8),
+        battery_state: status.map(|status| match status {
+            "Full" => "charged".to_string(),
+            "Not charging" => "AC attached".to_string(),
+            status => status.to_lowercase(),
+        }),
+        time_remaining: hours_left.map(|hours| {
+            let minutes = (hours * 60.0) as u32;
+            format!("{}:{:02}", minutes / 60, minutes % 60)
+        }),
+    }
+}
+
+/// Charge in mAh from `charge_*`, or from `energy_*` over the voltage on
+/// batteries that report in µWh.
+fn linux_battery_charge(supplies: &BTreeMap<String, BTreeMap<String, String>>) -> Option<BatteryCharge> {
+    let battery = linux_battery(supplies)?;
+    let attribute = |name: &str| battery.get(name).and_then(|value| value.parse::<f64>().ok());
+    let (now, full, rate) = match (attribute("charge_now"), attribute("charge_full"), attribute("current_now")) {
+        (Some(now), Some(full), Some(current)) => (now / 1000.0, full / 1000.0, current / 1000.0),
+        _ => {
+            let volts = attribute("voltage_now").filter(|voltage| *voltage > 0.0)? / 1_000_000.0;
+            (
+                attribute("energy_now")? / 1000.0 / volts,
+                attribute("energy_full")? / 1000.0 / volts,
+                attribute("power_now")? / 1000.0 / volts,
+            )
+        }
+    };
+    // Some drivers sign the current, others don't
+    let amperage = if battery.get("status").is_some_and(|status| status == "Discharging") { -rate.abs() } else { rate.abs() };
+    Some(BatteryCharge { current_mah: now as u32, max_mah: full as u32, amperage_ma: amperage as i32 })
+}
+
+fn linux_battery_health(supplies: &BTreeMap<String, BTreeMap<String, String>>) -> BatteryHealth {
+    let battery = linux_battery(supplies);
+    let attribute = |name: &str| battery.and_then(|battery| battery.get(name)).and_then(|value| value.parse::<f64>().ok());
+    let capacity = match (attribute("energy_full"), attribute("energy_full_design"), attribute("charge_full"), attribute("charge_full_design")) {
+        (Some(full), Some(design), _, _) | (_, _, Some(full), Some(design)) if design > 0.0 => Some(full / design * 100.0),
+        _ => None,
+    };
+    BatteryHealth {
+        // Drivers without a counter report 0
+        cycle_count: attribute("cycle_count").filter(|count| *count > 0.0).map(|count| count as u32),
+        condition: battery.and_then(|battery| battery.get("health")).cloned(),
+        maximum_capacity_percent: capacity.map(|pct| pct.round().min(100.0) as u8),
+    }
+}
+
+/// CPU and GPU temperatures and fan speeds from hwmon, falling back to the
+/// hottest thermal zone for the CPU. Values are in millidegrees.
+fn linux_temperatures(source: &Source) -> (Option<f32>, Option<f32>, Vec<FanSpeed>) {
+    let devices = sysfs(source, "/sys/class/hwmon/*/name /sys/class/hwmon/*/temp*_input /sys/class/hwmon/*/fan*_input \
+        /sys/class/thermal/thermal_zone*/type /sys/class/thermal/thermal_zone*/temp");
+    let hottest = |chips: &[&str]| devices.values()
+        .filter(|attributes| attributes.get("name").or(attributes.get("type"))
+            .is_some_and(|name| chips.iter().any(|chip| name.starts_with(chip))))
+        .flat_map(|attributes| attributes.iter()
+            .filter(|(name, _)| name.as_str() == "temp" || (name.starts_with("temp") && name.ends_with("_input")))
+            .filter_map(|(_, value)| value.parse::<f32>().ok()))
+        .map(|millidegrees| millidegrees / 1000.0)
+        .reduce(f32::max);
+
+    let cpu_celsius = hottest(&["coretemp", "k10temp", "zenpower", "cpu_thermal", "x86_pkg_temp", "cpu"])
+        .or_else(|| hottest(&["acpitz"]));
+    let gpu_celsius = hottest(&["amdgpu", "nouveau", "radeon"]);
+    let fans = devices.values()
+        .flat_map(|attributes| attributes.iter()
+            .filter(|(name, _)| name.starts_with("fan") && name.ends_with("_input"))
+            .filter_map(|(_, rpm)| rpm.parse().ok()))
+        .enumerate()
+        .map(|(fan, rpm)| FanSpeed { fan: fan as u8, rpm })
+        .collect();
+
+    (cpu_celsius, gpu_celsius, fans)
+}
+
+/// `/proc/meminfo` in bytes: "MemTotal:       16318480 kB".
+fn meminfo(source: &Source) -> Option<HashMap<String, u64>> {
+    let output = source.run("cat /proc/meminfo")?;
+    Some(output.lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            let kilobytes: u64 = value.split_whitespace().next()?.parse().ok()?;
+            Some((name.to_string(), kilobytes * 1024))
+        })
+        .collect())
+}
+
+/// Memory from `/proc/meminfo`: used is everything the kernel can't hand
+/// out without swapping, i.e. total less `MemAvailable`.
+fn linux_memory(source: &Source) -> MemoryReading {
+    let Some(meminfo) = meminfo(source) else { return MemoryReading::default() };
+    let bytes = |name: &str| meminfo.get(name).copied();
+    let total = bytes("MemTotal");
+    let available = bytes("MemAvailable");
+    MemoryReading {
+        total_bytes: total,
+        used_bytes: total.zip(available).map(|(total, available)| total.saturating_sub(available)),
+        app_bytes: bytes("AnonPages"),
+        wired_bytes: None,
+        compressed_bytes: None,
+        cached_bytes: bytes("Cached").zip(bytes("Buffers")).map(|(cached, buffers)| cached + buffers),
+        swap_used_bytes: bytes("SwapTotal").zip(bytes("SwapFree")).map(|(total, free)| total.saturating_sub(free)),
+        swap_total_bytes: bytes("SwapTotal"),
+        swapfiles: None,
+        swapfile_bytes: None,
+        pressure: None,
+        free_percent: total.zip(available)
+            .filter(|(total, _)| *total > 0)
+            .map(|(total, available)| (available * 100 / total) as u8),
+    }
+}
+
+/// Paging counters from `/proc/vmstat`: "pswpout 1234". Linux doesn't
+/// page out anonymous memory other than to swap, so pageouts are swapouts.
+fn linux_paging_from(output: &str) -> Option<PagingCounters> {
+    let counter = |name: &str| output.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .and_then(|value| value.trim().parse().ok());
+    let swapouts = counter("pswpout")?;
+    Some(PagingCounters { pageouts: swapouts, swapins: counter("pswpin")?, swapouts })
+}
+
+/// Lid state from the ACPI button, "state:      closed", and logind's
+/// `HandleLidSwitch`, the last setting winning like in logind.
+fn linux_lid(source: &Source) -> LidReading {
+    let output = source
+        .run("cat /proc/acpi/button/lid/*/state 2>/dev/null; grep -hs '^HandleLidSwitch=' /etc/systemd/logind.conf /etc/systemd/logind.conf.d/*.conf; true")
+        .unwrap_or_default();
+    let closed = output.lines()
+        .find_map(|line| line.strip_prefix("state:"))
+        .map(|state| state.trim() == "closed");
+    let ignored = output.lines()
+        .filter_map(|line| line.strip_prefix("HandleLidSwitch="))
+        .last()
+        .is_some_and(|action| action.trim() == "ignore");
+    LidReading {
+        closed,
+        sleep_inhibited: closed.map(|_| ignored),
+        inhibited_by: if ignored { vec!["logind ignores the lid".to_string()] } else { Vec::new() },
+    }
+}
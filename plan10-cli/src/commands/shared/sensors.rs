@@ -0,0 +1,50 @@
+use anyhow::Result;
+use crate::{Config, ExecutionMode};
+use crate::commands::utils::*;
+use crate::commands::shared::readings;
+use crate::sensors::SensorKind;
+use colored::*;
+
+/// `monitor sensors`: every sensor grouped by kind, as key, description and
+/// value.
+pub async fn execute_sensors(
+    kind: Option<SensorKind>,
+    host: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    verbose: bool,
+) -> Result<()> {
+    let target = readings::target_host(host, &execution_mode);
+    let report = readings::read_from(target.as_deref(), config, |source, _| readings::sensors(source)).await?;
+    let sensors: Vec<_> = report.reading?.sensors.into_iter()
+        .filter(|sensor| kind.map_or(true, |kind| sensor.kind == kind))
+        .collect();
+
+    print_header(&format!("Sensors: {}", report.host));
+    if sensors.is_empty() {
+        print_warning("No sensors could be read");
+        println!("Sensors are read from the SMC and, on Apple Silicon, the HID temperature sensors");
+        return Ok(());
+    }
+
+    let mut current = None;
+    for sensor in &sensors {
+        if current != Some(sensor.kind) {
+            current = Some(sensor.kind);
+            println!("\n{}:", sensor.kind.title().bold());
+        }
+        let value = match sensor.kind {
+            SensorKind::Fan => format!("{:.0} {}", sensor.value, sensor.kind.unit()),
+            _ => format!("{:.2} {}", sensor.value, sensor.kind.unit()),
+        };
+        println!(
+            "  {:<24} {:<26} {:>12}",
+            sensor.key,
+            sensor.description.as_deref().unwrap_or(""),
+            value
+        );
+    }
+    print_verbose(&format!("{} sensor(s)", sensors.len()), verbose);
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use crate::{Config, SetupMode};
 use crate::commands::utils::*;
-use crate::config::ServerDefinition;
+use crate::config::{AddressFamily, ServerDefinition};
 use colored::*;
 use std::io::{self, Write};
 
@@ -10,7 +10,7 @@ pub async fn execute(
     config: &Config,
     verbose: bool,
 ) -> Result<()> {
-    match mode {
+    let result = match mode {
         SetupMode::Auto => {
             auto_setup(config, verbose).await
         }
@@ -25,7 +25,16 @@ pub async fn execute(
             println!();
             server_setup(config, verbose).await
         }
+    };
+
+    // Suggest what to try next, based on the configuration setup just saved
+    if result.is_ok() {
+        let path = config.path();
+        if let Ok(config) = Config::load(path.as_deref().and_then(|p| p.to_str()), None) {
+            super::tips::print_next_steps(&config);
+        }
     }
+    result
 }
 
 async fn auto_setup(config: &Config, verbose: bool) -> Result<()> {
@@ -251,7 +260,7 @@ async fn server_setup(config: &Config, verbose: bool) -> Result<()> {
     println!("\n{}:", "Next Steps".bold());
     println!("1. Test monitoring: plan10 monitor system");
     println!("2. Check status: plan10 status --detailed");
-    println!("3. View logs: tail -f /var/log/plan10.log");
+    println!("3. View logs: plan10 monitor logs --follow");
     
     if !new_config.server.auto_restart_services {
         println!("4. Start services: plan10 server start");
@@ -279,6 +288,15 @@ async fn add_server_interactive(config: &mut Config) -> Result<()> {
         tags: vec!["manual".to_string()],
         enabled: true,
         last_seen: None,
+        site: None,
+        addresses: Vec::new(),
+        address_family: AddressFamily::Auto,
+        notes: None,
+        runbook: None,
+        jump_host: None,
+        agent_forwarding: false,
+        ssh_options: Default::default(),
+        thresholds: Default::default(),
     };
     
     config.add_server(server)?;
@@ -1,6 +1,10 @@
 use anyhow::Result;
-use crate::{Config, ExecutionMode};
+use crate::{Config, ExecutionMode, OutputFormat};
+use crate::alerts::{self, AlertInput};
 use crate::commands::utils::*;
+use crate::commands::shared::{battery_runtime, metrics_history, readings::{self, PowerSource, Source, TargetOs}};
+use crate::config::{ServerDefinition, Thresholds};
+use crate::snapshot::StatusSnapshot;
 use crate::ssh::SshClient;
 use crate::utils::system::{get_system_info, is_caffeinate_running, is_on_battery, is_on_ac_power, get_battery_percentage};
 use crate::utils::formatting::*;
@@ -9,30 +13,51 @@ use chrono::Utc;
 
 pub async fn execute(
     host: Option<String>,
+    tag: Option<String>,
+    group: Option<String>,
     detailed: bool,
+    cached: bool,
+    max_age: u64,
+    probe: bool,
     config: &Config,
     execution_mode: ExecutionMode,
+    output: OutputFormat,
     verbose: bool,
 ) -> Result<()> {
+    if cached {
+        print_cached_status(max_age);
+        return Ok(());
+    }
+
+    if output == OutputFormat::Json {
+        return print_status_json(host, tag, group, config, execution_mode, verbose).await;
+    }
+
+    if tag.is_some() || group.is_some() {
+        return for_each_host(target_hosts(None, tag, group, config)?, config, |host| async move {
+            execute_remote_status(&host, detailed, probe, config, verbose).await
+        }).await;
+    }
+
     match execution_mode {
         ExecutionMode::Local => {
-            execute_local_status(detailed, verbose).await
+            execute_local_status(detailed, config, verbose).await
         }
         ExecutionMode::Remote { host: default_host } => {
             let target_host = host.unwrap_or(default_host);
-            execute_remote_status(&target_host, detailed, config, verbose).await
+            execute_remote_status(&target_host, detailed, probe, config, verbose).await
         }
         ExecutionMode::Auto => {
             if let Some(target_host) = host {
-                execute_remote_status(&target_host, detailed, config, verbose).await
+                execute_remote_status(&target_host, detailed, probe, config, verbose).await
             } else {
-                execute_local_status(detailed, verbose).await
+                execute_local_status(detailed, config, verbose).await
             }
         }
     }
 }
 
-async fn execute_local_status(detailed: bool, verbose: bool) -> Result<()> {
+async fn execute_local_status(detailed: bool, config: &Config, verbose: bool) -> Result<()> {
     let timestamp = Utc::now();
     
     print_header(&format!("Plan 10 Status - {}", timestamp.format("%Y-%m-%d %H:%M:%S UTC")));
@@ -49,6 +74,8 @@ async fn execute_local_status(detailed: bool, verbose: bool) -> Result<()> {
         let (icon, status) = format_percentage_status(pct);
         println!("  Battery: {} {}% ({})", icon, pct, status);
     }
+    let lid = readings::lid(&Source::Local);
+    print_lid(&lid, on_battery);
     
     // Service status
     println!("\n{}:", "Services".bold());
@@ -81,12 +108,13 @@ async fn execute_local_status(detailed: bool, verbose: bool) -> Result<()> {
         // Configuration status
         println!("\n{}:", "Configuration".bold());
         println!("  Config file: {}", 
-                 Config::default_config_path()
+                 config.path()
                      .map(|p| p.display().to_string())
                      .unwrap_or_else(|| "Not found".to_string()));
         
         // Load config to show server count and default server
-        if let Ok(loaded_config) = Config::load(None) {
+        let path = config.path();
+        if let Ok(loaded_config) = Config::load(path.as_deref().and_then(|p| p.to_str()), None) {
             println!("  Servers configured: {}", loaded_config.servers.len());
             
             if let Some(default_server) = &loaded_config.client.default_server {
@@ -106,9 +134,15 @@ async fn execute_local_status(detailed: bool, verbose: bool) -> Result<()> {
         health_issues += 1;
     }
     
+    if lid.closed_on_battery(on_battery) {
+        println!("  {} Lid closed on battery power: the network drops and heat builds up", "🔴".red());
+        health_issues += 1;
+    }
+
+    let thresholds = config.thresholds(None);
     if on_battery {
         if let Some(pct) = battery_pct {
-            if pct < 20 {
+            if thresholds.battery_low(pct) {
                 println!("  {} Battery level critical ({}%)", "🔴".red(), pct);
                 health_issues += 1;
             } else if pct < 50 {
@@ -117,19 +151,166 @@ async fn execute_local_status(detailed: bool, verbose: bool) -> Result<()> {
             }
         }
     }
+
+    let root_disk_percent = get_system_info().ok()
+        .and_then(|info| info.disks.into_iter().find(|disk| disk.mount_point == "/"))
+        .map(|disk| disk.usage_percent);
+    if let Some(pct) = root_disk_percent.filter(|pct| thresholds.disk_full(*pct)) {
+        println!("  {} Disk nearly full ({}% used)", "🟡".yellow(), pct);
+        health_issues += 1;
+    }
+    let thermal = readings::thermal_limits(&Source::Local);
+    if let Some(limit) = thermal.throttled_to() {
+        println!("  {} CPU thermally throttled to {}%", "🔥".red(), limit);
+        health_issues += 1;
+    }
+    let sample = readings::sample(&Source::Local);
+    if let Some(celsius) = sample.cpu_celsius.filter(|celsius| thresholds.temp_exceeded(*celsius)) {
+        println!("  {} CPU at {:.1}°C (threshold {:.1}°C)", "🔥".red(), celsius, thresholds.temp);
+        health_issues += 1;
+    }
+    health_issues += print_cpu_issues(sample.cpu_usage, readings::cpu_load(&Source::Local), thresholds);
     
     if health_issues == 0 {
         println!("  {} All systems operational", "🟢".green());
     } else {
         println!("  {} {} issue(s) detected", "⚠️".yellow(), health_issues);
     }
+
+    let snapshot = StatusSnapshot {
+        timestamp,
+        hostname: hostname::get().unwrap_or_default().to_string_lossy().to_string(),
+        on_battery,
+        on_ac,
+        battery_percent: battery_pct,
+        caffeinate_running,
+        health_issues,
+    };
+    if let Err(e) = snapshot.save() {
+        print_verbose(&format!("Could not save status snapshot: {}", e), verbose);
+    }
+    let alert_input = AlertInput {
+        on_battery: Some(on_battery),
+        battery_percent: battery_pct,
+        battery_minutes_left: battery_runtime::estimate(&Source::Local, None, on_battery, battery_pct, config)
+            .map(|runtime| runtime.minutes),
+        cpu_celsius: sample.cpu_celsius,
+        cpu_throttled_to: thermal.throttled_to(),
+        disk_used_percent: root_disk_percent,
+        caffeinate_running: Some(caffeinate_running),
+    };
+    alerts::check(&config.server.name, &alert_input, None, config, verbose);
+    metrics_history::record_sample(config, &config.server.name, sample, verbose);
     
     Ok(())
 }
 
+/// Round trips timed for the latency line; the fastest is shown.
+const LATENCY_SAMPLES: u32 = 3;
+/// Size of the `--probe` download: enough to fill a slow link for a moment
+/// without delaying the status on a fast one.
+const PROBE_BYTES: usize = 1024 * 1024;
+
+/// "42 ms", green up to 50 ms, yellow up to 200 ms and red beyond.
+fn format_latency(latency: std::time::Duration) -> ColoredString {
+    let millis = latency.as_millis();
+    let text = format!("{} ms", millis);
+    match millis {
+        0..=50 => text.green(),
+        51..=200 => text.yellow(),
+        _ => text.red(),
+    }
+}
+
+/// The lid line of the power status, red when it's closed on battery.
+fn print_lid(lid: &readings::LidReading, on_battery: bool) {
+    let Some(text) = lid.describe() else { return };
+    if lid.closed_on_battery(on_battery) {
+        println!("  Lid: {}", text.red());
+    } else {
+        println!("  Lid: {}", text);
+    }
+}
+
+/// Health summary lines for a pegged CPU and a sustained load above the
+/// core count, e.g. a runaway process heating a closed MacBook. Returns how
+/// many were printed.
+fn print_cpu_issues(cpu_usage: Option<f32>, load: Option<readings::CpuLoad>, thresholds: Thresholds) -> usize {
+    let mut issues = 0;
+    if let Some(pct) = cpu_usage.filter(|pct| thresholds.cpu_pegged(*pct)) {
+        println!("  {} CPU busy at {:.0}% (warning at {}%)", "🟡".yellow(), pct, thresholds.cpu_warning_level);
+        issues += 1;
+    }
+    if let Some(load) = load.filter(readings::CpuLoad::saturated) {
+        println!(
+            "  {} Load {:.2} / {:.2} (5 / 15 min) on {} cores",
+            "🟡".yellow(), load.load_5m, load.load_15m, load.cores
+        );
+        issues += 1;
+    }
+    issues
+}
+
+/// Status as JSON: one object for a single machine, an array for `--tag` or
+/// `--group` where unreachable servers get an `error` entry instead.
+async fn print_status_json(
+    host: Option<String>,
+    tag: Option<String>,
+    group: Option<String>,
+    config: &Config,
+    execution_mode: ExecutionMode,
+    verbose: bool,
+) -> Result<()> {
+    let read = |source: &Source, server: Option<&ServerDefinition>| {
+        let mut status = readings::status(source, config.thresholds(server));
+        let on_battery = status.power.source == PowerSource::Battery;
+        status.battery_runtime = battery_runtime::estimate(source, server, on_battery, status.power.battery_percent, config);
+        status
+    };
+
+    if tag.is_none() && group.is_none() {
+        let target = readings::target_host(host, &execution_mode);
+        let report = readings::read_from(target.as_deref(), config, read).await?;
+        alerts::check(&report.host, &report.reading.alert_input(), config.resolve_server(&report.host), config, verbose);
+        metrics_history::record_sample(config, &report.host, report.reading.sample(), verbose);
+        return print_json(&report);
+    }
+
+    let mut reports = Vec::new();
+    for host in target_hosts(None, tag, group, config)? {
+        let report = match readings::read_from(Some(&host), config, read).await {
+            Ok(reading) => {
+                alerts::check(&reading.host, &reading.reading.alert_input(), config.resolve_server(&reading.host), config, verbose);
+                metrics_history::record_sample(config, &reading.host, reading.reading.sample(), verbose);
+                serde_json::to_value(reading)?
+            }
+            Err(e) => serde_json::json!({ "host": host, "error": e.to_string() }),
+        };
+        reports.push(report);
+    }
+    print_json(&reports)
+}
+
+/// Print the last saved local status in one line without collecting anything.
+/// Never fails, so it is safe to call from a shell prompt.
+fn print_cached_status(max_age: u64) {
+    match StatusSnapshot::load() {
+        Ok(Some(snapshot)) => {
+            let age = snapshot.age_seconds() as u64;
+            if age > max_age {
+                println!("⏳{}", format_duration(age));
+            } else {
+                println!("{}", snapshot.compact());
+            }
+        }
+        _ => println!("❓"),
+    }
+}
+
 async fn execute_remote_status(
     host: &str,
     detailed: bool,
+    probe: bool,
     config: &Config,
     verbose: bool,
 ) -> Result<()> {
@@ -138,6 +319,7 @@ async fn execute_remote_status(
 
     print_verbose(&format!("Connecting to {}", host), verbose);
     let client = SshClient::connect(server, config).await?;
+    print_verbose(&format!("Connected via {}", client.connection_summary()), verbose);
     
     let timestamp = Utc::now();
     print_header(&format!("Plan 10 Status - {} - {}", host, timestamp.format("%Y-%m-%d %H:%M:%S UTC")));
@@ -153,44 +335,48 @@ async fn execute_remote_status(
     
     let (icon, _) = format_percentage_status(100);
     println!("  Status: {} Connected", icon);
-    
-    // Get remote status using scripts
-    println!("\n{}:", "Power Status".bold());
-    match client.execute_command("pmset -g batt | head -1") {
-        Ok(result) if result.success => {
-            let output = result.stdout.trim();
-            if output.contains("Battery Power") {
-                println!("  Source: {}", "🔋 Battery Power".yellow());
-            } else if output.contains("AC Power") {
-                println!("  Source: {}", "🔌 AC Power".green());
-            } else {
-                println!("  Source: {}", "❓ Unknown".dimmed());
-            }
-            
-            // Extract battery percentage
-            for line in output.lines() {
-                if let Some(start) = line.find(char::is_numeric) {
-                    if let Some(end) = line[start..].find('%') {
-                        let pct_str = &line[start..start + end];
-                        if let Ok(pct) = pct_str.parse::<u8>() {
-                            let (icon, status) = format_percentage_status(pct);
-                            println!("  Battery: {} {}% ({})", icon, pct, status);
-                            break;
-                        }
-                    }
-                }
-            }
+    // A slow echo with a fast download points at a busy server rather than
+    // a degraded link
+    let latency = client.measure_latency(LATENCY_SAMPLES);
+    match &latency {
+        Ok(latency) => println!("  Latency: {}", format_latency(*latency)),
+        Err(e) => print_verbose(&format!("Could not measure latency: {}", e), verbose),
+    }
+    if probe {
+        match client.measure_throughput(PROBE_BYTES, latency.unwrap_or_default()) {
+            Ok(rate) => println!("  Throughput: {}/s ({} probe)", format_bytes(rate as u64), format_bytes(PROBE_BYTES as u64)),
+            Err(e) => println!("  Throughput: {} ({})", "❓ Unable to measure".dimmed(), e),
         }
-        _ => println!("  Source: {}", "❓ Unable to determine".dimmed()),
     }
     
+    let source = Source::Remote(&client);
+    let os = source.os();
+    print_verbose(&format!("{} runs {}", host, os.name()), verbose);
+
+    println!("\n{}:", "Power Status".bold());
+    let power = readings::power(&source);
+    let on_battery = power.source == PowerSource::Battery;
+    let battery_pct = power.battery_percent;
+    match power.source {
+        PowerSource::Battery => println!("  Source: {}", "🔋 Battery Power".yellow()),
+        PowerSource::Ac => println!("  Source: {}", "🔌 AC Power".green()),
+        PowerSource::Unknown => println!("  Source: {}", "❓ Unable to determine".dimmed()),
+    }
+    if let Some(pct) = battery_pct {
+        let (icon, status) = format_percentage_status(pct);
+        println!("  Battery: {} {}% ({})", icon, pct, status);
+    }
+    let lid = readings::lid(&source);
+    print_lid(&lid, on_battery);
+
     // Service status
     println!("\n{}:", "Services".bold());
-    let caffeinate_running = match client.execute_command("pgrep -x caffeinate") {
-        Ok(result) => result.success && !result.stdout.trim().is_empty(),
-        _ => false,
+    let caffeinate_running = readings::caffeinate_running(&source);
+    let sleep_service = match os {
+        TargetOs::Linux => "Sleep blocked",
+        TargetOs::MacOs => "Caffeinate",
     };
-    println!("  Caffeinate: {}", format_service_status(caffeinate_running, true));
+    println!("  {}: {}", sleep_service, format_service_status(caffeinate_running, true));
     
     if detailed {
         // System information
@@ -211,14 +397,20 @@ async fn execute_remote_status(
             }
         }
         
-        // Check for Plan 10 files
+        // Check for Plan 10 files; the server scripts are macOS-only
         println!("\n{}:", "Plan 10 Installation".bold());
-        let files_to_check = vec![
-            ("server_setup.sh", "~/server_setup.sh"),
-            ("temp script", "~/scripts/temp"),
-            ("battery script", "~/scripts/battery"),
-            ("power_diagnostics script", "~/scripts/power_diagnostics"),
-        ];
+        let files_to_check = match os {
+            TargetOs::Linux => vec![],
+            TargetOs::MacOs => vec![
+                ("server_setup.sh", "~/server_setup.sh"),
+                ("temp script", "~/scripts/temp"),
+                ("battery script", "~/scripts/battery"),
+                ("power_diagnostics script", "~/scripts/power_diagnostics"),
+            ],
+        };
+        if files_to_check.is_empty() {
+            println!("  {}", "Server scripts not used on Linux".dimmed());
+        }
         
         for (name, path) in files_to_check {
             match client.file_exists(path) {
@@ -234,7 +426,10 @@ async fn execute_remote_status(
     let mut health_issues = 0;
     
     if !caffeinate_running {
-        println!("  {} Caffeinate is not running", "⚠️".yellow());
+        match os {
+            TargetOs::Linux => println!("  {} Sleep is neither masked nor inhibited", "⚠️".yellow()),
+            TargetOs::MacOs => println!("  {} Caffeinate is not running", "⚠️".yellow()),
+        }
         health_issues += 1;
     }
     
@@ -246,12 +441,62 @@ async fn execute_remote_status(
             health_issues += 1;
         }
     }
+
+    if lid.closed_on_battery(on_battery) {
+        println!("  {} Lid closed on battery power: the network drops and heat builds up", "🔴".red());
+        health_issues += 1;
+    }
+
+    let thresholds = config.thresholds(Some(server));
+    if let Some(pct) = battery_pct.filter(|pct| on_battery && thresholds.battery_low(*pct)) {
+        println!("  {} Battery low ({}%, warning at {}%)", "🔴".red(), pct, thresholds.battery_warning_level);
+        health_issues += 1;
+    }
+    let disk_percent = readings::disk_usage(&source);
+    if let Some(pct) = disk_percent.filter(|pct| thresholds.disk_full(*pct)) {
+        println!("  {} Disk nearly full ({}% used, warning at {}%)", "🟡".yellow(), pct, thresholds.disk_warning_level);
+        health_issues += 1;
+    }
+    let sample = readings::sample(&source);
+    if let Some(celsius) = sample.cpu_celsius.filter(|celsius| thresholds.temp_exceeded(*celsius)) {
+        println!("  {} CPU at {:.1}°C (threshold {:.1}°C)", "🔥".red(), celsius, thresholds.temp);
+        health_issues += 1;
+    }
+    let thermal = readings::thermal_limits(&source);
+    if let Some(limit) = thermal.throttled_to() {
+        println!("  {} CPU thermally throttled to {}%", "🔥".red(), limit);
+        health_issues += 1;
+    }
+    health_issues += print_cpu_issues(sample.cpu_usage, readings::cpu_load(&source), thresholds);
     
     if health_issues == 0 {
         println!("  {} All systems operational", "🟢".green());
     } else {
         println!("  {} {} issue(s) detected", "⚠️".yellow(), health_issues);
     }
+    let alert_input = AlertInput {
+        on_battery: Some(on_battery),
+        battery_percent: battery_pct,
+        battery_minutes_left: battery_runtime::estimate(&source, Some(server), on_battery, battery_pct, config)
+            .map(|runtime| runtime.minutes),
+        cpu_celsius: sample.cpu_celsius,
+        cpu_throttled_to: thermal.throttled_to(),
+        disk_used_percent: disk_percent,
+        caffeinate_running: Some(caffeinate_running),
+    };
+    alerts::check(&server.name, &alert_input, Some(server), config, verbose);
+    metrics_history::record_sample(config, &server.name, sample, verbose);
+
+    // Operational context recorded for this machine
+    if server.notes.is_some() || server.runbook.is_some() {
+        println!("\n{}:", "Notes".bold());
+        if let Some(notes) = &server.notes {
+            println!("  {}", notes);
+        }
+        if let Some(runbook) = &server.runbook {
+            println!("  Runbook: {}", runbook.underline());
+        }
+    }
     
     Ok(())
 }
@@ -261,6 +506,8 @@ pub fn show_help() {
     println!();
     println!("Options:");
     println!("  -d, --detailed    Show detailed status information");
+    println!("      --cached      Print the last saved status in one line");
+    println!("      --max-age <S> Maximum snapshot age for --cached (default 60)");
     println!("  -H, --host <HOST> Target server (remote status check)");
     println!("  -v, --verbose     Verbose output");
     println!("  -h, --help        Show this help message");
@@ -269,4 +516,5 @@ pub fn show_help() {
     println!("  plan10 status                    # Local status check");
     println!("  plan10 status --detailed         # Detailed local status");
     println!("  plan10 status --host myserver    # Remote status check");
+    println!("  plan10 status --cached           # Fast status for shell prompts");
 }
\ No newline at end of file
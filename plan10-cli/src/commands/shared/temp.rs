@@ -1,8 +1,12 @@
 use anyhow::Result;
 use crate::Config;
 use crate::commands::utils::*;
-use crate::ssh::{SshClient, CommandResult};
+use crate::commands::shared::powermetrics::{self, PowermetricsReading};
+use crate::commands::shared::readings::{self, Source, TargetOs, TemperatureReading};
+use crate::ssh::{with_reconnect, SshClient, CommandResult};
 use crate::{ExecutionMode, MonitorCommands};
+use crate::sensors::{all_sensors, read_sensors, SensorKind};
+use crate::utils::formatting::format_temperature_status;
 use colored::*;
 use sysinfo::{System, SystemExt, CpuExt};
 use std::process::Command;
@@ -51,34 +55,120 @@ impl TempMonitor {
         let server = self.config.resolve_server(host)
             .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
 
-        let client = SshClient::connect(server, &self.config).await?;
-        
-        let command = if raw {
-            "~/scripts/temp -r"
+        let mut client = SshClient::connect(server, &self.config).await?;
+
+        // The server's own plan10 reads the sensors without sudo and returns
+        // a structured reading, judged by this server's threshold here; the
+        // deployed script is the fallback for servers without it
+        let linux = Source::Remote(&client).os() == TargetOs::Linux;
+        if !raw {
+            if let Some(report) = readings::remote_reading::<TemperatureReading>(&client, server, "temp") {
+                print_verbose(&format!("Temperature reading from plan10 on {}", report.host), verbose);
+                self.display_reading(&report.reading, self.config.thresholds(Some(server)).temp);
+                return Ok(());
+            }
+            if linux {
+                print_verbose("plan10 not installed on the Linux server, reading hwmon and thermal zones", verbose);
+                let thresholds = self.config.thresholds(Some(server));
+                self.display_reading(&readings::temperature(&Source::Remote(&client), &thresholds), thresholds.temp);
+                return Ok(());
+            }
+            print_verbose("plan10 not installed on the server, using ~/scripts/temp", verbose);
+        }
+        let command = if linux {
+            // lm-sensors when installed, the raw thermal zones otherwise
+            "sensors 2>/dev/null || grep -H . /sys/class/thermal/thermal_zone*/type /sys/class/thermal/thermal_zone*/temp"
+        } else if raw {
+            "if command -v plan10 >/dev/null 2>&1; then plan10 --server-mode monitor temp --raw; else ~/scripts/temp -r; fi"
         } else {
             "~/scripts/temp"
         };
 
-        let result = client.execute_command(command)?;
+        let result = with_reconnect(&mut client, &self.config, |client| client.execute_command(command)).await?;
         
         if result.success {
             println!("{}", result.stdout);
         } else {
-            print_error(&format!("Remote command failed: {}", result.stderr));
+            print_remote_failure("Remote command failed", &result);
         }
 
         Ok(())
     }
 
+    /// A reading from a server's plan10, laid out like the local report.
+    fn display_reading(&self, reading: &TemperatureReading, threshold: f32) {
+        print_header("System Temperature Status");
+
+        match reading.cpu_celsius {
+            Some(celsius) => {
+                let (icon, label) = format_temperature_status(celsius, threshold);
+                println!("CPU Temperature: {:.1}°C {} {} (threshold {:.1}°C)", celsius, icon, label, threshold);
+                if celsius > threshold {
+                    print_warning(&format!("CPU is above the {:.1}°C threshold", threshold));
+                }
+            }
+            None => print_warning("Unable to read the CPU temperature on the server"),
+        }
+        if let Some(celsius) = reading.gpu_celsius {
+            println!("GPU Temperature: {:.1}°C", celsius);
+        }
+
+        let thermal = &reading.thermal;
+        if let Some(limit) = thermal.cpu_speed_limit {
+            println!("CPU Speed Limit: {}%", limit);
+        }
+        if let Some(limit) = thermal.scheduler_limit {
+            println!("CPU Scheduler Limit: {}%", limit);
+        }
+        if let Some(cpus) = thermal.available_cpus {
+            println!("Available CPUs: {}", cpus);
+        }
+        if let Some(limit) = thermal.throttled_to() {
+            print_warning(&format!("Thermally throttled: the CPU is held to {}% of full speed", limit));
+        }
+
+        for gpu in &reading.gpus {
+            if let Some(percent) = gpu.utilization_percent {
+                println!("{} Utilization: {:.0}%", gpu.name.as_deref().unwrap_or("GPU"), percent);
+            }
+        }
+        if !reading.fans.is_empty() {
+            println!("\n{} Fan Status:", "💨".cyan());
+            for fan in &reading.fans {
+                println!("Fan {}: {:.0} rpm", fan.fan, fan.rpm);
+            }
+        }
+    }
+
     async fn display_formatted_temp(&self, verbose: bool) -> Result<()> {
         print_header("System Temperature Status");
 
-        // Try to get detailed temperature using powermetrics (requires sudo)
-        if let Ok(temp_data) = self.get_powermetrics_temp().await {
-            if !temp_data.is_empty() {
-                println!("{}", temp_data);
-            } else {
-                print_warning("Unable to get detailed temperature (requires sudo)");
+        let threshold = self.config.thresholds(None).temp;
+        let sensors = read_sensors();
+        let mut fans = sensors.fans.clone();
+        match sensors.cpu_celsius {
+            Some(celsius) => {
+                let (icon, label) = format_temperature_status(celsius, threshold);
+                println!("CPU Temperature: {:.1}°C {} {} (threshold {:.1}°C)", celsius, icon, label, threshold);
+                if celsius > threshold {
+                    print_warning(&format!("CPU is above the {:.1}°C threshold", threshold));
+                }
+                if let Some(celsius) = sensors.gpu_celsius {
+                    println!("GPU Temperature: {:.1}°C", celsius);
+                }
+            }
+            // No readable sensors; try powermetrics (requires sudo)
+            None => {
+                print_verbose("No temperature sensors readable, falling back to powermetrics", verbose);
+                match self.get_powermetrics_temp().await {
+                    Ok(sample) => {
+                        self.display_powermetrics(&sample, threshold);
+                        if sensors.fans.is_empty() {
+                            fans = sample.fans;
+                        }
+                    }
+                    Err(_) => print_warning("Unable to get detailed temperature (requires sudo)"),
+                }
             }
         }
 
@@ -89,6 +179,20 @@ impl TempMonitor {
             }
         }
 
+        let thermal = readings::thermal_limits(&Source::Local);
+        if let Some(limit) = thermal.cpu_speed_limit {
+            println!("CPU Speed Limit: {}%", limit);
+        }
+        if let Some(limit) = thermal.scheduler_limit {
+            println!("CPU Scheduler Limit: {}%", limit);
+        }
+        if let Some(cpus) = thermal.available_cpus {
+            println!("Available CPUs: {}", cpus);
+        }
+        if let Some(limit) = thermal.throttled_to() {
+            print_warning(&format!("Thermally throttled: the CPU is held to {}% of full speed", limit));
+        }
+
         // Get CPU usage as thermal indicator
         let cpu_usage = self.get_cpu_usage().await?;
         println!("CPU Usage: {:.1}%", cpu_usage);
@@ -102,11 +206,20 @@ impl TempMonitor {
             println!("{} Low CPU load - system cool", "❄️".blue());
         }
 
+        let gpus = readings::gpus(&Source::Local);
+        for gpu in &gpus {
+            let name = gpu.name.as_deref().unwrap_or("GPU");
+            match gpu.utilization_percent {
+                Some(percent) => println!("{} Utilization: {:.0}%", name, percent),
+                None => print_verbose(&format!("{} utilization unavailable", name), verbose),
+            }
+        }
+
         // Show fan status if available
-        if let Ok(fan_info) = self.get_fan_status().await {
-            if !fan_info.is_empty() {
-                println!("\n{} Fan Status:", "💨".cyan());
-                println!("{}", fan_info);
+        if !fans.is_empty() {
+            println!("\n{} Fan Status:", "💨".cyan());
+            for fan in &fans {
+                println!("Fan {}: {:.0} rpm", fan.fan, fan.rpm);
             }
         }
 
@@ -114,33 +227,71 @@ impl TempMonitor {
     }
 
     async fn display_raw_temp(&self) -> Result<()> {
-        if let Ok(output) = self.get_powermetrics_temp().await {
-            println!("{}", output);
-        } else {
-            println!("Unable to get raw temperature data");
+        let sensors: Vec<_> = all_sensors().into_iter()
+            .filter(|sensor| matches!(sensor.kind, SensorKind::Temperature | SensorKind::Fan))
+            .collect();
+        if !sensors.is_empty() {
+            for sensor in &sensors {
+                println!("{}: {:.1} {}", sensor.key, sensor.value, sensor.kind.unit());
+            }
+            return Ok(());
+        }
+
+        match self.get_powermetrics_temp().await {
+            Ok(sample) => {
+                let values = [
+                    ("cpu_die", sample.cpu_celsius, "°C"),
+                    ("gpu_die", sample.gpu_celsius, "°C"),
+                    ("package_power", sample.package_watts, "W"),
+                ];
+                for (key, value, unit) in values {
+                    if let Some(value) = value {
+                        println!("{}: {:.1} {}", key, value, unit);
+                    }
+                }
+                for fan in &sample.fans {
+                    println!("fan{}: {:.0} rpm", fan.fan, fan.rpm);
+                }
+            }
+            Err(_) => println!("Unable to get raw temperature data"),
         }
         Ok(())
     }
 
-    async fn get_powermetrics_temp(&self) -> Result<String> {
-        let output = Command::new("sudo")
-            .args(&["powermetrics", "--samplers", "smc", "-n", "1", "-i", "1000"])
-            .output()?;
+    /// One powermetrics sample as a plist. sudo may prompt for a password
+    /// here, unlike the `sudo -n` of remote readings.
+    async fn get_powermetrics_temp(&self) -> Result<PowermetricsReading> {
+        for samplers in [powermetrics::TEMPERATURE_SAMPLERS, powermetrics::POWER_SAMPLERS] {
+            let samplers = samplers.join(",");
+            let output = Command::new("sudo")
+                .args(["powermetrics", "--samplers", samplers.as_str(), "--format", "plist", "-n", "1", "-i", "1000"])
+                .output()?;
+            if output.status.success() {
+                return powermetrics::parse(&String::from_utf8_lossy(&output.stdout))
+                    .ok_or_else(|| anyhow::anyhow!("Unreadable powermetrics output"));
+            }
+        }
+        Err(anyhow::anyhow!("Failed to run powermetrics"))
+    }
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let temp_lines: Vec<&str> = stdout
-                .lines()
-                .filter(|line| {
-                    line.contains("CPU die temperature") || 
-                    line.contains("GPU die temperature")
-                })
-                .take(2)
-                .collect();
-            
-            Ok(temp_lines.join("\n"))
-        } else {
-            Err(anyhow::anyhow!("Failed to run powermetrics"))
+    /// Die temperatures and package power from powermetrics, for Macs whose
+    /// sensors can't be read directly.
+    fn display_powermetrics(&self, sample: &PowermetricsReading, threshold: f32) {
+        match sample.cpu_celsius {
+            Some(celsius) => {
+                let (icon, label) = format_temperature_status(celsius, threshold);
+                println!("CPU Temperature: {:.1}°C {} {} (threshold {:.1}°C)", celsius, icon, label, threshold);
+                if celsius > threshold {
+                    print_warning(&format!("CPU is above the {:.1}°C threshold", threshold));
+                }
+            }
+            None => print_warning("powermetrics reports no die temperatures on this Mac"),
+        }
+        if let Some(celsius) = sample.gpu_celsius {
+            println!("GPU Temperature: {:.1}°C", celsius);
+        }
+        if let Some(watts) = sample.package_watts {
+            println!("Package Power: {:.2} W", watts);
         }
     }
 
@@ -190,25 +341,6 @@ impl TempMonitor {
         let cpu_usage = system.global_cpu_info().cpu_usage();
         Ok(cpu_usage)
     }
-
-    async fn get_fan_status(&self) -> Result<String> {
-        let output = Command::new("sudo")
-            .args(&["powermetrics", "--samplers", "smc", "-n", "1", "-i", "500"])
-            .output()?;
-
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let fan_lines: Vec<&str> = stdout
-                .lines()
-                .filter(|line| line.to_lowercase().contains("fan"))
-                .take(3)
-                .collect();
-            
-            Ok(fan_lines.join("\n"))
-        } else {
-            Ok(String::new())
-        }
-    }
 }
 
 pub async fn execute_temp_command(
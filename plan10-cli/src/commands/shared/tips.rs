@@ -0,0 +1,216 @@
+use anyhow::Result;
+use crate::Config;
+use crate::commands::utils::*;
+use crate::ssh::SshClient;
+use colored::*;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use super::power_history::{local_power_settings, remote_power_settings};
+
+/// A suggestion for a feature that fits this setup but is not in use yet.
+pub struct Tip {
+    pub title: String,
+    pub hint: String,
+}
+
+impl Tip {
+    fn new(title: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            hint: hint.into(),
+        }
+    }
+}
+
+/// What a machine reports about itself, as far as tips are concerned.
+struct MachineFacts {
+    name: String,
+    arch: Option<String>,
+    has_battery: bool,
+    caffeinate_running: bool,
+    power_settings: BTreeMap<String, String>,
+}
+
+pub async fn execute(host: Option<String>, config: &Config, verbose: bool) -> Result<()> {
+    print_header("Plan 10 Tips");
+
+    let mut tips = config_tips(config);
+
+    let facts = match &host {
+        Some(host) => {
+            let server = config.resolve_server(host)
+                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", host))?;
+            let client = SshClient::connect(server, config).await?;
+            Some(remote_facts(&client, &server.name))
+        }
+        None if cfg!(target_os = "macos") => Some(local_facts(config)),
+        None => None,
+    };
+
+    match facts {
+        Some(Ok(facts)) => {
+            print_verbose(&format!("Inspected {}", facts.name), verbose);
+            tips.extend(machine_tips(&facts, config));
+        }
+        Some(Err(e)) => print_warning(&format!("Could not inspect the machine: {}", e)),
+        None => print_verbose("Only looking at the configuration", verbose),
+    }
+
+    print_tips(&tips);
+    Ok(())
+}
+
+/// Show configuration tips under a short heading; used after setup.
+pub fn print_next_steps(config: &Config) {
+    let tips = config_tips(config);
+    if !tips.is_empty() {
+        println!("\n{}:", "Next steps".bold());
+        print_tips(&tips);
+    }
+}
+
+fn print_tips(tips: &[Tip]) {
+    if tips.is_empty() {
+        print_success("Nothing to suggest, this setup already uses every feature that fits it");
+        return;
+    }
+
+    for tip in tips {
+        println!("{} {}", "💡".yellow(), tip.title.bold());
+        println!("   {}", tip.hint);
+    }
+}
+
+/// Suggestions derived from the configuration alone.
+pub fn config_tips(config: &Config) -> Vec<Tip> {
+    let mut tips = Vec::new();
+    let servers = config.list_servers();
+
+    if servers.is_empty() {
+        tips.push(Tip::new(
+            "No remote servers configured",
+            "Manage another Mac from here with: plan10 client add <name> --host <host> --user <user>",
+        ));
+        return tips;
+    }
+
+    if servers.len() > 1 && config.client.default_server.is_none() {
+        tips.push(Tip::new(
+            format!("You have {} servers but no default server set", servers.len()),
+            "Set client.default_server (plan10 config --edit) so commands work without --host",
+        ));
+    }
+
+    if config.notifications.enabled && config.notifications.webhook_url.is_none() && config.alerts.notifiers.is_empty() {
+        tips.push(Tip::new(
+            "Alerts have nowhere to go",
+            "Set notifications.webhook_url or add an [[alerts.notifiers]] entry, then run `plan10 client check` from cron",
+        ));
+    }
+
+    if servers.iter().all(|s| s.addresses.is_empty()) {
+        tips.push(Tip::new(
+            "No fallback addresses recorded",
+            "Add LAN/VPN addresses with `plan10 client add --address` so servers stay reachable off-site",
+        ));
+    }
+
+    if servers.len() >= 3 && servers.iter().all(|s| s.site.is_none()) {
+        tips.push(Tip::new(
+            "Servers are not grouped by site",
+            "Record a site per server (client add --site) to check one location at a time",
+        ));
+    }
+
+    if servers.len() >= 3 && servers.iter().all(|s| s.notes.is_none() && s.runbook.is_none()) {
+        tips.push(Tip::new(
+            "No server notes or runbooks",
+            "Keep context like \"flaky USB hub\" with the server: plan10 client notes <name> \"...\"",
+        ));
+    }
+
+    if servers.len() >= 10 && config.workspaces.is_empty() {
+        tips.push(Tip::new(
+            format!("{} servers in a single fleet", servers.len()),
+            "Split independent fleets into workspaces: plan10 workspace create <name>",
+        ));
+    }
+
+    if !config.power_profile.reapply_after_update {
+        tips.push(Tip::new(
+            "macOS updates can silently reset pmset settings",
+            "Set power_profile.reapply_after_update = true to restore the power profile automatically",
+        ));
+    }
+
+    tips
+}
+
+fn machine_tips(facts: &MachineFacts, config: &Config) -> Vec<Tip> {
+    let mut tips = Vec::new();
+
+    if !facts.caffeinate_running {
+        tips.push(Tip::new(
+            format!("caffeinate is not running on {}", facts.name),
+            "Start the keep-awake service with: plan10 server start",
+        ));
+    }
+
+    let drift = config.power_profile.drift(&facts.power_settings);
+    if !drift.is_empty() {
+        let keys: Vec<_> = drift.iter().map(|(key, _, _)| key.as_str()).collect();
+        tips.push(Tip::new(
+            format!("{} differs from the power profile ({})", facts.name, keys.join(", ")),
+            "Apply the server power settings with: plan10 server configure",
+        ));
+    }
+
+    let apple_silicon = facts.arch.as_deref() == Some("arm64");
+    if facts.has_battery && apple_silicon && !facts.power_settings.contains_key("haltlevel") {
+        tips.push(Tip::new(
+            "haltlevel is not supported on this chip",
+            "Apple Silicon ignores pmset haltlevel; use the battery charge limit in System Settings → Battery to protect an always-plugged-in battery",
+        ));
+    }
+
+    tips
+}
+
+fn local_facts(config: &Config) -> Result<MachineFacts> {
+    let run = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+    };
+
+    let (_, power_settings) = local_power_settings()?;
+    Ok(MachineFacts {
+        name: config.server.name.clone(),
+        arch: run("uname", &["-m"]),
+        has_battery: run("pmset", &["-g", "batt"]).is_some_and(|o| o.contains("InternalBattery")),
+        caffeinate_running: crate::utils::system::is_caffeinate_running().unwrap_or(false),
+        power_settings,
+    })
+}
+
+fn remote_facts(client: &SshClient, name: &str) -> Result<MachineFacts> {
+    let run = |command: &str| {
+        client.execute_command(command)
+            .ok()
+            .filter(|r| r.success)
+            .map(|r| r.stdout.trim().to_string())
+    };
+
+    let (_, power_settings) = remote_power_settings(client)?;
+    Ok(MachineFacts {
+        name: name.to_string(),
+        arch: run("uname -m"),
+        has_battery: run("pmset -g batt").is_some_and(|o| o.contains("InternalBattery")),
+        caffeinate_running: run("pgrep -x caffeinate").is_some(),
+        power_settings,
+    })
+}
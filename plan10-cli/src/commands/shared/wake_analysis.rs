@@ -0,0 +1,176 @@
+use crate::commands::utils::*;
+use crate::commands::shared::power_events::{self, PowerEvent, PowerEventKind};
+use crate::commands::shared::readings::{self, Source};
+use chrono::{DateTime, Duration, FixedOffset, Local, Utc};
+use colored::*;
+
+/// What a group of wakes or shutdowns most likely comes down to.
+pub struct Finding {
+    pub culprit: &'static str,
+    pub count: usize,
+    pub last: DateTime<FixedOffset>,
+    /// A raw reason from the log, e.g. "EC.ARPT/Maintenance"
+    pub example: String,
+    /// Whether this needs looking into on an always-on server
+    pub unexpected: bool,
+    pub advice: Option<&'static str>,
+}
+
+pub struct Analysis {
+    pub wakes: Vec<Finding>,
+    pub shutdowns: Vec<Finding>,
+    /// Sleeps forced by the battery or temperature
+    pub forced_sleeps: Vec<Finding>,
+}
+
+/// Wake reasons as pmset logs them, matched by substring, first match wins.
+const WAKE_CULPRITS: &[(&[&str], &str, bool, Option<&str>)] = &[
+    (&["LidOpen", "Lid Open"], "Lid opened", false, None),
+    (&["PWRB", "PowerButton", "Power Button"], "Power button", false, None),
+    (&["UserActivity", "HID", "Keyboard", "Trackpad"], "Keyboard, trackpad or user activity", false, None),
+    (&["ARPT", "WLAN", "WiFi", "Wi-Fi"], "Wi-Fi network activity", false,
+        Some("Expected with Wake for network access; disable with: sudo pmset -a womp 0")),
+    (&["GIGE", "ENET", "Ethernet", "LAN"], "Ethernet (Wake on LAN)", false, None),
+    (&["RTC", "Maintenance", "PowerNap", "Power Nap"], "Scheduled maintenance (Power Nap)", false,
+        Some("Disable with: sudo pmset -a powernap 0")),
+    (&["XHC", "USB"], "USB device", true, Some("Check for devices that wake the machine when they reconnect")),
+    (&["ACAttach", "AC Power", "PowerSourceChange"], "Power adapter connected", true,
+        Some("A power cut or a loose charger; check the adapter and cable")),
+    (&["SPMI", "SMC", "Battery"], "Battery or charger controller", true,
+        Some("Often the battery or adapter changing state; see 'plan10 monitor battery --detailed'")),
+    (&["Thermal", "thermal"], "Thermal event", true, Some("Check airflow and 'plan10 monitor temp'")),
+];
+
+/// Sleep reasons that mean the machine was forced down.
+const FORCED_SLEEP_CULPRITS: &[(&[&str], &str, Option<&str>)] = &[
+    (&["Low Power", "Low Battery", "LowBattery"], "Low battery",
+        Some("Running on battery until empty; check the adapter and 'plan10 monitor battery'")),
+    (&["Thermal"], "Thermal emergency", Some("Overheating; check airflow and 'plan10 monitor temp'")),
+];
+
+fn wake_culprit(reason: &str) -> (&'static str, bool, Option<&'static str>) {
+    WAKE_CULPRITS.iter()
+        .find(|(patterns, ..)| patterns.iter().any(|pattern| reason.contains(pattern)))
+        .map(|(_, culprit, unexpected, advice)| (*culprit, *unexpected, *advice))
+        .unwrap_or(("Other", false, None))
+}
+
+/// Groups of shutdown cause codes. Normal (5) and power button (3)
+/// shutdowns are someone's doing; the rest aren't.
+fn shutdown_culprit(cause: i32) -> (&'static str, bool, Option<&'static str>) {
+    match cause {
+        5 => ("Normal shutdown or restart", false, None),
+        3 => ("Forced shutdown (power button held)", true, Some("Someone held the power button, or the machine hung")),
+        0 | -103 | -104 => ("Low battery or power loss", true,
+            Some("The battery ran out or failed; check the adapter and 'plan10 monitor battery --trend'")),
+        -3 | -74 | -86 | -95 | -100 => ("Thermal shutdown", true,
+            Some("Overheating; check airflow, fans and 'plan10 monitor temp'")),
+        -60 | -61 | -62 | -64 => ("Crash (kernel panic or watchdog)", true,
+            Some("Look for panic reports in /Library/Logs/DiagnosticReports")),
+        _ => ("Unknown cause", true, Some("Often a power or memory fault; -128 is common after a power cut")),
+    }
+}
+
+/// Add one occurrence to the matching finding, or start one.
+fn tally(findings: &mut Vec<Finding>, culprit: &'static str, unexpected: bool, advice: Option<&'static str>, event: &PowerEvent) {
+    let example = match event.shutdown_cause {
+        Some(cause) => format!("cause {}", cause),
+        None => event.reason.clone().unwrap_or_default(),
+    };
+    match findings.iter_mut().find(|finding| finding.culprit == culprit) {
+        Some(finding) => {
+            finding.count += 1;
+            if event.timestamp > finding.last {
+                finding.last = event.timestamp;
+                finding.example = example;
+            }
+        }
+        None => findings.push(Finding { culprit, count: 1, last: event.timestamp, example, unexpected, advice }),
+    }
+}
+
+/// Categorise the wakes, forced sleeps and shutdowns of the last `window`,
+/// from `pmset -g log` and the unified log's "Previous shutdown cause".
+pub fn analyse(source: &Source, window: Duration) -> Analysis {
+    let cutoff = Utc::now() - window;
+    let mut events: Vec<PowerEvent> = power_events::parse_pmset_log(&readings::pmset_log(source).unwrap_or_default())
+        .into_iter()
+        .filter(|event| event.timestamp >= cutoff)
+        .collect();
+
+    // The unified log keeps shutdown causes pmset doesn't; skip ones pmset
+    // already logged within a few minutes
+    for (timestamp, cause) in readings::shutdown_causes(source, window) {
+        let logged = events.iter().any(|event| {
+            event.shutdown_cause == Some(cause) && (event.timestamp - timestamp).num_minutes().abs() <= 5
+        });
+        if timestamp >= cutoff && !logged {
+            events.push(power_events::shutdown_event(timestamp, cause));
+        }
+    }
+
+    let mut analysis = Analysis { wakes: Vec::new(), shutdowns: Vec::new(), forced_sleeps: Vec::new() };
+    for event in &events {
+        match event.kind {
+            PowerEventKind::Wake | PowerEventKind::DarkWake => {
+                let (culprit, unexpected, advice) = wake_culprit(event.reason.as_deref().unwrap_or(""));
+                tally(&mut analysis.wakes, culprit, unexpected, advice, event);
+            }
+            PowerEventKind::Sleep => {
+                let reason = event.reason.as_deref().unwrap_or("");
+                if let Some((_, culprit, advice)) = FORCED_SLEEP_CULPRITS.iter()
+                    .find(|(patterns, ..)| patterns.iter().any(|pattern| reason.contains(pattern)))
+                {
+                    tally(&mut analysis.forced_sleeps, culprit, true, *advice, event);
+                }
+            }
+            PowerEventKind::Shutdown => {
+                if let Some(cause) = event.shutdown_cause {
+                    let (culprit, unexpected, advice) = shutdown_culprit(cause);
+                    tally(&mut analysis.shutdowns, culprit, unexpected, advice, event);
+                }
+            }
+            PowerEventKind::AssertionCreated | PowerEventKind::AssertionReleased => {}
+        }
+    }
+    for findings in [&mut analysis.wakes, &mut analysis.shutdowns, &mut analysis.forced_sleeps] {
+        findings.sort_by(|a, b| b.count.cmp(&a.count));
+    }
+    analysis
+}
+
+/// The analysis as printed by `monitor power --sleep` and `client diagnose`.
+pub fn print_analysis(analysis: &Analysis, period: &str) {
+    println!("\n{} ({}):", "Wake Reasons".bold(), period);
+    if analysis.wakes.is_empty() {
+        println!("  No wakes logged");
+    }
+    print_findings(&analysis.wakes);
+
+    println!("\n{} ({}):", "Shutdowns and Forced Sleeps".bold(), period);
+    if analysis.shutdowns.is_empty() && analysis.forced_sleeps.is_empty() {
+        print_success("No shutdowns or forced sleeps logged");
+    }
+    print_findings(&analysis.shutdowns);
+    print_findings(&analysis.forced_sleeps);
+}
+
+fn print_findings(findings: &[Finding]) {
+    for finding in findings {
+        let line = format!(
+            "  {:>3}× {} ({}), last {}",
+            finding.count,
+            finding.culprit,
+            finding.example,
+            finding.last.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        );
+        if finding.unexpected {
+            println!("{}", line.yellow());
+        } else {
+            println!("{}", line);
+        }
+        if let Some(advice) = finding.advice.filter(|_| finding.unexpected || finding.count >= 10) {
+            println!("        {}", advice.dimmed());
+        }
+    }
+}
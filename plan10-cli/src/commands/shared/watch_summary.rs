@@ -0,0 +1,109 @@
+use colored::*;
+use std::time::Instant;
+
+/// Values `monitor watch` keeps statistics for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    CpuTemperature,
+    Battery,
+    CpuUsage,
+    Load,
+}
+
+impl Metric {
+    fn label(&self) -> &'static str {
+        match self {
+            Metric::CpuTemperature => "CPU temperature",
+            Metric::Battery => "Battery",
+            Metric::CpuUsage => "CPU usage",
+            Metric::Load => "Load (1m)",
+        }
+    }
+
+    fn format(&self, value: f32) -> String {
+        match self {
+            Metric::CpuTemperature => format!("{:.1}°C", value),
+            Metric::Battery | Metric::CpuUsage => format!("{:.0}%", value),
+            Metric::Load => format!("{:.2}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stat {
+    min: f32,
+    max: f32,
+    sum: f32,
+    count: u32,
+}
+
+/// Min, max and average of every value one `monitor watch` session saw,
+/// per host, printed once the watch stops.
+pub struct WatchSummary {
+    started: Instant,
+    refreshes: u32,
+    /// In the order hosts and metrics were first seen
+    hosts: Vec<(String, Vec<(Metric, Stat)>)>,
+}
+
+impl WatchSummary {
+    pub fn start() -> Self {
+        Self { started: Instant::now(), refreshes: 0, hosts: Vec::new() }
+    }
+
+    /// Record one refresh of `host`; missing values are skipped.
+    pub fn record(&mut self, host: &str, values: &[(Metric, Option<f32>)]) {
+        self.refreshes += 1;
+        let index = match self.hosts.iter().position(|(name, _)| name == host) {
+            Some(index) => index,
+            None => {
+                self.hosts.push((host.to_string(), Vec::new()));
+                self.hosts.len() - 1
+            }
+        };
+        let stats = &mut self.hosts[index].1;
+
+        for (metric, value) in values {
+            let Some(value) = *value else { continue };
+            match stats.iter_mut().find(|(seen, _)| seen == metric) {
+                Some((_, stat)) => {
+                    stat.min = stat.min.min(value);
+                    stat.max = stat.max.max(value);
+                    stat.sum += value;
+                    stat.count += 1;
+                }
+                None => stats.push((*metric, Stat { min: value, max: value, sum: value, count: 1 })),
+            }
+        }
+    }
+
+    pub fn print(&self) {
+        let minutes = self.started.elapsed().as_secs() / 60;
+        let seconds = self.started.elapsed().as_secs() % 60;
+        println!(
+            "\n{} Watched for {}m {:02}s, {} refresh(es)",
+            "📊".cyan(),
+            minutes,
+            seconds,
+            self.refreshes
+        );
+
+        for (host, stats) in &self.hosts {
+            println!("\n{}:", host.bold());
+            if stats.is_empty() {
+                println!("  {}", "no readings".dimmed());
+                continue;
+            }
+            println!("  {:<16} {:>9} {:>9} {:>9}", "", "min", "avg", "max");
+            for (metric, stat) in stats {
+                println!(
+                    "  {:<16} {:>9} {:>9} {:>9}",
+                    metric.label(),
+                    metric.format(stat.min),
+                    metric.format(stat.sum / stat.count as f32),
+                    metric.format(stat.max)
+                );
+            }
+        }
+    }
+}
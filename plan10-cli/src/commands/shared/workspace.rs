@@ -0,0 +1,111 @@
+use anyhow::Result;
+use crate::{Config, WorkspaceCommands};
+use crate::commands::utils::*;
+use crate::config::Workspace;
+use colored::*;
+
+pub async fn execute(cmd: WorkspaceCommands, config: &Config, verbose: bool) -> Result<()> {
+    match cmd {
+        WorkspaceCommands::List => list_workspaces(config, verbose),
+        WorkspaceCommands::Create { name, description } => create_workspace(name, description, config),
+        WorkspaceCommands::Remove { name } => remove_workspace(name, config),
+        WorkspaceCommands::Use { name } => use_workspace(name, config),
+    }
+}
+
+fn list_workspaces(config: &Config, verbose: bool) -> Result<()> {
+    print_header("Workspaces");
+
+    if config.workspaces.is_empty() {
+        print_info("No workspaces configured");
+        println!("Use 'plan10 workspace create <name>' to create one");
+        return Ok(());
+    }
+
+    let mut workspaces: Vec<_> = config.workspaces.iter().collect();
+    workspaces.sort_by_key(|(name, _)| *name);
+
+    for (name, workspace) in workspaces {
+        let active = config.workspace() == Some(name.as_str());
+        let marker = if active { "▶".green() } else { " ".normal() };
+        let is_default = config.client.default_workspace.as_deref() == Some(name.as_str());
+
+        // The active workspace's servers live in config.servers until saved
+        let server_count = if active { config.servers.len() } else { workspace.servers.len() };
+
+        println!(
+            "{} {} ({} server(s)){}",
+            marker,
+            name.bold(),
+            server_count,
+            if is_default { " [default]".dimmed().to_string() } else { String::new() }
+        );
+
+        if let Some(description) = &workspace.description {
+            println!("    {}", description.dimmed());
+        }
+
+        if verbose && workspace.notifications.is_some() {
+            println!("    Custom notification routing");
+        }
+    }
+
+    Ok(())
+}
+
+fn create_workspace(name: String, description: Option<String>, config: &Config) -> Result<()> {
+    if config.workspaces.contains_key(&name) {
+        print_error(&format!("Workspace '{}' already exists", name));
+        return Ok(());
+    }
+
+    let mut new_config = config.clone();
+    new_config.workspaces.insert(name.clone(), Workspace {
+        description,
+        ..Workspace::default()
+    });
+    new_config.save(None)?;
+
+    print_success(&format!("Workspace '{}' created", name));
+    println!("Add servers to it with: plan10 --workspace {} client add <name> --host <host> --user <user>", name);
+    Ok(())
+}
+
+fn remove_workspace(name: String, config: &Config) -> Result<()> {
+    if !config.workspaces.contains_key(&name) {
+        print_error(&format!("Workspace '{}' not found", name));
+        return Ok(());
+    }
+
+    if config.workspace() == Some(name.as_str()) {
+        anyhow::bail!("Cannot remove the active workspace '{}'; select another workspace first", name);
+    }
+
+    let mut new_config = config.clone();
+    new_config.workspaces.remove(&name);
+    if new_config.client.default_workspace.as_deref() == Some(name.as_str()) {
+        new_config.client.default_workspace = None;
+    }
+    new_config.save(None)?;
+
+    print_success(&format!("Workspace '{}' removed", name));
+    Ok(())
+}
+
+fn use_workspace(name: Option<String>, config: &Config) -> Result<()> {
+    if let Some(name) = &name {
+        if !config.workspaces.contains_key(name) {
+            anyhow::bail!("Workspace '{}' not found", name);
+        }
+    }
+
+    let mut new_config = config.clone();
+    new_config.client.default_workspace = name.clone();
+    new_config.save(None)?;
+
+    match name {
+        Some(name) => print_success(&format!("Default workspace set to '{}'", name)),
+        None => print_success("Default workspace cleared; using the top-level fleet"),
+    }
+    Ok(())
+}
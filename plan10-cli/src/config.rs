@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -10,22 +10,317 @@ pub struct Config {
     pub server: ServerConfig,
     pub servers: HashMap<String, ServerDefinition>,
     pub ssh: SshConfig,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// Rules raising notifications from monitoring readings
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    #[serde(default)]
+    pub power_profile: PowerProfile,
+    /// Connectivity checks and recovery run by `server watchdog`
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    #[serde(default)]
+    pub nms: NmsConfig,
+    /// Set on servers whose settings are pushed from a client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub managed: Option<ManagedStamp>,
+    /// Independent fleets, each with its own servers and alert routing
+    #[serde(default)]
+    pub workspaces: HashMap<String, Workspace>,
+    /// Named port forwards started with `client tunnel start`
+    #[serde(default)]
+    pub tunnels: HashMap<String, TunnelDefinition>,
+    /// Named sets of servers, targeted with `--group`
+    #[serde(default)]
+    pub groups: HashMap<String, ServerGroup>,
+    /// Named `monitor watch` layouts, picked with `--layout <name>`
+    #[serde(default)]
+    pub watch_layouts: HashMap<String, WatchLayoutDefinition>,
+    /// Directory of config fragments adding servers, groups and tunnels,
+    /// relative to this file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<String>,
+    /// Fragments merged from the `include` directory
+    #[serde(skip)]
+    fragments: Vec<(PathBuf, ConfigFragment)>,
+    /// Name of the active workspace, if one was selected
+    #[serde(skip)]
+    workspace: Option<String>,
+    /// Top-level servers and defaults, set aside while a workspace is active
+    #[serde(skip)]
+    root_fleet: Option<Workspace>,
+    /// File this config was loaded from, which `save(None)` writes back to
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// Name of the profile the config was loaded from, if any
+    #[serde(skip)]
+    profile: Option<String>,
+    /// Settings replaced by `PLAN10_*` environment variables for this run
+    #[serde(skip)]
+    env_overrides: Vec<EnvOverride>,
+    /// `PLAN10_*` variables that name no setting, skipped with a warning
+    #[serde(skip)]
+    ignored_env_vars: Vec<String>,
+    /// Settings that had placeholders expanded when loaded
+    #[serde(skip)]
+    templates: Vec<Template>,
+    /// The file's contents as loaded, which `save` merges against so that
+    /// changes made meanwhile by another plan10 aren't lost
+    #[serde(skip)]
+    on_disk: Option<serde_json::Value>,
+}
+
+/// Exclusive lock held while a config file is saved. It is taken on a
+/// `.lock` file beside the config, as saving replaces the config file itself.
+struct ConfigLock {
+    _file: fs::File,
+}
+
+impl ConfigLock {
+    fn acquire(path: &Path) -> Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .context(format!("Failed to open lock file: {}", Path::new(&lock_path).display()))?;
+
+        // Released when the file is closed on drop
+        if unsafe { nix::libc::flock(file.as_raw_fd(), nix::libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error())
+                .context(format!("Failed to lock config file: {}", path.display()));
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+/// Replace `path` with `content` in one step, so nothing ever reads a
+/// half-written file. The new file keeps the old one's permissions.
+fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    fs::write(&tmp_path, content)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(&tmp_path, metadata.permissions())?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+/// Three-way merge of two edits of `base`: a setting changed on only one
+/// side takes that side's value, and tables changed on both are merged key
+/// by key. Where both changed the same setting, `ours` wins. `None` is an
+/// absent entry.
+fn merge_values(
+    base: Option<&serde_json::Value>,
+    ours: Option<serde_json::Value>,
+    theirs: Option<serde_json::Value>,
+) -> Option<serde_json::Value> {
+    if ours.as_ref() == base {
+        return theirs;
+    }
+    if theirs.as_ref() == base || theirs == ours {
+        return ours;
+    }
+    match (base, ours, theirs) {
+        (
+            Some(serde_json::Value::Object(base)),
+            Some(serde_json::Value::Object(mut ours)),
+            Some(serde_json::Value::Object(mut theirs)),
+        ) => {
+            let mut keys: Vec<String> = ours.keys().chain(theirs.keys()).cloned().collect();
+            keys.sort();
+            keys.dedup();
+            let merged = keys.into_iter()
+                .filter_map(|key| {
+                    let value = merge_values(base.get(&key), ours.remove(&key), theirs.remove(&key));
+                    value.map(|value| (key, value))
+                })
+                .collect();
+            Some(serde_json::Value::Object(merged))
+        }
+        (_, ours, _) => ours,
+    }
+}
+
+/// A string setting as written, with placeholders, and as expanded.
+#[derive(Debug, Clone)]
+struct Template {
+    /// Keys from the root of the config down to the setting
+    path: Vec<String>,
+    template: String,
+    expanded: String,
+}
+
+/// Expand the placeholders in the strings under `value`, recording each
+/// one changed. `server` is the name of the server definition being walked.
+fn expand_strings(
+    value: &mut serde_json::Value,
+    path: &mut Vec<String>,
+    server: Option<&str>,
+    templates: &mut Vec<Template>,
+) {
+    match value {
+        serde_json::Value::String(text) => {
+            let expanded = expand_placeholders(text, server);
+            if expanded != *text {
+                templates.push(Template {
+                    path: path.clone(),
+                    template: std::mem::replace(text, expanded.clone()),
+                    expanded,
+                });
+            }
+        }
+        serde_json::Value::Object(map) => {
+            let in_servers = path.last().is_some_and(|last| last == "servers");
+            for (key, child) in map.iter_mut() {
+                path.push(key.clone());
+                expand_strings(child, path, if in_servers { Some(key) } else { server }, templates);
+                path.pop();
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (index, child) in items.iter_mut().enumerate() {
+                path.push(index.to_string());
+                expand_strings(child, path, server, templates);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn expand_placeholders(text: &str, server: Option<&str>) -> String {
+    if !text.contains('{') {
+        return text.to_string();
+    }
+    let mut text = text.to_string();
+    if let Some(home) = dirs::home_dir() {
+        text = text.replace("{home}", &home.to_string_lossy());
+    }
+    if let Ok(hostname) = hostname::get() {
+        text = text.replace("{hostname}", &hostname.to_string_lossy());
+    }
+    if let Some(server) = server {
+        text = text.replace("{server.name}", server);
+    }
+    text
+}
+
+/// Prefix of the environment variables that override config settings.
+const ENV_PREFIX: &str = "PLAN10_";
+
+/// Environment variables read by the command line parser rather than mapped
+/// onto a setting.
+pub const CLI_ENV_VARS: &[&str] = &["PLAN10_CONFIG", "PLAN10_PROFILE", "PLAN10_WORKSPACE"];
+
+/// Variables plan10 sets for the commands it runs, which a `plan10` started
+/// from such a command must not take for overrides.
+pub const RESERVED_ENV_VARS: &[&str] = &["PLAN10_ALERT", "PLAN10_INTERFACE", "PLAN10_PROBE"];
+
+/// Shorthand variables kept from before any setting could be overridden;
+/// `PLAN10_HOST` with `PLAN10_USER` defines a server named `env`.
+pub const LEGACY_ENV_VARS: &[&str] = &[
+    "PLAN10_HOST",
+    "PLAN10_USER",
+    "PLAN10_PORT",
+    "PLAN10_SSH_KEY",
+    "PLAN10_LOG_LEVEL",
+];
+
+/// A setting replaced for this run by an environment variable. Overrides
+/// are never written back to the config file.
+#[derive(Debug, Clone)]
+pub struct EnvOverride {
+    pub var: String,
+    /// Dotted path of the setting, as used by `config get`
+    pub key: String,
+    pub value: serde_json::Value,
+    /// The value from the config file; `None` if the entry didn't exist
+    pub original: Option<serde_json::Value>,
+}
+
+/// Records which version of the centrally managed settings a server's config
+/// was last given by `client config push`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManagedStamp {
+    pub version: u64,
+    /// Checksum of the managed settings as pushed; a mismatch means they were
+    /// changed on the server afterwards
+    pub checksum: String,
+    pub pushed_at: chrono::DateTime<chrono::Utc>,
+    pub pushed_from: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Workspace {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub default_server: Option<String>,
+    #[serde(default)]
+    pub servers: HashMap<String, ServerDefinition>,
+    /// Alert routing for this fleet; the top-level settings apply when unset
+    #[serde(default)]
+    pub notifications: Option<NotificationConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientConfig {
     pub default_server: Option<String>,
+    /// Workspace used when --workspace is not given
+    #[serde(default)]
+    pub default_workspace: Option<String>,
     pub deployment_timeout: u64,
     pub concurrent_operations: usize,
     pub auto_backup: bool,
+    /// Connect to whichever recorded address of a server answers fastest
+    #[serde(default = "default_true")]
+    pub latency_routing: bool,
+    /// Settings for servers added without them
+    #[serde(default)]
+    pub defaults: ServerDefaults,
+}
+
+/// What `client add`, `deploy` and `client import` give a new server when
+/// the command line doesn't say.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerDefaults {
+    /// Login, before falling back to the local user name
+    pub user: Option<String>,
+    /// SSH port instead of 22
+    pub port: Option<u16>,
+    pub ssh_key: Option<String>,
+    /// Tags added to every new server
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub name: String,
     pub monitoring_interval: u64,
+    /// Days of monitoring samples kept in the local history; 0 keeps them all
+    #[serde(default = "default_sample_retention_days")]
+    pub sample_retention_days: u32,
     pub temp_threshold: f32,
     pub battery_warning_level: u8,
+    /// Usage percentage of `/` at or above which the disk is nearly full
+    #[serde(default = "default_disk_warning_level")]
+    pub disk_warning_level: u8,
+    /// CPU usage percentage at or above which the CPU counts as pegged
+    #[serde(default = "default_cpu_warning_level")]
+    pub cpu_warning_level: u8,
+    /// Maximum battery capacity (percent of design) at which the battery is
+    /// due for replacement; `monitor battery --trend` projects when
+    #[serde(default = "default_battery_replacement_capacity")]
+    pub battery_replacement_capacity: u8,
     pub auto_restart_services: bool,
     pub log_level: String,
     pub services: Vec<String>,
@@ -35,12 +330,369 @@ pub struct ServerConfig {
 pub struct ServerDefinition {
     pub name: String,
     pub host: String,
+    /// May be left empty for a member of a group that sets `user`
+    #[serde(default)]
     pub user: String,
     pub port: u16,
     pub ssh_key: Option<String>,
     pub tags: Vec<String>,
     pub enabled: bool,
     pub last_seen: Option<chrono::DateTime<chrono::Utc>>,
+    /// Physical location or site the server belongs to (e.g. "office", "home")
+    #[serde(default)]
+    pub site: Option<String>,
+    /// Fallback addresses for the same machine (LAN IP, VPN IP, public DNS),
+    /// tried in order after `host`
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    /// Which IP family to use when a host resolves to both A and AAAA records
+    #[serde(default)]
+    pub address_family: AddressFamily,
+    /// Free-form operational context ("flaky USB hub, don't unplug")
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Link to the runbook for this machine
+    #[serde(default)]
+    pub runbook: Option<String>,
+    /// Bastion to tunnel through: the name of another configured server or
+    /// `[user@]host[:port]`
+    #[serde(default)]
+    pub jump_host: Option<String>,
+    /// Forward the local ssh-agent to commands run on this server
+    #[serde(default)]
+    pub agent_forwarding: bool,
+    /// Connection settings that differ from `[ssh]` for this server
+    #[serde(default)]
+    pub ssh_options: ServerSshOptions,
+    /// Monitoring thresholds that differ from `[server]` for this server
+    #[serde(default)]
+    pub thresholds: ServerThresholds,
+}
+
+/// Per-server SSH overrides, e.g. for an old Mac that only speaks legacy
+/// algorithms. Algorithm lists are in order of preference and replace
+/// libssh2's defaults; empty lists keep them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSshOptions {
+    /// Key exchange methods, e.g. `diffie-hellman-group14-sha1`
+    pub kex: Vec<String>,
+    /// Host key types, e.g. `ssh-rsa`
+    pub host_key_algorithms: Vec<String>,
+    /// Ciphers, e.g. `aes128-cbc`
+    pub ciphers: Vec<String>,
+    /// MACs, e.g. `hmac-sha1`
+    pub macs: Vec<String>,
+    /// ssh-agent socket to use instead of `$SSH_AUTH_SOCK`
+    pub identity_agent: Option<String>,
+    /// Replaces `ssh.connect_timeout` for this server
+    pub connect_timeout: Option<u64>,
+}
+
+impl ServerSshOptions {
+    pub fn is_default(&self) -> bool {
+        self.kex.is_empty()
+            && self.host_key_algorithms.is_empty()
+            && self.ciphers.is_empty()
+            && self.macs.is_empty()
+            && self.identity_agent.is_none()
+            && self.connect_timeout.is_none()
+    }
+}
+
+impl std::fmt::Display for ServerSshOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        for (name, list) in [
+            ("kex", &self.kex),
+            ("host keys", &self.host_key_algorithms),
+            ("ciphers", &self.ciphers),
+            ("macs", &self.macs),
+        ] {
+            if !list.is_empty() {
+                parts.push(format!("{} {}", name, list.join(",")));
+            }
+        }
+        if let Some(agent) = &self.identity_agent {
+            parts.push(format!("agent {}", agent));
+        }
+        if let Some(timeout) = self.connect_timeout {
+            parts.push(format!("connect timeout {}s", timeout));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// Per-server monitoring thresholds, e.g. for a fanless Mac that runs hotter
+/// than the rest of the fleet. Unset values fall back to `[server]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerThresholds {
+    /// Temperature in °C above which the server counts as hot
+    pub temp_threshold: Option<f32>,
+    /// Battery percentage at or below which a server on battery is low
+    pub battery_warning_level: Option<u8>,
+    /// Usage percentage of `/` at or above which the disk is nearly full
+    pub disk_warning_level: Option<u8>,
+    /// CPU usage percentage at or above which the CPU counts as pegged
+    pub cpu_warning_level: Option<u8>,
+}
+
+impl ServerThresholds {
+    pub fn is_default(&self) -> bool {
+        self.temp_threshold.is_none()
+            && self.battery_warning_level.is_none()
+            && self.disk_warning_level.is_none()
+            && self.cpu_warning_level.is_none()
+    }
+}
+
+impl std::fmt::Display for ServerThresholds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(temp) = self.temp_threshold {
+            parts.push(format!("temperature {:.1}°C", temp));
+        }
+        if let Some(level) = self.battery_warning_level {
+            parts.push(format!("battery {}%", level));
+        }
+        if let Some(level) = self.disk_warning_level {
+            parts.push(format!("disk {}%", level));
+        }
+        if let Some(level) = self.cpu_warning_level {
+            parts.push(format!("CPU {}%", level));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// The thresholds in effect for one server.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Thresholds {
+    pub temp: f32,
+    pub battery_warning_level: u8,
+    pub disk_warning_level: u8,
+    pub cpu_warning_level: u8,
+}
+
+impl Thresholds {
+    pub fn temp_exceeded(&self, celsius: f32) -> bool {
+        celsius > self.temp
+    }
+
+    pub fn battery_low(&self, percent: u8) -> bool {
+        percent <= self.battery_warning_level
+    }
+
+    pub fn disk_full(&self, percent_used: u8) -> bool {
+        percent_used >= self.disk_warning_level
+    }
+
+    pub fn cpu_pegged(&self, percent: f32) -> bool {
+        percent >= f32::from(self.cpu_warning_level)
+    }
+}
+
+/// A named set of servers, with defaults for the settings its members leave
+/// unset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerGroup {
+    /// Server names
+    pub members: Vec<String>,
+    /// Login for members with no `user` of their own
+    pub user: Option<String>,
+    /// Key for members with no `ssh_key` of their own
+    pub ssh_key: Option<String>,
+    /// Tags added to every member's own
+    pub tags: Vec<String>,
+}
+
+/// One file in the `include` directory, e.g. dropped there per server or
+/// per site by a provisioning tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ConfigFragment {
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    servers: HashMap<String, ServerDefinition>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    groups: HashMap<String, ServerGroup>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    tunnels: HashMap<String, TunnelDefinition>,
+}
+
+/// A port forward through one of the servers, like `ssh -L` or `ssh -R`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunnelDefinition {
+    /// Server the tunnel goes through
+    pub server: String,
+    pub direction: TunnelDirection,
+    /// Port listened on: locally for `local`, on the server for `remote`
+    pub listen_port: u16,
+    /// Where connections are forwarded to, as seen from the other end
+    pub target_host: String,
+    pub target_port: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TunnelDirection {
+    /// Listen on this machine and connect from the server
+    Local,
+    /// Listen on the server and connect from this machine
+    Remote,
+}
+
+impl std::fmt::Display for TunnelDefinition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.direction {
+            TunnelDirection::Local => write!(
+                f,
+                "localhost:{} → {} → {}:{}",
+                self.listen_port, self.server, self.target_host, self.target_port
+            ),
+            TunnelDirection::Remote => write!(
+                f,
+                "{}:{} → {}:{}",
+                self.server, self.listen_port, self.target_host, self.target_port
+            ),
+        }
+    }
+}
+
+/// Which panels `monitor watch` shows, in what order and how much of each.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchLayoutDefinition {
+    /// Panels in the order shown
+    pub panels: Vec<WatchPanel>,
+    /// Only the key reading of each panel, without sparklines
+    pub compact: bool,
+    /// Pane arrangement when watching several hosts
+    pub arrangement: WatchArrangement,
+}
+
+impl Default for WatchLayoutDefinition {
+    fn default() -> Self {
+        Self {
+            panels: WatchPanel::ALL.to_vec(),
+            compact: false,
+            arrangement: WatchArrangement::Grid,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchPanel {
+    Temp,
+    Battery,
+    Power,
+    System,
+}
+
+impl WatchPanel {
+    pub const ALL: [WatchPanel; 4] = [WatchPanel::Temp, WatchPanel::Battery, WatchPanel::Power, WatchPanel::System];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchArrangement {
+    /// Roughly square grid of panes
+    #[default]
+    Grid,
+    /// One pane per row, stacked vertically
+    Rows,
+    /// All panes side by side
+    Columns,
+    /// One row per host: power source, battery, temperature and load
+    Table,
+}
+
+impl WatchArrangement {
+    const NAMES: [(&'static str, WatchArrangement); 4] = [
+        ("grid", WatchArrangement::Grid),
+        ("rows", WatchArrangement::Rows),
+        ("columns", WatchArrangement::Columns),
+        ("table", WatchArrangement::Table),
+    ];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum AddressFamily {
+    /// Use addresses in the order the resolver returns them
+    #[default]
+    Auto,
+    /// Prefer IPv4, falling back to IPv6
+    Ipv4,
+    /// Prefer IPv6, falling back to IPv4
+    Ipv6,
+    /// Never use IPv6
+    Ipv4Only,
+    /// Never use IPv4
+    Ipv6Only,
+}
+
+impl std::fmt::Display for AddressFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AddressFamily::Auto => "auto",
+            AddressFamily::Ipv4 => "ipv4",
+            AddressFamily::Ipv6 => "ipv6",
+            AddressFamily::Ipv4Only => "ipv4-only",
+            AddressFamily::Ipv6Only => "ipv6-only",
+        };
+        f.write_str(name)
+    }
+}
+
+impl ServerDefinition {
+    /// The primary host followed by any alternative addresses, without duplicates.
+    pub fn candidate_hosts(&self) -> Vec<String> {
+        let mut hosts = vec![self.host.clone()];
+        for address in &self.addresses {
+            if !hosts.contains(address) {
+                hosts.push(address.clone());
+            }
+        }
+        hosts
+    }
+
+    pub fn in_site(&self, site: Option<&str>) -> bool {
+        match site {
+            Some(site) => self.site.as_deref() == Some(site),
+            None => true,
+        }
+    }
+
+    pub fn has_tag(&self, tag: Option<&str>) -> bool {
+        match tag {
+            Some(tag) => self.tags.iter().any(|t| t == tag),
+            None => true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_disk_warning_level() -> u8 {
+    90
+}
+
+fn default_cpu_warning_level() -> u8 {
+    90
+}
+
+/// Where macOS starts recommending service.
+fn default_battery_replacement_capacity() -> u8 {
+    80
+}
+
+fn default_sample_retention_days() -> u32 {
+    90
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,8 +701,311 @@ pub struct SshConfig {
     pub command_timeout: u64,
     pub key_path: Option<String>,
     pub known_hosts_file: Option<String>,
+    /// Negotiate zlib compression; worth it on slow links, costs CPU on fast ones
     pub compression: bool,
     pub keep_alive: bool,
+    /// Seconds between SSH keepalive messages, also used as the idle time
+    /// before TCP keepalive probes start
+    #[serde(default = "default_keep_alive_interval")]
+    pub keep_alive_interval: u64,
+    /// Per-address connect timeout used when a server has fallback addresses
+    #[serde(default = "default_failover_timeout")]
+    pub failover_timeout: u64,
+    /// Directories prepended to PATH for every remote command
+    #[serde(default = "default_remote_path")]
+    pub remote_path: Vec<String>,
+    /// Shell file sourced before every remote command, if it exists
+    #[serde(default = "default_remote_env_file")]
+    pub remote_env_file: Option<String>,
+    /// Apply HostName, User, Port, IdentityFile, ProxyJump and ForwardAgent
+    /// from ~/.ssh/config when a server's host is an alias defined there
+    #[serde(default = "default_true")]
+    pub use_ssh_config: bool,
+    /// Fall back to password / keyboard-interactive authentication (saved in
+    /// the keychain or prompted for) when key and agent auth fail
+    #[serde(default = "default_true")]
+    pub password_auth: bool,
+    /// Extra connection attempts after a failed one, e.g. on flaky Wi-Fi
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Seconds before the first retry; doubled for each further attempt
+    #[serde(default = "default_retry_delay")]
+    pub retry_delay: u64,
+    /// SOCKS5 proxy to connect through: `[socks5://][user:password@]host[:port]`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Command whose stdin/stdout carry the connection instead, as in
+    /// OpenSSH; `%h` and `%p` are replaced with the server's host and port
+    #[serde(default)]
+    pub proxy_command: Option<String>,
+    /// For links that drop mid-command (e.g. a MacBook on battery in
+    /// clamshell mode): reconnect and rerun read-only commands when the
+    /// connection dies, and prefer mosh for `client shell`
+    #[serde(default)]
+    pub resilient: bool,
+}
+
+fn default_keep_alive_interval() -> u64 {
+    15
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+fn default_retry_delay() -> u64 {
+    1
+}
+
+fn default_failover_timeout() -> u64 {
+    5
+}
+
+fn default_remote_path() -> Vec<String> {
+    vec![
+        "/opt/homebrew/bin".to_string(),
+        "/opt/homebrew/sbin".to_string(),
+        "/usr/local/bin".to_string(),
+    ]
+}
+
+fn default_remote_env_file() -> Option<String> {
+    Some("~/.plan10/env".to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    pub webhook_url: Option<String>,
+    /// Minimum seconds between two notifications for the same event kind
+    pub cooldown: u64,
+    /// Number of affected hosts at which an event is reported as fleet-wide
+    pub fleet_threshold: usize,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            webhook_url: None,
+            cooldown: 900,
+            fleet_threshold: 2,
+        }
+    }
+}
+
+/// Which readings raise alerts. Unset thresholds fall back to the server's
+/// `temp_threshold`, `battery_warning_level` and `disk_warning_level`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    pub enabled: bool,
+    /// Battery percentage at or below which a server on battery alerts
+    pub battery_percent: Option<u8>,
+    /// CPU temperature in °C above which a server alerts
+    pub temperature: Option<f32>,
+    /// Usage percentage of `/` at or above which a server alerts
+    pub disk_percent: Option<u8>,
+    /// Alert when caffeinate is not running
+    pub caffeinate_down: bool,
+    /// Alert while macOS holds the CPU below full speed because it runs hot
+    pub throttling: bool,
+    /// Alert while a server runs on battery, i.e. has lost AC power
+    pub ac_loss: bool,
+    /// Alert when a server on battery is estimated to reach its halt level
+    /// within this many minutes; 0 turns it off
+    pub runtime_minutes: u32,
+    /// Where alerts are sent, in addition to `notifications.webhook_url`
+    pub notifiers: Vec<NotifierConfig>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            battery_percent: None,
+            temperature: None,
+            disk_percent: None,
+            caffeinate_down: true,
+            throttling: true,
+            ac_loss: true,
+            runtime_minutes: 60,
+            notifiers: Vec::new(),
+        }
+    }
+}
+
+/// One `[[alerts.notifiers]]` entry: a webhook `url` or a shell `command`,
+/// depending on `type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifierConfig {
+    #[serde(rename = "type")]
+    pub kind: NotifierKind,
+    /// Where a webhook POSTs the notification as JSON; may be a `keychain:` reference
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Shell command run with the notification as JSON on stdin
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotifierKind {
+    Webhook,
+    Command,
+}
+
+impl NotifierConfig {
+    /// The setting this kind of notifier needs, and its value if set.
+    pub fn target(&self) -> (&'static str, Option<&str>) {
+        match self.kind {
+            NotifierKind::Webhook => ("url", self.url.as_deref()),
+            NotifierKind::Command => ("command", self.command.as_deref()),
+        }
+    }
+}
+
+/// How `server watchdog` decides connectivity is lost and what it tries,
+/// in order, to get it back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    /// Host to ping; the default gateway when unset
+    pub probe: Option<String>,
+    /// Seconds between probes
+    pub interval: u64,
+    /// Consecutive failed probes before connectivity counts as lost
+    pub failures: u32,
+    /// Recovery steps, one per further failed probe, until a probe succeeds
+    pub actions: Vec<WatchdogAction>,
+    /// Interface to bounce; the default route's when unset
+    pub interface: Option<String>,
+    /// Shell command for the `script` action
+    pub script: Option<String>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            probe: None,
+            interval: 30,
+            failures: 3,
+            actions: vec![WatchdogAction::Caffeinate, WatchdogAction::BounceInterface],
+            interface: None,
+            script: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchdogAction {
+    /// Start caffeinate if it isn't running, so the machine can't sleep
+    Caffeinate,
+    /// Take the interface down and up again (needs passwordless sudo)
+    BounceInterface,
+    /// Run `watchdog.script`
+    Script,
+}
+
+/// Passive-check endpoints of legacy monitoring systems that `client check`
+/// reports every server's state to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NmsConfig {
+    #[serde(default)]
+    pub zabbix: Option<ZabbixConfig>,
+    #[serde(default)]
+    pub nagios: Option<NagiosConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZabbixConfig {
+    /// Zabbix server or proxy receiving trapper items
+    pub server: String,
+    #[serde(default = "default_zabbix_port")]
+    pub port: u16,
+    /// Path to zabbix_sender
+    #[serde(default = "default_zabbix_sender")]
+    pub sender: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NagiosConfig {
+    /// Host running the NSCA daemon
+    pub host: String,
+    #[serde(default = "default_nsca_port")]
+    pub port: u16,
+    /// Passive service description the results are submitted under
+    #[serde(default = "default_nagios_service")]
+    pub service: String,
+    /// Path to send_nsca
+    #[serde(default = "default_send_nsca")]
+    pub send_nsca: String,
+    /// send_nsca configuration file (encryption settings)
+    #[serde(default)]
+    pub config_file: Option<String>,
+}
+
+fn default_zabbix_port() -> u16 {
+    10051
+}
+
+fn default_zabbix_sender() -> String {
+    "zabbix_sender".to_string()
+}
+
+fn default_nsca_port() -> u16 {
+    5667
+}
+
+fn default_nagios_service() -> String {
+    "Plan 10".to_string()
+}
+
+fn default_send_nsca() -> String {
+    "send_nsca".to_string()
+}
+
+/// The pmset settings every server is expected to keep. macOS updates
+/// routinely reset them, so `client check` re-verifies the profile whenever a
+/// server's OS version changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerProfile {
+    pub settings: BTreeMap<String, String>,
+    /// Reapply drifted settings after an OS update instead of only alerting
+    pub reapply_after_update: bool,
+}
+
+impl Default for PowerProfile {
+    fn default() -> Self {
+        // Mirrors the settings applied by server_setup.sh
+        let settings = [
+            ("sleep", "0"),
+            ("disksleep", "0"),
+            ("standby", "0"),
+            ("hibernatemode", "0"),
+            ("powernap", "0"),
+            ("tcpkeepalive", "1"),
+            ("womp", "1"),
+        ];
+
+        Self {
+            settings: settings.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            reapply_after_update: false,
+        }
+    }
+}
+
+impl PowerProfile {
+    /// Settings whose current value differs from the profile, as
+    /// (setting, expected, actual).
+    pub fn drift(&self, current: &BTreeMap<String, String>) -> Vec<(String, String, Option<String>)> {
+        self.settings.iter()
+            .filter(|(key, expected)| current.get(*key) != Some(*expected))
+            .map(|(key, expected)| (key.clone(), expected.clone(), current.get(key).cloned()))
+            .collect()
+    }
 }
 
 impl Default for Config {
@@ -58,9 +1013,12 @@ impl Default for Config {
         Self {
             client: ClientConfig {
                 default_server: None,
+                default_workspace: None,
                 deployment_timeout: 300,
                 concurrent_operations: 4,
                 auto_backup: true,
+                latency_routing: true,
+                defaults: ServerDefaults::default(),
             },
             server: ServerConfig {
                 name: hostname::get()
@@ -68,8 +1026,12 @@ impl Default for Config {
                     .to_string_lossy()
                     .to_string(),
                 monitoring_interval: 30,
+                sample_retention_days: default_sample_retention_days(),
                 temp_threshold: 80.0,
                 battery_warning_level: 20,
+                disk_warning_level: default_disk_warning_level(),
+                cpu_warning_level: default_cpu_warning_level(),
+                battery_replacement_capacity: default_battery_replacement_capacity(),
                 auto_restart_services: true,
                 log_level: "info".to_string(),
                 services: vec![
@@ -85,37 +1047,116 @@ impl Default for Config {
                 known_hosts_file: None,
                 compression: true,
                 keep_alive: true,
+                keep_alive_interval: default_keep_alive_interval(),
+                failover_timeout: default_failover_timeout(),
+                remote_path: default_remote_path(),
+                remote_env_file: default_remote_env_file(),
+                use_ssh_config: true,
+                password_auth: true,
+                retries: default_retries(),
+                retry_delay: default_retry_delay(),
+                proxy: None,
+                proxy_command: None,
+                resilient: false,
             },
+            notifications: NotificationConfig::default(),
+            power_profile: PowerProfile::default(),
+            alerts: AlertConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            nms: NmsConfig::default(),
+            managed: None,
+            workspaces: HashMap::new(),
+            tunnels: HashMap::new(),
+            groups: HashMap::new(),
+            watch_layouts: HashMap::new(),
+            include: None,
+            fragments: Vec::new(),
+            env_overrides: Vec::new(),
+            ignored_env_vars: Vec::new(),
+            templates: Vec::new(),
+            on_disk: None,
+            workspace: None,
+            root_fleet: None,
+            path: None,
+            profile: None,
         }
     }
 }
 
+const CONFIG_EXTENSIONS: &[&str] = &["toml", "yaml", "yml", "json"];
+
+/// File formats the config can be kept in, chosen by file extension. TOML
+/// is the default; YAML and JSON suit configs generated by other tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        Ok(match self {
+            ConfigFormat::Toml => toml::from_str(content)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+            ConfigFormat::Json => serde_json::from_str(content)?,
+        })
+    }
+
+    fn serialize<T: Serialize>(self, value: &T) -> Result<String> {
+        Ok(match self {
+            ConfigFormat::Toml => toml::to_string_pretty(value)?,
+            ConfigFormat::Yaml => serde_yaml::to_string(value)?,
+            ConfigFormat::Json => serde_json::to_string_pretty(value)? + "\n",
+        })
+    }
+}
+
 impl Config {
-    pub fn load(config_path: Option<&str>) -> Result<Self> {
-        let path = config_path
-            .map(PathBuf::from)
-            .or_else(|| Self::default_config_path())
-            .context("Could not determine config file path")?;
+    /// Load the config from `config_path`, else from the named profile's
+    /// file, else from the default location. A missing file is created with
+    /// the defaults, so a new profile starts out empty.
+    pub fn load(config_path: Option<&str>, profile: Option<&str>) -> Result<Self> {
+        let path = match (config_path, profile) {
+            (Some(path), _) => Some(PathBuf::from(path)),
+            (None, Some(profile)) => Self::profile_config_path(profile)?,
+            (None, None) => Self::default_config_path(),
+        }
+        .context("Could not determine config file path")?;
 
-        if path.exists() {
+        let mut config = if path.exists() {
             let content = fs::read_to_string(&path)
                 .context(format!("Failed to read config file: {}", path.display()))?;
             
-            let config: Config = toml::from_str(&content)
-                .context("Failed to parse config file")?;
-            
-            Ok(config)
+            Self::parse_file(&path, &content)
+                .context(format!("Failed to parse config file: {}", path.display()))?
         } else {
             // Create default config
             let config = Config::default();
             config.save(Some(&path))?;
-            Ok(config)
-        }
+            config
+        };
+
+        config.on_disk = Some(serde_json::to_value(&config)?);
+        config.expand_templates()?;
+        config.path = Some(path);
+        config.profile = profile.filter(|_| config_path.is_none()).map(str::to_string);
+        config.merge_env_vars()?;
+        Ok(config)
     }
 
     pub fn save(&self, config_path: Option<&Path>) -> Result<()> {
         let path = config_path
             .map(PathBuf::from)
+            .or_else(|| self.path.clone())
             .or_else(|| Self::default_config_path())
             .context("Could not determine config file path")?;
 
@@ -124,19 +1165,388 @@ impl Config {
                 .context("Failed to create config directory")?;
         }
 
-        let content = toml::to_string_pretty(self)
+        // Write through a symlinked config rather than replacing the link
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        let _lock = ConfigLock::acquire(&path)?;
+
+        let mut config = self.for_disk();
+        config.revert_env_overrides()?;
+        config.revert_templates()?;
+        let mut fragments = self.fragments.clone();
+
+        // Another plan10 may have saved since this config was loaded; keep
+        // its changes where they don't clash with ours
+        let loaded_from_here = self.path.as_deref()
+            .is_some_and(|loaded| fs::canonicalize(loaded).is_ok_and(|loaded| loaded == path));
+        if let (true, Some(base)) = (loaded_from_here && path.exists(), &self.on_disk) {
+            let content = fs::read_to_string(&path)
+                .context(format!("Failed to read config file: {}", path.display()))?;
+            let current = Self::parse_file(&path, &content)
+                .context(format!("Failed to parse config file: {}", path.display()))?;
+            let theirs = serde_json::to_value(&current)?;
+            if theirs != *base {
+                let merged = merge_values(Some(base), Some(serde_json::to_value(&config)?), Some(theirs));
+                config = serde_json::from_value(merged.unwrap_or_default())
+                    .context("Failed to merge with changes saved by another plan10")?;
+                fragments = current.fragments;
+            }
+        }
+
+        for (file, loaded) in &fragments {
+            let fragment = config.take_fragment(loaded);
+            if serde_json::to_value(&fragment)? == serde_json::to_value(loaded)? {
+                continue;
+            }
+            let content = ConfigFormat::of(file).serialize(&fragment)
+                .context("Failed to serialize config fragment")?;
+            write_atomically(file, &content)
+                .context(format!("Failed to write config fragment: {}", file.display()))?;
+        }
+
+        let content = ConfigFormat::of(&path).serialize(&config)
             .context("Failed to serialize config")?;
 
-        fs::write(&path, content)
+        write_atomically(&path, &content)
             .context(format!("Failed to write config file: {}", path.display()))?;
 
         Ok(())
     }
 
+    /// Merge every config file in the `include` directory, in file name
+    /// order. A name defined twice is an error rather than silently
+    /// shadowed.
+    fn load_includes(&mut self, config_path: &Path) -> Result<()> {
+        let Some(dir) = self.include_dir(config_path) else {
+            return Ok(());
+        };
+        if !dir.is_dir() {
+            return Ok(());
+        }
+
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+            .context(format!("Failed to read include directory: {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| {
+                file.is_file() && file.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| CONFIG_EXTENSIONS.contains(&ext))
+            })
+            .collect();
+        files.sort();
+
+        for file in files {
+            let content = fs::read_to_string(&file)
+                .context(format!("Failed to read config fragment: {}", file.display()))?;
+            let fragment: ConfigFragment = ConfigFormat::of(&file).parse(&content)
+                .context(format!("Failed to parse config fragment: {}", file.display()))?;
+
+            let duplicate = |kind: &str, name: &String| {
+                anyhow::anyhow!("{} '{}' in {} is already defined", kind, name, file.display())
+            };
+            if let Some(name) = fragment.servers.keys().find(|name| self.servers.contains_key(*name)) {
+                return Err(duplicate("Server", name));
+            }
+            if let Some(name) = fragment.groups.keys().find(|name| self.groups.contains_key(*name)) {
+                return Err(duplicate("Group", name));
+            }
+            if let Some(name) = fragment.tunnels.keys().find(|name| self.tunnels.contains_key(*name)) {
+                return Err(duplicate("Tunnel", name));
+            }
+
+            self.servers.extend(fragment.servers.clone());
+            self.groups.extend(fragment.groups.clone());
+            self.tunnels.extend(fragment.tunnels.clone());
+            self.fragments.push((file, fragment));
+        }
+        Ok(())
+    }
+
+    /// The `include` directory, relative paths resolved against the
+    /// directory of the config file.
+    fn include_dir(&self, config_path: &Path) -> Option<PathBuf> {
+        let dir = PathBuf::from(shellexpand::tilde(self.include.as_ref()?).to_string());
+        Some(match config_path.parent() {
+            Some(parent) if dir.is_relative() => parent.join(dir),
+            _ => dir,
+        })
+    }
+
+    /// Move the entries `loaded` from a fragment out of this config, as they
+    /// are now. Entries removed since loading stay removed.
+    fn take_fragment(&mut self, loaded: &ConfigFragment) -> ConfigFragment {
+        ConfigFragment {
+            servers: loaded.servers.keys()
+                .filter_map(|name| self.servers.remove_entry(name))
+                .collect(),
+            groups: loaded.groups.keys()
+                .filter_map(|name| self.groups.remove_entry(name))
+                .collect(),
+            tunnels: loaded.tunnels.keys()
+                .filter_map(|name| self.tunnels.remove_entry(name))
+                .collect(),
+        }
+    }
+
+    /// The fragment files merged from the `include` directory.
+    pub fn included_files(&self) -> Vec<&Path> {
+        self.fragments.iter().map(|(file, _)| file.as_path()).collect()
+    }
+
     pub fn default_config_path() -> Option<PathBuf> {
         dirs::config_dir().map(|dir| dir.join("plan10").join("config.toml"))
     }
 
+    /// Where the named profile's config lives: `profiles/<name>.toml` next
+    /// to the default config, or an existing `.yaml`, `.yml` or `.json` file.
+    pub fn profile_config_path(profile: &str) -> Result<Option<PathBuf>> {
+        if profile.is_empty() || profile.contains(['/', '\\']) || profile.starts_with('.') {
+            anyhow::bail!("Invalid profile name '{}'", profile);
+        }
+        Ok(Self::profiles_dir().map(|dir| {
+            CONFIG_EXTENSIONS.iter()
+                .map(|ext| dir.join(format!("{}.{}", profile, ext)))
+                .find(|path| path.exists())
+                .unwrap_or_else(|| dir.join(format!("{}.toml", profile)))
+        }))
+    }
+
+    fn profiles_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("plan10").join("profiles"))
+    }
+
+    /// Names of the profiles in the config directory, sorted.
+    pub fn profiles() -> Vec<String> {
+        let Some(entries) = Self::profiles_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+            return Vec::new();
+        };
+        let mut profiles: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| CONFIG_EXTENSIONS.iter().any(|e| ext == *e)))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .collect();
+        profiles.sort();
+        profiles.dedup();
+        profiles
+    }
+
+    /// Parse config file contents in the format `path`'s extension calls for,
+    /// merging the fragments in its `include` directory.
+    pub fn parse_file(path: &Path, content: &str) -> Result<Self> {
+        let mut config: Config = ConfigFormat::of(path).parse(content)?;
+        config.load_includes(path)?;
+        Ok(config)
+    }
+
+    /// The file this config is read from and saved to.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.path.clone().or_else(Self::default_config_path)
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Make the named workspace the active fleet: its servers, default server
+    /// and notification settings replace the top-level ones until saved.
+    pub fn use_workspace(&mut self, name: &str) -> Result<()> {
+        if self.workspace.as_deref() == Some(name) {
+            return Ok(());
+        }
+        if self.workspace.is_some() {
+            anyhow::bail!("A workspace is already active");
+        }
+
+        let workspace = self.workspaces.get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Workspace '{}' not found", name))?;
+
+        self.root_fleet = Some(Workspace {
+            description: None,
+            default_server: self.client.default_server.take(),
+            servers: std::mem::take(&mut self.servers),
+            notifications: Some(self.notifications.clone()),
+        });
+
+        self.servers = workspace.servers;
+        self.client.default_server = workspace.default_server;
+        if let Some(notifications) = workspace.notifications {
+            self.notifications = notifications;
+        }
+        self.workspace = Some(name.to_string());
+
+        Ok(())
+    }
+
+    pub fn workspace(&self) -> Option<&str> {
+        self.workspace.as_deref()
+    }
+
+    /// The setting at a dotted path such as `ssh.key_path` or
+    /// `servers.mini.port`; array elements are addressed by index.
+    pub fn get_value(&self, key: &str) -> Result<serde_json::Value> {
+        let value = serde_json::to_value(self)?;
+        let mut current = &value;
+        for part in key.split('.') {
+            current = match current {
+                serde_json::Value::Object(map) => map.get(part),
+                serde_json::Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            }
+            .with_context(|| format!("Unknown config key '{}'", key))?;
+        }
+        Ok(current.clone())
+    }
+
+    /// Change the setting at a dotted path. `raw` is taken as a string where
+    /// the setting is one, and parsed as JSON (`85`, `true`, `["a","b"]`,
+    /// `null`) otherwise. The result must still be a valid config.
+    pub fn set_value(&mut self, key: &str, raw: &str) -> Result<()> {
+        let new_value = self.parse_value(key, raw);
+        self.replace_value(key, Some(new_value))
+            .with_context(|| format!("Invalid value for {}: {}", key, raw))
+    }
+
+    /// Clear an optional setting at a dotted path.
+    pub fn unset_value(&mut self, key: &str) -> Result<()> {
+        self.replace_value(key, Some(serde_json::Value::Null))
+            .with_context(|| format!("{} cannot be unset", key))
+    }
+
+    fn parse_value(&self, key: &str, raw: &str) -> serde_json::Value {
+        match self.get_value(key).ok() {
+            Some(serde_json::Value::String(_)) => serde_json::Value::String(raw.to_string()),
+            _ => serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string())),
+        }
+    }
+
+    /// Put `new_value` at a dotted path, or remove the entry there when it
+    /// is `None`.
+    fn replace_value(&mut self, key: &str, new_value: Option<serde_json::Value>) -> Result<()> {
+        let mut value = serde_json::to_value(&*self)?;
+        let (parents, last) = match key.rsplit_once('.') {
+            Some((parents, last)) => (Some(parents), last),
+            None => (None, key),
+        };
+        let mut parent = &mut value;
+        for part in parents.into_iter().flat_map(|parents| parents.split('.')) {
+            parent = match parent {
+                serde_json::Value::Object(map) => map.get_mut(part),
+                serde_json::Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+                _ => None,
+            }
+            .with_context(|| format!("Unknown config key '{}'", key))?;
+        }
+        let removing = new_value.is_none();
+        match (parent, new_value) {
+            (serde_json::Value::Object(map), Some(new_value)) => {
+                map.insert(last.to_string(), new_value);
+            }
+            (serde_json::Value::Object(map), None) => {
+                map.remove(last);
+            }
+            (serde_json::Value::Array(items), Some(new_value)) => {
+                let slot = last.parse::<usize>().ok().and_then(|i| items.get_mut(i))
+                    .with_context(|| format!("Unknown config key '{}'", key))?;
+                *slot = new_value;
+            }
+            _ => anyhow::bail!("Unknown config key '{}'", key),
+        }
+
+        let updated: Config = serde_json::from_value(value)?;
+        // Unknown keys are dropped when deserializing; catch typos
+        if !removing && updated.get_value(key).is_err() {
+            anyhow::bail!("Unknown config key '{}'", key);
+        }
+        updated.validate()?;
+
+        self.replace_with(updated);
+        Ok(())
+    }
+
+    /// Take the settings of `updated`, keeping the state that isn't part of
+    /// the file.
+    fn replace_with(&mut self, mut updated: Config) {
+        updated.fragments = std::mem::take(&mut self.fragments);
+        updated.workspace = self.workspace.take();
+        updated.root_fleet = self.root_fleet.take();
+        updated.path = self.path.take();
+        updated.profile = self.profile.take();
+        updated.env_overrides = std::mem::take(&mut self.env_overrides);
+        updated.ignored_env_vars = std::mem::take(&mut self.ignored_env_vars);
+        updated.templates = std::mem::take(&mut self.templates);
+        updated.on_disk = self.on_disk.take();
+        *self = updated;
+    }
+
+    /// Expand the placeholders in every string setting: `{home}` and
+    /// `{hostname}` for this machine, and `{server.name}` within a server's
+    /// definition. Unknown placeholders are left alone.
+    fn expand_templates(&mut self) -> Result<()> {
+        let mut value = serde_json::to_value(&*self)?;
+        let mut templates = Vec::new();
+        expand_strings(&mut value, &mut Vec::new(), None, &mut templates);
+        if templates.is_empty() {
+            return Ok(());
+        }
+
+        let updated: Config = serde_json::from_value(value)
+            .context("Invalid value after expanding placeholders")?;
+        self.replace_with(updated);
+        self.templates = templates;
+        Ok(())
+    }
+
+    /// Put the placeholders back into the settings expanded from them,
+    /// unless they have been changed since.
+    fn revert_templates(&mut self) -> Result<()> {
+        let templates = std::mem::take(&mut self.templates);
+        if templates.is_empty() {
+            return Ok(());
+        }
+
+        let mut value = serde_json::to_value(&*self)?;
+        for template in templates {
+            let current = template.path.iter().try_fold(&mut value, |current, part| match current {
+                serde_json::Value::Object(map) => map.get_mut(part),
+                serde_json::Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+                _ => None,
+            });
+            if let Some(current) = current.filter(|current| current.as_str() == Some(&template.expanded)) {
+                *current = template.template.into();
+            }
+        }
+        let updated: Config = serde_json::from_value(value)?;
+        self.replace_with(updated);
+        Ok(())
+    }
+
+    /// The config as it should be written: the active workspace's fleet goes
+    /// back under `[workspaces.<name>]` and the top-level fleet is restored.
+    fn for_disk(&self) -> Config {
+        let (Some(name), Some(root)) = (&self.workspace, &self.root_fleet) else {
+            return self.clone();
+        };
+
+        let mut config = self.clone();
+        let workspace = config.workspaces.entry(name.clone()).or_default();
+        workspace.servers = std::mem::replace(&mut config.servers, root.servers.clone());
+        workspace.default_server = std::mem::replace(
+            &mut config.client.default_server,
+            root.default_server.clone(),
+        );
+        if let Some(notifications) = &root.notifications {
+            config.notifications = notifications.clone();
+        }
+        config.workspace = None;
+        config.root_fleet = None;
+        config
+    }
+
+    pub fn state_dir() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("plan10"))
+    }
+
     pub fn add_server(&mut self, server: ServerDefinition) -> Result<()> {
         if self.servers.contains_key(&server.name) {
             anyhow::bail!("Server '{}' already exists", server.name);
@@ -161,6 +1571,55 @@ impl Config {
         Ok(())
     }
 
+    /// Rename a server along with everything that refers to it by name: the
+    /// default server, group members, tunnels and other servers' jump host.
+    /// Returns a description of each reference that was updated.
+    pub fn rename_server(&mut self, old: &str, new: &str) -> Result<Vec<String>> {
+        if new.trim().is_empty() {
+            anyhow::bail!("Invalid server name '{}'", new);
+        }
+        if self.servers.contains_key(new) {
+            anyhow::bail!("Server '{}' already exists", new);
+        }
+        let mut server = self.servers.remove(old)
+            .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", old))?;
+        server.name = new.to_string();
+        self.servers.insert(new.to_string(), server);
+
+        // Keep the server in the include file it came from
+        for (_, fragment) in &mut self.fragments {
+            if let Some(server) = fragment.servers.remove(old) {
+                fragment.servers.insert(new.to_string(), server);
+            }
+        }
+
+        let mut updated = Vec::new();
+        if self.client.default_server.as_deref() == Some(old) {
+            self.client.default_server = Some(new.to_string());
+            updated.push("default server".to_string());
+        }
+        for (name, group) in &mut self.groups {
+            for member in group.members.iter_mut().filter(|member| *member == old) {
+                *member = new.to_string();
+                updated.push(format!("group '{}'", name));
+            }
+        }
+        for (name, tunnel) in &mut self.tunnels {
+            if tunnel.server == old {
+                tunnel.server = new.to_string();
+                updated.push(format!("tunnel '{}'", name));
+            }
+        }
+        for (name, server) in &mut self.servers {
+            if server.jump_host.as_deref() == Some(old) {
+                server.jump_host = Some(new.to_string());
+                updated.push(format!("jump host of '{}'", name));
+            }
+        }
+        updated.sort();
+        Ok(updated)
+    }
+
     pub fn get_server(&self, name: &str) -> Option<&ServerDefinition> {
         self.servers.get(name)
     }
@@ -175,6 +1634,135 @@ impl Config {
         self.servers.values().collect()
     }
 
+    /// Names of the enabled servers, sorted.
+    pub fn enabled_server_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.servers.values()
+            .filter(|s| s.enabled)
+            .map(|s| s.name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Names of the disabled servers that `matches`, sorted.
+    pub fn disabled_server_names(&self, matches: impl Fn(&ServerDefinition) -> bool) -> Vec<String> {
+        let mut names: Vec<String> = self.servers.values()
+            .filter(|s| !s.enabled && matches(s))
+            .map(|s| s.name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Names of the enabled servers carrying `tag`, sorted. Tags given by a
+    /// server's groups count.
+    pub fn tagged_server_names(&self, tag: &str) -> Result<Vec<String>> {
+        let mut names: Vec<String> = self.servers.values()
+            .filter(|s| s.enabled && self.with_group_defaults(s).has_tag(Some(tag)))
+            .map(|s| s.name.clone())
+            .collect();
+        if names.is_empty() {
+            anyhow::bail!("No enabled servers are tagged '{}'", tag);
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Names of the enabled members of `group`, sorted.
+    pub fn group_server_names(&self, group: &str) -> Result<Vec<String>> {
+        let definition = self.groups.get(group)
+            .ok_or_else(|| anyhow::anyhow!("Group '{}' not found", group))?;
+
+        let mut names = Vec::new();
+        for member in &definition.members {
+            let server = self.servers.get(member)
+                .ok_or_else(|| anyhow::anyhow!("Group '{}' lists unknown server '{}'", group, member))?;
+            if server.enabled && !names.contains(member) {
+                names.push(member.clone());
+            }
+        }
+        if names.is_empty() {
+            anyhow::bail!("Group '{}' has no enabled servers", group);
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// The `monitor watch` layout called `name`: one from `[watch_layouts]`,
+    /// or a built-in arrangement (grid, rows, columns, table) of every panel.
+    /// Layouts in the config take precedence over the built-in names.
+    pub fn watch_layout(&self, name: &str) -> Result<WatchLayoutDefinition> {
+        if let Some(layout) = self.watch_layouts.get(name) {
+            return Ok(layout.clone());
+        }
+        if let Some((_, arrangement)) = WatchArrangement::NAMES.iter().find(|(builtin, _)| *builtin == name) {
+            return Ok(WatchLayoutDefinition { arrangement: *arrangement, ..Default::default() });
+        }
+
+        let mut names: Vec<&str> = WatchArrangement::NAMES.iter().map(|(builtin, _)| *builtin).collect();
+        let mut configured: Vec<&str> = self.watch_layouts.keys().map(String::as_str).collect();
+        configured.sort();
+        names.extend(configured);
+        anyhow::bail!("Unknown watch layout '{}' (available: {})", name, names.join(", "))
+    }
+
+    /// Names of the groups `server` belongs to, sorted.
+    pub fn server_groups(&self, server: &str) -> Vec<&str> {
+        let mut groups: Vec<&str> = self.groups.iter()
+            .filter(|(_, group)| group.members.iter().any(|m| m == server))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        groups.sort();
+        groups
+    }
+
+    /// The server with the defaults of its groups filled in. When several
+    /// groups set the same default, the first group by name wins; tags from
+    /// all of them are added.
+    pub fn with_group_defaults(&self, server: &ServerDefinition) -> ServerDefinition {
+        let mut server = server.clone();
+        for name in self.server_groups(&server.name) {
+            let group = &self.groups[name];
+            if server.user.is_empty() {
+                if let Some(user) = &group.user {
+                    server.user = user.clone();
+                }
+            }
+            if server.ssh_key.is_none() {
+                server.ssh_key = group.ssh_key.clone();
+            }
+            for tag in &group.tags {
+                if !server.tags.contains(tag) {
+                    server.tags.push(tag.clone());
+                }
+            }
+        }
+        server
+    }
+
+    /// Save when servers last answered a command. Servers defined only by
+    /// environment variables, or no longer configured, are left out.
+    pub fn save_last_seen(&self, seen: &BTreeMap<String, chrono::DateTime<chrono::Utc>>) -> Result<()> {
+        let mut config = self.clone();
+        let mut changed = false;
+        for (name, time) in seen {
+            let key = format!("servers.{}", name);
+            if self.env_overrides.iter().any(|env| env.key == key) {
+                continue;
+            }
+            if let Some(server) = config.servers.get_mut(name) {
+                if server.last_seen.is_none_or(|last_seen| last_seen < *time) {
+                    server.last_seen = Some(*time);
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            config.save(None)?;
+        }
+        Ok(())
+    }
+
     pub fn update_server_last_seen(&mut self, name: &str) -> Result<()> {
         if let Some(server) = self.servers.get_mut(name) {
             server.last_seen = Some(chrono::Utc::now());
@@ -190,8 +1778,10 @@ impl Config {
             return Some(server);
         }
 
-        // Then try host match
-        self.servers.values().find(|server| server.host == name_or_host)
+        // Then try host match, including alternative addresses
+        self.servers.values().find(|server| {
+            server.host == name_or_host || server.addresses.iter().any(|a| a == name_or_host)
+        })
     }
 
     pub fn get_ssh_key_path(&self) -> Option<PathBuf> {
@@ -212,49 +1802,203 @@ impl Config {
             })
     }
 
+    /// The monitoring thresholds for `server`: its own overrides, falling
+    /// back to `[server]`. `None` gives the global thresholds.
+    pub fn thresholds(&self, server: Option<&ServerDefinition>) -> Thresholds {
+        let overrides = server.map(|server| server.thresholds.clone()).unwrap_or_default();
+        Thresholds {
+            temp: overrides.temp_threshold.unwrap_or(self.server.temp_threshold),
+            battery_warning_level: overrides.battery_warning_level.unwrap_or(self.server.battery_warning_level),
+            disk_warning_level: overrides.disk_warning_level.unwrap_or(self.server.disk_warning_level),
+            cpu_warning_level: overrides.cpu_warning_level.unwrap_or(self.server.cpu_warning_level),
+        }
+    }
+
+    /// Fail on the first error `diagnose` finds.
     pub fn validate(&self) -> Result<()> {
-        // Validate server configurations
-        for (name, server) in &self.servers {
-            if server.name != *name {
-                anyhow::bail!("Server name mismatch: key '{}' vs name '{}'", name, server.name);
+        match self.diagnose().into_iter().find(|issue| issue.severity == IssueSeverity::Error) {
+            Some(issue) => anyhow::bail!("{}", issue),
+            None => Ok(()),
+        }
+    }
+
+    /// Every problem with the configuration that can be found without
+    /// touching the network or filesystem, errors and warnings alike.
+    pub fn diagnose(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        let mut fleets = vec![(None, &self.servers, &self.client.default_server)];
+        let mut workspace_names: Vec<_> = self.workspaces.keys().collect();
+        workspace_names.sort();
+        for name in workspace_names {
+            let workspace = &self.workspaces[name];
+            fleets.push((Some(name.as_str()), &workspace.servers, &workspace.default_server));
+        }
+
+        for (workspace, servers, default_server) in fleets {
+            let prefix: Vec<&str> = match workspace {
+                Some(name) => vec!["workspaces", name],
+                None => Vec::new(),
+            };
+            let at = |keys: &[&str]| -> Vec<String> {
+                prefix.iter().chain(keys).map(|key| key.to_string()).collect()
+            };
+
+            let mut names: Vec<_> = servers.keys().collect();
+            names.sort();
+            let mut seen_hosts: HashMap<(String, u16), &str> = HashMap::new();
+            for name in names {
+                let server = &servers[name];
+                if server.name != *name {
+                    issues.push(ConfigIssue::error(
+                        at(&["servers", name, "name"]),
+                        format!("server name mismatch: key '{}' vs name '{}'", name, server.name),
+                    ).with_hint(format!("set name = \"{}\"", name)));
+                }
+                if server.host.is_empty() {
+                    issues.push(ConfigIssue::error(at(&["servers", name, "host"]), "empty host"));
+                }
+                if self.with_group_defaults(server).user.is_empty() {
+                    issues.push(ConfigIssue::error(at(&["servers", name, "user"]), "empty user")
+                        .with_hint("set user, or add the server to a group that sets one"));
+                }
+                if server.port == 0 {
+                    issues.push(ConfigIssue::error(at(&["servers", name, "port"]), "invalid port: 0"));
+                }
+                let thresholds = &server.thresholds;
+                issues.extend(threshold_issues(
+                    &at(&["servers", name, "thresholds"]),
+                    thresholds.temp_threshold,
+                    thresholds.battery_warning_level,
+                    thresholds.disk_warning_level,
+                    thresholds.cpu_warning_level,
+                ));
+                if !server.host.is_empty() {
+                    match seen_hosts.get(&(server.host.clone(), server.port)) {
+                        Some(other) => issues.push(ConfigIssue::warning(
+                            at(&["servers", name, "host"]),
+                            format!("same host and port as server '{}'", other),
+                        ).with_hint("remove one of them, or list the other address under `addresses`")),
+                        None => {
+                            seen_hosts.insert((server.host.clone(), server.port), name);
+                        }
+                    }
+                }
             }
-            
-            if server.host.is_empty() {
-                anyhow::bail!("Server '{}' has empty host", name);
+
+            if let Some(default_server) = default_server {
+                if !servers.contains_key(default_server) {
+                    let key = match workspace {
+                        Some(_) => at(&["default_server"]),
+                        None => at(&["client", "default_server"]),
+                    };
+                    issues.push(ConfigIssue::error(key, format!("default server '{}' not found", default_server)));
+                }
             }
-            
-            if server.user.is_empty() {
-                anyhow::bail!("Server '{}' has empty user", name);
+        }
+
+        if let Some(default_workspace) = &self.client.default_workspace {
+            if !self.workspaces.contains_key(default_workspace) {
+                issues.push(ConfigIssue::error(
+                    path(&["client", "default_workspace"]),
+                    format!("default workspace '{}' not found", default_workspace),
+                ));
             }
-            
-            if server.port == 0 || server.port > 65535 {
-                anyhow::bail!("Server '{}' has invalid port: {}", name, server.port);
+        }
+
+        let mut group_names: Vec<_> = self.groups.keys().collect();
+        group_names.sort();
+        for name in group_names {
+            for member in &self.groups[name].members {
+                let known = self.servers.contains_key(member)
+                    || self.workspaces.values().any(|w| w.servers.contains_key(member));
+                if !known {
+                    issues.push(ConfigIssue::error(
+                        path(&["groups", name, "members"]),
+                        format!("unknown server '{}'", member),
+                    ));
+                }
             }
         }
 
-        // Validate default server exists
-        if let Some(default_server) = &self.client.default_server {
-            if !self.servers.contains_key(default_server) {
-                anyhow::bail!("Default server '{}' not found in servers list", default_server);
+        let mut tunnel_names: Vec<_> = self.tunnels.keys().collect();
+        tunnel_names.sort();
+        for name in tunnel_names {
+            let server = &self.tunnels[name].server;
+            if !self.servers.contains_key(server) {
+                issues.push(ConfigIssue::error(
+                    path(&["tunnels", name, "server"]),
+                    format!("unknown server '{}'", server),
+                ));
             }
         }
 
-        // Validate thresholds
-        if self.server.temp_threshold < 0.0 || self.server.temp_threshold > 150.0 {
-            anyhow::bail!("Invalid temperature threshold: {}", self.server.temp_threshold);
+        let mut layout_names: Vec<_> = self.watch_layouts.keys().collect();
+        layout_names.sort();
+        for name in layout_names {
+            if self.watch_layouts[name].panels.is_empty() {
+                issues.push(ConfigIssue::error(path(&["watch_layouts", name, "panels"]), "no panels listed")
+                    .with_hint("list some of temp, battery, power and system"));
+            }
         }
-        
-        if self.server.battery_warning_level > 100 {
-            anyhow::bail!("Invalid battery warning level: {}", self.server.battery_warning_level);
+
+        if self.ssh.proxy.is_some() && self.ssh.proxy_command.is_some() {
+            issues.push(ConfigIssue::error(path(&["ssh", "proxy_command"]), "ssh.proxy and ssh.proxy_command are both set")
+                .with_hint("keep only one of them"));
         }
 
-        Ok(())
+        issues.extend(threshold_issues(
+            &path(&["server"]),
+            Some(self.server.temp_threshold),
+            Some(self.server.battery_warning_level),
+            Some(self.server.disk_warning_level),
+            Some(self.server.cpu_warning_level),
+        ));
+        if !(1..=100).contains(&self.server.battery_replacement_capacity) {
+            issues.push(ConfigIssue::error(
+                path(&["server", "battery_replacement_capacity"]),
+                format!("invalid battery replacement capacity: {}", self.server.battery_replacement_capacity),
+            ).with_hint("use a percentage between 1 and 100"));
+        }
+        if self.notifications.fleet_threshold == 0 {
+            issues.push(ConfigIssue::error(path(&["notifications", "fleet_threshold"]), "must be at least 1"));
+        }
+        for (index, notifier) in self.alerts.notifiers.iter().enumerate() {
+            if let (key, None) = notifier.target() {
+                issues.push(ConfigIssue::error(path(&["alerts", "notifiers", &index.to_string()]), format!("missing `{}`", key))
+                    .with_hint("the notifier is skipped until it is set"));
+            }
+        }
+        if self.watchdog.failures == 0 {
+            issues.push(ConfigIssue::error(path(&["watchdog", "failures"]), "must be at least 1"));
+        }
+        if self.watchdog.interval == 0 {
+            issues.push(ConfigIssue::error(path(&["watchdog", "interval"]), "must be at least 1 second"));
+        }
+        if self.watchdog.actions.contains(&WatchdogAction::Script) && self.watchdog.script.is_none() {
+            issues.push(ConfigIssue::warning(path(&["watchdog", "actions"]), "`script` is listed but watchdog.script is not set")
+                .with_hint("the action is skipped until it is set"));
+        }
+        if self.client.concurrent_operations == 0 {
+            issues.push(ConfigIssue::warning(path(&["client", "concurrent_operations"]), "0 is treated as 1"));
+        }
+
+        issues
     }
 
-    pub fn merge_env_vars(&mut self) {
-        // Override with environment variables
+    /// Apply the `PLAN10_*` environment variables. Any setting can be
+    /// overridden by its dotted path in capitals with `__` between the
+    /// parts, e.g. `PLAN10_SERVER__TEMP_THRESHOLD=85` or
+    /// `PLAN10_SERVERS__MINI__PORT=2222`; the older shorthands in
+    /// [`LEGACY_ENV_VARS`] still work, and the more specific variable wins.
+    pub fn merge_env_vars(&mut self) -> Result<()> {
+        let mut vars: Vec<(String, String)> = std::env::vars()
+            .filter(|(var, _)| var.starts_with(ENV_PREFIX))
+            .collect();
+        vars.sort();
+
         if let Ok(host) = std::env::var("PLAN10_HOST") {
-            if let Some(user) = std::env::var("PLAN10_USER").ok() {
+            if let Ok(user) = std::env::var("PLAN10_USER") {
                 let port = std::env::var("PLAN10_PORT")
                     .ok()
                     .and_then(|p| p.parse().ok())
@@ -269,19 +2013,305 @@ impl Config {
                     tags: vec!["env".to_string()],
                     enabled: true,
                     last_seen: None,
+                    site: None,
+                    addresses: Vec::new(),
+                    address_family: AddressFamily::Auto,
+                    notes: None,
+                    runbook: None,
+                    jump_host: None,
+                    agent_forwarding: false,
+                    ssh_options: ServerSshOptions::default(),
+                    thresholds: ServerThresholds::default(),
                 };
 
-                self.servers.insert("env".to_string(), server);
-                self.client.default_server = Some("env".to_string());
+                self.override_value("PLAN10_HOST", "servers.env", serde_json::to_value(server)?)?;
+                self.override_value("PLAN10_HOST", "client.default_server", "env".into())?;
             }
         }
 
         if let Ok(key_path) = std::env::var("PLAN10_SSH_KEY") {
-            self.ssh.key_path = Some(key_path);
+            self.override_value("PLAN10_SSH_KEY", "ssh.key_path", key_path.into())?;
         }
 
         if let Ok(log_level) = std::env::var("PLAN10_LOG_LEVEL") {
-            self.server.log_level = log_level;
+            self.override_value("PLAN10_LOG_LEVEL", "server.log_level", log_level.into())?;
+        }
+
+        for (var, raw) in vars {
+            if CLI_ENV_VARS.contains(&var.as_str())
+                || LEGACY_ENV_VARS.contains(&var.as_str())
+                || RESERVED_ENV_VARS.contains(&var.as_str())
+            {
+                continue;
+            }
+            let key = self.env_key(&var);
+            // Anything can set a PLAN10_ variable; one that isn't meant for
+            // us mustn't stop every command, only a bad value for a real
+            // setting does
+            if self.get_value(&key).is_err() {
+                tracing::warn!("Ignoring {}: there is no setting {}", var, key);
+                self.ignored_env_vars.push(var);
+                continue;
+            }
+            let value = self.parse_value(&key, &raw);
+            self.override_value(&var, &key, value)?;
+        }
+        Ok(())
+    }
+
+    /// The dotted path a variable names. Parts are matched against the
+    /// existing keys ignoring case, with `_` standing in for `-`, so servers
+    /// named `mac-mini` are reachable as `PLAN10_SERVERS__MAC_MINI__PORT`.
+    fn env_key(&self, var: &str) -> String {
+        let mut current = serde_json::to_value(self).unwrap_or_default();
+        let mut parts = Vec::new();
+        for part in var.trim_start_matches(ENV_PREFIX).split("__") {
+            let key = current.as_object()
+                .and_then(|map| map.keys().find(|key| key.replace('-', "_").eq_ignore_ascii_case(part)))
+                .cloned()
+                .unwrap_or_else(|| part.to_lowercase());
+            current = current.get(&key)
+                .or_else(|| key.parse::<usize>().ok().and_then(|i| current.get(i)))
+                .cloned()
+                .unwrap_or_default();
+            parts.push(key);
+        }
+        parts.join(".")
+    }
+
+    fn override_value(&mut self, var: &str, key: &str, value: serde_json::Value) -> Result<()> {
+        let original = self.get_value(key).ok();
+        self.replace_value(key, Some(value))
+            .with_context(|| format!("Invalid environment override {} ({})", var, key))?;
+        let value = self.get_value(key)?;
+        self.env_overrides.push(EnvOverride {
+            var: var.to_string(),
+            key: key.to_string(),
+            value,
+            original,
+        });
+        Ok(())
+    }
+
+    /// Put back the file's values for the settings overridden from the
+    /// environment, unless they have been changed since.
+    fn revert_env_overrides(&mut self) -> Result<()> {
+        for env in std::mem::take(&mut self.env_overrides).into_iter().rev() {
+            if self.get_value(&env.key).ok().as_ref() == Some(&env.value) {
+                self.replace_value(&env.key, env.original)?;
+            }
         }
+        Ok(())
     }
-}
\ No newline at end of file
+
+    /// The settings overridden from the environment, in the order applied.
+    pub fn env_overrides(&self) -> &[EnvOverride] {
+        &self.env_overrides
+    }
+
+    /// The `PLAN10_*` variables skipped because they name no setting.
+    pub fn ignored_env_vars(&self) -> &[String] {
+        &self.ignored_env_vars
+    }
+}
+
+/// Prefix for config values kept in the keychain instead of the file, e.g.
+/// `webhook_url = "keychain:slack-webhook"`.
+const KEYCHAIN_REFERENCE: &str = "keychain:";
+
+/// A credential kept in the macOS login keychain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Secret {
+    /// Passphrase of an encrypted SSH key
+    KeyPassphrase(PathBuf),
+    /// Login password for `user@host`
+    SshPassword { user: String, host: String },
+    /// sudo password for `user@host`
+    SudoPassword { user: String, host: String },
+    /// A value referenced from the config as `keychain:<name>`, such as a
+    /// webhook URL with a token in it
+    Named(String),
+}
+
+impl Secret {
+    /// The keychain account the secret is stored under.
+    fn account(&self) -> String {
+        match self {
+            Secret::KeyPassphrase(path) => format!("key:{}", path.display()),
+            Secret::SshPassword { user, host } => format!("ssh:{}@{}", user, host),
+            Secret::SudoPassword { user, host } => format!("sudo:{}@{}", user, host),
+            Secret::Named(name) => format!("secret:{}", name),
+        }
+    }
+}
+
+impl std::str::FromStr for Secret {
+    type Err = anyhow::Error;
+
+    /// Parse `key:<path>`, `ssh:<user>@<host>`, `sudo:<user>@<host>` or a
+    /// plain name.
+    fn from_str(name: &str) -> Result<Self> {
+        let user_host = |rest: &str| -> Result<(String, String)> {
+            rest.split_once('@')
+                .filter(|(user, host)| !user.is_empty() && !host.is_empty())
+                .map(|(user, host)| (user.to_string(), host.to_string()))
+                .ok_or_else(|| anyhow::anyhow!("Invalid secret '{}': expected <user>@<host>", name))
+        };
+
+        match name.split_once(':') {
+            Some(("key", path)) if !path.is_empty() => {
+                Ok(Secret::KeyPassphrase(PathBuf::from(shellexpand::tilde(path).into_owned())))
+            }
+            Some(("ssh", rest)) => user_host(rest).map(|(user, host)| Secret::SshPassword { user, host }),
+            Some(("sudo", rest)) => user_host(rest).map(|(user, host)| Secret::SudoPassword { user, host }),
+            Some(("secret", rest)) if !rest.is_empty() => Ok(Secret::Named(rest.to_string())),
+            None if !name.is_empty() => Ok(Secret::Named(name.to_string())),
+            _ => anyhow::bail!("Invalid secret '{}': expected key:<path>, ssh:<user>@<host>, sudo:<user>@<host> or a name", name),
+        }
+    }
+}
+
+impl std::fmt::Display for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Secret::Named(name) => write!(f, "{}", name),
+            other => write!(f, "{}", other.account()),
+        }
+    }
+}
+
+/// Where credentials live instead of the config file: the login keychain,
+/// under the `plan10` service.
+pub struct SecretsStore;
+
+impl SecretsStore {
+    /// The stored secret, or None when there is none or the keychain is not
+    /// available.
+    pub fn get(secret: &Secret) -> Option<String> {
+        crate::keychain::find_password(&secret.account())
+    }
+
+    pub fn set(secret: &Secret, value: &str) -> Result<()> {
+        crate::keychain::store_password(&secret.account(), value)
+    }
+
+    pub fn remove(secret: &Secret) -> Result<()> {
+        crate::keychain::delete_password(&secret.account())
+    }
+
+    /// A config value with a `keychain:<name>` reference replaced by the
+    /// stored secret. Other values are returned unchanged.
+    pub fn resolve(value: &str) -> Result<String> {
+        let Some(name) = value.strip_prefix(KEYCHAIN_REFERENCE) else {
+            return Ok(value.to_string());
+        };
+        let secret = Secret::Named(name.to_string());
+        Self::get(&secret).ok_or_else(|| anyhow::anyhow!(
+            "Secret '{}' is not in the keychain; store it with: plan10 config secret set {}",
+            name,
+            name
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueSeverity {
+    Warning,
+    Error,
+}
+
+/// A problem found in the configuration, with the key it is about.
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub severity: IssueSeverity,
+    /// Key path, e.g. `["servers", "mini", "port"]`
+    pub path: Vec<String>,
+    pub message: String,
+    /// What to do about it
+    pub hint: Option<String>,
+}
+
+impl ConfigIssue {
+    pub fn error(path: Vec<String>, message: impl Into<String>) -> Self {
+        Self { severity: IssueSeverity::Error, path, message: message.into(), hint: None }
+    }
+
+    pub fn warning(path: Vec<String>, message: impl Into<String>) -> Self {
+        Self { severity: IssueSeverity::Warning, path, message: message.into(), hint: None }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// The dotted key, e.g. `servers.mini.port`.
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// The 1-based line of the issue's key in the config file `content`, if
+    /// it can be found. Works on TOML, YAML and JSON alike by looking for each
+    /// key of the path in turn, as a table header or as a `key =`/`key:` line.
+    pub fn line_in(&self, content: &str) -> Option<usize> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut line = 0;
+        for key in &self.path {
+            line += lines[line..].iter().position(|text| line_has_key(text, key))?;
+        }
+        Some(line + 1)
+    }
+}
+
+fn line_has_key(line: &str, key: &str) -> bool {
+    let line = line.trim_start().trim_start_matches("- ");
+    if let Some(header) = line.strip_prefix('[') {
+        let header = header.trim_start_matches('[').split(']').next().unwrap_or("");
+        return header.split('.').any(|part| part.trim().trim_matches('"') == key);
+    }
+    [format!("{} ", key), format!("{}=", key), format!("{}:", key), format!("\"{}\"", key)]
+        .iter()
+        .any(|prefix| line.starts_with(prefix.as_str()))
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key(), self.message)
+    }
+}
+
+fn path(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| key.to_string()).collect()
+}
+
+/// Range checks for the thresholds set under the table at `prefix`.
+fn threshold_issues(
+    prefix: &[String],
+    temp: Option<f32>,
+    battery: Option<u8>,
+    disk: Option<u8>,
+    cpu: Option<u8>,
+) -> Vec<ConfigIssue> {
+    let at = |key: &str| -> Vec<String> {
+        prefix.iter().cloned().chain([key.to_string()]).collect()
+    };
+
+    let mut issues = Vec::new();
+    if let Some(temp) = temp.filter(|temp| !(0.0..=150.0).contains(temp)) {
+        issues.push(ConfigIssue::error(at("temp_threshold"), format!("invalid temperature threshold: {}", temp))
+            .with_hint("use a value between 0 and 150 °C"));
+    }
+    if let Some(level) = battery.filter(|level| *level > 100) {
+        issues.push(ConfigIssue::error(at("battery_warning_level"), format!("invalid battery warning level: {}", level))
+            .with_hint("use a percentage between 0 and 100"));
+    }
+    if let Some(level) = disk.filter(|level| *level > 100) {
+        issues.push(ConfigIssue::error(at("disk_warning_level"), format!("invalid disk warning level: {}", level))
+            .with_hint("use a percentage between 0 and 100"));
+    }
+    if let Some(level) = cpu.filter(|level| *level > 100) {
+        issues.push(ConfigIssue::error(at("cpu_warning_level"), format!("invalid CPU warning level: {}", level))
+            .with_hint("use a percentage between 0 and 100"));
+    }
+    issues
+}
@@ -0,0 +1,480 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+/// How long an unchanged power settings snapshot is trusted before another
+/// copy is written; bounds how precisely a change can be placed in time.
+const POWER_SETTINGS_REFRESH_MINUTES: i64 = 60;
+
+/// How long an unchanged battery health record is trusted before another
+/// copy is written, so the trend keeps a point per day even while the
+/// values hold steady.
+const BATTERY_HEALTH_REFRESH_HOURS: i64 = 24;
+
+/// Minimum spacing of monitoring samples per host, so a `monitor watch` at a
+/// 5s interval doesn't grow the log by thousands of lines an hour.
+pub const SAMPLE_INTERVAL_SECONDS: i64 = 60;
+
+/// How much of the end of the history `record_sample` reads to find the
+/// host's last sample; a minute of records from a large fleet fits easily.
+const SAMPLE_TAIL_BYTES: u64 = 256 * 1024;
+
+/// Hours between passes that drop expired samples.
+const PRUNE_INTERVAL_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub timestamp: DateTime<Utc>,
+    pub host: String,
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistoryEntry {
+    PowerSettings {
+        os_version: Option<String>,
+        settings: BTreeMap<String, String>,
+    },
+    /// A human note on the timeline ("replaced charger")
+    Annotation {
+        text: String,
+        author: Option<String>,
+    },
+    /// One monitoring reading, for `monitor history`
+    Sample(Sample),
+    /// Battery wear, for `monitor battery --trend`
+    BatteryHealth {
+        cycle_count: Option<u32>,
+        maximum_capacity_percent: Option<u8>,
+    },
+    /// Lost connectivity seen by `server watchdog`, stamped when it ended
+    Outage {
+        started: DateTime<Utc>,
+        probe: String,
+        /// Recovery actions run during the outage, in order
+        actions: Vec<String>,
+    },
+    /// Whether a server answered `client check` or `client ping`, for
+    /// `client report availability`
+    Reachability {
+        reachable: bool,
+        /// When the server last booted, if it answered and said
+        boot_time: Option<DateTime<Utc>>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    /// "ac", "battery" or "unknown"
+    pub power_source: String,
+    pub battery_percent: Option<u8>,
+    pub cpu_celsius: Option<f32>,
+    pub cpu_usage: Option<f32>,
+    /// Swap in use, from `vm.swapusage`
+    pub swap_used_bytes: Option<u64>,
+    /// Pages swapped in and out since boot; `monitor history` shows the rate
+    /// between consecutive samples
+    pub swapins: Option<u64>,
+    pub swapouts: Option<u64>,
+}
+
+/// Append-only JSON Lines log of samples collected from each host.
+pub struct History {
+    path: PathBuf,
+    /// Age at which samples are dropped; other records are kept
+    sample_retention: Option<Duration>,
+}
+
+/// Exclusive lock held by everything that writes a history, so rewriting it
+/// can't lose lines appended meanwhile. It is taken on a `.lock` file beside
+/// the history, as a rewrite replaces the history file itself.
+struct HistoryLock {
+    _file: File,
+}
+
+impl HistoryLock {
+    fn acquire(path: &Path) -> Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .context("Failed to create state directory")?;
+        }
+        let lock_path = path.with_extension("jsonl.lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .context(format!("Failed to open lock file: {}", lock_path.display()))?;
+
+        // Released when the file is closed on drop
+        if unsafe { nix::libc::flock(file.as_raw_fd(), nix::libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error())
+                .context(format!("Failed to lock history: {}", path.display()));
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+impl History {
+    pub fn open(config: &Config) -> Result<Self> {
+        // Keep each workspace's history apart, like its notification state
+        let file_name = match config.workspace() {
+            Some(workspace) => format!("history-{}.jsonl", workspace),
+            None => "history.jsonl".to_string(),
+        };
+        let path = Config::state_dir()
+            .context("Could not determine state directory")?
+            .join(file_name);
+        let retention_days = config.server.sample_retention_days;
+        let sample_retention = (retention_days > 0).then(|| Duration::days(retention_days.into()));
+        Ok(Self { path, sample_retention })
+    }
+
+    pub fn append(&self, record: &HistoryRecord) -> Result<()> {
+        let _lock = HistoryLock::acquire(&self.path)?;
+        self.write_record(record)
+    }
+
+    /// Append without taking the lock, for callers already holding it.
+    fn write_record(&self, record: &HistoryRecord) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context(format!("Failed to open history: {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)
+            .context(format!("Failed to write history: {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// Move a renamed host's records to its new name. Returns how many were
+    /// changed.
+    pub fn rename_host(&self, old: &str, new: &str) -> Result<usize> {
+        if !self.path.exists() {
+            return Ok(0);
+        }
+        let _lock = HistoryLock::acquire(&self.path)?;
+
+        let content = fs::read_to_string(&self.path)
+            .context(format!("Failed to read history: {}", self.path.display()))?;
+        let mut renamed = 0;
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            // Edit lines as JSON so ones this version can't parse survive
+            match serde_json::from_str::<serde_json::Value>(line) {
+                Ok(mut value) if value.get("host").and_then(|host| host.as_str()) == Some(old) => {
+                    value["host"] = new.into();
+                    lines.push(serde_json::to_string(&value)?);
+                    renamed += 1;
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+        if renamed == 0 {
+            return Ok(0);
+        }
+
+        self.rewrite(&lines)?;
+        Ok(renamed)
+    }
+
+    /// Replace the whole file with `lines`, through a temporary file so a
+    /// crash never leaves half a history behind. The caller holds the lock
+    /// from before it read the lines until this returns.
+    fn rewrite(&self, lines: &[String]) -> Result<()> {
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp_path, lines.join("\n") + "\n")
+            .context(format!("Failed to write history: {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .context(format!("Failed to write history: {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// All records for a host in chronological order. Lines that fail to
+    /// parse (e.g. from a newer version) are skipped.
+    pub fn records(&self, host: &str) -> Result<Vec<HistoryRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .context(format!("Failed to read history: {}", self.path.display()))?;
+        let mut records: Vec<HistoryRecord> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .filter(|record: &HistoryRecord| record.host == host)
+            .collect();
+        records.sort_by_key(|record| record.timestamp);
+        Ok(records)
+    }
+
+    /// Every host with records, sorted.
+    pub fn hosts(&self) -> Result<Vec<String>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .context(format!("Failed to read history: {}", self.path.display()))?;
+        let mut hosts: Vec<String> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<HistoryRecord>(line).ok())
+            .map(|record| record.host)
+            .collect();
+        hosts.sort();
+        hosts.dedup();
+        Ok(hosts)
+    }
+
+    pub fn annotations(&self, host: &str, since: Option<DateTime<Utc>>) -> Result<Vec<HistoryRecord>> {
+        Ok(self.records(host)?
+            .into_iter()
+            .filter(|record| matches!(record.entry, HistoryEntry::Annotation { .. }))
+            .filter(|record| since.map_or(true, |since| record.timestamp >= since))
+            .collect())
+    }
+
+    pub fn latest_power_settings(&self, host: &str) -> Result<Option<HistoryRecord>> {
+        Ok(self.records(host)?
+            .into_iter()
+            .rev()
+            .find(|record| matches!(record.entry, HistoryEntry::PowerSettings { .. })))
+    }
+
+    /// Store a pmset snapshot unless it matches the latest one and that one is
+    /// still fresh. Returns whether a record was written.
+    pub fn record_power_settings(
+        &self,
+        host: &str,
+        os_version: Option<String>,
+        settings: BTreeMap<String, String>,
+    ) -> Result<bool> {
+        let latest = self.latest_power_settings(host)?;
+
+        if let Some(HistoryRecord {
+            timestamp,
+            entry: HistoryEntry::PowerSettings { os_version: last_os, settings: last_settings },
+            ..
+        }) = latest
+        {
+            let fresh = Utc::now().signed_duration_since(timestamp)
+                < Duration::minutes(POWER_SETTINGS_REFRESH_MINUTES);
+            if fresh && last_os == os_version && last_settings == settings {
+                return Ok(false);
+            }
+        }
+
+        self.append(&HistoryRecord {
+            timestamp: Utc::now(),
+            host: host.to_string(),
+            entry: HistoryEntry::PowerSettings { os_version, settings },
+        })?;
+        Ok(true)
+    }
+
+    pub fn samples(&self, host: &str, since: DateTime<Utc>) -> Result<Vec<(DateTime<Utc>, Sample)>> {
+        Ok(self.records(host)?
+            .into_iter()
+            .filter(|record| record.timestamp >= since)
+            .filter_map(|record| match record.entry {
+                HistoryEntry::Sample(sample) => Some((record.timestamp, sample)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub fn outages(&self, host: &str, since: DateTime<Utc>) -> Result<Vec<HistoryRecord>> {
+        Ok(self.records(host)?
+            .into_iter()
+            .filter(|record| record.timestamp >= since)
+            .filter(|record| matches!(record.entry, HistoryEntry::Outage { .. }))
+            .collect())
+    }
+
+    pub fn battery_health(&self, host: &str, since: DateTime<Utc>) -> Result<Vec<HistoryRecord>> {
+        Ok(self.records(host)?
+            .into_iter()
+            .filter(|record| record.timestamp >= since)
+            .filter(|record| matches!(record.entry, HistoryEntry::BatteryHealth { .. }))
+            .collect())
+    }
+
+    pub fn reachability(&self, host: &str) -> Result<Vec<HistoryRecord>> {
+        Ok(self.records(host)?
+            .into_iter()
+            .filter(|record| matches!(record.entry, HistoryEntry::Reachability { .. }))
+            .collect())
+    }
+
+    /// Store a poll result unless the host's last one is under a minute old
+    /// and says the same. Returns whether a record was written.
+    pub fn record_reachability(&self, host: &str, reachable: bool, boot_time: Option<DateTime<Utc>>) -> Result<bool> {
+        let cutoff = Utc::now() - Duration::seconds(SAMPLE_INTERVAL_SECONDS);
+        let unchanged = self.reachability(host)?.last().is_some_and(|record| record.timestamp >= cutoff && matches!(
+            record.entry,
+            HistoryEntry::Reachability { reachable: last_reachable, boot_time: last_boot }
+                if last_reachable == reachable && last_boot == boot_time
+        ));
+        if unchanged {
+            return Ok(false);
+        }
+
+        self.append(&HistoryRecord {
+            timestamp: Utc::now(),
+            host: host.to_string(),
+            entry: HistoryEntry::Reachability { reachable, boot_time },
+        })?;
+        Ok(true)
+    }
+
+    /// Store a battery health reading unless it matches the host's latest
+    /// one and that one is under a day old. Returns whether a record was
+    /// written.
+    pub fn record_battery_health(&self, host: &str, cycle_count: Option<u32>, maximum_capacity_percent: Option<u8>) -> Result<bool> {
+        if cycle_count.is_none() && maximum_capacity_percent.is_none() {
+            return Ok(false);
+        }
+
+        let cutoff = Utc::now() - Duration::hours(BATTERY_HEALTH_REFRESH_HOURS);
+        let unchanged = self.battery_health(host, cutoff)?.last().is_some_and(|record| matches!(
+            record.entry,
+            HistoryEntry::BatteryHealth { cycle_count: last_cycles, maximum_capacity_percent: last_capacity }
+                if last_cycles == cycle_count && last_capacity == maximum_capacity_percent
+        ));
+        if unchanged {
+            return Ok(false);
+        }
+
+        self.append(&HistoryRecord {
+            timestamp: Utc::now(),
+            host: host.to_string(),
+            entry: HistoryEntry::BatteryHealth { cycle_count, maximum_capacity_percent },
+        })?;
+        Ok(true)
+    }
+
+    /// Store a monitoring sample unless the host's last one is under a
+    /// minute old. Returns whether a record was written.
+    pub fn record_sample(&self, host: &str, sample: Sample) -> Result<bool> {
+        // Held across the check too, so two recorders can't both decide the
+        // last sample is old enough
+        let _lock = HistoryLock::acquire(&self.path)?;
+        let cutoff = Utc::now() - Duration::seconds(SAMPLE_INTERVAL_SECONDS);
+        if self.has_recent_sample(host, cutoff)? {
+            return Ok(false);
+        }
+
+        self.write_record(&HistoryRecord {
+            timestamp: Utc::now(),
+            host: host.to_string(),
+            entry: HistoryEntry::Sample(sample),
+        })?;
+        self.prune_samples()?;
+        Ok(true)
+    }
+
+    /// Whether the host has a sample from `cutoff` on. Records are appended
+    /// in time order, so only the end of the file is read, back to the first
+    /// record older than `cutoff`.
+    fn has_recent_sample(&self, host: &str, cutoff: DateTime<Utc>) -> Result<bool> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e).context(format!("Failed to read history: {}", self.path.display())),
+        };
+        let start = file.metadata()?.len().saturating_sub(SAMPLE_TAIL_BYTES);
+        let mut tail = Vec::new();
+        file.seek(SeekFrom::Start(start))?;
+        file.read_to_end(&mut tail)
+            .context(format!("Failed to read history: {}", self.path.display()))?;
+        let tail = String::from_utf8_lossy(&tail);
+
+        // Starting mid-file, the first line is most likely cut off
+        let tail = match start {
+            0 => &tail[..],
+            _ => tail.split_once('\n').map_or("", |(_, rest)| rest),
+        };
+        for record in tail.lines().rev().filter_map(|line| serde_json::from_str::<HistoryRecord>(line).ok()) {
+            if record.timestamp < cutoff {
+                break;
+            }
+            if record.host == host && matches!(record.entry, HistoryEntry::Sample(_)) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Drop samples older than `server.sample_retention_days`, at most once
+    /// a day. The time of the last pass is kept next to the history. The
+    /// caller holds the lock.
+    fn prune_samples(&self) -> Result<()> {
+        let Some(retention) = self.sample_retention else {
+            return Ok(());
+        };
+        let stamp_path = self.path.with_extension("jsonl.pruned");
+        let last_pruned = fs::read_to_string(&stamp_path).ok()
+            .and_then(|stamp| DateTime::parse_from_rfc3339(stamp.trim()).ok());
+        if last_pruned.is_some_and(|last| Utc::now().signed_duration_since(last) < Duration::hours(PRUNE_INTERVAL_HOURS)) {
+            return Ok(());
+        }
+
+        let cutoff = Utc::now() - retention;
+        let content = fs::read_to_string(&self.path)
+            .context(format!("Failed to read history: {}", self.path.display()))?;
+        let mut kept = Vec::new();
+        let mut dropped = 0;
+        for line in content.lines() {
+            // Lines this version can't parse are kept, as in rename_host
+            let expired = serde_json::from_str::<HistoryRecord>(line).is_ok_and(|record| {
+                matches!(record.entry, HistoryEntry::Sample(_)) && record.timestamp < cutoff
+            });
+            if expired {
+                dropped += 1;
+            } else {
+                kept.push(line.to_string());
+            }
+        }
+        if dropped > 0 {
+            self.rewrite(&kept)?;
+        }
+
+        fs::write(&stamp_path, Utc::now().to_rfc3339())
+            .context(format!("Failed to write {}", stamp_path.display()))?;
+        Ok(())
+    }
+}
+
+/// Parse a relative time span such as `30m`, `12h`, `7d` or `2w`.
+pub fn parse_since(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse()
+        .context(format!("Invalid time span '{}' (expected e.g. 30m, 12h, 7d)", value))?;
+
+    let span = match unit {
+        "m" | "min" => Duration::try_minutes(amount),
+        "h" | "" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => anyhow::bail!("Invalid time unit '{}' in '{}' (use m, h, d or w)", unit, value),
+    };
+    span.context(format!("Time span '{}' is too long", value))
+}
+
+/// When a window of `span` (from `parse_since`) reaching back from now starts.
+pub fn window_start(span: Duration) -> Result<DateTime<Utc>> {
+    Utc::now().checked_sub_signed(span)
+        .context("Time span reaches back too far")
+}
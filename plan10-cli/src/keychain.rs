@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::utils::run_command_with_status;
+
+/// Keychain service that every Plan 10 secret is stored under, so
+/// `server decommission` can find and delete them.
+const SERVICE: &str = "plan10";
+
+/// Read a secret from the login keychain. Returns None when nothing is stored
+/// or the keychain is not available (non-macOS, locked, access denied).
+pub fn find_password(account: &str) -> Option<String> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+
+    let (stdout, _, success) = run_command_with_status(
+        "security",
+        &["find-generic-password", "-s", SERVICE, "-a", account, "-w"],
+    ).ok()?;
+    let password = stdout.trim_end_matches('\n').to_string();
+    (success && !password.is_empty()).then_some(password)
+}
+
+/// Store or replace a secret in the login keychain. The command goes to
+/// `security -i` on stdin; as an argument the secret would show in `ps`.
+pub fn store_password(account: &str, password: &str) -> Result<()> {
+    crate::utils::require_macos()?;
+    if password.contains(['\n', '\r']) {
+        anyhow::bail!("Keychain passwords can't contain line breaks");
+    }
+
+    let mut child = Command::new("security")
+        .arg("-i")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run security")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(
+            stdin,
+            "add-generic-password -U -s {} -a {} -w {}",
+            quote(SERVICE),
+            quote(account),
+            quote(password)
+        )?;
+    }
+
+    // Interactive mode reports a failed command on stderr but may still
+    // exit 0
+    let output = child.wait_with_output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !stderr.trim().is_empty() {
+        anyhow::bail!("Failed to store password in keychain: {}", stderr.trim());
+    }
+    Ok(())
+}
+
+/// A double-quoted word for `security -i`'s command line.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn delete_password(account: &str) -> Result<()> {
+    crate::utils::require_macos()?;
+
+    let (_, stderr, success) = run_command_with_status(
+        "security",
+        &["delete-generic-password", "-s", SERVICE, "-a", account],
+    )?;
+    if !success {
+        anyhow::bail!("Failed to delete keychain item: {}", stderr.trim());
+    }
+    Ok(())
+}
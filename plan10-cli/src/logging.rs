@@ -0,0 +1,85 @@
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use tracing::Level;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, Layer};
+
+/// Where the log files are kept, next to the monitor log.
+pub const LOG_DIR: &str = "~/logs";
+
+/// Log file name; the appender adds the date, e.g. plan10-cli.log.2026-10-16.
+pub const LOG_FILE: &str = "plan10-cli.log";
+
+/// Days of log files kept before the oldest is deleted.
+const KEEP_DAYS: usize = 14;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One readable line per event
+    Text,
+    /// One JSON object per event, for wrappers that collect stderr
+    Json,
+}
+
+/// The terminal level for the number of `-v` flags.
+fn terminal_level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::INFO,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Install the global subscriber. Events go to stderr at the level picked
+/// with `-v`/`-vv` (`RUST_LOG` overrides it), so they never mix with
+/// `--output json` on stdout. `--log-format json` makes them JSON lines.
+/// Independently of `-v`, everything plan10 logs at debug and above goes to
+/// a daily file under `~/logs`, so a failed deploy can be looked into after
+/// the fact.
+pub fn init(verbosity: u8, format: LogFormat) {
+    // Other crates only get a say about warnings, unless RUST_LOG asks
+    let terminal_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("warn,plan10={}", terminal_level(verbosity))));
+    let terminal = match format {
+        LogFormat::Text => fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_ansi(std::io::stderr().is_terminal())
+            .without_time()
+            .with_target(false)
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_writer(std::io::stderr)
+            .boxed(),
+    };
+
+    // The file stays text whatever the terminal gets, so every line in it
+    // reads the same way to `tail`
+    let file = log_file().map(|appender| {
+        fmt::layer()
+            .with_writer(appender)
+            .with_ansi(false)
+            .with_filter(Targets::new().with_target("plan10", Level::DEBUG).with_default(Level::WARN))
+    });
+
+    // A second init (there is none today) keeps the first subscriber
+    let _ = tracing_subscriber::registry()
+        .with(terminal.with_filter(terminal_filter))
+        .with(file)
+        .try_init();
+}
+
+/// The rotating appender, or None when `~/logs` can't be written; logging
+/// to the terminal still works then.
+fn log_file() -> Option<RollingFileAppender> {
+    let dir = PathBuf::from(shellexpand::tilde(LOG_DIR).into_owned());
+    std::fs::create_dir_all(&dir).ok()?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE)
+        .max_log_files(KEEP_DAYS)
+        .build(dir)
+        .ok()
+}
@@ -1,11 +1,21 @@
 use clap::{Parser, Subcommand};
 use anyhow::Result;
 use colored::*;
-use std::env;
 
+mod alerts;
 mod commands;
 mod config;
+mod history;
+mod keychain;
+mod logging;
+mod nms;
+mod schema;
+mod sensors;
+mod notifications;
+mod proxy;
+mod snapshot;
 mod ssh;
+mod ssh_config;
 mod utils;
 
 use commands::{client, server, shared};
@@ -26,9 +36,31 @@ struct Cli {
     #[arg(short, long, global = true, env = "PLAN10_CONFIG")]
     config: Option<String>,
 
-    /// Verbose output
-    #[arg(short, long, global = true)]
-    verbose: bool,
+    /// Configuration profile (e.g. work, home), kept in profiles/<name>.toml
+    /// in the config directory
+    #[arg(short = 'P', long, global = true, env = "PLAN10_PROFILE", conflicts_with = "config")]
+    profile: Option<String>,
+
+    /// Workspace (fleet) to operate on
+    #[arg(short, long, global = true, env = "PLAN10_WORKSPACE")]
+    workspace: Option<String>,
+
+    /// Verbose output; -vv also logs what remote commands printed
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Format of the log lines written to stderr
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    log_format: logging::LogFormat,
+
+    /// Output format; json is supported by status, monitor temp/sensors/network/battery/power/assertions,
+    /// client list and server services
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    output: OutputFormat,
+
+    /// Shorthand for --output json
+    #[arg(long, global = true)]
+    json: bool,
 
     /// Force server mode (run commands locally)
     #[arg(long, global = true)]
@@ -56,11 +88,26 @@ enum Commands {
     /// Quick status check
     Status {
         /// Target server (if not specified, runs locally)
-        #[arg(short, long)]
+        #[arg(short = 'H', long)]
         host: Option<String>,
+        /// Show every enabled server with this tag
+        #[arg(long, conflicts_with = "host")]
+        tag: Option<String>,
+        /// Show every enabled server in this group
+        #[arg(long, conflicts_with_all = ["host", "tag"])]
+        group: Option<String>,
         /// Show detailed status
         #[arg(short, long)]
         detailed: bool,
+        /// Print the last saved local status in one line (for shell prompts)
+        #[arg(long, conflicts_with_all = ["host", "tag", "group", "detailed"])]
+        cached: bool,
+        /// Maximum age in seconds before --cached reports the status as stale
+        #[arg(long, default_value = "60", requires = "cached")]
+        max_age: u64,
+        /// Also measure SSH throughput with a small download from the server
+        #[arg(long, conflicts_with = "cached")]
+        probe: bool,
     },
 
     /// Interactive setup wizard
@@ -70,6 +117,33 @@ enum Commands {
         mode: SetupMode,
     },
 
+    /// Add a note to a host's history timeline, or list existing notes
+    Annotate {
+        /// Note text (e.g. "replaced charger")
+        #[arg(required_unless_present = "list")]
+        text: Option<String>,
+        /// Server the note is about (defaults to this machine)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// List annotations instead of adding one
+        #[arg(short, long)]
+        list: bool,
+        /// Only list annotations from this period (e.g. 30d)
+        #[arg(long, requires = "list")]
+        since: Option<String>,
+    },
+
+    /// Suggest features that fit this setup but are not in use yet
+    Tips {
+        /// Also inspect this server (defaults to this machine on macOS)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+
+    /// Manage workspaces (independent fleets)
+    #[command(subcommand)]
+    Workspace(WorkspaceCommands),
+
     /// Show configuration
     Config {
         /// Show configuration for specific server
@@ -78,6 +152,8 @@ enum Commands {
         /// Edit configuration
         #[arg(short, long)]
         edit: bool,
+        #[command(subcommand)]
+        command: Option<ConfigCommands>,
     },
 }
 
@@ -86,14 +162,21 @@ enum ClientCommands {
     /// Deploy Plan 10 to a server
     Deploy {
         /// Target server hostname or IP
-        #[arg(short = 'H', long)]
-        host: String,
-        /// SSH user
+        #[arg(short = 'H', long, required_unless_present_any = ["tag", "group"])]
+        host: Option<String>,
+        /// Deploy to every enabled server with this tag
+        #[arg(long, conflicts_with = "host")]
+        tag: Option<String>,
+        /// Deploy to every enabled server in this group
+        #[arg(long, conflicts_with_all = ["host", "tag"])]
+        group: Option<String>,
+        /// SSH user for a host that isn't configured
         #[arg(short, long)]
         user: Option<String>,
-        /// SSH port
-        #[arg(short, long, default_value = "22")]
-        port: u16,
+        /// SSH port for a host that isn't configured (defaults to
+        /// client.defaults.port, then 22)
+        #[arg(short, long)]
+        port: Option<u16>,
         /// Deploy everything (scripts, configs, services)
         #[arg(short, long)]
         all: bool,
@@ -108,8 +191,14 @@ enum ClientCommands {
     /// Manage remote servers
     Manage {
         /// Target server
-        #[arg(short = 'H', long)]
-        host: String,
+        #[arg(short = 'H', long, required_unless_present_any = ["tag", "group"])]
+        host: Option<String>,
+        /// Act on every enabled server with this tag
+        #[arg(long, conflicts_with = "host")]
+        tag: Option<String>,
+        /// Act on every enabled server in this group
+        #[arg(long, conflicts_with_all = ["host", "tag"])]
+        group: Option<String>,
         #[command(subcommand)]
         action: ManageActions,
     },
@@ -117,8 +206,14 @@ enum ClientCommands {
     /// Run diagnostics on remote server
     Diagnose {
         /// Target server
-        #[arg(short = 'H', long)]
-        host: String,
+        #[arg(short = 'H', long, required_unless_present_any = ["tag", "group"])]
+        host: Option<String>,
+        /// Diagnose every enabled server with this tag
+        #[arg(long, conflicts_with = "host")]
+        tag: Option<String>,
+        /// Diagnose every enabled server in this group
+        #[arg(long, conflicts_with_all = ["host", "tag"])]
+        group: Option<String>,
         /// Focus on battery diagnostics
         #[arg(short, long)]
         battery: bool,
@@ -135,6 +230,9 @@ enum ClientCommands {
         /// Show detailed server information
         #[arg(short, long)]
         detailed: bool,
+        /// Only show servers at this site
+        #[arg(long)]
+        site: Option<String>,
     },
 
     /// Add a new server configuration
@@ -144,18 +242,291 @@ enum ClientCommands {
         /// Server hostname or IP
         #[arg(short = 'H', long)]
         host: String,
-        /// SSH user
+        /// SSH user (defaults to the User from ~/.ssh/config, then
+        /// client.defaults.user, then the local user)
+        #[arg(short, long)]
+        user: Option<String>,
+        /// SSH port (defaults to client.defaults.port, then 22)
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// SSH private key (defaults to client.defaults.ssh_key)
+        #[arg(short = 'i', long = "key")]
+        ssh_key: Option<String>,
+        /// Site or location of the server
+        #[arg(long)]
+        site: Option<String>,
+        /// Fallback address (LAN IP, VPN IP, public DNS); may be repeated
+        #[arg(long = "address")]
+        addresses: Vec<String>,
+        /// Address family preference for dual-stack hosts
+        #[arg(long, value_enum, default_value = "auto")]
+        family: config::AddressFamily,
+        /// Operational notes about this machine
+        #[arg(long)]
+        notes: Option<String>,
+        /// Runbook URL for this machine
+        #[arg(long)]
+        runbook: Option<String>,
+        /// Reach the server through this bastion (server name or [user@]host[:port])
+        #[arg(short = 'J', long = "jump")]
+        jump_host: Option<String>,
+        /// Forward the local ssh-agent to commands run on the server
+        #[arg(short = 'A', long)]
+        forward_agent: bool,
+        /// Tag for targeting groups of servers with --tag; may be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Add the server to this group, taking its default user
+        #[arg(long)]
+        group: Option<String>,
+    },
+
+    /// Remove server configuration
+    Remove {
+        /// Server name
+        name: String,
+    },
+
+    /// Rename a server, updating the default server, groups, tunnels and
+    /// jump hosts that refer to it
+    Rename {
+        /// Current server name
+        old: String,
+        /// New server name
+        new: String,
+    },
+
+    /// Include a disabled server in fleet-wide operations again
+    Enable {
+        /// Server name
+        name: String,
+    },
+
+    /// Leave a server out of fleet-wide operations, keeping its configuration
+    Disable {
+        /// Server name
+        name: String,
+    },
+
+    /// Show or update a server's notes and runbook link
+    Notes {
+        /// Server name
+        name: String,
+        /// New notes text (replaces the existing notes)
+        text: Option<String>,
+        /// Runbook URL
+        #[arg(long)]
+        runbook: Option<String>,
+        /// Remove the notes and runbook link
+        #[arg(long, conflicts_with_all = ["text", "runbook"])]
+        clear: bool,
+    },
+
+    /// Sync servers from an MDM inventory export (Jamf or Mosyle CSV)
+    ImportMdm {
+        /// Path to the CSV export
+        file: String,
+        /// Export format
+        #[arg(short, long, value_enum, default_value = "auto")]
+        format: commands::client::inventory::InventoryFormat,
+        /// SSH user for newly imported servers
         #[arg(short, long)]
         user: String,
-        /// SSH port
+        /// SSH port for newly imported servers
         #[arg(short, long, default_value = "22")]
         port: u16,
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
     },
 
-    /// Remove server configuration
+    /// Write the server inventory as JSON, without machine-local settings
+    Export {
+        /// File to write instead of standard output
+        #[arg(short, long)]
+        file: Option<String>,
+    },
+
+    /// Import servers from an exported inventory, ~/.ssh/config or known_hosts
+    Import {
+        /// Inventory file written by `client export`
+        #[arg(conflicts_with_all = ["from_ssh_config", "from_known_hosts", "all", "user", "tags"])]
+        file: Option<String>,
+        /// Offer the concrete Host aliases in ~/.ssh/config
+        #[arg(long, required_unless_present_any = ["from_known_hosts", "file"])]
+        from_ssh_config: bool,
+        /// Offer the hosts in ~/.ssh/known_hosts
+        #[arg(long)]
+        from_known_hosts: bool,
+        /// Import every host listed without asking
+        #[arg(short, long)]
+        all: bool,
+        /// SSH user for hosts without a User in ~/.ssh/config
+        #[arg(short, long)]
+        user: Option<String>,
+        /// Extra tag for imported servers; may be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check all enabled servers and send batched notifications
+    Check {
+        /// Show notifications without sending them
+        #[arg(long)]
+        dry_run: bool,
+        /// Only check servers at this site
+        #[arg(long)]
+        site: Option<String>,
+    },
+
+    /// Run a shell command on every enabled server in parallel
+    Exec {
+        /// Command to run
+        command: String,
+        /// Only run on servers at this site
+        #[arg(long)]
+        site: Option<String>,
+        /// Only run on servers with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only run on servers in this group
+        #[arg(long)]
+        group: Option<String>,
+    },
+
+    /// Manage the settings of a server's own Plan 10 configuration
+    #[command(subcommand)]
+    Config(RemoteConfigCommands),
+
+    /// Check that servers answer over SSH, recording when they were last seen
+    Ping {
+        /// Target server (defaults to client.default_server)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Ping every enabled server with this tag
+        #[arg(long, conflicts_with = "host")]
+        tag: Option<String>,
+        /// Ping every enabled server in this group
+        #[arg(long, conflicts_with_all = ["host", "tag"])]
+        group: Option<String>,
+        /// Ping every enabled server
+        #[arg(long, conflicts_with_all = ["host", "tag", "group"])]
+        all: bool,
+    },
+
+    /// Reports built from the history this client records
+    #[command(subcommand)]
+    Report(ReportCommands),
+
+    /// Show where servers differ from what deploy and config push would produce
+    ConfigDiff {
+        /// Target server
+        #[arg(short = 'H', long, required_unless_present_any = ["tag", "group"])]
+        host: Option<String>,
+        /// Check every enabled server with this tag
+        #[arg(long, conflicts_with = "host")]
+        tag: Option<String>,
+        /// Check every enabled server in this group
+        #[arg(long, conflicts_with_all = ["host", "tag"])]
+        group: Option<String>,
+    },
+
+    /// Manage named port forwards through a server
+    #[command(subcommand)]
+    Tunnel(TunnelCommands),
+
+    /// Open an interactive shell on a server (via mosh when resilient)
+    Shell {
+        /// Target server
+        #[arg(short = 'H', long)]
+        host: String,
+        /// Use mosh even when ssh.resilient is off
+        #[arg(long)]
+        mosh: bool,
+        /// Always use plain ssh
+        #[arg(long, conflicts_with = "mosh")]
+        no_mosh: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TunnelCommands {
+    /// Define a tunnel
+    Add {
+        /// Tunnel name
+        name: String,
+        /// Server to tunnel through
+        #[arg(short = 'H', long)]
+        host: String,
+        /// Listen locally and forward through the server (PORT:HOST:HOSTPORT)
+        #[arg(short = 'L', long, conflicts_with = "remote", required_unless_present = "remote")]
+        local: Option<String>,
+        /// Listen on the server and forward back through this machine (PORT:HOST:HOSTPORT)
+        #[arg(short = 'R', long)]
+        remote: Option<String>,
+    },
+    /// Stop and delete a tunnel
     Remove {
-        /// Server name
+        /// Tunnel name
+        name: String,
+    },
+    /// List tunnels and whether they are running
+    List,
+    /// Start a tunnel in the background
+    Start {
+        /// Tunnel name
         name: String,
+        /// Stay in the foreground until interrupted
+        #[arg(long)]
+        foreground: bool,
+    },
+    /// Stop a running tunnel
+    Stop {
+        /// Tunnel name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommands {
+    /// Uptime percentage and downtime windows per server, from the polls of
+    /// client check and client ping
+    Availability {
+        /// Calendar month to report on, as YYYY-MM (this month when given
+        /// without a value; default: the last 30 days)
+        #[arg(long, value_name = "YYYY-MM", num_args = 0..=1, default_missing_value = "")]
+        month: Option<String>,
+        /// Only this server (default: every enabled server)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RemoteConfigCommands {
+    /// Push thresholds, services, alert routing and power profile to a server
+    Push {
+        /// Target server name or host
+        #[arg(short = 'H', long)]
+        host: String,
+        /// Overwrite settings that were changed on the server
+        #[arg(short, long)]
+        force: bool,
+        /// Show the changes without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show a server's managed settings and how they differ from this client's
+    Pull {
+        /// Target server name or host
+        #[arg(short = 'H', long)]
+        host: String,
+        /// Save the server's settings as this client's configuration
+        #[arg(long)]
+        adopt: bool,
     },
 }
 
@@ -216,6 +587,78 @@ enum ServerCommands {
         #[command(subcommand)]
         action: MaintenanceActions,
     },
+
+    /// Serve the latest local status over a read-only HTTP API
+    Api {
+        /// Address to listen on when not started by launchd
+        #[arg(short, long, default_value = "127.0.0.1:9110")]
+        listen: String,
+        /// Install a LaunchDaemon that owns the socket from boot (requires sudo)
+        #[arg(long)]
+        install: bool,
+    },
+
+    /// Expose battery, power, temperature and system metrics for Prometheus
+    Exporter {
+        /// Port to serve /metrics on
+        #[arg(short, long, default_value = "9100")]
+        port: u16,
+        /// Address to bind; 0.0.0.0 lets other machines scrape
+        #[arg(short, long, default_value = "127.0.0.1")]
+        bind: String,
+    },
+
+    /// Ping the gateway or a probe host and try to recover lost connectivity
+    Watchdog {
+        /// Host to ping (default: watchdog.probe, else the default gateway)
+        #[arg(short, long)]
+        probe: Option<String>,
+        /// Seconds between probes (default: watchdog.interval)
+        #[arg(short, long)]
+        interval: Option<u64>,
+        /// Install a LaunchDaemon that keeps the watchdog running (requires sudo)
+        #[arg(long, conflicts_with = "outages")]
+        install: bool,
+        /// List recorded outages instead of watching
+        #[arg(long)]
+        outages: bool,
+        /// How far back --outages looks (e.g. 12h, 7d, 2w)
+        #[arg(long, default_value = "7d", requires = "outages")]
+        since: String,
+    },
+
+    /// Sample temperature, battery and power every server.monitoring_interval
+    /// seconds into ~/logs/plan10-monitor.jsonl and check the alert rules
+    MonitorDaemon {
+        /// Seconds between samples (default: server.monitoring_interval)
+        #[arg(short, long)]
+        interval: Option<u64>,
+        /// Install a LaunchAgent that keeps the daemon running
+        #[arg(long)]
+        install: bool,
+    },
+
+    /// Remove Plan 10 from this machine before it is retired or handed over
+    Decommission {
+        /// Hostname of this machine, to skip the interactive confirmation
+        #[arg(long)]
+        confirm: Option<String>,
+        /// Also disable automatic login
+        #[arg(long)]
+        disable_autologin: bool,
+        /// Also restore default power management settings
+        #[arg(long)]
+        reset_power: bool,
+        /// Where to write the final report
+        #[arg(short, long)]
+        report: Option<String>,
+        /// SSH private key used to sign the report
+        #[arg(long)]
+        sign_key: Option<String>,
+        /// Show what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -238,16 +681,20 @@ enum MonitorCommands {
         /// Show raw battery data
         #[arg(short, long)]
         raw: bool,
+        /// Show how capacity and cycle count changed over time, and when the
+        /// battery is projected to need replacing
+        #[arg(short, long, conflicts_with_all = ["detailed", "raw"])]
+        trend: bool,
+        /// How far back --trend looks (e.g. 12w, 365d)
+        #[arg(long, default_value = "52w", requires = "trend")]
+        since: String,
         /// Target server (remote monitoring)
         #[arg(short = 'H', long)]
         host: Option<String>,
     },
 
-    /// Power diagnostics
+    /// Power diagnostics; -v shows the full pmset output
     Power {
-        /// Show verbose output
-        #[arg(short, long)]
-        verbose: bool,
         /// Focus on battery issues
         #[arg(short, long)]
         battery: bool,
@@ -260,13 +707,136 @@ enum MonitorCommands {
         /// Show recommended fixes
         #[arg(short, long)]
         fixes: bool,
+        /// Show when power settings changed, from recorded history
+        #[arg(long)]
+        changes: bool,
+        /// How far back to look for changes (e.g. 12h, 7d, 2w)
+        #[arg(long, default_value = "7d", requires = "changes")]
+        since: String,
+        /// Target server (remote monitoring)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+
+    /// Show network interfaces: link state, addresses, throughput and Wi-Fi signal
+    Network {
+        /// Only show this interface (e.g. en0)
+        #[arg(short, long)]
+        interface: Option<String>,
+        /// Include inactive and virtual interfaces
+        #[arg(short, long)]
+        all: bool,
+        /// Target server (remote monitoring)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+
+    /// Show sleep, wake, assertion and shutdown events from the power management log
+    Events {
+        /// How far back to look (e.g. 30m, 24h, 7d)
+        #[arg(long, default_value = "24h")]
+        since: String,
+        /// Only show these kinds of event (repeatable)
+        #[arg(short, long, value_enum)]
+        kind: Vec<shared::power_events::PowerEventKind>,
+        /// Only show assertions held by a process whose name contains this
+        #[arg(short, long)]
+        process: Option<String>,
+        /// Target server (remote monitoring)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+
+    /// Show the power assertions keeping the machine awake, by owning process
+    Assertions {
+        /// Refresh until Ctrl+C, marking assertions taken since the last refresh
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between refreshes with --watch
+        #[arg(short, long, default_value = "5", requires = "watch")]
+        interval: u64,
+        /// Target server (remote monitoring)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+
+    /// Show the Plan 10 services' logs
+    Logs {
+        /// Only show these services' logs (repeatable; default: all)
+        #[arg(short, long, value_enum)]
+        service: Vec<shared::logs::LogService>,
+        /// Keep printing new lines until Ctrl+C
+        #[arg(short, long)]
+        follow: bool,
+        /// Lines to show from the end of each log
+        #[arg(short = 'n', long, default_value = "50")]
+        lines: usize,
         /// Target server (remote monitoring)
         #[arg(short = 'H', long)]
         host: Option<String>,
     },
 
+    /// Show disk SMART health, wear and I/O rates
+    Disk {
+        /// Only show this disk (e.g. disk0)
+        #[arg(short, long)]
+        device: Option<String>,
+        /// Target server (remote monitoring)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+
+    /// List every hardware sensor: temperatures, fans, power, voltages and currents
+    Sensors {
+        /// Only show one kind of sensor
+        #[arg(short, long, value_enum)]
+        kind: Option<sensors::SensorKind>,
+        /// Target server (needs plan10 installed there)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+
+    /// Show recorded battery, temperature, CPU and power source samples
+    History {
+        /// How far back to look (e.g. 12h, 7d, 2w)
+        #[arg(long, default_value = "24h")]
+        since: String,
+        /// Only show one metric
+        #[arg(short, long, value_enum)]
+        metric: Option<HistoryMetric>,
+        /// Print CSV for spreadsheets instead of a table
+        #[arg(long)]
+        csv: bool,
+        /// Target server (history recorded from this machine)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+
+    /// Write the recorded monitoring samples to a file for spreadsheets and
+    /// analysis tools
+    Export {
+        /// File format
+        #[arg(short, long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+        /// How far back to export (e.g. 12h, 7d, 2w)
+        #[arg(long, default_value = "7d")]
+        since: String,
+        /// File to write (default: standard output); --output is the global format flag
+        #[arg(short = 'o', long = "file", value_name = "FILE")]
+        file: Option<String>,
+        /// Target server (history recorded from this machine)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Export every host in the history
+        #[arg(long, conflicts_with = "host")]
+        all: bool,
+    },
+
     /// System overview
     System {
+        /// How many of the busiest processes to list, by CPU and by memory (0 to hide)
+        #[arg(long, default_value = "5")]
+        top: usize,
         /// Target server (remote monitoring)
         #[arg(short = 'H', long)]
         host: Option<String>,
@@ -280,13 +850,108 @@ enum MonitorCommands {
         /// What to monitor
         #[arg(value_enum, default_value = "all")]
         monitor: WatchType,
-        /// Target server (remote monitoring)
+        /// Target server (remote monitoring); repeat to watch several hosts side by side
         #[arg(short = 'H', long)]
-        host: Option<String>,
+        host: Vec<String>,
+        /// Watch every enabled server
+        #[arg(long, conflicts_with = "host")]
+        all: bool,
+        /// grid, rows, columns, table, or a layout from [watch_layouts]
+        #[arg(long, default_value = "grid")]
+        layout: String,
+        /// Highlight values that changed since the previous refresh
+        #[arg(long)]
+        diff: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the configuration file schema
+    Schema {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "json-schema")]
+        format: SchemaFormat,
+    },
+    /// Check the configuration file, including that SSH keys exist and
+    /// servers resolve and answer
+    Validate {
+        /// Skip the DNS and port checks
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Print one setting, addressed by a dotted path like ssh.key_path
+    Get {
+        /// Dotted path of the setting
+        key: String,
+    },
+    /// Change one setting, e.g. `config set server.temp_threshold 85`
+    Set {
+        /// Dotted path of the setting
+        key: String,
+        /// New value; JSON for numbers, booleans, lists and null
+        value: String,
     },
+    /// Clear an optional setting
+    Unset {
+        /// Dotted path of the setting
+        key: String,
+    },
+    /// Show the settings overridden by PLAN10_* environment variables
+    Env,
+    /// Manage secrets kept in the macOS login keychain
+    #[command(subcommand)]
+    Secret(SecretCommands),
 }
 
+/// Secrets are named `key:<path>` (SSH key passphrase), `ssh:<user>@<host>`
+/// (login password), `sudo:<user>@<host>` (sudo password), or anything else
+/// for values referenced from the config as `keychain:<name>`.
 #[derive(Subcommand)]
+enum SecretCommands {
+    /// Store a secret, read from the terminal or stdin
+    Set {
+        /// Secret name
+        name: String,
+    },
+    /// Print a stored secret
+    Get {
+        /// Secret name
+        name: String,
+    },
+    /// Delete a stored secret
+    #[command(alias = "remove")]
+    Rm {
+        /// Secret name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkspaceCommands {
+    /// List workspaces
+    List,
+    /// Create an empty workspace
+    Create {
+        /// Workspace name
+        name: String,
+        /// Short description (e.g. the client it belongs to)
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+    /// Remove a workspace and all of its servers
+    Remove {
+        /// Workspace name
+        name: String,
+    },
+    /// Set the workspace used when --workspace is not given
+    Use {
+        /// Workspace name; omit to go back to the top-level fleet
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Clone, Copy)]
 enum ManageActions {
     /// Start services on remote server
     Start,
@@ -334,7 +999,7 @@ enum MaintenanceActions {
     Backup {
         /// Backup destination
         #[arg(short, long)]
-        output: Option<String>,
+        file: Option<String>,
     },
     /// Restore configuration
     Restore {
@@ -353,7 +1018,7 @@ enum SetupMode {
     Both,
 }
 
-#[derive(clap::ValueEnum, Clone)]
+#[derive(clap::ValueEnum, Clone, PartialEq)]
 enum WatchType {
     All,
     Temp,
@@ -362,46 +1027,115 @@ enum WatchType {
     System,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryMetric {
+    /// Battery charge in percent
+    Battery,
+    /// CPU die temperature
+    Temp,
+    /// CPU usage in percent
+    Cpu,
+    /// Power source (ac or battery)
+    Power,
+    /// Pages swapped out per minute since the previous sample
+    Swap,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// Structured JSON for jq and dashboards
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum SchemaFormat {
+    /// JSON Schema for editor validation and completion
+    JsonSchema,
+    /// Markdown reference documentation
+    Markdown,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    
-    // Initialize logging
-    if cli.verbose {
-        env::set_var("RUST_LOG", "debug");
-    }
+    logging::init(cli.verbose, cli.log_format);
+    tracing::debug!(args = ?std::env::args().collect::<Vec<_>>(), "plan10 {}", env!("CARGO_PKG_VERSION"));
+    let verbose = cli.verbose > 0;
     
     // Load configuration
-    let config = Config::load(cli.config.as_deref())?;
+    let mut config = Config::load(cli.config.as_deref(), cli.profile.as_deref())?;
+
+    // Select the workspace (fleet) to operate on
+    let workspace = cli.workspace.clone().or_else(|| config.client.default_workspace.clone());
+    if let Some(workspace) = workspace {
+        config.use_workspace(&workspace)?;
+    }
     
     // Determine execution mode
-    let execution_mode = determine_execution_mode(&cli);
+    let execution_mode = determine_execution_mode(&cli, &config);
     
-    if cli.verbose {
-        eprintln!("{} Running in {:?} mode", "INFO".blue(), execution_mode);
-    }
+    tracing::debug!(
+        mode = ?execution_mode,
+        profile = config.profile(),
+        workspace = config.workspace(),
+        "Configuration loaded"
+    );
     
+    let output = if cli.json { OutputFormat::Json } else { cli.output };
+
     // Execute command
-    match cli.command {
+    let result = match cli.command {
         Commands::Client(cmd) => {
-            client::execute(cmd, &config, cli.verbose).await
+            client::execute(cmd, &config, output, verbose).await
         }
         Commands::Server(cmd) => {
-            server::execute(cmd, &config, cli.verbose).await
+            server::execute(cmd, &config, output, verbose).await
         }
         Commands::Monitor(cmd) => {
-            shared::monitor::execute(cmd, &config, execution_mode, cli.verbose).await
+            shared::monitor::execute(cmd, &config, execution_mode, output, verbose).await
         }
-        Commands::Status { host, detailed } => {
-            shared::status::execute(host, detailed, &config, execution_mode, cli.verbose).await
+        Commands::Status { host, tag, group, detailed, cached, max_age, probe } => {
+            shared::status::execute(host, tag, group, detailed, cached, max_age, probe, &config, execution_mode, output, verbose).await
         }
         Commands::Setup { mode } => {
-            shared::setup::execute(mode, &config, cli.verbose).await
+            shared::setup::execute(mode, &config, verbose).await
         }
-        Commands::Config { server, edit } => {
-            shared::config_cmd::execute(server, edit, &config, cli.verbose).await
+        Commands::Annotate { text, host, list, since } => {
+            shared::annotate::execute(host, text, list, since, &config, verbose).await
         }
+        Commands::Tips { host } => {
+            shared::tips::execute(host, &config, verbose).await
+        }
+        Commands::Workspace(cmd) => {
+            shared::workspace::execute(cmd, &config, verbose).await
+        }
+        Commands::Config { server, edit, command } => {
+            shared::config_cmd::execute(server, edit, command, &config, verbose).await
+        }
+    };
+
+    // Record which servers answered, whether or not the command succeeded
+    let seen = ssh::take_seen_servers();
+    if !seen.is_empty() {
+        if let Err(e) = config.save_last_seen(&seen) {
+            eprintln!("{} Could not save last seen times: {:#}", "WARNING".yellow(), e);
+        }
+    }
+
+    if let Err(e) = &result {
+        tracing::debug!("Command failed: {:#}", e);
     }
+    result
 }
 
 #[derive(Debug, Clone)]
@@ -411,13 +1145,17 @@ pub enum ExecutionMode {
     Auto,
 }
 
-fn determine_execution_mode(cli: &Cli) -> ExecutionMode {
+/// Where commands without `--host` run. A Mac runs them locally, as it may
+/// be the server itself; any other machine can only be a client, so it
+/// targets `client.default_server` when one is set. `--client-mode` asks for
+/// the default server on a Mac too.
+fn determine_execution_mode(cli: &Cli, config: &Config) -> ExecutionMode {
+    let default_server = config.client.default_server.clone();
     if cli.server_mode {
         ExecutionMode::Local
-    } else if cli.client_mode {
-        ExecutionMode::Auto
+    } else if let (Some(host), true) = (default_server, cli.client_mode || !cfg!(target_os = "macos")) {
+        ExecutionMode::Remote { host }
     } else {
-        // Auto-detect based on environment or command context
         ExecutionMode::Auto
     }
 }
\ No newline at end of file
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::commands::utils::*;
+use crate::config::{NagiosConfig, NmsConfig, ZabbixConfig};
+use crate::notifications::{EventKind, FleetEvent};
+
+/// Nagios plugin states, also used as the Zabbix `plan10.status` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckState {
+    Ok = 0,
+    Warning = 1,
+    Critical = 2,
+}
+
+/// The state and one-line message of a single server after a fleet check.
+pub struct CheckResult {
+    pub host: String,
+    pub state: CheckState,
+    pub message: String,
+}
+
+impl CheckResult {
+    pub fn from_events(host: &str, events: &[FleetEvent]) -> Self {
+        let state = events.iter()
+            .map(|event| match event.kind {
+                EventKind::Unreachable | EventKind::LowBattery | EventKind::ProfileDrift => CheckState::Critical,
+                _ => CheckState::Warning,
+            })
+            .max()
+            .unwrap_or(CheckState::Ok);
+
+        let message = if events.is_empty() {
+            "All checks passed".to_string()
+        } else {
+            events.iter().map(|e| e.detail.as_str()).collect::<Vec<_>>().join("; ")
+        };
+
+        Self {
+            host: host.to_string(),
+            state,
+            message,
+        }
+    }
+}
+
+impl NmsConfig {
+    pub fn is_configured(&self) -> bool {
+        self.zabbix.is_some() || self.nagios.is_some()
+    }
+
+    /// Submit results to every configured endpoint. A failing endpoint is
+    /// reported but does not stop delivery to the others.
+    pub fn push(&self, results: &[CheckResult], verbose: bool) {
+        if let Some(zabbix) = &self.zabbix {
+            match push_zabbix(zabbix, results) {
+                Ok(()) => print_verbose(&format!("Sent {} result(s) to Zabbix at {}", results.len(), zabbix.server), verbose),
+                Err(e) => print_warning(&format!("Zabbix push failed: {}", e)),
+            }
+        }
+
+        if let Some(nagios) = &self.nagios {
+            match push_nagios(nagios, results) {
+                Ok(()) => print_verbose(&format!("Sent {} result(s) to NSCA at {}", results.len(), nagios.host), verbose),
+                Err(e) => print_warning(&format!("Nagios push failed: {}", e)),
+            }
+        }
+    }
+}
+
+fn push_zabbix(config: &ZabbixConfig, results: &[CheckResult]) -> Result<()> {
+    // zabbix_sender input format: <host> <key> <value>, values quoted
+    let mut input = String::new();
+    for result in results {
+        input.push_str(&format!("\"{}\" plan10.status {}\n", result.host, result.state as u8));
+        input.push_str(&format!("\"{}\" plan10.message \"{}\"\n", result.host, result.message.replace('"', "'")));
+    }
+
+    let port = config.port.to_string();
+    run_with_stdin(
+        &config.sender,
+        &["-z", &config.server, "-p", &port, "-i", "-"],
+        &input,
+    )
+}
+
+fn push_nagios(config: &NagiosConfig, results: &[CheckResult]) -> Result<()> {
+    // send_nsca input format: <host>\t<service>\t<code>\t<output>
+    let mut input = String::new();
+    for result in results {
+        input.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            result.host,
+            config.service,
+            result.state as u8,
+            result.message.replace(['\t', '\n'], " ")
+        ));
+    }
+
+    let port = config.port.to_string();
+    let mut args = vec!["-H", config.host.as_str(), "-p", port.as_str()];
+    if let Some(config_file) = &config.config_file {
+        args.extend(["-c", config_file.as_str()]);
+    }
+    run_with_stdin(&config.send_nsca, &args, &input)
+}
+
+fn run_with_stdin(program: &str, args: &[&str], input: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(format!("Failed to run {}", program))?;
+
+    child.stdin.take()
+        .context("Failed to open stdin")?
+        .write_all(input.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let message = if stderr.trim().is_empty() { stdout } else { stderr };
+        anyhow::bail!("{} exited with {}: {}", program, output.status, message.trim());
+    }
+
+    Ok(())
+}
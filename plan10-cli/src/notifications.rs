@@ -0,0 +1,302 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::commands::utils::*;
+use crate::config::{Config, NotificationConfig, NotifierKind, SecretsStore};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum EventKind {
+    Unreachable,
+    OnBattery,
+    LowBattery,
+    BatteryRunningOut,
+    CaffeinateStopped,
+    HighTemperature,
+    ThermalThrottling,
+    DiskFull,
+    ProfileDrift,
+    ProfileReapplied,
+}
+
+impl EventKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            EventKind::Unreachable => "Server unreachable",
+            EventKind::OnBattery => "Running on battery power",
+            EventKind::LowBattery => "Battery level low",
+            EventKind::BatteryRunningOut => "Battery running out",
+            EventKind::CaffeinateStopped => "Caffeinate not running",
+            EventKind::HighTemperature => "CPU temperature high",
+            EventKind::ThermalThrottling => "CPU thermally throttled",
+            EventKind::DiskFull => "Disk nearly full",
+            EventKind::ProfileDrift => "Power settings reset by macOS update",
+            EventKind::ProfileReapplied => "Power settings reapplied after macOS update",
+        }
+    }
+
+    /// Cooldown key for this kind of event on one host.
+    fn key(&self, host: &str) -> String {
+        format!("{:?}/{}", self, host)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FleetEvent {
+    pub host: String,
+    pub kind: EventKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub kind: EventKind,
+    pub title: String,
+    pub fleet_wide: bool,
+    pub hosts: Vec<String>,
+    pub details: Vec<String>,
+}
+
+impl Notification {
+    pub fn summary(&self) -> String {
+        if self.fleet_wide {
+            format!("[fleet] {} on {} hosts: {}", self.title, self.hosts.len(), self.hosts.join(", "))
+        } else {
+            format!("{} on {}", self.title, self.hosts.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct NotificationState {
+    last_sent: HashMap<String, DateTime<Utc>>,
+}
+
+/// Coalesces per-host events into one notification per event kind and
+/// suppresses repeats of the same kind on the same host inside the
+/// configured cooldown.
+pub struct NotificationBatcher {
+    config: NotificationConfig,
+    notifiers: Vec<Box<dyn Notifier>>,
+    state: NotificationState,
+    state_path: Option<PathBuf>,
+}
+
+impl NotificationBatcher {
+    pub fn load(config: &Config) -> Self {
+        // Keep cooldowns separate per workspace so fleets never affect each other
+        let file_name = match config.workspace() {
+            Some(workspace) => format!("notifications-{}.json", workspace),
+            None => "notifications.json".to_string(),
+        };
+        let state_path = Config::state_dir().map(|dir| dir.join(file_name));
+        let state = state_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            config: config.notifications.clone(),
+            notifiers: notifiers(config),
+            state,
+            state_path,
+        }
+    }
+
+    pub fn has_notifiers(&self) -> bool {
+        !self.notifiers.is_empty()
+    }
+
+    /// Group events by kind; returns the notifications that are due and the
+    /// number of host events that were suppressed by the cooldown.
+    pub fn batch(&mut self, events: &[FleetEvent]) -> (Vec<Notification>, usize) {
+        let now = Utc::now();
+        let cooldown = chrono::Duration::seconds(self.config.cooldown as i64);
+        // Entries past their cooldown have no effect any more; dropping them
+        // keeps hosts that left the fleet from piling up in the state file
+        self.state.last_sent.retain(|_, last| now.signed_duration_since(*last) < cooldown);
+
+        // A host already notified about a kind doesn't hold back the news
+        // that another host now has the same problem
+        let mut grouped: BTreeMap<EventKind, Vec<&FleetEvent>> = BTreeMap::new();
+        let mut suppressed = 0;
+        for event in events {
+            if self.state.last_sent.contains_key(&event.kind.key(&event.host)) {
+                suppressed += 1;
+                continue;
+            }
+            grouped.entry(event.kind).or_default().push(event);
+        }
+
+        let mut notifications = Vec::new();
+        for (kind, events) in grouped {
+            let mut hosts: Vec<String> = events.iter().map(|e| e.host.clone()).collect();
+            hosts.sort();
+            hosts.dedup();
+
+            for host in &hosts {
+                self.state.last_sent.insert(kind.key(host), now);
+            }
+            notifications.push(Notification {
+                kind,
+                title: kind.title().to_string(),
+                fleet_wide: hosts.len() >= self.config.fleet_threshold,
+                hosts,
+                details: events.iter().map(|e| format!("{}: {}", e.host, e.detail)).collect(),
+            });
+        }
+
+        (notifications, suppressed)
+    }
+
+    pub fn deliver(&self, notifications: &[Notification], verbose: bool) -> Result<()> {
+        for notification in notifications {
+            print_warning(&notification.summary());
+            for detail in &notification.details {
+                print_verbose(detail, verbose);
+            }
+            self.send(notification)?;
+        }
+        Ok(())
+    }
+
+    /// Hand one notification to every notifier. A failing notifier doesn't
+    /// stop the others, but fails the call so the caller can retry later.
+    pub fn send(&self, notification: &Notification) -> Result<()> {
+        let failures: Vec<String> = self.notifiers.iter()
+            .filter_map(|notifier| notifier.send(notification).err().map(|e| format!("{}: {}", notifier.name(), e)))
+            .collect();
+        if !failures.is_empty() {
+            anyhow::bail!("Notification delivery failed ({})", failures.join("; "));
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .context("Failed to create state directory")?;
+        }
+
+        let content = serde_json::to_string_pretty(&self.state)?;
+        fs::write(path, content)
+            .context(format!("Failed to write notification state: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// A channel notifications are delivered through.
+pub trait Notifier {
+    /// Short description for error messages
+    fn name(&self) -> String;
+    fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// `notifications.webhook_url` followed by each of `alerts.notifiers`.
+pub fn notifiers(config: &Config) -> Vec<Box<dyn Notifier>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    if let Some(url) = &config.notifications.webhook_url {
+        notifiers.push(Box::new(WebhookNotifier { url: url.clone() }));
+    }
+    for notifier in &config.alerts.notifiers {
+        // `config validate` reports entries missing their url or command
+        match (notifier.kind, notifier.target().1) {
+            (NotifierKind::Webhook, Some(url)) => notifiers.push(Box::new(WebhookNotifier { url: url.to_string() })),
+            (NotifierKind::Command, Some(command)) => notifiers.push(Box::new(CommandNotifier { command: command.to_string() })),
+            (_, None) => {}
+        }
+    }
+    notifiers
+}
+
+fn payload(notification: &Notification) -> serde_json::Value {
+    serde_json::json!({
+        "text": notification.summary(),
+        "notification": notification,
+    })
+}
+
+/// How long a webhook may take before curl gives up, so one endpoint that
+/// hangs can't hold up `client check`.
+const WEBHOOK_TIMEOUT_SECS: &str = "15";
+
+struct WebhookNotifier {
+    /// May be a `keychain:` reference, resolved on each send
+    url: String,
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> String {
+        "webhook".to_string()
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let url = SecretsStore::resolve(&self.url)?;
+        // The URL (often with a token in it) goes to curl as a config file
+        // on stdin, so it doesn't show in `ps`
+        let mut child = Command::new("curl")
+            .args(["-sS", "--max-time", WEBHOOK_TIMEOUT_SECS, "-K", "-", "-X", "POST", "-H", "Content-Type: application/json", "-d"])
+            .arg(payload(notification).to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run curl for webhook delivery")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "url = \"{}\"", url.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Webhook delivery failed: {}", stderr.trim());
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a shell command with the notification JSON on stdin and its summary
+/// in `PLAN10_ALERT`, e.g. to call `say`, `osascript` or a pager CLI.
+struct CommandNotifier {
+    command: String,
+}
+
+impl Notifier for CommandNotifier {
+    fn name(&self) -> String {
+        format!("command '{}'", self.command)
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let mut child = Command::new("/bin/sh")
+            .args(["-c", &self.command])
+            .env("PLAN10_ALERT", notification.summary())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start notifier command")?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // The command may not read its input; that's not a failure
+            let _ = stdin.write_all(payload(notification).to_string().as_bytes());
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("exited with {}: {}", output.status, stderr.trim());
+        }
+        Ok(())
+    }
+}
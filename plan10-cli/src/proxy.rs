@@ -0,0 +1,217 @@
+//! Reaching servers through a SOCKS5 proxy or a ProxyCommand instead of a
+//! direct TCP connection.
+
+use anyhow::{Context, Result};
+use crate::config::{SecretsStore, SshConfig};
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::process::{Command, Stdio};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const SOCKS_VERSION: u8 = 5;
+const SOCKS_DEFAULT_PORT: u16 = 1080;
+
+/// The proxy configured under `[ssh]`, if any.
+#[derive(Debug, Clone)]
+pub enum Proxy {
+    Socks5 {
+        host: String,
+        port: u16,
+        credentials: Option<(String, String)>,
+    },
+    /// A shell command whose stdin/stdout carry the connection, as in
+    /// OpenSSH's `ProxyCommand`
+    Command(String),
+}
+
+impl Proxy {
+    pub fn from_config(ssh: &SshConfig) -> Result<Option<Self>> {
+        match (&ssh.proxy, &ssh.proxy_command) {
+            (Some(_), Some(_)) => anyhow::bail!("Set either ssh.proxy or ssh.proxy_command, not both"),
+            (Some(url), None) => parse_socks5(&SecretsStore::resolve(url)?).map(Some),
+            (None, Some(command)) => Ok(Some(Proxy::Command(SecretsStore::resolve(command)?))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+impl std::fmt::Display for Proxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Proxy::Socks5 { host, port, .. } => write!(f, "SOCKS5 proxy {}", crate::ssh::host_port(host, *port)),
+            Proxy::Command(_) => write!(f, "ProxyCommand"),
+        }
+    }
+}
+
+/// Parse `[socks5://][user:password@]host[:port]`. `socks5h://` is accepted
+/// too; names are always resolved by the proxy.
+fn parse_socks5(url: &str) -> Result<Proxy> {
+    let rest = url
+        .strip_prefix("socks5h://")
+        .or_else(|| url.strip_prefix("socks5://"))
+        .unwrap_or(url);
+    if rest.contains("://") {
+        anyhow::bail!("Unsupported proxy '{}': only socks5:// proxies are supported", url);
+    }
+
+    let (credentials, address) = match rest.rsplit_once('@') {
+        Some((userinfo, address)) => {
+            let (user, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+            (Some((user.to_string(), password.to_string())), address)
+        }
+        None => (None, rest),
+    };
+
+    let (host, port) = match address.rsplit_once(':') {
+        // A bare IPv6 literal without a port has colons too
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+            let port = port.parse().context(format!("Invalid port in proxy '{}'", url))?;
+            (host, port)
+        }
+        _ => (address, SOCKS_DEFAULT_PORT),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        anyhow::bail!("Invalid proxy '{}': no host", url);
+    }
+
+    Ok(Proxy::Socks5 { host: host.to_string(), port, credentials })
+}
+
+/// Ask an open SOCKS5 proxy connection to connect to `host:port`. Afterwards
+/// the stream carries the connection to the target.
+pub async fn socks5_connect(
+    stream: &mut TcpStream,
+    credentials: Option<&(String, String)>,
+    host: &str,
+    port: u16,
+) -> Result<()> {
+    // Greeting: offer no-auth, plus username/password when configured
+    let methods: &[u8] = if credentials.is_some() { &[0x00, 0x02] } else { &[0x00] };
+    let mut greeting = vec![SOCKS_VERSION, methods.len() as u8];
+    greeting.extend_from_slice(methods);
+    stream.write_all(&greeting).await?;
+
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await.context("SOCKS5 proxy closed the connection")?;
+    if choice[0] != SOCKS_VERSION {
+        anyhow::bail!("Not a SOCKS5 proxy");
+    }
+    match (choice[1], credentials) {
+        (0x00, _) => {}
+        (0x02, Some((user, password))) => {
+            // RFC 1929 username/password subnegotiation
+            let mut request = vec![0x01, user.len() as u8];
+            request.extend_from_slice(user.as_bytes());
+            request.push(password.len() as u8);
+            request.extend_from_slice(password.as_bytes());
+            stream.write_all(&request).await?;
+
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).await?;
+            if status[1] != 0x00 {
+                anyhow::bail!("SOCKS5 proxy rejected the username or password");
+            }
+        }
+        _ => anyhow::bail!("SOCKS5 proxy requires an authentication method that is not configured"),
+    }
+
+    let target = host.trim_start_matches('[').trim_end_matches(']');
+    let mut request = vec![SOCKS_VERSION, 0x01, 0x00];
+    match target.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(std::net::IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            request.push(0x03);
+            request.push(target.len() as u8);
+            request.extend_from_slice(target.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await.context("SOCKS5 proxy closed the connection")?;
+    if reply[1] != 0x00 {
+        anyhow::bail!("SOCKS5 proxy could not connect to {}: {}", crate::ssh::host_port(host, port), socks5_error(reply[1]));
+    }
+
+    // Skip the bound address the proxy reports
+    let address_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        other => anyhow::bail!("SOCKS5 proxy sent an unknown address type {}", other),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(())
+}
+
+fn socks5_error(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+/// Run a ProxyCommand for `host:port` and return a socket connected to its
+/// stdin and stdout. `%h` and `%p` in the command are replaced as in
+/// OpenSSH. The process exits once the socket is closed.
+pub fn spawn_proxy_command(command: &str, host: &str, port: u16) -> Result<UnixStream> {
+    let command = expand_proxy_command(command, host, port);
+    let (local, remote) = UnixStream::pair()?;
+
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .stdin(Stdio::from(OwnedFd::from(remote.try_clone()?)))
+        .stdout(Stdio::from(OwnedFd::from(remote)))
+        .spawn()
+        // Not the command itself, which may hold a secret from the keychain
+        .context(format!("Failed to run ProxyCommand for {}", crate::ssh::host_port(host, port)))?;
+
+    // Reap it when it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+
+    Ok(local)
+}
+
+fn expand_proxy_command(command: &str, host: &str, port: u16) -> String {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let mut expanded = String::new();
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => expanded.push_str(host),
+            Some('p') => expanded.push_str(&port.to_string()),
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
@@ -0,0 +1,368 @@
+use anyhow::Result;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+use serde::de::value::{Error, StrDeserializer};
+use serde_json::{json, Map, Value};
+
+use crate::config::Config;
+
+/// Placeholder handed to string visitors while tracing; it parses as a
+/// timestamp too, so `last_seen` and friends deserialize cleanly.
+const TRACE_STRING: &str = "1970-01-01T00:00:00Z";
+
+/// The shape of a config type as seen by serde.
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Bool,
+    Integer { unsigned: bool },
+    Float,
+    String,
+    Enum(&'static [&'static str]),
+    Optional(Box<Shape>),
+    List(Box<Shape>),
+    Map(Box<Shape>),
+    Struct(Vec<(&'static str, Shape)>),
+    Any,
+}
+
+impl Shape {
+    /// Derive the shape of `T` by driving its `Deserialize` impl with a
+    /// deserializer that records every type hint it receives.
+    pub fn of<T: DeserializeOwned>() -> Result<Shape> {
+        let mut shape = Shape::Any;
+        T::deserialize(Tracer { shape: &mut shape })
+            .map_err(|e| anyhow::anyhow!("Failed to trace config schema: {}", e))?;
+        Ok(shape)
+    }
+
+    fn nested(&self) -> &Shape {
+        match self {
+            Shape::Optional(inner) => inner.nested(),
+            shape => shape,
+        }
+    }
+}
+
+/// JSON Schema (draft-07) for the config file, for use with TOML language
+/// servers such as taplo.
+pub fn json_schema() -> Result<Value> {
+    let shape = Shape::of::<Config>()?;
+    let mut schema = shape_to_json(&shape, Some(&config_defaults()?));
+    if let Value::Object(object) = &mut schema {
+        object.insert("$schema".to_string(), json!("http://json-schema.org/draft-07/schema#"));
+        object.insert("title".to_string(), json!("Plan 10 configuration"));
+    }
+    Ok(schema)
+}
+
+/// Markdown reference with one table per TOML section.
+pub fn markdown() -> Result<String> {
+    let shape = Shape::of::<Config>()?;
+    let mut output = String::from("# Plan 10 Configuration Reference\n");
+    write_section(&mut output, "", &shape, Some(&config_defaults()?));
+    Ok(output)
+}
+
+fn config_defaults() -> Result<Value> {
+    let mut defaults = serde_json::to_value(Config::default())?;
+    // The default server name is the local hostname, which is no default at all
+    if let Some(server) = defaults.get_mut("server").and_then(Value::as_object_mut) {
+        server.remove("name");
+    }
+    Ok(defaults)
+}
+
+fn shape_to_json(shape: &Shape, default: Option<&Value>) -> Value {
+    let mut schema = match shape {
+        Shape::Bool => json!({ "type": "boolean" }),
+        Shape::Integer { unsigned: true } => json!({ "type": "integer", "minimum": 0 }),
+        Shape::Integer { unsigned: false } => json!({ "type": "integer" }),
+        Shape::Float => json!({ "type": "number" }),
+        Shape::String => json!({ "type": "string" }),
+        Shape::Enum(variants) => json!({ "type": "string", "enum": variants }),
+        // TOML has no null; an unset option is simply a missing key
+        Shape::Optional(inner) => return shape_to_json(inner, default),
+        Shape::List(item) => json!({ "type": "array", "items": shape_to_json(item, None) }),
+        Shape::Map(value) => json!({ "type": "object", "additionalProperties": shape_to_json(value, None) }),
+        Shape::Struct(fields) => {
+            let properties: Map<String, Value> = fields.iter()
+                .map(|(name, field)| {
+                    let default = default.and_then(|d| d.get(*name));
+                    (name.to_string(), shape_to_json(field, default))
+                })
+                .collect();
+            return json!({ "type": "object", "properties": properties });
+        }
+        Shape::Any => json!({}),
+    };
+
+    if let (Value::Object(object), Some(default)) = (&mut schema, default) {
+        if !default.is_null() {
+            object.insert("default".to_string(), default.clone());
+        }
+    }
+    schema
+}
+
+fn write_section(output: &mut String, path: &str, shape: &Shape, defaults: Option<&Value>) {
+    let Shape::Struct(fields) = shape.nested() else {
+        return;
+    };
+
+    let scalars: Vec<_> = fields.iter()
+        .filter(|(_, field)| !is_table(field))
+        .collect();
+    if !scalars.is_empty() {
+        let heading = if path.is_empty() { "Top level".to_string() } else { format!("`[{}]`", path) };
+        output.push_str(&format!("\n## {}\n\n| Key | Type | Default |\n|-----|------|---------|\n", heading));
+        for (name, field) in scalars {
+            let default = defaults
+                .and_then(|d| d.get(*name))
+                .filter(|d| !d.is_null())
+                .map(|d| format!("`{}`", d))
+                .unwrap_or_default();
+            output.push_str(&format!("| `{}` | {} | {} |\n", name, type_name(field), default));
+        }
+    }
+
+    for (name, field) in fields.iter().filter(|(_, field)| is_table(field)) {
+        let child = if path.is_empty() { name.to_string() } else { format!("{}.{}", path, name) };
+        match field.nested() {
+            Shape::Map(value) => write_section(output, &format!("{}.<name>", child), value, None),
+            table => write_section(output, &child, table, defaults.and_then(|d| d.get(*name))),
+        }
+    }
+}
+
+fn is_table(shape: &Shape) -> bool {
+    match shape.nested() {
+        Shape::Struct(_) => true,
+        Shape::Map(value) => matches!(value.nested(), Shape::Struct(_)),
+        _ => false,
+    }
+}
+
+fn type_name(shape: &Shape) -> String {
+    match shape {
+        Shape::Bool => "boolean".to_string(),
+        Shape::Integer { .. } => "integer".to_string(),
+        Shape::Float => "number".to_string(),
+        Shape::String => "string".to_string(),
+        Shape::Enum(variants) => variants.iter()
+            .map(|v| format!("`\"{}\"`", v))
+            .collect::<Vec<_>>()
+            .join(" \\| "),
+        Shape::Optional(inner) => format!("{} (optional)", type_name(inner)),
+        Shape::List(item) => format!("list of {}", type_name(item)),
+        Shape::Map(value) => format!("table of {}", type_name(value)),
+        Shape::Struct(_) => "table".to_string(),
+        Shape::Any => "any".to_string(),
+    }
+}
+
+/// Deserializer that writes the requested type into `shape` and feeds the
+/// visitor a placeholder value of that type.
+struct Tracer<'a> {
+    shape: &'a mut Shape,
+}
+
+macro_rules! trace_primitive {
+    ($($method:ident => $shape:expr, $visit:ident($($value:expr)?);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                *self.shape = $shape;
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for Tracer<'a> {
+    type Error = Error;
+
+    trace_primitive! {
+        deserialize_bool => Shape::Bool, visit_bool(false);
+        deserialize_i8 => Shape::Integer { unsigned: false }, visit_i8(0);
+        deserialize_i16 => Shape::Integer { unsigned: false }, visit_i16(0);
+        deserialize_i32 => Shape::Integer { unsigned: false }, visit_i32(0);
+        deserialize_i64 => Shape::Integer { unsigned: false }, visit_i64(0);
+        deserialize_u8 => Shape::Integer { unsigned: true }, visit_u8(0);
+        deserialize_u16 => Shape::Integer { unsigned: true }, visit_u16(0);
+        deserialize_u32 => Shape::Integer { unsigned: true }, visit_u32(0);
+        deserialize_u64 => Shape::Integer { unsigned: true }, visit_u64(0);
+        deserialize_f32 => Shape::Float, visit_f32(0.0);
+        deserialize_f64 => Shape::Float, visit_f64(0.0);
+        deserialize_char => Shape::String, visit_char(' ');
+        deserialize_str => Shape::String, visit_str(TRACE_STRING);
+        deserialize_string => Shape::String, visit_str(TRACE_STRING);
+        deserialize_identifier => Shape::String, visit_str(TRACE_STRING);
+        deserialize_bytes => Shape::Any, visit_bytes(&[]);
+        deserialize_byte_buf => Shape::Any, visit_bytes(&[]);
+        deserialize_unit => Shape::Any, visit_unit();
+        deserialize_ignored_any => Shape::Any, visit_unit();
+        deserialize_any => Shape::Any, visit_unit();
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut inner = Shape::Any;
+        let value = visitor.visit_some(Tracer { shape: &mut inner })?;
+        *self.shape = Shape::Optional(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut item = Shape::Any;
+        let value = visitor.visit_seq(TraceSeq { item: &mut item, remaining: 1 })?;
+        *self.shape = Shape::List(Box::new(item));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        let mut item = Shape::Any;
+        let value = visitor.visit_seq(TraceSeq { item: &mut item, remaining: len })?;
+        *self.shape = Shape::List(Box::new(item));
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut value_shape = Shape::Any;
+        let value = visitor.visit_map(TraceMap { value: &mut value_shape, remaining: 1 })?;
+        *self.shape = Shape::Map(Box::new(value_shape));
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let mut traced = Vec::new();
+        let value = visitor.visit_map(TraceStruct { fields, traced: &mut traced })?;
+        *self.shape = Shape::Struct(traced);
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        *self.shape = Shape::Enum(variants);
+        visitor.visit_enum(TraceEnum { variant: variants.first().copied().unwrap_or_default() })
+    }
+}
+
+struct TraceSeq<'a> {
+    item: &'a mut Shape,
+    remaining: usize,
+}
+
+impl<'de, 'a> SeqAccess<'de> for TraceSeq<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(Tracer { shape: self.item }).map(Some)
+    }
+}
+
+/// A map with `remaining` placeholder keys, each with a traced value.
+struct TraceMap<'a> {
+    value: &'a mut Shape,
+    remaining: usize,
+}
+
+impl<'de, 'a> MapAccess<'de> for TraceMap<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let key: StrDeserializer<Error> = "name".into_deserializer();
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(Tracer { shape: self.value })
+    }
+}
+
+/// Presents every declared field of a struct once, in declaration order.
+struct TraceStruct<'a> {
+    fields: &'static [&'static str],
+    traced: &'a mut Vec<(&'static str, Shape)>,
+}
+
+impl<'de, 'a> MapAccess<'de> for TraceStruct<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        let Some(field) = self.fields.get(self.traced.len()) else {
+            return Ok(None);
+        };
+        self.traced.push((field, Shape::Any));
+        let key: StrDeserializer<Error> = field.into_deserializer();
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, Error> {
+        let (_, shape) = self.traced.last_mut()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(Tracer { shape })
+    }
+}
+
+struct TraceEnum {
+    variant: &'static str,
+}
+
+impl<'de> EnumAccess<'de> for TraceEnum {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let key: StrDeserializer<Error> = self.variant.into_deserializer();
+        Ok((seed.deserialize(key)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for TraceEnum {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(Tracer { shape: &mut Shape::Any })
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        Tracer { shape: &mut Shape::Any }.deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        Tracer { shape: &mut Shape::Any }.deserialize_struct("", fields, visitor)
+    }
+}
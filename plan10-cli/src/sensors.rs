@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::{ComponentExt, System, SystemExt};
+
+/// What a sensor measures, from the first letter of its SMC key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SensorKind {
+    Temperature,
+    Fan,
+    Power,
+    Voltage,
+    Current,
+}
+
+impl SensorKind {
+    pub fn unit(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "°C",
+            SensorKind::Fan => "rpm",
+            SensorKind::Power => "W",
+            SensorKind::Voltage => "V",
+            SensorKind::Current => "A",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "Temperatures",
+            SensorKind::Fan => "Fans",
+            SensorKind::Power => "Power",
+            SensorKind::Voltage => "Voltages",
+            SensorKind::Current => "Currents",
+        }
+    }
+
+    /// `T…` temperatures, `F<n>Ac`/`Mn`/`Mx`/`Tg` fan speeds, `P…` power,
+    /// `V…` voltage and `I…` current; other keys are flags and settings.
+    fn of_key(key: &str) -> Option<Self> {
+        let bytes = key.as_bytes();
+        match bytes.first()? {
+            b'T' => Some(SensorKind::Temperature),
+            b'F' if bytes.get(1).is_some_and(u8::is_ascii_digit)
+                && key.get(2..).is_some_and(|suffix| ["Ac", "Mn", "Mx", "Tg"].contains(&suffix)) => Some(SensorKind::Fan),
+            b'P' => Some(SensorKind::Power),
+            b'V' => Some(SensorKind::Voltage),
+            b'I' => Some(SensorKind::Current),
+            _ => None,
+        }
+    }
+}
+
+/// One reading from the SMC, or from the HID temperature sensors that Apple
+/// Silicon keeps outside it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sensor {
+    /// SMC key such as "TC0P", or the HID sensor name
+    pub key: String,
+    pub description: Option<String>,
+    pub kind: SensorKind,
+    pub value: f32,
+}
+
+/// Well-known SMC keys. The rest are listed by key alone, as their meaning
+/// varies between models.
+const DESCRIPTIONS: &[(&str, &str)] = &[
+    ("TA0P", "Ambient"),
+    ("TB0T", "Battery"),
+    ("TB1T", "Battery cell 1"),
+    ("TB2T", "Battery cell 2"),
+    ("TC0D", "CPU die"),
+    ("TC0E", "CPU die (PECI)"),
+    ("TC0F", "CPU die (PECI, filtered)"),
+    ("TC0P", "CPU proximity"),
+    ("TCXC", "CPU (PECI)"),
+    ("TG0D", "GPU die"),
+    ("TG0P", "GPU proximity"),
+    ("TM0P", "Memory proximity"),
+    ("TW0P", "Wireless module"),
+    ("Ts0P", "Palm rest"),
+    ("PC0C", "CPU core"),
+    ("PCPC", "CPU package"),
+    ("PCPG", "GPU package"),
+    ("PDTR", "DC in"),
+    ("PPBR", "Battery"),
+    ("PSTR", "System total"),
+    ("VD0R", "DC in"),
+    ("ID0R", "DC in"),
+];
+
+fn describe(key: &str) -> Option<String> {
+    if let Some((_, description)) = DESCRIPTIONS.iter().find(|(known, _)| *known == key) {
+        return Some(description.to_string());
+    }
+    // F0Ac, F1Mx, ...
+    let fan = key.strip_prefix('F')?.get(..1)?;
+    match key.get(2..)? {
+        "Ac" => Some(format!("Fan {} speed", fan)),
+        "Mn" => Some(format!("Fan {} minimum", fan)),
+        "Mx" => Some(format!("Fan {} maximum", fan)),
+        "Tg" => Some(format!("Fan {} target", fan)),
+        _ => None,
+    }
+}
+
+/// Every temperature, fan, power, voltage and current sensor on this Mac,
+/// sorted by kind and key. Readings that are out of range or zero (sensors
+/// that aren't wired up) are left out; fans at 0 rpm are kept.
+pub fn all_sensors() -> Vec<Sensor> {
+    let mut sensors: Vec<Sensor> = smc_values().into_iter()
+        .filter_map(|(key, value)| {
+            let kind = SensorKind::of_key(&key)?;
+            let plausible = match kind {
+                SensorKind::Temperature => value > 0.0 && value < 150.0,
+                SensorKind::Fan => value >= 0.0,
+                _ => value > 0.0,
+            };
+            plausible.then(|| Sensor { description: describe(&key), key, kind, value })
+        })
+        .collect();
+
+    // The die sensors of M-series chips are HID services, not SMC keys
+    if cfg!(target_arch = "aarch64") {
+        sensors.extend(hid_temperatures().into_iter().map(|(label, celsius)| Sensor {
+            key: label,
+            description: None,
+            kind: SensorKind::Temperature,
+            value: celsius,
+        }));
+    }
+
+    sensors.sort_by(|a, b| (a.kind, &a.key).cmp(&(b.kind, &b.key)));
+    sensors
+}
+
+/// The readings most commands need: headline temperatures and fan speeds.
+pub struct Sensors {
+    /// Hottest CPU sensor; on Apple Silicon the performance and efficiency
+    /// clusters report separately
+    pub cpu_celsius: Option<f32>,
+    pub gpu_celsius: Option<f32>,
+    pub fans: Vec<FanSpeed>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanSpeed {
+    pub fan: u8,
+    pub rpm: f32,
+}
+
+/// Temperatures come from sysinfo, which reads the SMC on Intel Macs and the
+/// HID temperature sensors on Apple Silicon; fans come from the SMC.
+pub fn read_sensors() -> Sensors {
+    let temperatures = hid_temperatures();
+    let hottest = |is_match: fn(&str) -> bool| temperatures.iter()
+        .filter(|(label, _)| is_match(label))
+        .map(|(_, celsius)| *celsius)
+        .reduce(f32::max);
+
+    Sensors {
+        cpu_celsius: hottest(is_cpu_sensor),
+        gpu_celsius: hottest(|label| label.contains("GPU")),
+        fans: fan_speeds(),
+    }
+}
+
+/// Temperature sensors as sysinfo sees them, by label.
+fn hid_temperatures() -> Vec<(String, f32)> {
+    let mut system = System::new();
+    system.refresh_components_list();
+
+    // Sensors that aren't wired up report 0 or nonsense
+    system.components().iter()
+        .map(|component| (component.label().to_string(), component.temperature()))
+        .filter(|(_, celsius)| *celsius > 0.0 && *celsius < 150.0)
+        .collect()
+}
+
+/// "PECI CPU" and "CPU Proximity" on Intel; "pACC MTR Temp Sensor3",
+/// "eACC MTR Temp Sensor0" and "PMU tdie4" on Apple Silicon.
+fn is_cpu_sensor(label: &str) -> bool {
+    label.contains("CPU") || label.contains("ACC MTR") || label.starts_with("PMU tdie")
+}
+
+/// Actual fan speeds from the SMC keys `FNum` and `F<n>Ac`. Fanless Macs
+/// report no fans.
+#[cfg(target_os = "macos")]
+fn fan_speeds() -> Vec<FanSpeed> {
+    let Some(smc) = smc::Smc::open() else {
+        return Vec::new();
+    };
+    let count = smc.read("FNum").map_or(0, |value| value as u8);
+    (0..count)
+        .filter_map(|fan| smc.read(&format!("F{}Ac", fan)).map(|rpm| FanSpeed { fan, rpm }))
+        .collect()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn fan_speeds() -> Vec<FanSpeed> {
+    Vec::new()
+}
+
+/// Every numeric SMC key with its value.
+#[cfg(target_os = "macos")]
+fn smc_values() -> Vec<(String, f32)> {
+    let Some(smc) = smc::Smc::open() else {
+        return Vec::new();
+    };
+    smc.keys().into_iter()
+        .filter_map(|key| smc.read(&key).filter(|value| value.is_finite()).map(|value| (key, value)))
+        .collect()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn smc_values() -> Vec<(String, f32)> {
+    Vec::new()
+}
+
+/// Minimal AppleSMC client over IOKit, enough to list keys and read numeric
+/// ones.
+#[cfg(target_os = "macos")]
+mod smc {
+    use nix::libc::{c_char, c_void};
+    use std::ffi::CString;
+
+    const KERNEL_INDEX_SMC: u32 = 2;
+    const SMC_CMD_READ_BYTES: u8 = 5;
+    const SMC_CMD_READ_INDEX: u8 = 8;
+    const SMC_CMD_READ_KEYINFO: u8 = 9;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        static mach_task_self_: u32;
+        fn IOServiceMatching(name: *const c_char) -> *mut c_void;
+        fn IOServiceGetMatchingService(main_port: u32, matching: *mut c_void) -> u32;
+        fn IOServiceOpen(service: u32, owning_task: u32, kind: u32, connection: *mut u32) -> i32;
+        fn IOServiceClose(connection: u32) -> i32;
+        fn IOObjectRelease(object: u32) -> i32;
+        fn IOConnectCallStructMethod(
+            connection: u32,
+            selector: u32,
+            input: *const c_void,
+            input_size: usize,
+            output: *mut c_void,
+            output_size: *mut usize,
+        ) -> i32;
+    }
+
+    /// `SMCKeyData_t` from the AppleSMC user client; most fields are only
+    /// there for the layout.
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct KeyData {
+        key: u32,
+        version: [u8; 6],
+        p_limit_data: [u32; 4],
+        data_size: u32,
+        data_type: u32,
+        data_attributes: u8,
+        padding: [u8; 3],
+        result: u8,
+        status: u8,
+        data8: u8,
+        data32: u32,
+        bytes: [u8; 32],
+    }
+
+    pub struct Smc {
+        connection: u32,
+    }
+
+    impl Smc {
+        pub fn open() -> Option<Self> {
+            let name = CString::new("AppleSMC").ok()?;
+            unsafe {
+                // IOServiceGetMatchingService consumes the matching dictionary
+                let service = IOServiceGetMatchingService(0, IOServiceMatching(name.as_ptr()));
+                if service == 0 {
+                    return None;
+                }
+                let mut connection = 0;
+                let result = IOServiceOpen(service, mach_task_self_, 0, &mut connection);
+                IOObjectRelease(service);
+                (result == 0).then_some(Smc { connection })
+            }
+        }
+
+        /// Every key the SMC knows, e.g. "TC0P" or "F0Ac".
+        pub fn keys(&self) -> Vec<String> {
+            let count = self.read("#KEY").map_or(0, |count| count as u32);
+            (0..count)
+                .filter_map(|index| self.call(KeyData { data8: SMC_CMD_READ_INDEX, data32: index, ..Default::default() }))
+                .map(|output| String::from_utf8_lossy(&output.key.to_be_bytes()).into_owned())
+                .collect()
+        }
+
+        /// The value of a numeric key, decoded by its SMC data type.
+        pub fn read(&self, key: &str) -> Option<f32> {
+            let key = four_char_code(key)?;
+            let info = self.call(KeyData { key, data8: SMC_CMD_READ_KEYINFO, ..Default::default() })?;
+            let value = self.call(KeyData {
+                key,
+                data_size: info.data_size,
+                data8: SMC_CMD_READ_BYTES,
+                ..Default::default()
+            })?;
+            decode(&info.data_type.to_be_bytes(), &value.bytes)
+        }
+
+        fn call(&self, input: KeyData) -> Option<KeyData> {
+            let mut output = KeyData::default();
+            let mut output_size = std::mem::size_of::<KeyData>();
+            let result = unsafe {
+                IOConnectCallStructMethod(
+                    self.connection,
+                    KERNEL_INDEX_SMC,
+                    &input as *const KeyData as *const c_void,
+                    std::mem::size_of::<KeyData>(),
+                    &mut output as *mut KeyData as *mut c_void,
+                    &mut output_size,
+                )
+            };
+            (result == 0 && output.result == 0).then_some(output)
+        }
+    }
+
+    impl Drop for Smc {
+        fn drop(&mut self) {
+            unsafe { IOServiceClose(self.connection) };
+        }
+    }
+
+    /// `flt ` is a little-endian f32 and the integers are big-endian.
+    /// `fpXY`/`spXY` are unsigned/signed 16-bit fixed point with Y (hex)
+    /// fraction bits, e.g. `fpe2` for Intel fan speeds and `sp78` for
+    /// Intel temperatures.
+    fn decode(data_type: &[u8; 4], bytes: &[u8; 32]) -> Option<f32> {
+        let u16_value = u16::from_be_bytes([bytes[0], bytes[1]]);
+        match data_type {
+            b"flt " => Some(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            b"ui8 " => Some(bytes[0] as f32),
+            b"ui16" => Some(u16_value as f32),
+            b"ui32" => Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32),
+            b"si8 " => Some(bytes[0] as i8 as f32),
+            b"si16" => Some(u16_value as i16 as f32),
+            [b'f', b'p', _, fraction] => Some(u16_value as f32 / fixed_point_scale(*fraction)?),
+            [b's', b'p', _, fraction] => Some(u16_value as i16 as f32 / fixed_point_scale(*fraction)?),
+            _ => None,
+        }
+    }
+
+    fn fixed_point_scale(fraction_digit: u8) -> Option<f32> {
+        let bits = (fraction_digit as char).to_digit(16)?;
+        Some((1u32 << bits) as f32)
+    }
+
+    fn four_char_code(key: &str) -> Option<u32> {
+        let bytes: [u8; 4] = key.as_bytes().try_into().ok()?;
+        Some(u32::from_be_bytes(bytes))
+    }
+}
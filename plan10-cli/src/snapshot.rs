@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+/// Last collected local status, persisted so that cheap consumers (shell
+/// prompts, tmux status lines) can read it without running pmset or pgrep.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub hostname: String,
+    pub on_battery: bool,
+    pub on_ac: bool,
+    pub battery_percent: Option<u8>,
+    pub caffeinate_running: bool,
+    pub health_issues: usize,
+}
+
+impl StatusSnapshot {
+    pub fn path() -> Option<PathBuf> {
+        Config::state_dir().map(|dir| dir.join("status.json"))
+    }
+
+    pub fn load() -> Result<Option<Self>> {
+        let Some(path) = Self::path() else {
+            return Ok(None);
+        };
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)
+            .context(format!("Failed to read status snapshot: {}", path.display()))?;
+        let snapshot = serde_json::from_str(&content)
+            .context("Failed to parse status snapshot")?;
+        Ok(Some(snapshot))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().context("Could not determine state directory")?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .context("Failed to create state directory")?;
+        }
+
+        // Write then rename so a prompt never reads a half-written file
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(self)?)
+            .context(format!("Failed to write status snapshot: {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .context(format!("Failed to write status snapshot: {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn age_seconds(&self) -> i64 {
+        Utc::now().signed_duration_since(self.timestamp).num_seconds().max(0)
+    }
+
+    /// One-line summary suitable for embedding in a shell prompt.
+    pub fn compact(&self) -> String {
+        let source = if self.on_battery { "🔋" } else { "🔌" };
+        let mut line = match self.battery_percent {
+            Some(pct) => format!("{}{}%", source, pct),
+            None => source.to_string(),
+        };
+
+        if !self.caffeinate_running {
+            line.push_str(" ☕✗");
+        }
+
+        if self.health_issues > 0 {
+            line.push_str(&format!(" ⚠{}", self.health_issues));
+        }
+
+        line
+    }
+}
@@ -0,0 +1,234 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::ServerDefinition;
+
+/// Settings OpenSSH would use for a host alias, as far as plan10 cares.
+#[derive(Debug, Clone, Default)]
+pub struct SshHostConfig {
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+    pub forward_agent: Option<bool>,
+}
+
+impl SshHostConfig {
+    /// Look up `alias` in `~/.ssh/config`. A missing or unreadable file
+    /// simply yields no settings.
+    pub fn lookup(alias: &str) -> Self {
+        let mut config = Self::default();
+        if let Some(path) = user_config_path() {
+            config.read_file(&path, alias, 0);
+        }
+        config
+    }
+
+    fn read_file(&mut self, path: &Path, alias: &str, depth: usize) {
+        // OpenSSH caps Include recursion as well
+        if depth > 8 {
+            return;
+        }
+        let Ok(content) = fs::read_to_string(path) else {
+            return;
+        };
+
+        let mut active = true;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (keyword, value) = split_directive(line);
+            match keyword.to_lowercase().as_str() {
+                "host" => active = host_matches(value, alias),
+                // Match criteria are not evaluated; skip their settings
+                "match" => active = value.trim().eq_ignore_ascii_case("all"),
+                "include" if active => {
+                    for pattern in value.split_whitespace() {
+                        for included in expand_include(pattern) {
+                            self.read_file(&included, alias, depth + 1);
+                        }
+                    }
+                }
+                // The first obtained value for each keyword wins
+                "hostname" if active && self.host_name.is_none() => {
+                    self.host_name = Some(value.replace("%h", alias));
+                }
+                "user" if active && self.user.is_none() => self.user = Some(value.to_string()),
+                "port" if active && self.port.is_none() => self.port = value.parse().ok(),
+                "identityfile" if active && self.identity_file.is_none() => {
+                    self.identity_file = Some(value.replace("%h", alias).replace("%d", "~"));
+                }
+                "proxyjump" if active && self.proxy_jump.is_none() && !value.eq_ignore_ascii_case("none") => {
+                    // Only the first hop is supported
+                    self.proxy_jump = value.split(',').next().map(|hop| hop.trim().to_string());
+                }
+                "forwardagent" if active && self.forward_agent.is_none() => {
+                    self.forward_agent = Some(value.eq_ignore_ascii_case("yes"));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// A copy of `server` with the settings its host alias defines in the
+    /// SSH config. Values set in the plan10 configuration take precedence;
+    /// the port only counts as set when it differs from 22.
+    pub fn apply(&self, server: &ServerDefinition, default_key: Option<&String>) -> ServerDefinition {
+        let mut server = server.clone();
+        if let Some(host_name) = &self.host_name {
+            server.host = host_name.clone();
+        }
+        if server.port == 22 {
+            if let Some(port) = self.port {
+                server.port = port;
+            }
+        }
+        if server.ssh_key.is_none() && default_key.is_none() {
+            server.ssh_key = self.identity_file.clone();
+        }
+        if server.jump_host.is_none() {
+            server.jump_host = self.proxy_jump.clone();
+        }
+        if !server.agent_forwarding {
+            server.agent_forwarding = self.forward_agent.unwrap_or(false);
+        }
+        server
+    }
+}
+
+/// The concrete host aliases defined in `~/.ssh/config` and its includes,
+/// in file order. Patterns with wildcards or negations name no single host
+/// and are left out.
+pub fn host_aliases() -> Vec<String> {
+    let mut aliases = Vec::new();
+    if let Some(path) = user_config_path() {
+        collect_aliases(&path, &mut aliases, 0);
+    }
+    aliases
+}
+
+fn collect_aliases(path: &Path, aliases: &mut Vec<String>, depth: usize) {
+    if depth > 8 {
+        return;
+    }
+    let Ok(content) = fs::read_to_string(path) else {
+        return;
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = split_directive(line);
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                for pattern in value.split_whitespace() {
+                    if !pattern.contains(['*', '?', '!']) && !aliases.iter().any(|a| a == pattern) {
+                        aliases.push(pattern.to_string());
+                    }
+                }
+            }
+            "include" => {
+                for pattern in value.split_whitespace() {
+                    for included in expand_include(pattern) {
+                        collect_aliases(&included, aliases, depth + 1);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// Split `Keyword value` or `Keyword=value`, dropping quotes around the value.
+fn split_directive(line: &str) -> (&str, &str) {
+    let split = line.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(line.len());
+    let (keyword, rest) = line.split_at(split);
+    let value = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '=').trim();
+    (keyword, value.trim_matches('"'))
+}
+
+/// `Host` patterns are whitespace separated; a negated match rules the host
+/// out even when another pattern matches.
+fn host_matches(patterns: &str, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, alias) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern, alias),
+        }
+    }
+    matched
+}
+
+/// Glob match supporting `*` and `?`, as used by ssh_config patterns.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Resolve an `Include` argument: relative paths are relative to `~/.ssh`
+/// and the file name may contain wildcards.
+fn expand_include(pattern: &str) -> Vec<PathBuf> {
+    let expanded = shellexpand::tilde(pattern).into_owned();
+    let path = if Path::new(&expanded).is_absolute() {
+        PathBuf::from(expanded)
+    } else {
+        match dirs::home_dir() {
+            Some(home) => home.join(".ssh").join(expanded),
+            None => return Vec::new(),
+        }
+    };
+
+    let file_pattern = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    if !file_pattern.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut matches: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| wildcard_match(&file_pattern, &name.to_string_lossy()))
+        })
+        .collect();
+    matches.sort();
+    matches
+}
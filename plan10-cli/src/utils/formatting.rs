@@ -20,12 +20,14 @@ pub fn format_percentage_status(percentage: u8) -> (ColoredString, &'static str)
     }
 }
 
-pub fn format_temperature_status(temp_celsius: f32) -> (ColoredString, &'static str) {
+/// Bands relative to the configured `temp_threshold`: hot above it, warm
+/// within 10°C of it, cool more than 25°C below it.
+pub fn format_temperature_status(temp_celsius: f32, threshold: f32) -> (ColoredString, &'static str) {
     match temp_celsius {
-        t if t < 60.0 => ("❄️".blue(), "Cool"),
-        t if t < 75.0 => ("🌡️".green(), "Normal"),
-        t if t < 85.0 => ("🔶".yellow(), "Warm"),
-        _ => ("🔥".red(), "Hot"),
+        t if t > threshold => ("🔥".red(), "Hot"),
+        t if t > threshold - 10.0 => ("🔶".yellow(), "Warm"),
+        t if t >= threshold - 25.0 => ("🌡️".green(), "Normal"),
+        _ => ("❄️".blue(), "Cool"),
     }
 }
 
@@ -54,4 +54,21 @@ pub fn check_sudo() -> Result<()> {
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+/// Read a line from the terminal without echoing it.
+pub fn prompt_password(message: &str) -> Result<String> {
+    use std::io::Write;
+
+    eprint!("{}", message);
+    std::io::stderr().flush()?;
+
+    // stty acts on the terminal attached to its stdin, which it inherits
+    let _ = Command::new("stty").arg("-echo").status();
+    let mut input = String::new();
+    let read = std::io::stdin().read_line(&mut input);
+    let _ = Command::new("stty").arg("echo").status();
+    eprintln!();
+
+    read?;
+    Ok(input.trim_end_matches(['\r', '\n']).to_string())
+}